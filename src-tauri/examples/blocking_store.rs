@@ -0,0 +1,114 @@
+//! Measures what a cache query costs the async runtime when it runs inline versus on the
+//! blocking pool, as `cache_store::with_store_blocking` does.
+//!
+//! Fills a cache table with 20k entries of 20 KB, then runs the key listing behind
+//! `get_cache_info` 20 times on a single-worker runtime while a 1 ms timer ticks alongside.
+//! Reports the time per query and the worst timer lag.
+//!
+//! ```sh
+//! cargo run --release --example blocking_store
+//! ```
+
+use rusqlite::{params, Connection};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ENTRIES: usize = 20_000;
+const ENTRY_BYTES: usize = 20_000;
+const QUERIES: u32 = 20;
+const TICKS: usize = 200;
+
+fn fill(path: &std::path::Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         CREATE TABLE cache_entries (
+             key TEXT PRIMARY KEY,
+             data TEXT NOT NULL,
+             timestamp INTEGER NOT NULL
+         );",
+    )?;
+
+    let tx = conn.unchecked_transaction()?;
+    let data = "x".repeat(ENTRY_BYTES);
+    for i in 0..ENTRIES {
+        tx.execute(
+            "INSERT INTO cache_entries (key, data, timestamp) VALUES (?1, ?2, ?3)",
+            params![format!("key-{}", i), data, i as i64],
+        )?;
+    }
+    tx.commit()?;
+    Ok(conn)
+}
+
+fn list_keys(conn: &Connection) -> usize {
+    let mut statement = conn
+        .prepare("SELECT key, timestamp FROM cache_entries")
+        .expect("Failed to prepare query");
+    statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .expect("Failed to run query")
+        .count()
+}
+
+/// Time per query and the worst lag of the timer running next to the queries
+async fn measure(store: Arc<Mutex<Connection>>, blocking: bool) -> (Duration, Duration) {
+    let ticker = tokio::spawn(async {
+        let mut worst = Duration::ZERO;
+        for _ in 0..TICKS {
+            let started = Instant::now();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            worst = worst.max(started.elapsed());
+        }
+        worst
+    });
+
+    let queries = tokio::spawn(async move {
+        let started = Instant::now();
+        for _ in 0..QUERIES {
+            if blocking {
+                let store = store.clone();
+                tokio::task::spawn_blocking(move || {
+                    list_keys(&store.lock().unwrap_or_else(|e| e.into_inner()))
+                })
+                .await
+                .expect("Query task failed");
+            } else {
+                list_keys(&store.lock().unwrap_or_else(|e| e.into_inner()));
+                tokio::task::yield_now().await;
+            }
+        }
+        started.elapsed() / QUERIES
+    });
+
+    let per_query = queries.await.expect("Query task failed");
+    let worst_lag = ticker.await.expect("Timer task failed");
+    (per_query, worst_lag)
+}
+
+fn main() {
+    let path = std::env::temp_dir().join("lotus-routine-blocking-store.db");
+    let _ = std::fs::remove_file(&path);
+    let store = Arc::new(Mutex::new(fill(&path).expect("Failed to fill cache")));
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .expect("Failed to start runtime");
+
+    for blocking in [false, true] {
+        let (per_query, worst_lag) = runtime.block_on(measure(store.clone(), blocking));
+        println!(
+            "{}: {:?} per query, worst timer lag {:?}",
+            if blocking { "spawn_blocking" } else { "inline" },
+            per_query,
+            worst_lag
+        );
+    }
+
+    drop(store);
+    let _ = std::fs::remove_file(&path);
+}
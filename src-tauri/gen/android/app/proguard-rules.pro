@@ -18,4 +18,12 @@
 
 # If you keep the line number information, uncomment this to
 # hide the original source file name.
-#-renamesourcefileattribute SourceFile
\ No newline at end of file
+#-renamesourcefileattribute SourceFile
+
+# The barcode scanner is called through JNI from Rust (src/scanner.rs), which R8
+# cannot see.
+-keep class com.google.mlkit.vision.codescanner.GmsBarcodeScanning { *; }
+-keep class com.google.mlkit.vision.codescanner.GmsBarcodeScanner { *; }
+-keep class com.google.mlkit.vision.barcode.common.Barcode { *; }
+-keep class com.google.android.gms.tasks.Tasks { *; }
+-keep class com.google.android.gms.tasks.Task { *; }
@@ -0,0 +1,64 @@
+package com.th3void.lotusroutine
+
+import android.content.Context
+import androidx.biometric.BiometricManager
+import androidx.biometric.BiometricManager.Authenticators.BIOMETRIC_WEAK
+import androidx.biometric.BiometricManager.Authenticators.DEVICE_CREDENTIAL
+import androidx.biometric.BiometricPrompt
+import androidx.core.content.ContextCompat
+import androidx.fragment.app.FragmentActivity
+import java.util.concurrent.CountDownLatch
+
+/**
+ * Unlocks the app lock (`app_lock.rs`) with the system biometric prompt, which falls back
+ * to the device PIN, pattern or password. Called from Rust on a background thread.
+ */
+object AppLockPrompt {
+  private const val AUTHENTICATORS = BIOMETRIC_WEAK or DEVICE_CREDENTIAL
+
+  @JvmStatic
+  fun isAvailable(context: Context): Boolean =
+    BiometricManager.from(context).canAuthenticate(AUTHENTICATORS) ==
+      BiometricManager.BIOMETRIC_SUCCESS
+
+  /**
+   * Shows the prompt and blocks until it closes. Returns whether the user was verified, and
+   * throws with the system's message if the prompt failed, e.g. after too many attempts.
+   */
+  @JvmStatic
+  fun authenticate(activity: FragmentActivity, title: String): Boolean {
+    val done = CountDownLatch(1)
+    var verified = false
+    var error: String? = null
+
+    activity.runOnUiThread {
+      val callback = object : BiometricPrompt.AuthenticationCallback() {
+        override fun onAuthenticationSucceeded(result: BiometricPrompt.AuthenticationResult) {
+          verified = true
+          done.countDown()
+        }
+
+        override fun onAuthenticationError(errorCode: Int, errString: CharSequence) {
+          val cancelled = errorCode == BiometricPrompt.ERROR_USER_CANCELED ||
+            errorCode == BiometricPrompt.ERROR_NEGATIVE_BUTTON ||
+            errorCode == BiometricPrompt.ERROR_CANCELED
+          if (!cancelled) {
+            error = errString.toString()
+          }
+          done.countDown()
+        }
+      }
+
+      val info = BiometricPrompt.PromptInfo.Builder()
+        .setTitle(title)
+        .setAllowedAuthenticators(AUTHENTICATORS)
+        .build()
+      BiometricPrompt(activity, ContextCompat.getMainExecutor(activity), callback)
+        .authenticate(info)
+    }
+
+    done.await()
+    error?.let { throw IllegalStateException(it) }
+    return verified
+  }
+}
@@ -0,0 +1,142 @@
+package com.th3void.lotusroutine
+
+import android.Manifest
+import android.app.Activity
+import android.content.ContentUris
+import android.content.ContentValues
+import android.content.pm.PackageManager
+import android.net.Uri
+import android.provider.CalendarContract
+import org.json.JSONArray
+import org.json.JSONObject
+import java.util.TimeZone
+
+/**
+ * Reads busy times from the device's calendars and writes habit blocks to a local calendar
+ * of the app's own (`calendar.rs`). Called from Rust on a background thread.
+ */
+object CalendarBridge {
+  private const val PERMISSION_REQUEST_CODE = 4208
+  private val PERMISSIONS = arrayOf(Manifest.permission.READ_CALENDAR, Manifest.permission.WRITE_CALENDAR)
+  private val WEEKDAYS = arrayOf("MO", "TU", "WE", "TH", "FR", "SA", "SU")
+
+  /** Whether calendar access is granted; asks for it otherwise, answered asynchronously. */
+  @JvmStatic
+  fun ensureAccess(activity: Activity): Boolean {
+    val granted = PERMISSIONS.all {
+      activity.checkSelfPermission(it) == PackageManager.PERMISSION_GRANTED
+    }
+    if (!granted) {
+      activity.requestPermissions(PERMISSIONS, PERMISSION_REQUEST_CODE)
+    }
+    return granted
+  }
+
+  /**
+   * Events between `from` and `to` (Unix seconds) as a JSON array of `BusySlot`s, leaving out
+   * all-day and free events and the calendar called `name`.
+   */
+  @JvmStatic
+  fun busySlots(activity: Activity, name: String, from: Long, to: Long): String {
+    val own = ownCalendarId(activity, name)
+    val slots = JSONArray()
+    val projection = arrayOf(
+      CalendarContract.Instances.BEGIN,
+      CalendarContract.Instances.END,
+      CalendarContract.Instances.TITLE,
+      CalendarContract.Instances.ALL_DAY,
+      CalendarContract.Instances.AVAILABILITY,
+      CalendarContract.Instances.CALENDAR_ID,
+    )
+
+    CalendarContract.Instances.query(activity.contentResolver, projection, from * 1000, to * 1000)
+      ?.use { cursor ->
+        while (cursor.moveToNext()) {
+          if (cursor.getInt(3) != 0 ||
+            cursor.getInt(4) == CalendarContract.Events.AVAILABILITY_FREE ||
+            cursor.getLong(5) == own
+          ) {
+            continue
+          }
+          slots.put(
+            JSONObject()
+              .put("start", cursor.getLong(0) / 1000)
+              .put("end", cursor.getLong(1) / 1000)
+              .put("summary", cursor.getString(2) ?: JSONObject.NULL),
+          )
+        }
+      }
+
+    return slots.toString()
+  }
+
+  /** Replaces the calendar called `name` with the `HabitBlock`s in `json`. */
+  @JvmStatic
+  fun writeBlocks(activity: Activity, name: String, json: String) {
+    val resolver = activity.contentResolver
+    ownCalendarId(activity, name)?.let { id ->
+      resolver.delete(
+        ContentUris.withAppendedId(asSyncAdapter(CalendarContract.Calendars.CONTENT_URI, name), id),
+        null,
+        null,
+      )
+    }
+
+    val calendar = ContentValues().apply {
+      put(CalendarContract.Calendars.ACCOUNT_NAME, name)
+      put(CalendarContract.Calendars.ACCOUNT_TYPE, CalendarContract.ACCOUNT_TYPE_LOCAL)
+      put(CalendarContract.Calendars.NAME, name)
+      put(CalendarContract.Calendars.CALENDAR_DISPLAY_NAME, name)
+      put(CalendarContract.Calendars.CALENDAR_ACCESS_LEVEL, CalendarContract.Calendars.CAL_ACCESS_OWNER)
+      put(CalendarContract.Calendars.OWNER_ACCOUNT, name)
+      put(CalendarContract.Calendars.VISIBLE, 1)
+      put(CalendarContract.Calendars.SYNC_EVENTS, 1)
+    }
+    val calendarUri = resolver.insert(asSyncAdapter(CalendarContract.Calendars.CONTENT_URI, name), calendar)
+      ?: throw IllegalStateException("Failed to create the habit calendar")
+    val calendarId = ContentUris.parseId(calendarUri)
+
+    val blocks = JSONArray(json)
+    for (i in 0 until blocks.length()) {
+      val block = blocks.getJSONObject(i)
+      val days = block.getJSONArray("days")
+      val rule = if (days.length() == 0) {
+        "FREQ=DAILY"
+      } else {
+        "FREQ=WEEKLY;BYDAY=" + (0 until days.length()).joinToString(",") { WEEKDAYS[days.getInt(it)] }
+      }
+
+      val event = ContentValues().apply {
+        put(CalendarContract.Events.CALENDAR_ID, calendarId)
+        put(CalendarContract.Events.TITLE, block.getString("title"))
+        if (!block.isNull("notes")) {
+          put(CalendarContract.Events.DESCRIPTION, block.getString("notes"))
+        }
+        put(CalendarContract.Events.DTSTART, block.getLong("start") * 1000)
+        put(CalendarContract.Events.DURATION, "PT${block.getLong("duration_secs")}S")
+        put(CalendarContract.Events.RRULE, rule)
+        put(CalendarContract.Events.EVENT_TIMEZONE, TimeZone.getDefault().id)
+        put(CalendarContract.Events.AVAILABILITY, CalendarContract.Events.AVAILABILITY_FREE)
+      }
+      resolver.insert(CalendarContract.Events.CONTENT_URI, event)
+        ?: throw IllegalStateException("Failed to add a habit block")
+    }
+  }
+
+  private fun ownCalendarId(activity: Activity, name: String): Long? =
+    activity.contentResolver.query(
+      CalendarContract.Calendars.CONTENT_URI,
+      arrayOf(CalendarContract.Calendars._ID),
+      "${CalendarContract.Calendars.ACCOUNT_TYPE} = ? AND ${CalendarContract.Calendars.ACCOUNT_NAME} = ?",
+      arrayOf(CalendarContract.ACCOUNT_TYPE_LOCAL, name),
+      null,
+    )?.use { cursor -> if (cursor.moveToFirst()) cursor.getLong(0) else null }
+
+  /** Local calendars can only be created and deleted by their sync adapter. */
+  private fun asSyncAdapter(uri: Uri, name: String): Uri =
+    uri.buildUpon()
+      .appendQueryParameter(CalendarContract.CALLER_IS_SYNCADAPTER, "true")
+      .appendQueryParameter(CalendarContract.Calendars.ACCOUNT_NAME, name)
+      .appendQueryParameter(CalendarContract.Calendars.ACCOUNT_TYPE, CalendarContract.ACCOUNT_TYPE_LOCAL)
+      .build()
+}
@@ -0,0 +1,49 @@
+package com.th3void.lotusroutine
+
+import android.content.ContentProvider
+import android.content.ContentValues
+import android.database.Cursor
+import android.database.MatrixCursor
+import android.net.Uri
+import java.io.File
+
+/**
+ * Serves today's habit summary written by the Rust side (`home_widget.rs`) to the
+ * home-screen widgets as a single-row cursor with a `json` column.
+ *
+ * Query `content://<applicationId>.widgetdata/summary`.
+ */
+class WidgetDataProvider : ContentProvider() {
+  override fun onCreate(): Boolean = true
+
+  override fun query(
+    uri: Uri,
+    projection: Array<out String>?,
+    selection: String?,
+    selectionArgs: Array<out String>?,
+    sortOrder: String?
+  ): Cursor? {
+    if (uri.lastPathSegment != "summary") return null
+
+    val file = File(context?.dataDir ?: return null, "widget-data.json")
+    val cursor = MatrixCursor(arrayOf("json"))
+    if (file.exists()) {
+      cursor.addRow(arrayOf(file.readText()))
+    }
+    cursor.setNotificationUri(context?.contentResolver, uri)
+    return cursor
+  }
+
+  override fun getType(uri: Uri): String = "application/json"
+
+  override fun insert(uri: Uri, values: ContentValues?): Uri? = null
+
+  override fun delete(uri: Uri, selection: String?, selectionArgs: Array<out String>?): Int = 0
+
+  override fun update(
+    uri: Uri,
+    values: ContentValues?,
+    selection: String?,
+    selectionArgs: Array<out String>?
+  ): Int = 0
+}
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+const MASK: &str = "[redacted]";
+const EMAIL_MASK: &str = "[email]";
+
+/// Fields removed unless the caller opts out of the default rules
+const REMOVED_FIELDS: &[&str] = &["email", "email_address", "phone", "phone_number"];
+
+/// Fields naming a person, replaced by [`MASK`]
+const MASKED_FIELDS: &[&str] = &[
+    "first_name",
+    "last_name",
+    "full_name",
+    "display_name",
+    "username",
+    "nickname",
+    "avatar_url",
+    "user.name",
+    "author.name",
+    "friends.name",
+    "members.name",
+];
+
+/// Identifiers of the user and their friends. They are replaced by pseudonyms rather than
+/// masked, so records about the same person still line up.
+const PSEUDONYMIZED_FIELDS: &[&str] = &[
+    "user_id",
+    "author_id",
+    "owner_id",
+    "friend_id",
+    "friend_ids",
+    "user.id",
+    "friends.id",
+    "members.id",
+];
+
+/// What happens to a field matched by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Redaction {
+    /// The field is left out
+    Remove,
+    /// The value is replaced by `[redacted]`
+    Mask,
+    /// Each distinct value is replaced by a stable stand-in such as `person-3`
+    Pseudonymize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Field name such as `email`, matched at any depth, or a dot-separated path such as
+    /// `friends.name` matched against the end of a field's path. Case-insensitive; array
+    /// indices are not part of paths.
+    pub field: String,
+    pub action: Redaction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnonymizeOptions {
+    /// Applied after the default rules, so they can override them for the same field
+    pub rules: Vec<RedactionRule>,
+    /// Leaves out the default rules for names, emails, phone numbers and friend ids
+    pub skip_default_rules: bool,
+    /// Replaces email addresses found inside any text, such as notes, with `[email]`
+    pub scrub_emails_in_text: bool,
+    /// Adds the local habits and their completed days
+    pub include_habits: bool,
+}
+
+impl Default for AnonymizeOptions {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            skip_default_rules: false,
+            scrub_emails_in_text: true,
+            include_habits: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnonymizedExport {
+    /// Cache keys written to the file
+    pub keys: Vec<String>,
+    /// Requested keys with nothing cached
+    pub missing: Vec<String>,
+    /// Fields removed, masked or pseudonymized, plus email addresses scrubbed from text
+    pub redactions: u64,
+}
+
+struct Redactor {
+    rules: Vec<(Vec<String>, Redaction)>,
+    scrub_emails: bool,
+    pseudonyms: HashMap<String, String>,
+    redactions: u64,
+}
+
+impl Redactor {
+    fn new(options: &AnonymizeOptions) -> Result<Self, String> {
+        let defaults = [
+            (REMOVED_FIELDS, Redaction::Remove),
+            (MASKED_FIELDS, Redaction::Mask),
+            (PSEUDONYMIZED_FIELDS, Redaction::Pseudonymize),
+        ];
+        let default_rules = defaults
+            .iter()
+            .filter(|_| !options.skip_default_rules)
+            .flat_map(|(fields, action)| fields.iter().map(|field| (field.to_string(), *action)));
+        let custom_rules = options
+            .rules
+            .iter()
+            .map(|rule| (rule.field.clone(), rule.action));
+
+        let mut rules = Vec::new();
+        for (field, action) in default_rules.chain(custom_rules) {
+            let path: Vec<String> = field
+                .split('.')
+                .map(|part| part.trim().to_lowercase())
+                .collect();
+            if path.iter().any(String::is_empty) {
+                return Err(format!("Invalid redaction field: '{}'", field));
+            }
+            rules.push((path, action));
+        }
+
+        Ok(Self {
+            rules,
+            scrub_emails: options.scrub_emails_in_text,
+            pseudonyms: HashMap::new(),
+            redactions: 0,
+        })
+    }
+
+    /// The last rule whose path ends `path`, so later rules win.
+    fn action(&self, path: &[String]) -> Option<Redaction> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(rule, _)| path.ends_with(rule))
+            .map(|(_, action)| *action)
+    }
+
+    fn redact(&mut self, value: &mut Value, path: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                let fields = std::mem::take(object);
+                for (key, mut field) in fields {
+                    path.push(key.to_lowercase());
+                    match self.action(path) {
+                        Some(Redaction::Remove) => self.redactions += 1,
+                        Some(Redaction::Mask) => {
+                            self.redactions += 1;
+                            object.insert(key, Value::String(MASK.to_string()));
+                        }
+                        Some(Redaction::Pseudonymize) => {
+                            self.redactions += 1;
+                            self.pseudonymize(&mut field);
+                            object.insert(key, field);
+                        }
+                        None => {
+                            self.redact(&mut field, path);
+                            object.insert(key, field);
+                        }
+                    }
+                    path.pop();
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact(item, path);
+                }
+            }
+            Value::String(text) if self.scrub_emails => {
+                let (scrubbed, count) = scrub_emails(text);
+                if count > 0 {
+                    *text = scrubbed;
+                    self.redactions += count;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces every scalar in `value` by its stand-in. The same value always gets the
+    /// same stand-in within one export.
+    fn pseudonymize(&mut self, value: &mut Value) {
+        match value {
+            Value::Object(object) => object.values_mut().for_each(|v| self.pseudonymize(v)),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.pseudonymize(v)),
+            Value::Null => {}
+            scalar => {
+                let original = match &*scalar {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                let next = self.pseudonyms.len() + 1;
+                let pseudonym = self
+                    .pseudonyms
+                    .entry(original)
+                    .or_insert_with(|| format!("person-{}", next));
+                *scalar = Value::String(pseudonym.clone());
+            }
+        }
+    }
+}
+
+fn is_email(word: &str) -> bool {
+    let Some((local, domain)) = word.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+}
+
+/// Replaces email addresses in free text with [`EMAIL_MASK`] and counts them.
+fn scrub_emails(text: &str) -> (String, u64) {
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut count = 0;
+
+    for word in text.split_inclusive(|c: char| c.is_whitespace() || "<>()[]\"',;:".contains(c)) {
+        let end = word
+            .char_indices()
+            .last()
+            .filter(|(_, c)| c.is_whitespace() || "<>()[]\"',;:".contains(*c))
+            .map_or(word.len(), |(index, _)| index);
+        let (candidate, separator) = word.split_at(end);
+        // A sentence may end right after the address
+        let address = candidate.trim_end_matches(['.', '!', '?']);
+
+        if is_email(address) {
+            scrubbed.push_str(EMAIL_MASK);
+            scrubbed.push_str(&candidate[address.len()..]);
+            count += 1;
+        } else {
+            scrubbed.push_str(candidate);
+        }
+        scrubbed.push_str(separator);
+    }
+
+    (scrubbed, count)
+}
+
+/// Writes the cached documents under `keys`, and the local habits if asked, to a JSON file
+/// at `path` with names, email addresses and friend identifiers taken out, so progress can
+/// be shared with a coach or researcher. `options.rules` adds or overrides redaction rules.
+#[tauri::command]
+pub async fn export_anonymized(
+    app: AppHandle,
+    keys: Vec<String>,
+    options: Option<AnonymizeOptions>,
+    path: String,
+) -> Result<AnonymizedExport, AppError> {
+    let options = options.unwrap_or_default();
+    let mut redactor = Redactor::new(&options).map_err(AppError::invalid_input)?;
+
+    let mut entries = Map::new();
+    let mut exported = Vec::new();
+    let mut missing = Vec::new();
+    for key in keys {
+        match crate::load_local_data(app.clone(), key.clone()).await? {
+            Some(cached) => {
+                let mut data = cached.data;
+                redactor.redact(&mut data, &mut Vec::new());
+                entries.insert(key.clone(), data);
+                exported.push(key);
+            }
+            None => missing.push(key),
+        }
+    }
+
+    let mut export = serde_json::json!({
+        "exported_at": chrono::Utc::now().timestamp(),
+        "entries": entries,
+    });
+    if options.include_habits {
+        let habits = if crate::demo::is_demo(&app) {
+            Vec::new()
+        } else {
+            crate::habits::all_habits(&app, false).await?
+        };
+        let mut habits = serde_json::to_value(habits)
+            .map_err(|e| AppError::internal(format!("Failed to serialize habits: {}", e)))?;
+        redactor.redact(&mut habits, &mut Vec::new());
+        export["habits"] = habits;
+    }
+
+    let json = serde_json::to_vec_pretty(&export)
+        .map_err(|e| AppError::internal(format!("Failed to serialize export: {}", e)))?;
+    crate::storage::run_blocking(move || {
+        std::fs::write(&path, json)
+            .map_err(|e| AppError::storage(format!("Failed to write export: {}", e)))
+    })
+    .await?;
+
+    Ok(AnonymizedExport {
+        keys: exported,
+        missing,
+        redactions: redactor.redactions,
+    })
+}
@@ -0,0 +1,332 @@
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, UriSchemeContext, UriSchemeResponder, Url, Wry};
+
+use crate::cache_store::{self, CachedAsset};
+use crate::error::AppError;
+use crate::progress::ProgressReporter;
+
+/// Custom scheme mirroring the website from the cache store, so the whole web app can
+/// start without a connection. `app-cache://localhost/habit/123` maps to
+/// `<website>/habit/123`.
+pub const APP_CACHE_SCHEME: &str = "app-cache";
+
+/// Downloads running at once while warming the cache
+const PREFETCH_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefetchSummary {
+    pub downloaded: u64,
+    pub failed: Vec<String>,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppCacheUsage {
+    pub assets: u64,
+    pub bytes: u64,
+}
+
+/// Website URL a request on the scheme stands for.
+fn website_target(app: &AppHandle, request: &Request<Vec<u8>>) -> Option<Url> {
+    let base = Url::parse(&crate::website_url(app)).ok()?;
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+
+    base.join(path).ok()
+}
+
+/// Type for assets saved without one, from the file extension.
+fn guess_content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("js" | "mjs") => "text/javascript",
+        Some("css") => "text/css",
+        Some("json" | "webmanifest") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        Some("woff") => "font/woff",
+        _ => "text/html; charset=utf-8",
+    }
+}
+
+/// Static files with an extension rarely change under the same name, so the cached copy
+/// is served first; pages and API responses try the network first.
+fn prefers_cache(url: &Url) -> bool {
+    url.path()
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.') && !name.ends_with(".html"))
+}
+
+async fn load(app: &AppHandle, url: &Url) -> Option<CachedAsset> {
+    let url = url.to_string();
+    cache_store::with_store_blocking(app, move |store| store.load_asset(&url))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("{}", e);
+            None
+        })
+}
+
+/// Downloads `url` into the cache store and returns it.
+pub async fn fetch(app: &AppHandle, url: &Url) -> Result<CachedAsset, String> {
+    let response = crate::http_client::client(app)
+        .get(url.clone())
+        .timeout(crate::http_client::REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?
+        .to_vec();
+
+    let url = url.to_string();
+    let asset = CachedAsset { content_type, body };
+    cache_store::with_store_blocking(app, move |store| {
+        store.save_asset(
+            &url,
+            asset.content_type.as_deref(),
+            &asset.body,
+            chrono::Utc::now().timestamp(),
+        )?;
+        Ok(asset)
+    })
+    .await
+}
+
+/// Whether the website's start page has been saved, so the web app can start offline.
+pub async fn has_cached_app(app: &AppHandle) -> bool {
+    let Some(root) = Url::parse(&crate::website_url(app))
+        .ok()
+        .and_then(|base| base.join("/").ok())
+    else {
+        return false;
+    };
+    load(app, &root).await.is_some()
+}
+
+fn respond_with(url: &Url, asset: CachedAsset) -> Response<Vec<u8>> {
+    let content_type = asset
+        .content_type
+        .unwrap_or_else(|| guess_content_type(url.path()).to_string());
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(asset.body)
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
+
+fn error_response(status: StatusCode) -> Response<Vec<u8>> {
+    let mut response = Response::new(Vec::new());
+    *response.status_mut() = status;
+    response
+}
+
+async fn respond(app: &AppHandle, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    if request.method() != Method::GET {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let Some(url) = website_target(app, &request) else {
+        return error_response(StatusCode::BAD_REQUEST);
+    };
+
+    if prefers_cache(&url) {
+        if let Some(asset) = load(app, &url).await {
+            return respond_with(&url, asset);
+        }
+    }
+
+    match fetch(app, &url).await {
+        Ok(asset) => return respond_with(&url, asset),
+        Err(e) => tracing::debug!("{}", e),
+    }
+
+    if let Some(asset) = load(app, &url).await {
+        return respond_with(&url, asset);
+    }
+
+    // Client-side routes are not files; the start page renders them
+    let navigation = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    if navigation {
+        if let Ok(root) = url.join("/") {
+            if let Some(asset) = load(app, &root).await {
+                return respond_with(&root, asset);
+            }
+        }
+    }
+
+    error_response(StatusCode::GATEWAY_TIMEOUT)
+}
+
+pub fn protocol(
+    context: UriSchemeContext<'_, Wry>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = context.app_handle().clone();
+    tauri::async_runtime::spawn(async move {
+        responder.respond(respond(&app, request).await);
+    });
+}
+
+/// Paths listed by an asset manifest: a plain array, an object with `assets` and `api`
+/// arrays, or a Vite build manifest (`.vite/manifest.json`).
+fn manifest_paths(manifest: &serde_json::Value) -> Vec<String> {
+    let strings = |value: Option<&serde_json::Value>| -> Vec<String> {
+        value
+            .and_then(|value| value.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    match manifest {
+        serde_json::Value::Array(_) => strings(Some(manifest)),
+        serde_json::Value::Object(object)
+            if object.contains_key("assets") || object.contains_key("api") =>
+        {
+            let mut paths = strings(object.get("assets"));
+            paths.extend(strings(object.get("api")));
+            paths
+        }
+        serde_json::Value::Object(chunks) => chunks
+            .values()
+            .flat_map(|chunk| {
+                let mut paths: Vec<String> = chunk
+                    .get("file")
+                    .and_then(|file| file.as_str())
+                    .map(str::to_string)
+                    .into_iter()
+                    .collect();
+                paths.extend(strings(chunk.get("css")));
+                paths.extend(strings(chunk.get("assets")));
+                paths
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Downloads the website's start page and every file and API endpoint in the manifest
+/// into the cache store, so the first offline launch after installing already works.
+/// Entries outside the website's origin are skipped, as the scheme cannot serve them.
+#[tauri::command]
+pub async fn prefetch_assets(
+    app: AppHandle,
+    manifest_url: String,
+    operation_id: Option<String>,
+) -> Result<PrefetchSummary, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let website = Url::parse(&crate::website_url(&app))
+        .map_err(|e| AppError::corrupt_data(format!("Invalid website URL: {}", e)))?;
+    let manifest_url = website
+        .join(&manifest_url)
+        .map_err(|e| AppError::invalid_input(format!("Invalid manifest URL: {}", e)))?;
+
+    let progress = ProgressReporter::new(&app, operation_id);
+    progress.report("manifest", 0, None);
+
+    let manifest: serde_json::Value = crate::http_client::client(&app)
+        .get(manifest_url.clone())
+        .timeout(crate::http_client::REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| {
+            AppError::from_reqwest(&e, format!("Failed to download asset manifest: {}", e))
+        })?
+        .json()
+        .await
+        .map_err(|e| AppError::from_reqwest(&e, format!("Failed to parse asset manifest: {}", e)))?;
+
+    let urls: BTreeSet<Url> = std::iter::once("/".to_string())
+        .chain(manifest_paths(&manifest))
+        .filter_map(|path| website.join(&path).ok())
+        .filter(|url| url.origin() == website.origin())
+        .collect();
+
+    let total = urls.len() as u64;
+    let mut summary = PrefetchSummary {
+        downloaded: 0,
+        failed: Vec::new(),
+        bytes: 0,
+    };
+    progress.report("downloading", 0, Some(total));
+
+    let mut downloads = futures_util::stream::iter(urls)
+        .map(|url| {
+            let app = app.clone();
+            async move {
+                let result = fetch(&app, &url).await;
+                (url, result)
+            }
+        })
+        .buffer_unordered(PREFETCH_CONCURRENCY);
+
+    let mut completed = 0;
+    while let Some((url, result)) = downloads.next().await {
+        match result {
+            Ok(asset) => {
+                summary.downloaded += 1;
+                summary.bytes += asset.body.len() as u64;
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                summary.failed.push(url.to_string());
+            }
+        }
+
+        completed += 1;
+        progress.report("downloading", completed, Some(total));
+    }
+
+    progress.finish(
+        "downloading",
+        total,
+        Some(total),
+        Some(format!("{} of {} files cached", summary.downloaded, total)),
+    );
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn get_app_cache_usage(app: AppHandle) -> Result<AppCacheUsage, AppError> {
+    let (assets, bytes) = cache_store::with_store_blocking(&app, |store| store.asset_usage())
+        .await
+        .map_err(AppError::storage)?;
+    Ok(AppCacheUsage { assets, bytes })
+}
+
+#[tauri::command]
+pub async fn clear_app_cache(app: AppHandle) -> Result<(), AppError> {
+    cache_store::with_store_blocking(&app, |store| store.clear_assets())
+        .await
+        .map_err(AppError::storage)
+}
@@ -0,0 +1,801 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::ipc::Invoke;
+use tauri::plugin::TauriPlugin;
+use tauri::{
+    AppHandle, Emitter, Manager, State, UriSchemeContext, Url, Webview, WebviewWindow, WindowEvent,
+    Wry,
+};
+
+use crate::error::{AppError, ErrorKind};
+use crate::profiles::scoped_secret_key;
+use crate::secrets;
+
+/// Emitted with an [`AppLockChanged`] when the app locks or unlocks
+pub const APP_LOCK_CHANGED_EVENT: &str = "app-lock-changed";
+
+const APP_LOCK_FILE: &str = "app_lock";
+
+/// Keychain entry holding the salted hash of the fallback passcode
+const PASSCODE_KEY: &str = "app_lock_passcode";
+
+/// Prefix of stored passcode hashes, followed by the iterations, salt and hash
+const PASSCODE_SCHEME: &str = "pbkdf2-sha256";
+
+const PASSCODE_ITERATIONS: u32 = 600_000;
+
+const PASSCODE_SALT_LEN: usize = 16;
+
+/// Wrong passcodes in a row, kept across launches
+const FAILED_ATTEMPTS_FILE: &str = "app_lock_attempts";
+
+/// Wrong passcodes allowed before each further one makes the next attempt wait
+const FREE_ATTEMPTS: u32 = 5;
+
+const BASE_BACKOFF_SECS: u64 = 30;
+
+const MAX_BACKOFF_SECS: u64 = 60 * 60;
+
+const MIN_PASSCODE_LEN: usize = 4;
+
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Custom scheme serving the lock page, so a locked app never has the website loaded
+pub const LOCK_SCHEME: &str = "lotus-lock";
+
+const LOCK_PAGE: &str = include_str!("../assets/lock.html");
+
+/// Commands the lock page calls; every other command fails while the app is locked
+const LOCK_PAGE_COMMANDS: &[&str] = &[
+    "get_app_lock",
+    "unlock_app",
+    "lock_app",
+    "record_app_activity",
+];
+
+/// Reports user input to the idle timer, at most every 30 seconds
+const ACTIVITY_SCRIPT: &str = r#"
+    (function() {
+        if (window.__tauriAppLockActivity) return;
+        window.__tauriAppLockActivity = true;
+
+        let last = 0;
+        const report = () => {
+            const now = Date.now();
+            if (now - last < 30000) return;
+            last = now;
+            window.__TAURI_INTERNALS__.invoke('record_app_activity').catch(() => {});
+        };
+
+        ['pointerdown', 'keydown', 'wheel', 'touchstart'].forEach((name) => {
+            window.addEventListener(name, report, { passive: true, capture: true });
+        });
+    })();
+"#;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppLockConfig {
+    pub enabled: bool,
+    /// Locks as soon as the main window is minimized or hidden to the tray
+    pub lock_on_minimize: bool,
+    /// Locks after this long without input; `None` never locks on idle
+    pub idle_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppLockStatus {
+    #[serde(flatten)]
+    pub config: AppLockConfig,
+    pub locked: bool,
+    pub has_passcode: bool,
+    /// Whether Windows Hello, Touch ID, Face ID or Android biometrics can unlock the app
+    pub system_auth_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppLockChanged {
+    pub locked: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct FailedAttempts {
+    count: u32,
+    /// Unix time before which no passcode is checked
+    retry_at: Option<i64>,
+}
+
+pub struct AppLockState {
+    locked: AtomicBool,
+    last_activity: Mutex<Instant>,
+    /// Where the main window was before locking, opened again after unlocking
+    return_url: Mutex<Option<Url>>,
+    /// Serializes passcode checks so the attempt count cannot be raced
+    passcode_check: tokio::sync::Mutex<()>,
+}
+
+impl AppLockState {
+    /// Starts locked when the lock is turned on for the active profile.
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        let config: AppLockConfig = crate::read_state_file(app, APP_LOCK_FILE)?;
+
+        Ok(Self {
+            locked: AtomicBool::new(config.enabled),
+            last_activity: Mutex::new(Instant::now()),
+            return_url: Mutex::new(None),
+            passcode_check: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+    }
+
+    fn set_return_url(&self, url: Url) {
+        *self.return_url.lock().unwrap_or_else(|e| e.into_inner()) = Some(url);
+    }
+
+    fn take_return_url(&self) -> Option<Url> {
+        self.return_url
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+}
+
+fn config(app: &AppHandle) -> AppLockConfig {
+    crate::read_state_file(app, APP_LOCK_FILE).unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        AppLockConfig::default()
+    })
+}
+
+fn store_passcode(app: &AppHandle, passcode: &str) -> Result<(), AppError> {
+    if passcode.chars().count() < MIN_PASSCODE_LEN {
+        return Err(AppError::invalid_input(format!(
+            "Passcode must be at least {} characters",
+            MIN_PASSCODE_LEN
+        )));
+    }
+
+    let mut salt = [0u8; PASSCODE_SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| AppError::new(ErrorKind::Internal, "Failed to generate passcode salt"))?;
+
+    let iterations = NonZeroU32::new(PASSCODE_ITERATIONS).expect("iterations are not zero");
+    let mut hash = [0u8; ring::digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        passcode.as_bytes(),
+        &mut hash,
+    );
+
+    secrets::write_secret(
+        &scoped_secret_key(app, PASSCODE_KEY),
+        &format!(
+            "{}${}${}${}",
+            PASSCODE_SCHEME,
+            iterations,
+            STANDARD.encode(salt),
+            STANDARD.encode(hash)
+        ),
+    )
+}
+
+fn stored_passcode(app: &AppHandle) -> Result<Option<String>, AppError> {
+    secrets::read_secret(&scoped_secret_key(app, PASSCODE_KEY))
+}
+
+/// Compares in constant time through `pbkdf2::verify`.
+fn verify_passcode(app: &AppHandle, passcode: &str) -> Result<bool, AppError> {
+    let Some(stored) = stored_passcode(app)? else {
+        return Ok(false);
+    };
+
+    let corrupt = || AppError::corrupt_data("Stored passcode is corrupt; set a new one");
+    let [PASSCODE_SCHEME, iterations, salt, hash] = stored.split('$').collect::<Vec<_>>()[..]
+    else {
+        return Err(corrupt());
+    };
+    let iterations: NonZeroU32 = iterations.parse().map_err(|_| corrupt())?;
+    let salt = STANDARD.decode(salt).map_err(|_| corrupt())?;
+    let hash = STANDARD.decode(hash).map_err(|_| corrupt())?;
+
+    Ok(pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        passcode.as_bytes(),
+        &hash,
+    )
+    .is_ok())
+}
+
+/// Seconds to wait after `count` wrong passcodes in a row, doubling with each one past
+/// the free attempts.
+fn backoff_secs(count: u32) -> Option<u64> {
+    let excess = count.checked_sub(FREE_ATTEMPTS)?;
+    Some(
+        BASE_BACKOFF_SECS
+            .saturating_mul(1 << excess.min(16))
+            .min(MAX_BACKOFF_SECS),
+    )
+}
+
+/// Checks a passcode unless a run of wrong ones is still backing off, so short passcodes
+/// cannot be guessed quickly.
+async fn check_passcode(app: &AppHandle, passcode: String) -> Result<bool, AppError> {
+    let state = app.state::<AppLockState>();
+    let _guard = state.passcode_check.lock().await;
+
+    let mut attempts: FailedAttempts =
+        crate::read_state_file_blocking(app, FAILED_ATTEMPTS_FILE).await?;
+    let now = chrono::Utc::now().timestamp();
+    if let Some(wait) = attempts
+        .retry_at
+        .map(|at| at - now)
+        .filter(|wait| *wait > 0)
+    {
+        return Err(AppError::new(
+            ErrorKind::AuthenticationFailed,
+            format!("Too many wrong passcodes; try again in {} seconds", wait),
+        ));
+    }
+
+    let handle = app.clone();
+    let verified =
+        tauri::async_runtime::spawn_blocking(move || verify_passcode(&handle, &passcode))
+            .await
+            .map_err(|e| {
+                AppError::new(
+                    ErrorKind::Internal,
+                    format!("Failed to check passcode: {}", e),
+                )
+            })??;
+
+    if verified {
+        attempts = FailedAttempts::default();
+    } else {
+        attempts.count += 1;
+        attempts.retry_at = backoff_secs(attempts.count).map(|secs| now + secs as i64);
+        tracing::warn!("Wrong app lock passcode ({} in a row)", attempts.count);
+    }
+    crate::write_state_file_blocking(app, FAILED_ATTEMPTS_FILE, &attempts).await?;
+
+    Ok(verified)
+}
+
+/// The system's own unlock prompt: Windows Hello, Touch ID or Face ID through
+/// LocalAuthentication, and BiometricPrompt on Android. Each lets the user fall back to the
+/// device password or PIN. Everything here blocks the calling thread.
+mod system_auth {
+    #[cfg(windows)]
+    pub fn available() -> bool {
+        use windows::Security::Credentials::UI::{
+            UserConsentVerifier, UserConsentVerifierAvailability,
+        };
+
+        UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|operation| operation.get())
+            .is_ok_and(|availability| availability == UserConsentVerifierAvailability::Available)
+    }
+
+    #[cfg(windows)]
+    pub fn verify(reason: &str) -> Result<bool, String> {
+        use windows::core::HSTRING;
+        use windows::Security::Credentials::UI::{
+            UserConsentVerificationResult, UserConsentVerifier,
+        };
+
+        UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|operation| operation.get())
+            .map(|result| result == UserConsentVerificationResult::Verified)
+            .map_err(|e| format!("Windows Hello failed: {}", e))
+    }
+
+    #[cfg(target_vendor = "apple")]
+    pub fn available() -> bool {
+        use objc2_local_authentication::{LAContext, LAPolicy};
+
+        let context = unsafe { LAContext::new() };
+        unsafe { context.canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthentication) }.is_ok()
+    }
+
+    #[cfg(target_vendor = "apple")]
+    pub fn verify(reason: &str) -> Result<bool, String> {
+        use block2::RcBlock;
+        use objc2::runtime::Bool;
+        use objc2_foundation::{NSError, NSString};
+        use objc2_local_authentication::{LAContext, LAError, LAPolicy};
+        use std::sync::mpsc;
+
+        let context = unsafe { LAContext::new() };
+        let (sender, receiver) = mpsc::channel();
+        // Called once, on a queue of the framework's own, when the prompt closes
+        let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+            let result = match unsafe { error.as_ref() } {
+                _ if success.as_bool() => Ok(true),
+                Some(error)
+                    if [LAError::AuthenticationFailed, LAError::UserCancel]
+                        .iter()
+                        .any(|code| code.0 == error.code()) =>
+                {
+                    Ok(false)
+                }
+                Some(error) => Err(error.localizedDescription().to_string()),
+                None => Ok(false),
+            };
+            let _ = sender.send(result);
+        });
+        unsafe {
+            context.evaluatePolicy_localizedReason_reply(
+                LAPolicy::DeviceOwnerAuthentication,
+                &NSString::from_str(reason),
+                &reply,
+            )
+        };
+
+        receiver
+            .recv()
+            .map_err(|e| format!("System authentication failed: {}", e))?
+    }
+
+    /// Calls the Kotlin `AppLockPrompt` helper, which is only visible through the app's
+    /// class loader.
+    #[cfg(target_os = "android")]
+    fn call_prompt<T>(
+        call: impl FnOnce(
+            &mut jni::JNIEnv,
+            &jni::objects::JObject,
+            &jni::objects::JClass,
+        ) -> jni::errors::Result<T>,
+    ) -> Result<T, String> {
+        use jni::objects::{JClass, JObject, JString, JValue};
+
+        let context = ndk_context::android_context();
+        let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+            .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+        let activity = unsafe { JObject::from_raw(context.context().cast()) };
+        let mut env = vm
+            .attach_current_thread()
+            .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+        let loader = env
+            .call_method(
+                &activity,
+                "getClassLoader",
+                "()Ljava/lang/ClassLoader;",
+                &[],
+            )
+            .and_then(|loader| loader.l());
+        let name = env.new_string("com.th3void.lotusroutine.AppLockPrompt");
+        let class = match (loader, name) {
+            (Ok(loader), Ok(name)) => env
+                .call_method(
+                    &loader,
+                    "loadClass",
+                    "(Ljava/lang/String;)Ljava/lang/Class;",
+                    &[JValue::from(&name)],
+                )
+                .and_then(|class| class.l())
+                .map(JClass::from),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+
+        match class.and_then(|class| call(&mut env, &activity, &class)) {
+            Ok(value) => Ok(value),
+            Err(jni::errors::Error::JavaException) => {
+                // The helper throws with the system's message, e.g. after too many attempts
+                let message = env.exception_occurred().and_then(|exception| {
+                    env.exception_clear()?;
+                    let message: JString = env
+                        .call_method(&exception, "getMessage", "()Ljava/lang/String;", &[])?
+                        .l()?
+                        .into();
+                    let message: String = env.get_string(&message)?.into();
+                    Ok(message)
+                });
+                let _ = env.exception_clear();
+                Err(message.unwrap_or_else(|_| "The system prompt reported an error".to_string()))
+            }
+            Err(e) => {
+                let _ = env.exception_clear();
+                Err(format!("System authentication failed: {}", e))
+            }
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    pub fn available() -> bool {
+        use jni::objects::JValue;
+
+        call_prompt(|env, activity, class| {
+            env.call_static_method(
+                class,
+                "isAvailable",
+                "(Landroid/content/Context;)Z",
+                &[JValue::from(activity)],
+            )?
+            .z()
+        })
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to check for biometrics: {}", e);
+            false
+        })
+    }
+
+    #[cfg(target_os = "android")]
+    pub fn verify(reason: &str) -> Result<bool, String> {
+        use jni::objects::JValue;
+
+        call_prompt(|env, activity, class| {
+            let title = env.new_string(reason)?;
+            env.call_static_method(
+                class,
+                "authenticate",
+                "(Landroidx/fragment/app/FragmentActivity;Ljava/lang/String;)Z",
+                &[JValue::from(activity), JValue::from(&title)],
+            )?
+            .z()
+        })
+    }
+
+    #[cfg(not(any(windows, target_vendor = "apple", target_os = "android")))]
+    pub fn available() -> bool {
+        false
+    }
+
+    #[cfg(not(any(windows, target_vendor = "apple", target_os = "android")))]
+    pub fn verify(_reason: &str) -> Result<bool, String> {
+        Err("System authentication is not available on this platform".to_string())
+    }
+}
+
+async fn system_auth_available() -> bool {
+    tauri::async_runtime::spawn_blocking(system_auth::available)
+        .await
+        .unwrap_or(false)
+}
+
+/// Shows the system prompt and returns whether the user passed it.
+async fn verify_with_system(app: &AppHandle) -> Result<bool, AppError> {
+    if !system_auth_available().await {
+        return Err(AppError::unsupported(
+            "System authentication is not available; enter the app passcode",
+        ));
+    }
+
+    let reason = crate::i18n::text(app, "lock.reason");
+    tauri::async_runtime::spawn_blocking(move || system_auth::verify(&reason))
+        .await
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::Internal,
+                format!("System authentication failed: {}", e),
+            )
+        })?
+        .map_err(|e| AppError::new(ErrorKind::AuthenticationFailed, e))
+}
+
+fn lock_page_url() -> Url {
+    crate::custom_scheme_url(LOCK_SCHEME)
+}
+
+/// Compares scheme and host, as custom schemes have opaque origins.
+fn is_lock_page(url: &Url) -> bool {
+    let lock_page = lock_page_url();
+    url.scheme() == lock_page.scheme() && url.host_str() == lock_page.host_str()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_lock_page(app: &AppHandle) -> String {
+    [
+        ("lang", crate::i18n::current_locale(app).to_string()),
+        ("title", crate::i18n::text(app, "lock.title")),
+        ("passcode", crate::i18n::text(app, "lock.passcode")),
+        ("unlock", crate::i18n::text(app, "lock.unlock")),
+        ("use_system", crate::i18n::text(app, "lock.use_system")),
+    ]
+    .iter()
+    .fold(LOCK_PAGE.to_string(), |page, (name, value)| {
+        page.replace(&format!("{{{{{}}}}}", name), &escape_html(value))
+    })
+}
+
+pub fn protocol(
+    context: UriSchemeContext<'_, Wry>,
+    request: Request<Vec<u8>>,
+) -> Response<Cow<'static, [u8]>> {
+    let builder = Response::builder();
+    let response = if request.uri().path() == "/" {
+        builder
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Cow::Owned(
+                render_lock_page(context.app_handle()).into_bytes(),
+            ))
+    } else {
+        builder
+            .status(StatusCode::NOT_FOUND)
+            .body(Cow::Borrowed(&[][..]))
+    };
+
+    response.unwrap_or_else(|e| {
+        tracing::warn!("Failed to build lock page response: {}", e);
+        Response::new(Cow::Borrowed(&[][..]))
+    })
+}
+
+/// URL the main window is created with: the lock page if the app starts locked, in which
+/// case `website` opens after unlocking.
+pub fn start_url(app: &AppHandle, website: Url) -> Url {
+    let state = app.state::<AppLockState>();
+    if !state.is_locked() {
+        return website;
+    }
+
+    state.set_return_url(website);
+    lock_page_url()
+}
+
+/// Opens `url` in the main window, or remembers it for after unlocking while locked.
+pub fn navigate(window: &WebviewWindow, url: Url) -> tauri::Result<()> {
+    let state = window.state::<AppLockState>();
+    if state.is_locked() {
+        state.set_return_url(url);
+        return Ok(());
+    }
+
+    window.navigate(url)
+}
+
+/// Plugin that keeps webviews on the lock page while locked, including navigations
+/// started by the page itself or from the devtools.
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri::plugin::Builder::new("app-lock")
+        .on_navigation(|webview, url| {
+            !webview.state::<AppLockState>().is_locked() || is_lock_page(url)
+        })
+        .build()
+}
+
+/// Wraps the command handler so that while the app is locked only the lock page's own
+/// commands run, and everything else fails with a `locked` error.
+pub fn guard<F>(handler: F) -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static
+where
+    F: Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        if !LOCK_PAGE_COMMANDS.contains(&invoke.message.command()) {
+            if let Err(e) = ensure_unlocked(invoke.message.webview_ref().app_handle()) {
+                invoke.resolver.reject(e);
+                return true;
+            }
+        }
+
+        handler(invoke)
+    }
+}
+
+/// Guard for actions started outside the page, e.g. from the tray or the menu bar.
+pub fn ensure_unlocked(app: &AppHandle) -> Result<(), AppError> {
+    if app
+        .try_state::<AppLockState>()
+        .is_some_and(|state| state.is_locked())
+    {
+        return Err(AppError::new(
+            ErrorKind::Locked,
+            "Unlock the app to continue",
+        ));
+    }
+    Ok(())
+}
+
+fn set_locked(app: &AppHandle, locked: bool) {
+    let state = app.state::<AppLockState>();
+    if state.locked.swap(locked, Ordering::SeqCst) == locked {
+        return;
+    }
+    state.touch();
+
+    if let Some(window) = app.get_webview_window("main") {
+        let target = if locked {
+            match window.url() {
+                Ok(url) if !is_lock_page(&url) => state.set_return_url(url),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to read the page to return to: {}", e),
+            }
+            Some(lock_page_url())
+        } else {
+            state
+                .take_return_url()
+                .or_else(|| Url::parse(&crate::website_url(app)).ok())
+        };
+
+        if let Some(target) = target {
+            if let Err(e) = window.navigate(target) {
+                tracing::warn!("Failed to update lock screen: {}", e);
+            }
+        }
+    }
+
+    #[cfg(desktop)]
+    if locked {
+        crate::widget::close(app);
+    }
+
+    if let Err(e) = app.emit(APP_LOCK_CHANGED_EVENT, &AppLockChanged { locked }) {
+        tracing::warn!("Failed to emit {}: {}", APP_LOCK_CHANGED_EVENT, e);
+    }
+}
+
+/// Starts reporting activity to the idle timer on every page but the lock page.
+pub fn apply_to_page(webview: &Webview) {
+    let app = webview.app_handle();
+    if !config(app).enabled || webview.url().is_ok_and(|url| is_lock_page(&url)) {
+        return;
+    }
+
+    if let Err(e) = webview.eval(ACTIVITY_SCRIPT) {
+        tracing::warn!("Failed to start activity tracking: {}", e);
+    }
+}
+
+/// Locks when the main window is minimized or hidden, if configured to.
+pub fn watch(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        let hidden = match event {
+            WindowEvent::Resized(_) => handle.is_minimized().unwrap_or(false),
+            WindowEvent::CloseRequested { .. } => true,
+            _ => false,
+        };
+        if !hidden {
+            return;
+        }
+
+        let config = config(handle.app_handle());
+        if config.enabled && config.lock_on_minimize {
+            set_locked(handle.app_handle(), true);
+        }
+    });
+}
+
+/// Starts the background task that locks the app after the idle timeout.
+pub fn spawn_idle_timer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let state = app.state::<AppLockState>();
+            if state.is_locked() {
+                continue;
+            }
+
+            let config = config(&app);
+            let Some(timeout) = config.idle_timeout_secs.filter(|_| config.enabled) else {
+                continue;
+            };
+            if state.idle_for() >= Duration::from_secs(timeout) {
+                set_locked(&app, true);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_app_lock(
+    app: AppHandle,
+    state: State<'_, AppLockState>,
+) -> Result<AppLockStatus, AppError> {
+    Ok(AppLockStatus {
+        config: crate::read_state_file_blocking(&app, APP_LOCK_FILE).await?,
+        locked: state.is_locked(),
+        has_passcode: stored_passcode(&app)?.is_some(),
+        system_auth_available: system_auth_available().await,
+    })
+}
+
+/// Turns the app lock on or off. A passcode is required when turning it on for the first
+/// time, as the fallback when system authentication is unavailable or fails.
+#[tauri::command]
+pub async fn configure_app_lock(
+    app: AppHandle,
+    state: State<'_, AppLockState>,
+    config: AppLockConfig,
+    passcode: Option<String>,
+) -> Result<(), AppError> {
+    if state.is_locked() {
+        return Err(AppError::invalid_state(
+            "Unlock the app before changing the lock settings",
+        ));
+    }
+
+    match passcode {
+        Some(passcode) => store_passcode(&app, &passcode)?,
+        None if config.enabled && stored_passcode(&app)?.is_none() => {
+            return Err(AppError::invalid_input(
+                "Choose a passcode to turn on the app lock",
+            ));
+        }
+        None => {}
+    }
+
+    crate::write_state_file_blocking(&app, APP_LOCK_FILE, &config).await?;
+    state.touch();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_app(app: AppHandle) -> Result<(), AppError> {
+    if !config(&app).enabled {
+        return Err(AppError::invalid_state("The app lock is turned off"));
+    }
+
+    set_locked(&app, true);
+    Ok(())
+}
+
+/// Unlocks with the passcode when one is given, and with the system prompt (Windows Hello,
+/// Touch ID, Face ID or Android biometrics) otherwise.
+#[tauri::command]
+pub async fn unlock_app(
+    app: AppHandle,
+    state: State<'_, AppLockState>,
+    passcode: Option<String>,
+) -> Result<(), AppError> {
+    if !state.is_locked() {
+        return Ok(());
+    }
+
+    let verified = match passcode {
+        Some(passcode) => check_passcode(&app, passcode).await?,
+        None => verify_with_system(&app).await?,
+    };
+    if !verified {
+        return Err(AppError::new(
+            ErrorKind::AuthenticationFailed,
+            "Authentication failed",
+        ));
+    }
+
+    set_locked(&app, false);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn record_app_activity(state: State<'_, AppLockState>) -> Result<(), AppError> {
+    state.touch();
+    Ok(())
+}
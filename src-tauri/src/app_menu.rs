@@ -0,0 +1,349 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::error::AppError;
+use crate::i18n::{text, text_with};
+use crate::report::ReportRange;
+
+const EXPORT_REPORT_ID: &str = "menu-export-report";
+const BACKUP_ID: &str = "menu-backup";
+const RELOAD_ID: &str = "menu-reload";
+const ZOOM_IN_ID: &str = "menu-zoom-in";
+const ZOOM_OUT_ID: &str = "menu-zoom-out";
+const ZOOM_RESET_ID: &str = "menu-zoom-reset";
+const CHECK_FOR_UPDATES_ID: &str = "menu-check-for-updates";
+
+/// Emitted with the [`UpdateInfo`](crate::updates::UpdateInfo) found through the Help menu
+pub const MENU_UPDATE_AVAILABLE_EVENT: &str = "menu-update-available";
+
+const ZOOM_STEP: f64 = 0.1;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+
+/// Zoom factor of the main webview, which the webview itself does not report
+pub struct ZoomState(Mutex<f64>);
+
+impl Default for ZoomState {
+    fn default() -> Self {
+        Self(Mutex::new(1.0))
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let name = app.package_info().name.clone();
+    let about = PredefinedMenuItem::about(
+        app,
+        Some(&text_with(app, "menu.about", &[("app", &name)])),
+        Some(crate::context_menu::about_metadata(app)),
+    )?;
+
+    let file = Submenu::with_items(
+        app,
+        text(app, "menu.file"),
+        true,
+        &[
+            &MenuItem::with_id(
+                app,
+                EXPORT_REPORT_ID,
+                text(app, "menu.export_report"),
+                true,
+                Some("CmdOrCtrl+E"),
+            )?,
+            &MenuItem::with_id(
+                app,
+                BACKUP_ID,
+                text(app, "menu.back_up"),
+                true,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, None)?,
+        ],
+    )?;
+
+    let view = Submenu::with_items(
+        app,
+        text(app, "menu.view"),
+        true,
+        &[
+            &MenuItem::with_id(
+                app,
+                RELOAD_ID,
+                text(app, "menu.reload"),
+                true,
+                Some("CmdOrCtrl+R"),
+            )?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(
+                app,
+                ZOOM_IN_ID,
+                text(app, "menu.zoom_in"),
+                true,
+                Some("CmdOrCtrl+="),
+            )?,
+            &MenuItem::with_id(
+                app,
+                ZOOM_OUT_ID,
+                text(app, "menu.zoom_out"),
+                true,
+                Some("CmdOrCtrl+-"),
+            )?,
+            &MenuItem::with_id(
+                app,
+                ZOOM_RESET_ID,
+                text(app, "menu.actual_size"),
+                true,
+                Some("CmdOrCtrl+0"),
+            )?,
+        ],
+    )?;
+
+    let help = Submenu::with_items(
+        app,
+        text(app, "menu.help"),
+        true,
+        &[
+            &MenuItem::with_id(
+                app,
+                CHECK_FOR_UPDATES_ID,
+                text(app, "menu.check_for_updates"),
+                true,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app)?,
+            &about,
+        ],
+    )?;
+
+    let menu = Menu::with_items(app, &[&file, &view, &help])?;
+
+    // macOS takes the first submenu as the application menu, and copy and paste
+    // shortcuts only reach the webview through an Edit menu
+    #[cfg(target_os = "macos")]
+    {
+        let app_menu = Submenu::with_items(
+            app,
+            &name,
+            true,
+            &[
+                &PredefinedMenuItem::about(
+                    app,
+                    None,
+                    Some(crate::context_menu::about_metadata(app)),
+                )?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::services(app, None)?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::hide(app, None)?,
+                &PredefinedMenuItem::hide_others(app, None)?,
+                &PredefinedMenuItem::show_all(app, None)?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::quit(app, None)?,
+            ],
+        )?;
+        let edit = Submenu::with_items(
+            app,
+            text(app, "menu.edit"),
+            true,
+            &[
+                &PredefinedMenuItem::undo(app, None)?,
+                &PredefinedMenuItem::redo(app, None)?,
+                &PredefinedMenuItem::separator(app)?,
+                &PredefinedMenuItem::cut(app, None)?,
+                &PredefinedMenuItem::copy(app, None)?,
+                &PredefinedMenuItem::paste(app, None)?,
+                &PredefinedMenuItem::select_all(app, None)?,
+            ],
+        )?;
+
+        menu.prepend(&app_menu)?;
+        menu.insert(&edit, 2)?;
+    }
+
+    Ok(menu)
+}
+
+/// Shows a native dialog titled with the translation of `title_key`.
+fn show_message(app: &AppHandle, title_key: &str, message: String, kind: MessageDialogKind) {
+    app.dialog()
+        .message(message)
+        .title(text(app, title_key))
+        .kind(kind)
+        .show(|_| {});
+}
+
+async fn choose_backup_path(app: &AppHandle) -> Result<Option<PathBuf>, AppError> {
+    let file_name = format!(
+        "lotus-routine-backup-{}.tar.gz",
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Backup", &["gz"])
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+
+    match receiver.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| AppError::invalid_input(format!("Invalid backup path: {}", e))),
+        None => Ok(None),
+    }
+}
+
+async fn export_report(app: AppHandle) {
+    // The menu bar stays usable while the app is locked
+    if let Err(e) = crate::app_lock::ensure_unlocked(&app) {
+        show_message(
+            &app,
+            "dialog.export_failed",
+            e.message,
+            MessageDialogKind::Error,
+        );
+        return;
+    }
+
+    if let Err(e) = crate::report::generate_report(app.clone(), ReportRange::Month, None).await {
+        show_message(
+            &app,
+            "dialog.export_failed",
+            e.message,
+            MessageDialogKind::Error,
+        );
+    }
+}
+
+async fn back_up(app: AppHandle) {
+    if let Err(e) = crate::app_lock::ensure_unlocked(&app) {
+        show_message(
+            &app,
+            "dialog.backup_failed",
+            e.message,
+            MessageDialogKind::Error,
+        );
+        return;
+    }
+
+    let result = match choose_backup_path(&app).await {
+        Ok(Some(path)) => {
+            let path = path.to_string_lossy().into_owned();
+            crate::backup::export_backup(app.clone(), path, None).await
+        }
+        Ok(None) => return,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(manifest) => show_message(
+            &app,
+            "dialog.backup_complete",
+            text_with(
+                &app,
+                "dialog.backup_complete_message",
+                &[("count", &manifest.entry_count.to_string())],
+            ),
+            MessageDialogKind::Info,
+        ),
+        Err(e) => show_message(
+            &app,
+            "dialog.backup_failed",
+            e.message,
+            MessageDialogKind::Error,
+        ),
+    }
+}
+
+async fn check_for_updates(app: AppHandle) {
+    match crate::updates::check_for_update(app.clone()).await {
+        Ok(Some(update)) => {
+            crate::tray::show_main_window(&app);
+            if let Err(e) = app.emit(MENU_UPDATE_AVAILABLE_EVENT, &update) {
+                tracing::warn!("Failed to emit {}: {}", MENU_UPDATE_AVAILABLE_EVENT, e);
+            }
+        }
+        Ok(None) => show_message(
+            &app,
+            "dialog.no_updates",
+            text_with(
+                &app,
+                "dialog.no_updates_message",
+                &[("version", &app.package_info().version.to_string())],
+            ),
+            MessageDialogKind::Info,
+        ),
+        Err(e) => show_message(
+            &app,
+            "dialog.update_check_failed",
+            e.message,
+            MessageDialogKind::Error,
+        ),
+    }
+}
+
+fn set_zoom(app: &AppHandle, change: impl FnOnce(f64) -> f64) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+
+    let state = app.state::<ZoomState>();
+    let mut zoom = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    let updated = change(*zoom).clamp(MIN_ZOOM, MAX_ZOOM);
+
+    match window.set_zoom(updated) {
+        Ok(()) => *zoom = updated,
+        Err(e) => tracing::warn!("Failed to set zoom: {}", e),
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        EXPORT_REPORT_ID => {
+            tauri::async_runtime::spawn(export_report(app.clone()));
+        }
+        BACKUP_ID => {
+            tauri::async_runtime::spawn(back_up(app.clone()));
+        }
+        RELOAD_ID => {
+            if let Some(window) = app.get_webview_window("main") {
+                if let Err(e) = window.eval("window.location.reload()") {
+                    tracing::warn!("Failed to reload: {}", e);
+                }
+            }
+        }
+        ZOOM_IN_ID => set_zoom(app, |zoom| zoom + ZOOM_STEP),
+        ZOOM_OUT_ID => set_zoom(app, |zoom| zoom - ZOOM_STEP),
+        ZOOM_RESET_ID => set_zoom(app, |_| 1.0),
+        CHECK_FOR_UPDATES_ID => {
+            tauri::async_runtime::spawn(check_for_updates(app.clone()));
+        }
+        _ => {}
+    }
+}
+
+/// Rebuilds the menu bar, e.g. after the language of native strings changes.
+pub fn refresh(app: &AppHandle) {
+    match build_menu(app) {
+        Ok(menu) => {
+            if let Err(e) = app.set_menu(menu) {
+                tracing::warn!("Failed to update menu bar: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to build menu bar: {}", e),
+    }
+}
+
+/// Installs the application menu bar.
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    app.manage(ZoomState::default());
+    app.set_menu(build_menu(app)?)?;
+    app.on_menu_event(handle_menu_event);
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use base64::Engine;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::cache_store::with_store;
+use crate::error::AppError;
+
+/// Largest attachment accepted, so photos cannot fill up the data directory
+const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A stored file, named by the SHA-256 of its content so identical files are kept once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Hex-encoded SHA-256 of the content
+    pub hash: String,
+    pub content_type: String,
+    pub size: u64,
+    pub created_at: i64,
+    /// When the queued upload went through; `None` while pending or if none was queued
+    pub uploaded_at: Option<i64>,
+}
+
+/// An attachment with its content, base64-encoded
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentData {
+    #[serde(flatten)]
+    pub attachment: Attachment,
+    pub data: String,
+}
+
+/// Where to upload a new attachment once the website is reachable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentUpload {
+    pub url: String,
+    /// Defaults to `POST`
+    pub method: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+}
+
+pub fn attachments_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::active_data_dir(app)?.join("attachments"))
+}
+
+/// Path of the stored file, refusing anything but a SHA-256 hex digest so a hash from
+/// the frontend cannot point outside the directory.
+pub fn attachment_path(app: &AppHandle, hash: &str) -> Result<PathBuf, AppError> {
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(AppError::invalid_input(format!(
+            "Invalid attachment hash: {}",
+            hash
+        )));
+    }
+
+    Ok(attachments_dir(app)?.join(hash.to_ascii_lowercase()))
+}
+
+/// Decodes plain base64 or a `data:` URL, returning the URL's content type if it has one.
+fn decode(data: &str) -> Result<(Vec<u8>, Option<String>), AppError> {
+    let (content_type, data) = match data
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+    {
+        Some((content_type, data)) => (Some(content_type.to_string()), data),
+        None => (None, data),
+    };
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| AppError::invalid_input(format!("Invalid attachment data: {}", e)))?;
+    Ok((
+        bytes,
+        content_type.filter(|content_type| !content_type.is_empty()),
+    ))
+}
+
+/// Recognizes the image formats phones and browsers produce.
+fn sniff_content_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && &bytes[8..11] == b"hei" {
+        "image/heic"
+    } else {
+        DEFAULT_CONTENT_TYPE
+    }
+}
+
+pub fn load(app: &AppHandle, hash: &str) -> Result<Option<Attachment>, AppError> {
+    with_store(app, |store| {
+        store
+            .connection()
+            .query_row(
+                "SELECT hash, content_type, size, created_at, uploaded_at
+                 FROM attachments WHERE hash = ?1",
+                params![hash.to_ascii_lowercase()],
+                |row| {
+                    Ok(Attachment {
+                        hash: row.get(0)?,
+                        content_type: row.get(1)?,
+                        size: row.get(2)?,
+                        created_at: row.get(3)?,
+                        uploaded_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read attachment: {}", e))
+    })
+    .map_err(AppError::storage)
+}
+
+/// Whether the attachment's metadata and file are both still present.
+pub fn exists(app: &AppHandle, hash: &str) -> bool {
+    let stored = load(app, hash).is_ok_and(|attachment| attachment.is_some());
+    stored && attachment_path(app, hash).is_ok_and(|path| path.is_file())
+}
+
+/// The attachment and its content, for uploading.
+pub fn read(app: &AppHandle, hash: &str) -> Result<(Attachment, Vec<u8>), AppError> {
+    let attachment = load(app, hash)?
+        .ok_or_else(|| AppError::not_found(format!("Unknown attachment: {}", hash)))?;
+    let data = std::fs::read(attachment_path(app, hash)?)
+        .map_err(|e| AppError::storage(format!("Failed to read attachment: {}", e)))?;
+    Ok((attachment, data))
+}
+
+/// Stores `bytes` under their hash and returns the attachment. Storing the same content
+/// twice keeps a single copy.
+pub fn save(app: &AppHandle, bytes: &[u8], content_type: &str) -> Result<Attachment, AppError> {
+    if bytes.is_empty() {
+        return Err(AppError::invalid_input("Attachment is empty"));
+    }
+    if bytes.len() > MAX_ATTACHMENT_BYTES {
+        return Err(AppError::invalid_input(format!(
+            "Attachment is too large (limit is {} MB)",
+            MAX_ATTACHMENT_BYTES / 1024 / 1024
+        )));
+    }
+
+    let hash: String = Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let path = attachment_path(app, &hash)?;
+    if !path.is_file() {
+        let dir = attachments_dir(app)?;
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            AppError::storage(format!("Failed to create attachments directory: {}", e))
+        })?;
+
+        // A crash never leaves a partial file under the final hash
+        crate::storage::write_atomic(&path, bytes)
+            .map_err(|e| AppError::storage(format!("Failed to write attachment: {}", e)))?;
+    }
+
+    with_store(app, |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT OR IGNORE INTO attachments (hash, content_type, size, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    hash,
+                    content_type,
+                    bytes.len() as u64,
+                    chrono::Utc::now().timestamp()
+                ],
+            )
+            .map_err(|e| format!("Failed to record attachment: {}", e))?;
+        Ok(())
+    })
+    .map_err(AppError::storage)?;
+
+    load(app, &hash)?.ok_or_else(|| AppError::storage("Attachment disappeared after saving"))
+}
+
+/// Queues the upload of a stored attachment and starts flushing the queue.
+pub fn queue_upload(
+    app: &AppHandle,
+    hash: &str,
+    upload: &AttachmentUpload,
+) -> Result<(), AppError> {
+    crate::outbox::enqueue_upload(
+        app,
+        upload.method.as_deref().unwrap_or("POST"),
+        &upload.url,
+        upload.headers.as_ref(),
+        hash,
+    )?;
+    crate::outbox::schedule_flush(app);
+    Ok(())
+}
+
+/// Records that the queued upload of an attachment went through.
+pub fn mark_uploaded(app: &AppHandle, hash: &str) {
+    let result = with_store(app, |store| {
+        store
+            .connection()
+            .execute(
+                "UPDATE attachments SET uploaded_at = ?2 WHERE hash = ?1",
+                params![hash.to_ascii_lowercase(), chrono::Utc::now().timestamp()],
+            )
+            .map_err(|e| format!("Failed to update attachment: {}", e))
+    });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to mark attachment {} as uploaded: {}", hash, e);
+    }
+}
+
+/// Stores a file (base64 or a `data:` URL), e.g. a progress photo for a check-in, and
+/// returns it with its hash. Storing the same content twice keeps a single copy.
+///
+/// With `upload`, the file is also queued for upload and sent as the raw request body
+/// once the website is reachable.
+#[tauri::command]
+pub async fn store_attachment(
+    app: AppHandle,
+    data: String,
+    content_type: Option<String>,
+    upload: Option<AttachmentUpload>,
+) -> Result<Attachment, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    crate::storage::run_blocking(move || {
+        let (bytes, data_url_type) = decode(&data)?;
+        let content_type = content_type
+            .or(data_url_type)
+            .unwrap_or_else(|| sniff_content_type(&bytes).to_string());
+
+        let attachment = save(&app, &bytes, &content_type)?;
+        if let Some(upload) = upload {
+            queue_upload(&app, &attachment.hash, &upload)?;
+        }
+
+        Ok(attachment)
+    })
+    .await
+}
+
+/// Returns a stored attachment with its content, or `None` if there is none with that hash.
+#[tauri::command]
+pub async fn get_attachment(
+    app: AppHandle,
+    hash: String,
+) -> Result<Option<AttachmentData>, AppError> {
+    // Guest mode never shows the real profile's files
+    if crate::demo::is_demo(&app) {
+        return Ok(None);
+    }
+
+    crate::storage::run_blocking(move || {
+        let Some(attachment) = load(&app, &hash)? else {
+            return Ok(None);
+        };
+        let data = match std::fs::read(attachment_path(&app, &hash)?) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(AppError::storage(format!(
+                    "Failed to read attachment: {}",
+                    e
+                )))
+            }
+        };
+
+        Ok(Some(AttachmentData {
+            attachment,
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        }))
+    })
+    .await
+}
+
+/// Deletes an attachment along with any upload of it still queued. Returns whether
+/// there was one.
+#[tauri::command]
+pub async fn delete_attachment(app: AppHandle, hash: String) -> Result<bool, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    crate::storage::run_blocking(move || {
+        let path = attachment_path(&app, &hash)?;
+        let hash = hash.to_ascii_lowercase();
+
+        crate::outbox::remove_uploads(&app, &hash)?;
+        crate::thumbnails::remove(&app, &hash)?;
+        let removed = with_store(&app, |store| {
+            store
+                .connection()
+                .execute("DELETE FROM attachments WHERE hash = ?1", params![hash])
+                .map_err(|e| format!("Failed to delete attachment: {}", e))
+        })
+        .map_err(AppError::storage)?;
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(removed > 0),
+            Err(e) => Err(AppError::storage(format!(
+                "Failed to delete attachment file: {}",
+                e
+            ))),
+        }
+    })
+    .await
+}
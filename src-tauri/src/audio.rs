@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// Sounds synthesized in the app, so they work without any files
+pub const BUILT_IN_SOUNDS: [&str; 3] = ["chime", "bell", "beep"];
+
+/// Sound name that plays nothing, e.g. for a reminder that should stay quiet
+pub const NO_SOUND: &str = "none";
+
+/// Audio formats accepted for user-provided sounds
+const SOUND_EXTENSIONS: [&str; 4] = ["wav", "mp3", "ogg", "flac"];
+
+/// Notification sounds are short; anything larger is almost certainly a song
+const MAX_SOUND_BYTES: u64 = 5 * 1024 * 1024;
+
+const MAX_NAME_LENGTH: usize = 64;
+
+/// How sounds are played, persisted with the other settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// Playback volume from 0.0 to 1.0
+    pub volume: f32,
+    pub muted: bool,
+    /// Played when a focus session runs out
+    pub timer_sound: String,
+    /// Played for reminders that do not pick their own sound
+    pub reminder_sound: String,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.8,
+            muted: false,
+            timer_sound: "chime".to_string(),
+            reminder_sound: "bell".to_string(),
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn validate(&self, app: &AppHandle) -> Result<(), AppError> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(AppError::invalid_input("Volume must be between 0 and 1"));
+        }
+        check_sound(app, &self.timer_sound)?;
+        check_sound(app, &self.reminder_sound)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoundInfo {
+    pub name: String,
+    /// Whether the sound ships with the app rather than being imported by the user
+    pub built_in: bool,
+}
+
+// Only read when playing, which mobile does not support
+#[cfg_attr(mobile, allow(dead_code))]
+enum Sound {
+    BuiltIn(&'static str),
+    File(PathBuf),
+}
+
+fn sounds_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::active_data_dir(app)?.join("sounds"))
+}
+
+/// Imported sounds are named after their file stem, which is checked here so a name from
+/// the frontend cannot point outside the directory.
+fn check_name(name: &str) -> Result<(), AppError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '));
+    if !valid {
+        return Err(AppError::invalid_input(format!(
+            "Sound names may only contain letters, digits, spaces, '-' and '_' (up to {} characters)",
+            MAX_NAME_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+fn sound_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .filter(|extension| SOUND_EXTENSIONS.contains(&extension.as_str()))
+}
+
+fn find_custom(app: &AppHandle, name: &str) -> Result<Option<PathBuf>, AppError> {
+    check_name(name)?;
+    let dir = sounds_dir(app)?;
+
+    Ok(SOUND_EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{}.{}", name, extension)))
+        .find(|path| path.is_file()))
+}
+
+fn resolve(app: &AppHandle, name: &str) -> Result<Sound, AppError> {
+    if let Some(built_in) = BUILT_IN_SOUNDS.iter().find(|built_in| **built_in == name) {
+        return Ok(Sound::BuiltIn(built_in));
+    }
+
+    find_custom(app, name)?
+        .map(Sound::File)
+        .ok_or_else(|| AppError::not_found(format!("Unknown sound: {}", name)))
+}
+
+/// Fails unless `name` is a built-in sound, an imported one, or [`NO_SOUND`].
+pub fn check_sound(app: &AppHandle, name: &str) -> Result<(), AppError> {
+    if name == NO_SOUND {
+        return Ok(());
+    }
+
+    resolve(app, name).map(drop)
+}
+
+/// Plays a sound at the configured volume. Returns immediately; nothing is played while
+/// muted or for [`NO_SOUND`].
+pub fn play(app: &AppHandle, name: &str) -> Result<(), AppError> {
+    let settings = crate::settings::current(app).sound;
+    if settings.muted || name == NO_SOUND {
+        return Ok(());
+    }
+
+    output::play(resolve(app, name)?, settings.volume.clamp(0.0, 1.0))
+}
+
+/// Plays the sound for a timer or reminder, logging instead of failing the caller. On
+/// mobile the notification's system sound is used instead.
+pub fn play_alert(app: &AppHandle, name: &str) {
+    if cfg!(mobile) {
+        return;
+    }
+
+    if let Err(e) = play(app, name) {
+        tracing::warn!("Failed to play sound '{}': {}", name, e);
+    }
+}
+
+#[cfg(desktop)]
+mod output {
+    use rodio::source::SineWave;
+    use rodio::{Decoder, OutputStreamBuilder, Sink, Source};
+    use std::time::Duration;
+
+    use super::Sound;
+    use crate::error::AppError;
+
+    /// Notes of a built-in sound as (frequency in Hz, length in ms); 0 Hz is a pause
+    fn notes(name: &str) -> &'static [(f32, u64)] {
+        match name {
+            "chime" => &[(784.0, 140), (1046.5, 320)],
+            "bell" => &[(659.3, 900)],
+            _ => &[(1000.0, 120), (0.0, 80), (1000.0, 120)],
+        }
+    }
+
+    /// Plays on a thread of its own, since an output stream cannot move between threads
+    /// and has to stay open until the sound ends.
+    pub fn play(sound: Sound, volume: f32) -> Result<(), AppError> {
+        // Decoded up front so an unreadable file is reported to the caller
+        let decoder = match &sound {
+            Sound::File(path) => Some(
+                std::fs::File::open(path)
+                    .map_err(|e| AppError::storage(format!("Failed to open sound: {}", e)))
+                    .and_then(|file| {
+                        Decoder::try_from(file).map_err(|e| {
+                            AppError::corrupt_data(format!("Failed to decode sound: {}", e))
+                        })
+                    })?,
+            ),
+            Sound::BuiltIn(_) => None,
+        };
+
+        let (opened, result) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name("sound".to_string())
+            .spawn(move || {
+                let mut stream = match OutputStreamBuilder::open_default_stream() {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        let _ = opened.send(Err(AppError::unsupported(format!(
+                            "Failed to open audio output: {}",
+                            e
+                        ))));
+                        return;
+                    }
+                };
+                stream.log_on_drop(false);
+
+                let sink = Sink::connect_new(stream.mixer());
+                sink.set_volume(volume);
+                match (decoder, sound) {
+                    (Some(decoder), _) => sink.append(decoder),
+                    (None, Sound::BuiltIn(name)) => {
+                        for &(frequency, millis) in notes(name) {
+                            let length = Duration::from_millis(millis);
+                            sink.append(
+                                SineWave::new(frequency)
+                                    .take_duration(length)
+                                    .fade_out(length)
+                                    .amplify(0.4),
+                            );
+                        }
+                    }
+                    (None, Sound::File(_)) => {}
+                }
+
+                let _ = opened.send(Ok(()));
+                sink.sleep_until_end();
+            })
+            .map_err(|e| AppError::internal(format!("Failed to start playback: {}", e)))?;
+
+        result
+            .recv()
+            .map_err(|_| AppError::internal("Playback stopped unexpectedly"))?
+    }
+
+    /// Whether the file holds audio that can be played.
+    pub fn check_file(path: &std::path::Path) -> Result<(), AppError> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| AppError::storage(format!("Failed to open sound: {}", e)))?;
+        Decoder::try_from(file)
+            .map(drop)
+            .map_err(|e| AppError::invalid_input(format!("Unsupported sound file: {}", e)))
+    }
+}
+
+#[cfg(mobile)]
+mod output {
+    use super::Sound;
+    use crate::error::AppError;
+
+    pub fn play(_sound: Sound, _volume: f32) -> Result<(), AppError> {
+        Err(AppError::unsupported(
+            "Sound playback is not supported on this platform",
+        ))
+    }
+
+    pub fn check_file(_path: &std::path::Path) -> Result<(), AppError> {
+        Ok(())
+    }
+}
+
+/// Plays a built-in or imported sound, e.g. to preview it in settings.
+#[tauri::command]
+pub async fn play_sound(app: AppHandle, name: String) -> Result<(), AppError> {
+    play(&app, &name)
+}
+
+/// Lists the built-in sounds followed by the imported ones.
+#[tauri::command]
+pub async fn list_sounds(app: AppHandle) -> Result<Vec<SoundInfo>, AppError> {
+    let mut sounds: Vec<SoundInfo> = BUILT_IN_SOUNDS
+        .iter()
+        .map(|name| SoundInfo {
+            name: name.to_string(),
+            built_in: true,
+        })
+        .collect();
+
+    let entries = match std::fs::read_dir(sounds_dir(&app)?) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(sounds),
+        Err(e) => return Err(AppError::storage(format!("Failed to read sounds: {}", e))),
+    };
+
+    let mut imported: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| sound_extension(path).is_some())
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    imported.sort();
+    sounds.extend(imported.into_iter().map(|name| SoundInfo {
+        name,
+        built_in: false,
+    }));
+
+    Ok(sounds)
+}
+
+/// Copies a WAV, MP3, Ogg Vorbis or FLAC file into the profile's sounds, named `name` or
+/// after the file. An imported sound with the same name is replaced.
+#[tauri::command]
+pub async fn import_sound(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<SoundInfo, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let source = Path::new(&path);
+    let extension = sound_extension(source)
+        .ok_or_else(|| {
+            AppError::invalid_input("Only .wav, .mp3, .ogg and .flac files can be imported")
+        })?;
+    let name = match name {
+        Some(name) => name.trim().to_string(),
+        None => source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().trim().to_string())
+            .unwrap_or_default(),
+    };
+    check_name(&name)?;
+    if BUILT_IN_SOUNDS.contains(&name.as_str()) || name == NO_SOUND {
+        return Err(AppError::invalid_input(format!(
+            "'{}' is reserved for a built-in sound",
+            name
+        )));
+    }
+
+    let size = std::fs::metadata(source)
+        .map_err(|e| AppError::storage(format!("Failed to read sound: {}", e)))?
+        .len();
+    if size > MAX_SOUND_BYTES {
+        return Err(AppError::invalid_input(format!(
+            "Sound is too large (limit is {} MB)",
+            MAX_SOUND_BYTES / 1024 / 1024
+        )));
+    }
+    output::check_file(source)?;
+
+    let dir = sounds_dir(&app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::storage(format!("Failed to create sounds directory: {}", e)))?;
+    if let Some(existing) = find_custom(&app, &name)? {
+        std::fs::remove_file(existing)
+            .map_err(|e| AppError::storage(format!("Failed to replace sound: {}", e)))?;
+    }
+    std::fs::copy(source, dir.join(format!("{}.{}", name, extension)))
+        .map_err(|e| AppError::storage(format!("Failed to import sound: {}", e)))?;
+
+    Ok(SoundInfo {
+        name,
+        built_in: false,
+    })
+}
+
+/// Deletes an imported sound. Returns whether there was one; timers and reminders still
+/// set to it stay quiet until another sound is picked.
+#[tauri::command]
+pub async fn delete_sound(app: AppHandle, name: String) -> Result<bool, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    match find_custom(&app, &name)? {
+        Some(path) => std::fs::remove_file(path)
+            .map(|_| true)
+            .map_err(|e| AppError::storage(format!("Failed to delete sound: {}", e))),
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub async fn get_sound_settings(
+    state: State<'_, SettingsState>,
+) -> Result<SoundSettings, AppError> {
+    Ok(state.get().sound)
+}
+
+#[tauri::command]
+pub async fn set_sound_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: SoundSettings,
+) -> Result<(), AppError> {
+    settings.validate(&app)?;
+
+    state.update(&app, |current| current.sound = settings)?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::middleware::Middleware;
+use crate::profiles::scoped_secret_key;
+use crate::secrets::{self, SESSION_TOKEN_KEY};
+
+/// Hosts that may receive the session token and run the native sign-in. The configured
+/// website host does not count: the webview can change the base URL, which would hand the
+/// token to whatever host it names. Everything else is sent without credentials.
+const AUTH_ALLOWED_HOSTS: &[&str] = &["accounted.th3void.com"];
+
+/// Attaches the keychain-held session token to requests for allowlisted hosts,
+/// so the frontend never has to pass the Authorization header over IPC.
+pub struct AuthHeaderMiddleware {
+    app: AppHandle,
+}
+
+impl AuthHeaderMiddleware {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl Middleware for AuthHeaderMiddleware {
+    fn name(&self) -> &str {
+        "auth-header"
+    }
+
+    fn on_request(&self, request: &mut reqwest::Request) -> Result<(), String> {
+        // An explicit header from the caller always wins
+        if request.headers().contains_key(AUTHORIZATION) {
+            return Ok(());
+        }
+
+        if let Some(value) = session_authorization(&self.app, request.url())? {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `url` is a secure URL on one of the [`AUTH_ALLOWED_HOSTS`].
+pub fn is_trusted_url(url: &reqwest::Url) -> bool {
+    matches!(url.scheme(), "https" | "wss")
+        && url
+            .host_str()
+            .is_some_and(|host| AUTH_ALLOWED_HOSTS.contains(&host))
+}
+
+/// `Authorization` value carrying the session token, if `url` may receive it.
+pub fn session_authorization(
+    app: &AppHandle,
+    url: &reqwest::Url,
+) -> Result<Option<HeaderValue>, String> {
+    if !is_trusted_url(url) {
+        return Ok(None);
+    }
+
+    match secrets::read_secret(&scoped_secret_key(app, SESSION_TOKEN_KEY)) {
+        Ok(Some(token)) => {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|e| format!("Invalid session token: {}", e))?;
+            value.set_sensitive(true);
+            Ok(Some(value))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => {
+            tracing::warn!("Failed to read session token: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Stores (or clears, when `None`) the session token used for automatic header injection.
+/// Either way, the refresh token of a native sign-in is forgotten, so it cannot replace
+/// this token later.
+#[tauri::command]
+pub async fn set_session_token(app: AppHandle, token: Option<String>) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    crate::oauth::forget(&app).await?;
+    let key = scoped_secret_key(&app, SESSION_TOKEN_KEY);
+
+    match token {
+        Some(token) => secrets::write_secret(&key, &token),
+        None => secrets::remove_secret(&key),
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::error::AppError;
+
+const AUTOSTART_FILE: &str = "autostart";
+
+/// Passed by the login item so a launch at login can be told apart from one by the user
+const AUTOSTART_ARG: &str = "--autostart";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AutostartConfig {
+    /// Keeps the main window hidden when launched at login, leaving only the tray icon
+    start_minimized: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    pub start_minimized: bool,
+}
+
+/// Login item plugin (a launch agent on macOS). The entry it creates launches the app with [`AUTOSTART_ARG`].
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_autostart::Builder::new()
+        .arg(AUTOSTART_ARG)
+        .build()
+}
+
+/// Hides the main window when the app was started at login with "start minimized" on.
+/// The reminder scheduler and the tray keep running either way.
+pub fn apply_launch_visibility(app: &AppHandle) {
+    if !std::env::args().any(|arg| arg == AUTOSTART_ARG) {
+        return;
+    }
+
+    let config: AutostartConfig = crate::read_state_file(app, AUTOSTART_FILE).unwrap_or_default();
+    if !config.start_minimized {
+        return;
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.hide() {
+            tracing::warn!("Failed to hide main window at login: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_autostart(app: AppHandle) -> Result<AutostartStatus, AppError> {
+    let enabled = app
+        .autolaunch()
+        .is_enabled()
+        .map_err(|e| AppError::storage(format!("Failed to read autostart state: {}", e)))?;
+    let config: AutostartConfig = crate::read_state_file_blocking(&app, AUTOSTART_FILE).await?;
+
+    Ok(AutostartStatus {
+        enabled,
+        start_minimized: config.start_minimized,
+    })
+}
+
+/// Turns launching at login on or off. `start_minimized` is kept as is when not given.
+#[tauri::command]
+pub async fn set_autostart(
+    app: AppHandle,
+    enabled: bool,
+    start_minimized: Option<bool>,
+) -> Result<AutostartStatus, AppError> {
+    let autolaunch = app.autolaunch();
+    if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    }
+    .map_err(|e| AppError::storage(format!("Failed to update autostart: {}", e)))?;
+
+    let mut config: AutostartConfig = crate::read_state_file_blocking(&app, AUTOSTART_FILE).await?;
+    if let Some(start_minimized) = start_minimized {
+        config.start_minimized = start_minimized;
+        crate::write_state_file_blocking(&app, AUTOSTART_FILE, &config).await?;
+    }
+
+    Ok(AutostartStatus {
+        enabled,
+        start_minimized: config.start_minimized,
+    })
+}
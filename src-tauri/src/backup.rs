@@ -0,0 +1,412 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use tauri::AppHandle;
+
+use crate::cache_store::{decode_payload, encode_payload, with_store, with_store_mut_blocking};
+use crate::error::AppError;
+use crate::outbox::PendingMutation;
+use crate::progress::ProgressReporter;
+use crate::settings::Settings;
+
+/// Bumped whenever the archive layout changes incompatibly
+const BACKUP_SCHEMA_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CACHE_FILE: &str = "cache.json";
+const POLICIES_FILE: &str = "cache-policies.json";
+const MUTATIONS_FILE: &str = "mutations.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub schema_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub entry_count: u64,
+    pub mutation_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupEntry {
+    /// Account the entry was cached for; backups from before scopes existed only hold
+    /// shared entries
+    #[serde(default)]
+    scope: String,
+    key: String,
+    data: serde_json::Value,
+    timestamp: i64,
+    expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Entries written; keys already cached with a newer copy are left alone
+    pub entries_imported: u64,
+    pub entries_skipped: u64,
+    pub mutations_imported: u64,
+}
+
+/// Every account's cached entries. Entries that no longer read back are quarantined and
+/// left out rather than failing the backup.
+fn cached_entries(app: &AppHandle) -> Result<Vec<BackupEntry>, AppError> {
+    with_store(app, |store| {
+        let mut statement = store
+            .connection()
+            .prepare(
+                "SELECT key, data, compressed, timestamp, expires_at, checksum, scope
+                 FROM cache_entries ORDER BY scope, key",
+            )
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        let rows = statement
+            .query_map([], |row| {
+                let key: String = row.get(0)?;
+                let scope: String = row.get(6)?;
+                let entry = (|| {
+                    let checksum: Option<String> = row.get(5)?;
+                    Ok((
+                        (row.get(1)?, row.get(2)?, checksum),
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                })()
+                .map_err(|e: rusqlite::Error| format!("Failed to read cache entry: {}", e))
+                .and_then(
+                    |((data, compressed, checksum), timestamp, expires_at)| {
+                        Ok(BackupEntry {
+                            data: decode_payload(data, compressed, checksum.as_deref())?,
+                            scope: scope.clone(),
+                            key: key.clone(),
+                            timestamp,
+                            expires_at,
+                        })
+                    },
+                );
+                Ok((scope, key, entry))
+            })
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        let mut entries = Vec::new();
+        let mut corrupted = Vec::new();
+        for row in rows {
+            match row.map_err(|e| format!("Failed to read cache entries: {}", e))? {
+                (_, _, Ok(entry)) => entries.push(entry),
+                (scope, key, Err(e)) => corrupted.push((scope, key, e)),
+            }
+        }
+        drop(statement);
+
+        for (scope, key, reason) in &corrupted {
+            store.quarantine_in(scope, key, reason)?;
+        }
+        if !corrupted.is_empty() {
+            tracing::warn!(
+                "Left {} corrupted cache entries out of the backup",
+                corrupted.len()
+            );
+        }
+
+        Ok(entries)
+    })
+    .map_err(AppError::storage)
+}
+
+/// Queued mutations without their credentials, as backups may be shared or restored on
+/// another device
+fn exported_mutations(app: &AppHandle) -> Result<Vec<PendingMutation>, AppError> {
+    let mut mutations = with_store(app, crate::outbox::read_pending).map_err(AppError::storage)?;
+    for mutation in &mut mutations {
+        if let Some(headers) = &mut mutation.headers {
+            crate::outbox::strip_credentials(headers);
+        }
+    }
+    Ok(mutations)
+}
+
+fn cache_policies(app: &AppHandle) -> Result<HashMap<String, i64>, AppError> {
+    with_store(app, |store| {
+        let mut statement = store
+            .connection()
+            .prepare("SELECT key, ttl_secs FROM cache_policies")
+            .map_err(|e| format!("Failed to query cache policies: {}", e))?;
+
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query cache policies: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read cache policies: {}", e))
+    })
+    .map_err(AppError::storage)
+}
+
+fn append_json<W: std::io::Write, T: Serialize>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+) -> Result<(), AppError> {
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|e| AppError::internal(format!("Failed to serialize {}: {}", name, e)))?;
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    header.set_cksum();
+
+    archive
+        .append_data(&mut header, name, bytes.as_slice())
+        .map_err(|e| AppError::storage(format!("Failed to write {} to backup: {}", name, e)))
+}
+
+fn read_archive(path: &str) -> Result<HashMap<String, Vec<u8>>, AppError> {
+    let file =
+        File::open(path).map_err(|e| AppError::storage(format!("Failed to open backup: {}", e)))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut files = HashMap::new();
+
+    for entry in archive
+        .entries()
+        .map_err(|e| AppError::corrupt_data(format!("Failed to read backup: {}", e)))?
+    {
+        let mut entry =
+            entry.map_err(|e| AppError::corrupt_data(format!("Failed to read backup: {}", e)))?;
+        let name = entry
+            .path()
+            .map_err(|e| AppError::corrupt_data(format!("Failed to read backup: {}", e)))?
+            .to_string_lossy()
+            .to_string();
+
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).map_err(|e| {
+            AppError::corrupt_data(format!("Failed to read {} from backup: {}", name, e))
+        })?;
+        files.insert(name, bytes);
+    }
+
+    Ok(files)
+}
+
+fn parse_file<T: serde::de::DeserializeOwned>(
+    files: &HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<Option<T>, AppError> {
+    files
+        .get(name)
+        .map(|bytes| {
+            serde_json::from_slice(bytes)
+                .map_err(|e| AppError::corrupt_data(format!("Failed to parse {}: {}", name, e)))
+        })
+        .transpose()
+}
+
+fn check_manifest(files: &HashMap<String, Vec<u8>>) -> Result<BackupManifest, AppError> {
+    let manifest: BackupManifest = parse_file(files, MANIFEST_FILE)?
+        .ok_or_else(|| AppError::corrupt_data("Backup is missing its manifest"))?;
+    if manifest.schema_version > BACKUP_SCHEMA_VERSION {
+        return Err(AppError::unsupported(format!(
+            "Backup was made by a newer version of the app (schema {})",
+            manifest.schema_version
+        )));
+    }
+
+    Ok(manifest)
+}
+
+/// Reads the manifest of a backup without restoring it, failing for backups this version
+/// cannot restore.
+pub fn read_manifest(path: &str) -> Result<BackupManifest, AppError> {
+    check_manifest(&read_archive(path)?)
+}
+
+fn export(
+    app: &AppHandle,
+    path: &str,
+    progress: &ProgressReporter,
+) -> Result<BackupManifest, AppError> {
+    progress.report("collecting", 0, None);
+    let entries = cached_entries(app)?;
+    let policies = cache_policies(app)?;
+    let mutations = exported_mutations(app)?;
+    let settings = crate::settings::current(app);
+
+    let manifest = BackupManifest {
+        schema_version: BACKUP_SCHEMA_VERSION,
+        app_version: app.package_info().version.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        entry_count: entries.len() as u64,
+        mutation_count: mutations.len() as u64,
+    };
+
+    let file = File::create(path)
+        .map_err(|e| AppError::storage(format!("Failed to create backup: {}", e)))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    progress.report("writing", 0, Some(5));
+    append_json(&mut archive, MANIFEST_FILE, &manifest)?;
+    append_json(&mut archive, CACHE_FILE, &entries)?;
+    append_json(&mut archive, POLICIES_FILE, &policies)?;
+    append_json(&mut archive, MUTATIONS_FILE, &mutations)?;
+    append_json(&mut archive, SETTINGS_FILE, &settings)?;
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| AppError::storage(format!("Failed to finish backup: {}", e)))?;
+
+    progress.finish(
+        "writing",
+        5,
+        Some(5),
+        Some(format!("Backed up {} cached entries", manifest.entry_count)),
+    );
+
+    Ok(manifest)
+}
+
+/// Merges the archive's cache and queue into the store, returning the settings it holds
+/// for the caller to restore.
+fn import(
+    app: &AppHandle,
+    path: &str,
+    progress: &ProgressReporter,
+) -> Result<(ImportSummary, Option<Settings>), AppError> {
+    progress.report("reading", 0, None);
+    let files = read_archive(path)?;
+    check_manifest(&files)?;
+
+    let entries: Vec<BackupEntry> = parse_file(&files, CACHE_FILE)?.unwrap_or_default();
+    let policies: HashMap<String, i64> = parse_file(&files, POLICIES_FILE)?.unwrap_or_default();
+    let mutations: Vec<PendingMutation> = parse_file(&files, MUTATIONS_FILE)?.unwrap_or_default();
+    let settings: Option<Settings> = parse_file(&files, SETTINGS_FILE)?;
+
+    let total = entries.len() as u64;
+    progress.report("importing", 0, Some(total));
+
+    let mut summary = with_store(app, |store| {
+        let tx = store
+            .connection()
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start backup import: {}", e))?;
+
+        let mut summary = ImportSummary::default();
+        for entry in &entries {
+            let payload = encode_payload(&entry.data)?;
+
+            let changed = tx
+                .execute(
+                    "INSERT INTO cache_entries
+                        (key, data, timestamp, expires_at, size, last_accessed, compressed, checksum,
+                         scope, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?3, ?6, ?7, ?8, ?3)
+                     ON CONFLICT(scope, key) DO UPDATE SET
+                        data = excluded.data,
+                        timestamp = excluded.timestamp,
+                        expires_at = excluded.expires_at,
+                        size = excluded.size,
+                        compressed = excluded.compressed,
+                        checksum = excluded.checksum,
+                        etag = NULL,
+                        last_modified = NULL
+                     WHERE excluded.timestamp > cache_entries.timestamp",
+                    params![
+                        entry.key,
+                        payload.value,
+                        entry.timestamp,
+                        entry.expires_at,
+                        payload.size,
+                        payload.compressed,
+                        payload.checksum,
+                        entry.scope
+                    ],
+                )
+                .map_err(|e| format!("Failed to import '{}': {}", entry.key, e))?;
+
+            if changed > 0 {
+                summary.entries_imported += 1;
+            } else {
+                summary.entries_skipped += 1;
+            }
+        }
+
+        for (key, ttl_secs) in &policies {
+            tx.execute(
+                "INSERT OR REPLACE INTO cache_policies (key, ttl_secs) VALUES (?1, ?2)",
+                params![key, ttl_secs],
+            )
+            .map_err(|e| format!("Failed to import cache policy '{}': {}", key, e))?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit backup import: {}", e))?;
+        store.forget_documents();
+        Ok(summary)
+    })
+    .map_err(AppError::storage)?;
+
+    summary.mutations_imported = crate::outbox::restore(app, &mutations)?;
+
+    Ok((summary, settings))
+}
+
+/// Writes every cached entry, cache policy, queued mutation, and the settings of the
+/// active profile to a gzipped tar archive at `path`.
+#[tauri::command]
+pub async fn export_backup(
+    app: AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<BackupManifest, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    let progress = ProgressReporter::new(&app, operation_id);
+
+    crate::storage::run_blocking(move || export(&app, &path, &progress)).await
+}
+
+/// Restores a backup made by [`export_backup`] into the active profile.
+///
+/// Cached entries are merged, keeping whichever copy of a key is newer; queued mutations
+/// are appended after any already pending; settings are replaced, except for the website
+/// address, proxy and certificate pinning.
+#[tauri::command]
+pub async fn import_backup(
+    app: AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<ImportSummary, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    let progress = ProgressReporter::new(&app, operation_id);
+
+    let (handle, reporter) = (app.clone(), progress.clone());
+    let (summary, settings) =
+        crate::storage::run_blocking(move || import(&handle, &path, &reporter)).await?;
+
+    if let Some(settings) = settings {
+        crate::settings::restore(&app, settings).await?;
+    }
+
+    // Applies the (possibly imported) size limit to the merged cache
+    let max_bytes = crate::settings::current(&app).max_cache_bytes;
+    with_store_mut_blocking(&app, move |store| store.set_max_bytes(max_bytes))
+        .await
+        .map_err(AppError::storage)?;
+
+    let total = summary.entries_imported + summary.entries_skipped;
+    progress.finish(
+        "importing",
+        total,
+        Some(total),
+        Some(format!(
+            "Imported {} cached entries and {} queued changes",
+            summary.entries_imported, summary.mutations_imported
+        )),
+    );
+
+    Ok(summary)
+}
@@ -0,0 +1,166 @@
+use chrono::Local;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::cache_store::with_store;
+use crate::error::AppError;
+
+/// How often the automatic count is recomputed, so it resets when the day changes
+const BADGE_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Badge shown on the dock or taskbar icon
+#[derive(Default)]
+pub struct BadgeState {
+    /// Count set through [`set_badge_count`], which takes over from the automatic one
+    manual: Mutex<Option<u32>>,
+    /// Count currently on the icon, to skip redundant platform calls
+    shown: Mutex<Option<u32>>,
+}
+
+/// Habits not yet completed today. Guest mode has no habits of its own.
+fn pending_today(app: &AppHandle) -> Result<u32, String> {
+    if crate::demo::is_demo(app) {
+        return Ok(0);
+    }
+
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    let pending = with_store(app, |store| crate::habits::read_habits(store, false))?
+        .iter()
+        .filter(|habit| !habit.completions.contains(&today))
+        .count();
+
+    Ok(pending as u32)
+}
+
+/// Draws a red disc with the count in white, as the Windows taskbar has no numeric badge.
+#[cfg(windows)]
+fn overlay_icon(count: u32) -> tauri::image::Image<'static> {
+    const SIZE: usize = 32;
+    /// 3x5 glyphs for the digits and `+`, one row per entry, most significant bit left
+    const GLYPHS: [[u8; 5]; 11] = [
+        [0b111, 0b101, 0b101, 0b101, 0b111],
+        [0b010, 0b110, 0b010, 0b010, 0b111],
+        [0b111, 0b001, 0b111, 0b100, 0b111],
+        [0b111, 0b001, 0b111, 0b001, 0b111],
+        [0b101, 0b101, 0b111, 0b001, 0b001],
+        [0b111, 0b100, 0b111, 0b001, 0b111],
+        [0b111, 0b100, 0b111, 0b101, 0b111],
+        [0b111, 0b001, 0b001, 0b001, 0b001],
+        [0b111, 0b101, 0b111, 0b101, 0b111],
+        [0b111, 0b101, 0b111, 0b001, 0b111],
+        [0b000, 0b010, 0b111, 0b010, 0b000],
+    ];
+
+    let mut rgba = vec![0u8; SIZE * SIZE * 4];
+    let center = (SIZE as f32 - 1.0) / 2.0;
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            if dx * dx + dy * dy <= center * center {
+                rgba[(y * SIZE + x) * 4..][..4].copy_from_slice(&[0xe5, 0x39, 0x35, 0xff]);
+            }
+        }
+    }
+
+    let glyphs: Vec<usize> = if count > 9 {
+        vec![9, 10]
+    } else {
+        vec![count as usize]
+    };
+    let scale = if glyphs.len() == 1 { 4 } else { 3 };
+    let width = glyphs.len() * 3 * scale + (glyphs.len() - 1) * scale;
+    let (left, top) = ((SIZE - width) / 2, (SIZE - 5 * scale) / 2);
+
+    for (i, glyph) in glyphs.iter().enumerate() {
+        let glyph_left = left + i * 4 * scale;
+        for (row, bits) in GLYPHS[*glyph].iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for y in top + row * scale..top + (row + 1) * scale {
+                    for x in glyph_left + column * scale..glyph_left + (column + 1) * scale {
+                        rgba[(y * SIZE + x) * 4..][..4].copy_from_slice(&[0xff; 4]);
+                    }
+                }
+            }
+        }
+    }
+
+    tauri::image::Image::new_owned(rgba, SIZE as u32, SIZE as u32)
+}
+
+fn show(app: &AppHandle, count: u32) -> Result<(), String> {
+    let state = app.state::<BadgeState>();
+    let mut shown = state.shown.lock().unwrap_or_else(|e| e.into_inner());
+    if *shown == Some(count) {
+        return Ok(());
+    }
+
+    // Android launchers only badge apps with notifications, so there is nothing to set
+    #[cfg(not(target_os = "android"))]
+    {
+        let window = app
+            .get_webview_window("main")
+            .ok_or_else(|| "Main window is not open".to_string())?;
+
+        #[cfg(windows)]
+        let result = window.set_overlay_icon((count > 0).then(|| overlay_icon(count)));
+        #[cfg(not(windows))]
+        let result = window.set_badge_count((count > 0).then_some(i64::from(count)));
+
+        result.map_err(|e| format!("Failed to set badge: {}", e))?;
+    }
+
+    *shown = Some(count);
+    Ok(())
+}
+
+/// Shows the manual count if one is set, otherwise the number of habits left today. The
+/// count is read on the blocking thread pool.
+pub fn refresh(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let manual = *app
+            .state::<BadgeState>()
+            .manual
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let result = match manual {
+            Some(count) => Ok(count),
+            None => pending_today(&app),
+        }
+        .and_then(|count| show(&app, count));
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to update badge: {}", e);
+        }
+    });
+}
+
+/// Starts the background task that keeps the automatic count current.
+pub fn spawn_updater(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(BADGE_REFRESH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            refresh(&app);
+        }
+    });
+}
+
+/// Shows `count` on the app icon (`0` clears it), or with `None` goes back to showing the
+/// number of habits left today.
+#[tauri::command]
+pub async fn set_badge_count(
+    app: AppHandle,
+    state: tauri::State<'_, BadgeState>,
+    count: Option<u32>,
+) -> Result<(), AppError> {
+    *state.manual.lock().unwrap_or_else(|e| e.into_inner()) = count;
+    refresh(&app);
+    Ok(())
+}
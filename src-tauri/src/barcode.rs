@@ -0,0 +1,289 @@
+use barcoders::sym::code128::Code128;
+use base64::Engine;
+use qrcode::{EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Size used when the caller does not pick one, in pixels
+const DEFAULT_SIZE: u32 = 256;
+
+const MAX_SIZE: u32 = 4096;
+
+/// Light modules around a QR code, as required by the specification
+const QR_QUIET_ZONE: usize = 4;
+
+/// Light modules on either side of a Code 128 barcode
+const CODE128_QUIET_ZONE: usize = 10;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeFormat {
+    Qr,
+    /// Linear barcode for printable ASCII, using code set B
+    Code128,
+}
+
+/// Share of a QR code that can be damaged and still be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCorrection {
+    /// About 7%
+    L,
+    /// About 15%
+    #[default]
+    M,
+    /// About 25%
+    Q,
+    /// About 30%
+    H,
+}
+
+impl From<ErrorCorrection> for EcLevel {
+    fn from(ecc: ErrorCorrection) -> Self {
+        match ecc {
+            ErrorCorrection::L => EcLevel::L,
+            ErrorCorrection::M => EcLevel::M,
+            ErrorCorrection::Q => EcLevel::Q,
+            ErrorCorrection::H => EcLevel::H,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BarcodeOptions {
+    pub output: ImageFormat,
+    /// QR codes only
+    pub error_correction: ErrorCorrection,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Barcode {
+    pub mime_type: String,
+    /// Image bytes, base64-encoded so they can go straight into a `data:` URL
+    pub data: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Dark and light modules of a symbol, quiet zone included
+struct Matrix {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+fn qr_matrix(data: &str, ecc: ErrorCorrection) -> Result<Matrix, String> {
+    let qr = QrCode::with_error_correction_level(data.as_bytes(), ecc.into())
+        .map_err(|e| format!("Cannot encode as a QR code: {}", e))?;
+    let size = qr.width();
+    let width = size + 2 * QR_QUIET_ZONE;
+
+    let mut dark = vec![false; width * width];
+    for (i, color) in qr.into_colors().into_iter().enumerate() {
+        let (x, y) = (i % size, i / size);
+        dark[(y + QR_QUIET_ZONE) * width + x + QR_QUIET_ZONE] = color == qrcode::Color::Dark;
+    }
+
+    Ok(Matrix {
+        width,
+        height: width,
+        dark,
+    })
+}
+
+/// A single row of modules; it is stretched vertically when rendered.
+fn code128_matrix(data: &str) -> Result<Matrix, String> {
+    if data.is_empty() {
+        return Err("Nothing to encode".to_string());
+    }
+    // Anything else would be read as a code set switch or function character
+    if let Some(c) = data.chars().find(|c| !matches!(c, ' '..='~')) {
+        return Err(format!("Code 128 cannot encode '{}'", c));
+    }
+
+    // The leading 'Ɓ' selects code set B
+    let bars = Code128::new(format!("\u{181}{}", data))
+        .map_err(|e| format!("Cannot encode as Code 128: {}", e))?
+        .encode();
+
+    let mut dark = vec![false; CODE128_QUIET_ZONE];
+    dark.extend(bars.into_iter().map(|bar| bar == 1));
+    dark.extend(std::iter::repeat_n(false, CODE128_QUIET_ZONE));
+
+    Ok(Matrix {
+        width: dark.len(),
+        height: 1,
+        dark,
+    })
+}
+
+/// Pixel size of each module and of the whole image, as close to `size` wide as whole
+/// pixels per module allow. Linear barcodes are a third as tall as they are wide.
+fn layout(matrix: &Matrix, size: u32) -> (usize, usize, usize) {
+    let scale = (size as usize / matrix.width).max(1);
+    let width = matrix.width * scale;
+    let height = if matrix.height == 1 {
+        width / 3
+    } else {
+        matrix.height * scale
+    };
+    (scale, width, height)
+}
+
+fn render_png(matrix: &Matrix, size: u32) -> Result<(Vec<u8>, usize, usize), String> {
+    let (scale, width, height) = layout(matrix, size);
+    let row_height = height / matrix.height;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = (y / row_height).min(matrix.height - 1);
+        for x in 0..width {
+            let dark = matrix.dark[row * matrix.width + x / scale];
+            pixels.push(if dark { 0x00 } else { 0xff });
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+
+    Ok((png, width, height))
+}
+
+fn render_svg(matrix: &Matrix, size: u32) -> (String, usize, usize) {
+    let (_, width, height) = layout(matrix, size);
+
+    let mut path = String::new();
+    for y in 0..matrix.height {
+        let row = &matrix.dark[y * matrix.width..(y + 1) * matrix.width];
+        let mut x = 0;
+        while x < matrix.width {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let run = row[x..].iter().take_while(|&&dark| dark).count();
+            path.push_str(&format!("M{} {}h{}v1h-{}z", x, y, run, run));
+            x += run;
+        }
+    }
+
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" preserveAspectRatio="none" shape-rendering="crispEdges"><rect width="100%" height="100%" fill="#fff"/><path fill="#000" d="{}"/></svg>"##,
+        width, height, matrix.width, matrix.height, path
+    );
+    (svg, width, height)
+}
+
+/// Renders `data` as a QR code or Code 128 barcode about `size` pixels wide (256 by
+/// default), as a PNG or SVG image.
+#[tauri::command]
+pub async fn generate_barcode(
+    data: String,
+    format: BarcodeFormat,
+    size: Option<u32>,
+    options: Option<BarcodeOptions>,
+) -> Result<Barcode, AppError> {
+    let options = options.unwrap_or_default();
+    let size = size.unwrap_or(DEFAULT_SIZE).min(MAX_SIZE);
+
+    let matrix = match format {
+        BarcodeFormat::Qr => {
+            qr_matrix(&data, options.error_correction).map_err(AppError::invalid_input)?
+        }
+        BarcodeFormat::Code128 => code128_matrix(&data).map_err(AppError::invalid_input)?,
+    };
+
+    let (mime_type, bytes, width, height) = match options.output {
+        ImageFormat::Png => {
+            let (png, width, height) = render_png(&matrix, size).map_err(AppError::internal)?;
+            ("image/png", png, width, height)
+        }
+        ImageFormat::Svg => {
+            let (svg, width, height) = render_svg(&matrix, size);
+            ("image/svg+xml", svg.into_bytes(), width, height)
+        }
+    };
+
+    Ok(Barcode {
+        mime_type: mime_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        width: width as u32,
+        height: height as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(matrix: &Matrix) -> String {
+        matrix
+            .dark
+            .iter()
+            .map(|&dark| if dark { '1' } else { '0' })
+            .collect()
+    }
+
+    #[test]
+    fn qr_codes_decode_to_their_data() {
+        let data = "https://accounted.th3void.com/habits/42?ref=share";
+        for ecc in [
+            ErrorCorrection::L,
+            ErrorCorrection::M,
+            ErrorCorrection::Q,
+            ErrorCorrection::H,
+        ] {
+            let matrix = qr_matrix(data, ecc).unwrap();
+            let scale = 4;
+            let mut image = rqrr::PreparedImage::prepare_from_greyscale(
+                matrix.width * scale,
+                matrix.height * scale,
+                |x, y| {
+                    if matrix.dark[(y / scale) * matrix.width + x / scale] {
+                        0
+                    } else {
+                        255
+                    }
+                },
+            );
+
+            let grids = image.detect_grids();
+            assert_eq!(grids.len(), 1);
+            let (_, content) = grids[0].decode().unwrap();
+            assert_eq!(content, data);
+        }
+    }
+
+    #[test]
+    fn code128_matches_the_specification() {
+        let matrix = code128_matrix("A").unwrap();
+        let quiet = "0".repeat(CODE128_QUIET_ZONE);
+        // Start B, 'A', checksum (104 + 33) % 103 = 34, stop
+        let symbol = "11010010000".to_string() + "10100011000" + "10001011000" + "1100011101011";
+
+        assert_eq!(bits(&matrix), format!("{}{}{}", quiet, symbol, quiet));
+    }
+
+    #[test]
+    fn code128_rejects_characters_outside_code_set_b() {
+        assert!(code128_matrix("").is_err());
+        assert!(code128_matrix("caf\u{e9}").is_err());
+        assert!(code128_matrix("\u{181}A").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::cache_store::{CacheUsage, EntryStats};
+use crate::error::AppError;
+
+/// How the cache answered reads since launch, or since the last profile switch
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CacheCounters {
+    /// Reads answered with a cached copy
+    pub hits: u64,
+    /// Reads that found no usable cached copy
+    pub misses: u64,
+    /// Cached copies served because the website could not be reached
+    pub fallbacks: u64,
+    /// Cached copies served after they had expired
+    pub stale_served: u64,
+}
+
+impl CacheCounters {
+    fn add(&mut self, other: &CacheCounters) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.fallbacks += other.fallbacks;
+        self.stale_served += other.stale_served;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheKeyMetrics {
+    pub key: String,
+    /// Bytes on disk; 0 for keys that were looked up but are not cached
+    pub size: u64,
+    pub timestamp: Option<i64>,
+    pub last_accessed: Option<i64>,
+    pub expires_at: Option<i64>,
+    #[serde(flatten)]
+    pub counters: CacheCounters,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheMetrics {
+    /// When counting started
+    pub since: i64,
+    pub totals: CacheCounters,
+    /// Share of reads answered from the cache, if there were any
+    pub hit_rate: Option<f64>,
+    /// Share of served copies that came from a fallback, if any were served
+    pub fallback_rate: Option<f64>,
+    pub usage: CacheUsage,
+    /// Most recently accessed first
+    pub entries: Vec<CacheKeyMetrics>,
+}
+
+struct Counts {
+    since: i64,
+    keys: HashMap<String, CacheCounters>,
+}
+
+/// Per-key read counters of the active profile's cache, kept in memory only
+pub struct CacheMetricsState(Mutex<Counts>);
+
+impl Default for CacheMetricsState {
+    fn default() -> Self {
+        Self(Mutex::new(Counts {
+            since: chrono::Utc::now().timestamp(),
+            keys: HashMap::new(),
+        }))
+    }
+}
+
+fn record(app: &AppHandle, key: &str, change: impl FnOnce(&mut CacheCounters)) {
+    let state = app.state::<CacheMetricsState>();
+    let mut counts = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    change(counts.keys.entry(key.to_string()).or_default());
+}
+
+/// Counts a read of `key` answered from the cache.
+pub fn record_hit(app: &AppHandle, key: &str, stale: bool) {
+    record(app, key, |counters| {
+        counters.hits += 1;
+        if stale {
+            counters.stale_served += 1;
+        }
+    });
+}
+
+pub fn record_miss(app: &AppHandle, key: &str) {
+    record(app, key, |counters| counters.misses += 1);
+}
+
+/// Counts a cached copy of `key` served in place of the network.
+pub fn record_fallback(app: &AppHandle, key: &str, stale: bool) {
+    record(app, key, |counters| {
+        counters.hits += 1;
+        counters.fallbacks += 1;
+        if stale {
+            counters.stale_served += 1;
+        }
+    });
+}
+
+/// Starts counting afresh, e.g. for a newly activated profile.
+pub fn reset(app: &AppHandle) {
+    *app.state::<CacheMetricsState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Counts {
+        since: chrono::Utc::now().timestamp(),
+        keys: HashMap::new(),
+    };
+}
+
+fn ratio(part: u64, whole: u64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
+
+fn collect(counts: &Counts, stats: Vec<EntryStats>, usage: CacheUsage) -> CacheMetrics {
+    let mut keys = counts.keys.clone();
+    let mut entries: Vec<CacheKeyMetrics> = stats
+        .into_iter()
+        .map(|stats| CacheKeyMetrics {
+            counters: keys.remove(&stats.key).unwrap_or_default(),
+            key: stats.key,
+            size: stats.size,
+            timestamp: Some(stats.timestamp),
+            last_accessed: Some(stats.last_accessed),
+            expires_at: stats.expires_at,
+        })
+        .collect();
+    entries.extend(keys.into_iter().map(|(key, counters)| CacheKeyMetrics {
+        key,
+        size: 0,
+        timestamp: None,
+        last_accessed: None,
+        expires_at: None,
+        counters,
+    }));
+    entries.sort_by(|a, b| {
+        b.last_accessed
+            .cmp(&a.last_accessed)
+            .then(a.key.cmp(&b.key))
+    });
+
+    let mut totals = CacheCounters::default();
+    for entry in &entries {
+        totals.add(&entry.counters);
+    }
+
+    CacheMetrics {
+        since: counts.since,
+        hit_rate: ratio(totals.hits, totals.hits + totals.misses),
+        fallback_rate: ratio(totals.fallbacks, totals.hits),
+        totals,
+        usage,
+        entries,
+    }
+}
+
+pub async fn current(app: &AppHandle) -> Result<CacheMetrics, String> {
+    let (stats, usage) = crate::cache_store::with_store_blocking(app, |store| {
+        Ok((store.entry_stats()?, store.usage()?))
+    })
+    .await?;
+
+    let state = app.state::<CacheMetricsState>();
+    let counts = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(collect(&counts, stats, usage))
+}
+
+/// Reports the cache footprint, each key's size and access times, and how reads were
+/// answered since launch, for a diagnostics screen.
+#[tauri::command]
+pub async fn get_cache_metrics(app: AppHandle) -> Result<CacheMetrics, AppError> {
+    current(&app).await.map_err(AppError::storage)
+}
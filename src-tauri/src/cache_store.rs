@@ -0,0 +1,1361 @@
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::progress::ProgressReporter;
+
+const DATABASE_FILE: &str = "cache.db";
+
+/// Directory (inside the profile data dir) that receives the pre-SQLite cache files
+const LEGACY_ARCHIVE_DIR: &str = "legacy-cache";
+
+const LEGACY_MIGRATED_META_KEY: &str = "legacy_json_migrated";
+
+const EVICTED_ENTRIES_META_KEY: &str = "evicted_entries";
+const EVICTED_BYTES_META_KEY: &str = "evicted_bytes";
+const LAST_EVICTION_META_KEY: &str = "last_eviction_at";
+
+const PAYLOADS_COMPRESSED_META_KEY: &str = "payloads_compressed";
+
+const ACTIVE_SCOPE_META_KEY: &str = "active_scope";
+
+/// Scope of entries cached while no account is signed in, and of everything cached before
+/// scopes existed
+pub const SHARED_SCOPE: &str = "";
+
+/// Payloads smaller than this are stored as plain JSON; compressing them saves nothing
+const COMPRESSION_THRESHOLD_BYTES: usize = 512;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Documents kept parsed in memory in front of SQLite
+const MEMORY_CACHE_ENTRIES: usize = 128;
+
+/// Documents larger than this on disk are read from SQLite every time
+const MEMORY_CACHE_MAX_ENTRY_BYTES: i64 = 256 * 1024;
+
+/// Reads served from memory record the access on disk at most this often, which is all
+/// LRU eviction needs
+const ACCESS_WRITE_INTERVAL_SECS: i64 = 60;
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how many have run.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE cache_entries (
+        key TEXT PRIMARY KEY,
+        data TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    "CREATE TABLE mutations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        headers TEXT,
+        body TEXT,
+        created_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE cache_entries ADD COLUMN expires_at INTEGER;
+    CREATE TABLE cache_policies (
+        key TEXT PRIMARY KEY,
+        ttl_secs INTEGER NOT NULL
+    );",
+    "ALTER TABLE cache_entries ADD COLUMN size INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE cache_entries ADD COLUMN last_accessed INTEGER NOT NULL DEFAULT 0;
+    UPDATE cache_entries SET size = length(CAST(data AS BLOB)), last_accessed = timestamp;
+    CREATE INDEX cache_entries_last_accessed ON cache_entries (last_accessed);",
+    "ALTER TABLE cache_entries ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE cache_entries ADD COLUMN etag TEXT;
+    ALTER TABLE cache_entries ADD COLUMN last_modified TEXT;",
+    "CREATE TABLE habits (
+        id TEXT PRIMARY KEY,
+        server_id TEXT UNIQUE,
+        name TEXT NOT NULL,
+        description TEXT,
+        color TEXT,
+        archived INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        deleted_at INTEGER
+    );
+    CREATE TABLE habit_completions (
+        habit_id TEXT NOT NULL,
+        date TEXT NOT NULL,
+        completed_at INTEGER NOT NULL,
+        PRIMARY KEY (habit_id, date)
+    );",
+    "ALTER TABLE mutations ADD COLUMN cache_key TEXT;
+    ALTER TABLE mutations ADD COLUMN base_etag TEXT;
+    ALTER TABLE mutations ADD COLUMN base_last_modified TEXT;
+    CREATE TABLE conflicts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        headers TEXT,
+        body TEXT,
+        cache_key TEXT,
+        server_data TEXT,
+        created_at INTEGER NOT NULL,
+        detected_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE cache_entries ADD COLUMN sync_cursor TEXT;",
+    "CREATE TABLE assets (
+        url TEXT PRIMARY KEY,
+        content_type TEXT,
+        body BLOB NOT NULL,
+        fetched_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE attachments (
+        hash TEXT PRIMARY KEY,
+        content_type TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        uploaded_at INTEGER
+    );
+    ALTER TABLE mutations ADD COLUMN attachment TEXT;",
+    "CREATE TABLE app_usage (
+        date TEXT NOT NULL,
+        app TEXT NOT NULL,
+        seconds INTEGER NOT NULL,
+        PRIMARY KEY (date, app)
+    );",
+    "ALTER TABLE cache_entries ADD COLUMN checksum TEXT;
+    CREATE TABLE corrupted_entries (
+        key TEXT PRIMARY KEY,
+        reason TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        detected_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE mutations ADD COLUMN upload TEXT;",
+    "ALTER TABLE mutations ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE mutations ADD COLUMN last_attempt_at INTEGER;
+    ALTER TABLE mutations ADD COLUMN last_error TEXT;",
+    "CREATE TABLE scoped_cache_entries (
+        scope TEXT NOT NULL DEFAULT '',
+        key TEXT NOT NULL,
+        data TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        expires_at INTEGER,
+        size INTEGER NOT NULL DEFAULT 0,
+        last_accessed INTEGER NOT NULL DEFAULT 0,
+        compressed INTEGER NOT NULL DEFAULT 0,
+        etag TEXT,
+        last_modified TEXT,
+        sync_cursor TEXT,
+        checksum TEXT,
+        PRIMARY KEY (scope, key)
+    );
+    INSERT INTO scoped_cache_entries
+        (key, data, timestamp, expires_at, size, last_accessed, compressed, etag,
+         last_modified, sync_cursor, checksum)
+    SELECT key, data, timestamp, expires_at, size, last_accessed, compressed, etag,
+        last_modified, sync_cursor, checksum
+    FROM cache_entries;
+    DROP TABLE cache_entries;
+    ALTER TABLE scoped_cache_entries RENAME TO cache_entries;
+    CREATE INDEX cache_entries_last_accessed ON cache_entries (last_accessed);",
+    "ALTER TABLE cache_entries ADD COLUMN created_at INTEGER;
+    ALTER TABLE cache_entries ADD COLUMN source_url TEXT;
+    UPDATE cache_entries SET created_at = timestamp;",
+    "CREATE TABLE scoped_corrupted_entries (
+        scope TEXT NOT NULL DEFAULT '',
+        key TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        detected_at INTEGER NOT NULL,
+        PRIMARY KEY (scope, key)
+    );
+    INSERT INTO scoped_corrupted_entries (key, reason, size, detected_at)
+    SELECT key, reason, size, detected_at FROM corrupted_entries;
+    DROP TABLE corrupted_entries;
+    ALTER TABLE scoped_corrupted_entries RENAME TO corrupted_entries;",
+];
+
+/// Cache schema version of a fully migrated database
+pub const LATEST_SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// A website file or API response kept for offline use
+pub struct CachedAsset {
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// A payload ready for the `data` column: JSON text, or zstd-compressed JSON in a blob
+pub struct EncodedPayload {
+    pub value: SqlValue,
+    pub compressed: bool,
+    /// Bytes occupied on disk, used for the cache size limit
+    pub size: i64,
+    /// SHA-256 of the uncompressed JSON, checked on every read
+    pub checksum: String,
+}
+
+fn checksum(json: &[u8]) -> String {
+    Sha256::digest(json)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn encode_payload(data: &serde_json::Value) -> Result<EncodedPayload, String> {
+    let json_string =
+        serde_json::to_string(data).map_err(|e| format!("Failed to serialize data: {}", e))?;
+    let checksum = checksum(json_string.as_bytes());
+
+    if json_string.len() < COMPRESSION_THRESHOLD_BYTES {
+        return Ok(EncodedPayload {
+            size: json_string.len() as i64,
+            value: SqlValue::Text(json_string),
+            compressed: false,
+            checksum,
+        });
+    }
+
+    let bytes = zstd::encode_all(json_string.as_bytes(), ZSTD_LEVEL)
+        .map_err(|e| format!("Failed to compress data: {}", e))?;
+
+    Ok(EncodedPayload {
+        size: bytes.len() as i64,
+        value: SqlValue::Blob(bytes),
+        compressed: true,
+        checksum,
+    })
+}
+
+/// Reverses [`encode_payload`], failing if the JSON does not match `expected_checksum`.
+/// Rows written before compression existed are plain text, and rows written before
+/// checksums existed have none to check.
+pub fn decode_payload(
+    value: SqlValue,
+    compressed: bool,
+    expected_checksum: Option<&str>,
+) -> Result<serde_json::Value, String> {
+    let bytes = match value {
+        SqlValue::Text(text) => text.into_bytes(),
+        SqlValue::Blob(bytes) => bytes,
+        other => return Err(format!("Unexpected cache payload type: {:?}", other.data_type())),
+    };
+
+    let bytes = if compressed {
+        zstd::decode_all(bytes.as_slice())
+            .map_err(|e| format!("Failed to decompress cache entry: {}", e))?
+    } else {
+        bytes
+    };
+
+    if expected_checksum.is_some_and(|expected| expected != checksum(&bytes)) {
+        return Err("Cache entry does not match its checksum".to_string());
+    }
+
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse cache entry: {}", e))
+}
+
+/// The `data`, `compressed` and `checksum` columns of a cache entry
+type StoredPayload = (SqlValue, bool, Option<String>);
+
+/// A cache entry that failed to read back and was removed
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptedEntry {
+    /// Account the entry was cached for; empty for shared entries and everything
+    /// recorded before scopes were
+    pub scope: String,
+    pub key: String,
+    pub reason: String,
+    /// Bytes the entry occupied on disk
+    pub size: i64,
+    pub detected_at: i64,
+}
+
+/// A cached document and the time it was stored
+#[derive(Debug, Clone)]
+pub struct CachedEntry {
+    pub data: serde_json::Value,
+    pub timestamp: i64,
+    pub expires_at: Option<i64>,
+}
+
+/// Current cache size against its limit, plus how much LRU eviction has removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheUsage {
+    pub total_bytes: u64,
+    pub entry_count: u64,
+    pub max_bytes: u64,
+    pub evicted_entries: u64,
+    pub evicted_bytes: u64,
+    pub last_eviction_at: Option<i64>,
+}
+
+/// What a cache management screen shows about one entry
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntryInfo {
+    pub key: String,
+    /// Account the entry belongs to; empty for the shared scope
+    pub scope: String,
+    /// Bytes the payload occupies on disk
+    pub size: u64,
+    /// When the key was first cached
+    pub created_at: i64,
+    /// When the payload was last written or confirmed current
+    pub updated_at: i64,
+    pub last_accessed: i64,
+    pub expires_at: Option<i64>,
+    /// Freshness window the entry was saved with
+    pub ttl_secs: Option<i64>,
+    /// URL the payload was fetched from; `None` for data saved by the page itself
+    pub source_url: Option<String>,
+    pub compressed: bool,
+    /// Payloads are not encrypted at rest, so this is always `false`
+    pub encrypted: bool,
+}
+
+/// Size and access times of one cache entry
+#[derive(Debug, Clone)]
+pub struct EntryStats {
+    pub key: String,
+    pub size: u64,
+    pub timestamp: i64,
+    pub last_accessed: i64,
+    pub expires_at: Option<i64>,
+}
+
+/// HTTP validators of the response a cache entry came from, for conditional refreshes
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+struct MemoryEntry {
+    entry: CachedEntry,
+    /// Value of the use counter at the last read or write
+    last_used: u64,
+    /// When the access was last written to the `last_accessed` column
+    access_recorded_at: i64,
+}
+
+/// Most recently used documents, written through on every change so they never differ
+/// from the copies on disk
+#[derive(Default)]
+struct MemoryCache {
+    entries: HashMap<String, MemoryEntry>,
+    uses: u64,
+}
+
+impl MemoryCache {
+    fn get(&mut self, key: &str) -> Option<&mut MemoryEntry> {
+        self.uses += 1;
+        let cached = self.entries.get_mut(key)?;
+        cached.last_used = self.uses;
+        Some(cached)
+    }
+
+    fn insert(&mut self, key: &str, entry: CachedEntry, accessed_at: i64) {
+        if self.entries.len() >= MEMORY_CACHE_ENTRIES && !self.entries.contains_key(key) {
+            let least_recent = self
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(least_recent) = least_recent {
+                self.entries.remove(&least_recent);
+            }
+        }
+
+        self.uses += 1;
+        self.entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                entry,
+                last_used: self.uses,
+                access_recorded_at: accessed_at,
+            },
+        );
+    }
+}
+
+/// SQLite-backed key/value store holding cached API responses for one profile. Entries
+/// belong to a scope, one per website account, and only the active scope's entries are
+/// visible through the key-based methods.
+pub struct CacheStore {
+    conn: Connection,
+    /// Total payload size above which least recently used entries are evicted
+    max_bytes: u64,
+    /// Scope that reads and writes by key go to
+    scope: String,
+    /// Parsed copies of recently used documents; the store is only ever used behind the
+    /// [`CacheState`] lock
+    memory: RefCell<MemoryCache>,
+}
+
+impl CacheStore {
+    pub fn open(path: &Path, max_bytes: u64) -> Result<Self, String> {
+        let conn =
+            Connection::open(path).map_err(|e| format!("Failed to open cache database: {}", e))?;
+
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("Failed to configure cache database: {}", e))?;
+
+        let mut store = Self {
+            conn,
+            max_bytes,
+            scope: SHARED_SCOPE.to_string(),
+            memory: RefCell::default(),
+        };
+        store.migrate()?;
+        store.scope = store.meta(ACTIVE_SCOPE_META_KEY)?.unwrap_or_default();
+        Ok(store)
+    }
+
+    /// Number of migrations applied to the database
+    pub fn schema_version(&self) -> Result<u32, String> {
+        self.conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(|e| format!("Failed to read cache schema version: {}", e))
+    }
+
+    fn migrate(&mut self) -> Result<(), String> {
+        let version = self.schema_version()? as usize;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self
+                .conn
+                .transaction()
+                .map_err(|e| format!("Failed to start migration: {}", e))?;
+
+            tx.execute_batch(migration)
+                .map_err(|e| format!("Cache migration {} failed: {}", index + 1, e))?;
+            tx.pragma_update(None, "user_version", index + 1)
+                .map_err(|e| format!("Failed to record cache schema version: {}", e))?;
+
+            tx.commit()
+                .map_err(|e| format!("Failed to commit cache migration: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Raw connection for subsystems that keep their own tables in the cache database
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+
+    /// Switches reads and writes to `scope`, remembering it for the next launch. Entries
+    /// of other scopes are kept until they are purged or evicted.
+    pub fn set_scope(&mut self, scope: &str) -> Result<(), String> {
+        if scope == SHARED_SCOPE {
+            self.conn.execute(
+                "DELETE FROM meta WHERE key = ?1",
+                params![ACTIVE_SCOPE_META_KEY],
+            )
+        } else {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                params![ACTIVE_SCOPE_META_KEY, scope],
+            )
+        }
+        .map_err(|e| format!("Failed to record cache scope: {}", e))?;
+
+        self.scope = scope.to_string();
+        self.forget_documents();
+        Ok(())
+    }
+
+    /// Deletes every entry of `scope` and returns how many there were.
+    pub fn purge_scope(&self, scope: &str) -> Result<u64, String> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM cache_entries WHERE scope = ?1", params![scope])
+            .map_err(|e| format!("Failed to purge cache scope: {}", e))?;
+        self.conn
+            .execute(
+                "DELETE FROM corrupted_entries WHERE scope = ?1",
+                params![scope],
+            )
+            .map_err(|e| format!("Failed to purge cache scope: {}", e))?;
+
+        if scope == self.scope {
+            self.forget_documents();
+        }
+        Ok(removed as u64)
+    }
+
+    /// Stores `data` under `key`, fetched from `source_url` if it came from the network,
+    /// and returns its expiry. An explicit `ttl_secs` wins over the key's cache policy;
+    /// with neither, the entry never goes stale.
+    ///
+    /// If the write pushes the cache over its size limit, other entries are evicted in
+    /// least-recently-used order until it fits again.
+    pub fn save(
+        &self,
+        key: &str,
+        data: &serde_json::Value,
+        timestamp: i64,
+        ttl_secs: Option<i64>,
+        source_url: Option<&str>,
+    ) -> Result<Option<i64>, String> {
+        let payload = encode_payload(data)?;
+
+        let ttl_secs = match ttl_secs {
+            Some(ttl_secs) => Some(ttl_secs),
+            None => self.policy_ttl(key)?,
+        };
+        let expires_at = ttl_secs.map(|ttl_secs| timestamp + ttl_secs);
+
+        self.conn
+            .execute(
+                "INSERT INTO cache_entries
+                    (key, data, timestamp, expires_at, size, last_accessed, compressed, checksum,
+                     scope, created_at, source_url)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?3, ?6, ?7, ?8, ?3, ?9)
+                 ON CONFLICT(scope, key) DO UPDATE SET
+                    data = excluded.data,
+                    timestamp = excluded.timestamp,
+                    expires_at = excluded.expires_at,
+                    size = excluded.size,
+                    last_accessed = excluded.last_accessed,
+                    compressed = excluded.compressed,
+                    checksum = excluded.checksum,
+                    source_url = excluded.source_url,
+                    etag = NULL,
+                    last_modified = NULL,
+                    sync_cursor = NULL",
+                params![
+                    key,
+                    payload.value,
+                    timestamp,
+                    expires_at,
+                    payload.size,
+                    payload.compressed,
+                    payload.checksum,
+                    self.scope,
+                    source_url
+                ],
+            )
+            .map_err(|e| format!("Failed to write cache entry: {}", e))?;
+
+        let mut memory = self.memory.borrow_mut();
+        if payload.size <= MEMORY_CACHE_MAX_ENTRY_BYTES {
+            let entry = CachedEntry {
+                data: data.clone(),
+                timestamp,
+                expires_at,
+            };
+            memory.insert(key, entry, timestamp);
+        } else {
+            memory.entries.remove(key);
+        }
+        drop(memory);
+
+        self.evict_to_fit(Some(key))?;
+        Ok(expires_at)
+    }
+
+    /// Records the validators of the response `key` was just saved from.
+    pub fn set_validators(&self, key: &str, validators: &Validators) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE cache_entries SET etag = ?2, last_modified = ?3
+                 WHERE key = ?1 AND scope = ?4",
+                params![key, validators.etag, validators.last_modified, self.scope],
+            )
+            .map_err(|e| format!("Failed to store cache validators: {}", e))?;
+        Ok(())
+    }
+
+    /// Records the delta-sync cursor `key`'s cached document is current as of.
+    pub fn set_sync_cursor(&self, key: &str, cursor: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE cache_entries SET sync_cursor = ?2 WHERE key = ?1 AND scope = ?3",
+                params![key, cursor, self.scope],
+            )
+            .map_err(|e| format!("Failed to store sync cursor: {}", e))?;
+        Ok(())
+    }
+
+    pub fn sync_cursor(&self, key: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row(
+                "SELECT sync_cursor FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                params![key, self.scope],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| format!("Failed to read sync cursor: {}", e))
+    }
+
+    pub fn validators(&self, key: &str) -> Result<Option<Validators>, String> {
+        self.conn
+            .query_row(
+                "SELECT etag, last_modified FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                params![key, self.scope],
+                |row| {
+                    Ok(Validators {
+                        etag: row.get(0)?,
+                        last_modified: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map(|validators| validators.filter(|v| !v.is_empty()))
+            .map_err(|e| format!("Failed to read cache validators: {}", e))
+    }
+
+    /// Marks `key` as confirmed current at `timestamp` after a `304 Not Modified`,
+    /// keeping the entry's original freshness window.
+    pub fn touch(&self, key: &str, timestamp: i64) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE cache_entries SET
+                    expires_at = ?2 + (expires_at - timestamp),
+                    timestamp = ?2,
+                    last_accessed = ?2
+                 WHERE key = ?1 AND scope = ?3",
+                params![key, timestamp, self.scope],
+            )
+            .map_err(|e| format!("Failed to refresh cache entry: {}", e))?;
+
+        if let Some(cached) = self.memory.borrow_mut().get(key) {
+            let entry = &mut cached.entry;
+            entry.expires_at = entry
+                .expires_at
+                .map(|expires_at| timestamp + (expires_at - entry.timestamp));
+            entry.timestamp = timestamp;
+            cached.access_recorded_at = timestamp;
+        }
+        Ok(())
+    }
+
+    fn total_size(&self) -> Result<u64, String> {
+        self.conn
+            .query_row("SELECT COALESCE(SUM(size), 0) FROM cache_entries", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|size| size as u64)
+            .map_err(|e| format!("Failed to measure cache size: {}", e))
+    }
+
+    /// Evicts least recently used entries until the cache fits its limit. `keep` is never
+    /// evicted, so a single oversized entry can still be cached.
+    fn evict_to_fit(&self, keep: Option<&str>) -> Result<(), String> {
+        let total = self.total_size()?;
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        // The limit covers all scopes, so any account's entries may be evicted
+        let candidates: Vec<(String, String, i64)> = {
+            let mut statement = self
+                .conn
+                .prepare(
+                    "SELECT scope, key, size FROM cache_entries
+                     WHERE NOT (scope = ?1 AND key IS ?2)
+                     ORDER BY last_accessed, timestamp",
+                )
+                .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+            let rows = statement
+                .query_map(params![self.scope, keep], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read cache entries: {}", e))?
+        };
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start cache eviction: {}", e))?;
+
+        let mut remaining = total;
+        let mut evicted_entries = 0u64;
+        let mut evicted_bytes = 0u64;
+        for (scope, key, size) in candidates {
+            if remaining <= self.max_bytes {
+                break;
+            }
+
+            tx.execute(
+                "DELETE FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                params![key, scope],
+            )
+            .map_err(|e| format!("Failed to evict cache entry: {}", e))?;
+            if scope == self.scope {
+                self.memory.borrow_mut().entries.remove(&key);
+            }
+
+            remaining = remaining.saturating_sub(size as u64);
+            evicted_entries += 1;
+            evicted_bytes += size as u64;
+        }
+
+        if evicted_entries > 0 {
+            let now = chrono::Utc::now().timestamp();
+            for (meta_key, value) in [
+                (
+                    EVICTED_ENTRIES_META_KEY,
+                    self.meta_count(EVICTED_ENTRIES_META_KEY)? + evicted_entries,
+                ),
+                (
+                    EVICTED_BYTES_META_KEY,
+                    self.meta_count(EVICTED_BYTES_META_KEY)? + evicted_bytes,
+                ),
+                (LAST_EVICTION_META_KEY, now as u64),
+            ] {
+                tx.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                    params![meta_key, value.to_string()],
+                )
+                .map_err(|e| format!("Failed to record cache eviction: {}", e))?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit cache eviction: {}", e))
+    }
+
+    /// Changes the size limit, evicting immediately if the cache is already over it.
+    pub fn set_max_bytes(&mut self, max_bytes: u64) -> Result<(), String> {
+        self.max_bytes = max_bytes;
+        self.evict_to_fit(None)
+    }
+
+    pub fn usage(&self) -> Result<CacheUsage, String> {
+        let entry_count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM cache_entries", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count cache entries: {}", e))?;
+
+        Ok(CacheUsage {
+            total_bytes: self.total_size()?,
+            entry_count: entry_count as u64,
+            max_bytes: self.max_bytes,
+            evicted_entries: self.meta_count(EVICTED_ENTRIES_META_KEY)?,
+            evicted_bytes: self.meta_count(EVICTED_BYTES_META_KEY)?,
+            last_eviction_at: self
+                .meta(LAST_EVICTION_META_KEY)?
+                .and_then(|value| value.parse().ok()),
+        })
+    }
+
+    /// Reads `key`'s entry. An entry that fails its checksum or no longer parses is
+    /// quarantined and reported as missing, so the next fetch replaces it.
+    pub fn load(&self, key: &str) -> Result<Option<CachedEntry>, String> {
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(cached) = self.memory.borrow_mut().get(key) {
+            if now - cached.access_recorded_at >= ACCESS_WRITE_INTERVAL_SECS {
+                self.record_access(key, now)?;
+                cached.access_recorded_at = now;
+            }
+            return Ok(Some(cached.entry.clone()));
+        }
+
+        let row: Option<(StoredPayload, i64, Option<i64>, i64)> = self
+            .conn
+            .query_row(
+                "SELECT data, compressed, checksum, timestamp, expires_at, size
+                 FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                params![key, self.scope],
+                |row| {
+                    Ok((
+                        (row.get(0)?, row.get(1)?, row.get(2)?),
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read cache entry: {}", e))?;
+
+        let Some(((data, compressed, checksum), timestamp, expires_at, size)) = row else {
+            return Ok(None);
+        };
+
+        let data = match decode_payload(data, compressed, checksum.as_deref()) {
+            Ok(data) => data,
+            Err(e) => {
+                self.quarantine(key, &e)?;
+                return Ok(None);
+            }
+        };
+
+        self.record_access(key, now)?;
+
+        let entry = CachedEntry {
+            data,
+            timestamp,
+            expires_at,
+        };
+        if size <= MEMORY_CACHE_MAX_ENTRY_BYTES {
+            self.memory.borrow_mut().insert(key, entry.clone(), now);
+        }
+        Ok(Some(entry))
+    }
+
+    fn record_access(&self, key: &str, timestamp: i64) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE cache_entries SET last_accessed = ?2 WHERE key = ?1 AND scope = ?3",
+                params![key, timestamp, self.scope],
+            )
+            .map_err(|e| format!("Failed to update cache access time: {}", e))?;
+        Ok(())
+    }
+
+    /// Drops the in-memory copies, for callers that change `cache_entries` directly
+    /// through [`Self::connection`].
+    pub fn forget_documents(&self) {
+        self.memory.borrow_mut().entries.clear();
+    }
+
+    /// Removes a corrupted entry, recording it for [`Self::corrupted_entries`].
+    fn quarantine(&self, key: &str, reason: &str) -> Result<(), String> {
+        self.quarantine_in(&self.scope, key, reason)
+    }
+
+    /// [`Self::quarantine`] for an entry of any account, for callers that read
+    /// `cache_entries` directly through [`Self::connection`].
+    pub fn quarantine_in(&self, scope: &str, key: &str, reason: &str) -> Result<(), String> {
+        tracing::warn!("Removing corrupted cache entry '{}': {}", key, reason);
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start cache quarantine: {}", e))?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO corrupted_entries (scope, key, reason, size, detected_at)
+             SELECT scope, key, ?2, size, ?3 FROM cache_entries WHERE key = ?1 AND scope = ?4",
+            params![key, reason, chrono::Utc::now().timestamp(), scope],
+        )
+        .map_err(|e| format!("Failed to record corrupted cache entry: {}", e))?;
+        tx.execute(
+            "DELETE FROM cache_entries WHERE key = ?1 AND scope = ?2",
+            params![key, scope],
+        )
+        .map_err(|e| format!("Failed to remove corrupted cache entry: {}", e))?;
+        if scope == self.scope {
+            self.memory.borrow_mut().entries.remove(key);
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit cache quarantine: {}", e))
+    }
+
+    /// Entries of every account removed for failing to read back, most recent first
+    pub fn corrupted_entries(&self) -> Result<Vec<CorruptedEntry>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT scope, key, reason, size, detected_at FROM corrupted_entries
+                 ORDER BY detected_at DESC",
+            )
+            .map_err(|e| format!("Failed to query corrupted entries: {}", e))?;
+
+        let rows = statement
+            .query_map([], |row| {
+                Ok(CorruptedEntry {
+                    scope: row.get(0)?,
+                    key: row.get(1)?,
+                    reason: row.get(2)?,
+                    size: row.get(3)?,
+                    detected_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query corrupted entries: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read corrupted entries: {}", e))
+    }
+
+    fn policy_ttl(&self, key: &str) -> Result<Option<i64>, String> {
+        self.conn
+            .query_row(
+                "SELECT ttl_secs FROM cache_policies WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read cache policy: {}", e))
+    }
+
+    /// Sets the freshness window applied to future writes of `key`; `None` removes it.
+    pub fn set_policy(&self, key: &str, ttl_secs: Option<i64>) -> Result<(), String> {
+        match ttl_secs {
+            Some(ttl_secs) => self.conn.execute(
+                "INSERT OR REPLACE INTO cache_policies (key, ttl_secs) VALUES (?1, ?2)",
+                params![key, ttl_secs],
+            ),
+            None => self
+                .conn
+                .execute("DELETE FROM cache_policies WHERE key = ?1", params![key]),
+        }
+        .map_err(|e| format!("Failed to update cache policy: {}", e))?;
+
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "DELETE FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                params![key, self.scope],
+            )
+            .map_err(|e| format!("Failed to remove cache entry: {}", e))?;
+        self.memory.borrow_mut().entries.remove(key);
+        Ok(())
+    }
+
+    /// Deletes the entries of every scope.
+    pub fn clear(&self) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM cache_entries", [])
+            .map_err(|e| format!("Failed to clear cache: {}", e))?;
+        self.forget_documents();
+        Ok(())
+    }
+
+    /// Saves a website file or API response for the `app-cache://` scheme.
+    pub fn save_asset(
+        &self,
+        url: &str,
+        content_type: Option<&str>,
+        body: &[u8],
+        fetched_at: i64,
+    ) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO assets (url, content_type, body, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![url, content_type, body, fetched_at],
+            )
+            .map_err(|e| format!("Failed to write cached asset: {}", e))?;
+        Ok(())
+    }
+
+    pub fn load_asset(&self, url: &str) -> Result<Option<CachedAsset>, String> {
+        self.conn
+            .query_row(
+                "SELECT content_type, body FROM assets WHERE url = ?1",
+                params![url],
+                |row| {
+                    Ok(CachedAsset {
+                        content_type: row.get(0)?,
+                        body: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read cached asset: {}", e))
+    }
+
+    /// Number of cached assets and their total size in bytes.
+    pub fn asset_usage(&self) -> Result<(u64, u64), String> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(body)), 0) FROM assets",
+                [],
+                |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+            )
+            .map_err(|e| format!("Failed to measure cached assets: {}", e))
+    }
+
+    pub fn clear_assets(&self) -> Result<(), String> {
+        self.conn
+            .execute("DELETE FROM assets", [])
+            .map_err(|e| format!("Failed to clear cached assets: {}", e))?;
+        Ok(())
+    }
+
+    pub fn timestamps(&self) -> Result<HashMap<String, i64>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT key, timestamp FROM cache_entries WHERE scope = ?1")
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        let rows = statement
+            .query_map(params![self.scope], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read cache entries: {}", e))
+    }
+
+    /// Entries of every scope, ordered by scope and key.
+    pub fn entries(&self) -> Result<Vec<CacheEntryInfo>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT key, scope, size, COALESCE(created_at, timestamp), timestamp,
+                    last_accessed, expires_at, source_url, compressed
+                 FROM cache_entries ORDER BY scope, key",
+            )
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        let rows = statement
+            .query_map([], |row| {
+                let updated_at: i64 = row.get(4)?;
+                let expires_at: Option<i64> = row.get(6)?;
+                Ok(CacheEntryInfo {
+                    key: row.get(0)?,
+                    scope: row.get(1)?,
+                    size: row.get::<_, i64>(2)? as u64,
+                    created_at: row.get(3)?,
+                    updated_at,
+                    last_accessed: row.get(5)?,
+                    expires_at,
+                    ttl_secs: expires_at.map(|expires_at| expires_at - updated_at),
+                    source_url: row.get(7)?,
+                    compressed: row.get(8)?,
+                    encrypted: false,
+                })
+            })
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read cache entries: {}", e))
+    }
+
+    pub fn entry_stats(&self) -> Result<Vec<EntryStats>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT key, size, timestamp, last_accessed, expires_at FROM cache_entries
+                 WHERE scope = ?1",
+            )
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        let rows = statement
+            .query_map(params![self.scope], |row| {
+                Ok(EntryStats {
+                    key: row.get(0)?,
+                    size: row.get(1)?,
+                    timestamp: row.get(2)?,
+                    last_accessed: row.get(3)?,
+                    expires_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read cache entries: {}", e))
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(|e| format!("Failed to read cache metadata: {}", e))
+    }
+
+    fn meta_count(&self, key: &str) -> Result<u64, String> {
+        Ok(self
+            .meta(key)?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// Compresses entries stored before payload compression existed, exactly once, then
+    /// reclaims the freed pages.
+    fn compress_existing(&mut self, progress: &ProgressReporter) -> Result<(), String> {
+        if self.meta(PAYLOADS_COMPRESSED_META_KEY)?.is_some() {
+            return Ok(());
+        }
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Failed to start cache recompaction: {}", e))?;
+
+        let rows: Vec<(String, String, SqlValue)> = {
+            let mut statement = tx
+                .prepare("SELECT scope, key, data FROM cache_entries WHERE compressed = 0")
+                .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+            let rows = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .map_err(|e| format!("Failed to query cache: {}", e))?;
+
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read cache entries: {}", e))?
+        };
+
+        let total = rows.len() as u64;
+        progress.report("compressing", 0, Some(total));
+
+        let mut compressed = 0;
+        for (index, (scope, key, data)) in rows.into_iter().enumerate() {
+            progress.report("compressing", index as u64, Some(total));
+
+            let Ok(data) = decode_payload(data, false, None) else {
+                tracing::warn!("Skipping unreadable cache entry '{}'", key);
+                continue;
+            };
+
+            let payload = encode_payload(&data)?;
+            if !payload.compressed {
+                continue;
+            }
+
+            tx.execute(
+                "UPDATE cache_entries SET data = ?2, size = ?3, compressed = 1, checksum = ?4
+                 WHERE key = ?1 AND scope = ?5",
+                params![key, payload.value, payload.size, payload.checksum, scope],
+            )
+            .map_err(|e| format!("Failed to compress cache entry '{}': {}", key, e))?;
+            compressed += 1;
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![PAYLOADS_COMPRESSED_META_KEY, chrono::Utc::now().timestamp().to_string()],
+        )
+        .map_err(|e| format!("Failed to record cache recompaction: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit cache recompaction: {}", e))?;
+
+        progress.report("reclaiming", total, Some(total));
+        self.conn
+            .execute_batch("VACUUM")
+            .map_err(|e| format!("Failed to reclaim cache space: {}", e))?;
+
+        progress.finish(
+            "reclaiming",
+            total,
+            Some(total),
+            Some(format!("Compressed {} cached entries", compressed)),
+        );
+        Ok(())
+    }
+
+    /// Imports pre-SQLite `{key}.json` files from `data_dir` exactly once.
+    ///
+    /// The import runs in a single transaction and every entry is read back and compared
+    /// before committing; any mismatch rolls the whole import back and leaves the original
+    /// files untouched. After a successful commit the originals are moved to
+    /// `legacy-cache/` rather than deleted, so they remain available for recovery.
+    fn import_legacy_json(
+        &mut self,
+        data_dir: &Path,
+        progress: &ProgressReporter,
+    ) -> Result<(), String> {
+        if self.meta(LEGACY_MIGRATED_META_KEY)?.is_some() {
+            return Ok(());
+        }
+
+        let legacy_files: Vec<_> = std::fs::read_dir(data_dir)
+            .map_err(|e| format!("Failed to read data directory: {}", e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json")
+            })
+            .collect();
+
+        let total = legacy_files.len() as u64;
+        progress.report("importing", 0, Some(total));
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Failed to start legacy import: {}", e))?;
+
+        let mut imported = Vec::new();
+        for (index, path) in legacy_files.iter().enumerate() {
+            let Some(key) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            let parsed = std::fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+
+            // Unreadable files stay where they are; they were already unusable
+            let Some(parsed) = parsed else {
+                tracing::warn!("Skipping unreadable legacy cache file {:?}", path);
+                continue;
+            };
+
+            let data = parsed.get("data").cloned().unwrap_or(serde_json::Value::Null);
+            let timestamp = parsed.get("timestamp").and_then(|t| t.as_i64()).unwrap_or(0);
+            let json_string = serde_json::to_string(&data)
+                .map_err(|e| format!("Failed to serialize legacy entry: {}", e))?;
+
+            tx.execute(
+                "INSERT OR REPLACE INTO cache_entries
+                    (key, data, timestamp, size, last_accessed, checksum)
+                 VALUES (?1, ?2, ?3, ?4, ?3, ?5)",
+                params![
+                    key,
+                    json_string,
+                    timestamp,
+                    json_string.len() as i64,
+                    checksum(json_string.as_bytes())
+                ],
+            )
+            .map_err(|e| format!("Failed to import legacy entry '{}': {}", key, e))?;
+
+            imported.push((key.to_string(), data, timestamp, path.clone()));
+            progress.report("importing", index as u64 + 1, Some(total));
+        }
+
+        progress.report("verifying", 0, Some(imported.len() as u64));
+        for (key, data, timestamp, _) in &imported {
+            let (stored, stored_timestamp): (String, i64) = tx
+                .query_row(
+                    "SELECT data, timestamp FROM cache_entries WHERE key = ?1 AND scope = ?2",
+                    params![key, SHARED_SCOPE],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| format!("Failed to verify legacy entry '{}': {}", key, e))?;
+
+            let stored: serde_json::Value = serde_json::from_str(&stored)
+                .map_err(|e| format!("Failed to verify legacy entry '{}': {}", key, e))?;
+
+            if &stored != data || stored_timestamp != *timestamp {
+                // Dropping the transaction rolls the import back
+                return Err(format!("Legacy entry '{}' did not verify; import rolled back", key));
+            }
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![LEGACY_MIGRATED_META_KEY, chrono::Utc::now().timestamp().to_string()],
+        )
+        .map_err(|e| format!("Failed to record legacy import: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit legacy import: {}", e))?;
+
+        self.evict_to_fit(None)?;
+
+        let archive_dir = data_dir.join(LEGACY_ARCHIVE_DIR);
+        if !imported.is_empty() {
+            std::fs::create_dir_all(&archive_dir)
+                .map_err(|e| format!("Failed to create legacy archive directory: {}", e))?;
+        }
+
+        for (_, _, _, path) in &imported {
+            if let Some(file_name) = path.file_name() {
+                if let Err(e) = std::fs::rename(path, archive_dir.join(file_name)) {
+                    tracing::warn!("Failed to archive legacy cache file {:?}: {}", path, e);
+                }
+            }
+        }
+
+        progress.finish(
+            "importing",
+            imported.len() as u64,
+            Some(total),
+            Some(format!("Imported {} cached entries", imported.len())),
+        );
+
+        Ok(())
+    }
+}
+
+/// Managed handle to the active profile's cache store
+pub struct CacheState(Mutex<CacheStore>);
+
+/// Opens the active profile's store, importing any legacy JSON cache on first use.
+fn open_for_active_profile(app: &AppHandle) -> Result<CacheStore, String> {
+    let data_dir = crate::get_data_dir(app)?;
+    let max_bytes = crate::settings::current(app).max_cache_bytes;
+    let mut store = CacheStore::open(&data_dir.join(DATABASE_FILE), max_bytes)?;
+
+    let progress = ProgressReporter::new(app, Some("cache-migration".to_string()));
+    if let Err(e) = store.import_legacy_json(&data_dir, &progress) {
+        tracing::warn!("Legacy cache import failed: {}", e);
+        progress.finish("importing", 0, None, Some(e));
+    }
+
+    // Runs after the legacy import so imported entries are compressed too
+    let progress = ProgressReporter::new(app, Some("cache-compaction".to_string()));
+    if let Err(e) = store.compress_existing(&progress) {
+        tracing::warn!("Cache recompaction failed: {}", e);
+        progress.finish("compressing", 0, None, Some(e));
+    }
+
+    Ok(store)
+}
+
+impl CacheState {
+    pub fn open(app: &AppHandle) -> Result<Self, String> {
+        Ok(Self(Mutex::new(open_for_active_profile(app)?)))
+    }
+}
+
+/// Points the managed store at the active profile after a switch.
+pub fn reopen(app: &AppHandle) -> Result<(), String> {
+    let store = open_for_active_profile(app)?;
+    *app.state::<CacheState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = store;
+    Ok(())
+}
+
+/// Runs `f` against the active profile's store with exclusive access.
+pub fn with_store_mut<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut CacheStore) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = app.state::<CacheState>();
+    let mut store = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut store)
+}
+
+/// Runs `f` against the active profile's store.
+pub fn with_store<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&CacheStore) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = app.state::<CacheState>();
+    let store = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    f(&store)
+}
+
+/// [`with_store`] for async code: waiting for the lock and the SQLite I/O happen on the
+/// blocking thread pool.
+pub async fn with_store_blocking<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(&CacheStore) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let app = app.clone();
+    crate::storage::run_blocking(move || with_store(&app, f)).await
+}
+
+/// [`with_store_mut`] for async code
+pub async fn with_store_mut_blocking<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut CacheStore) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let app = app.clone();
+    crate::storage::run_blocking(move || with_store_mut(&app, f)).await
+}
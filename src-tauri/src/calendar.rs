@@ -0,0 +1,975 @@
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+
+const CALENDAR_FILE: &str = "calendar";
+
+/// How long fetched calendars are reused by the reminder scheduler
+const BUSY_CACHE_SECS: i64 = 15 * 60;
+
+/// Upper bound on occurrences expanded per recurring event
+const MAX_OCCURRENCES: usize = 1000;
+
+/// Calendar the habit blocks are written to in the system calendar
+const HABIT_CALENDAR_NAME: &str = "Lotus Routine";
+
+/// Length of the block written for each reminder
+const HABIT_BLOCK_SECS: i64 = 15 * 60;
+
+/// Calendars read for busy times and where habit blocks are written.
+///
+/// Besides the calendars on the device, iCalendar feeds can be read: the "secret address"
+/// of a Google calendar, a published iCloud or Outlook calendar, or a local `.ics` file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// `http(s)://` or `webcal://` feed URLs, or local file paths
+    pub sources: Vec<String>,
+    /// Reads busy times from the calendars on this device too, and writes habit blocks to
+    /// a calendar there instead of to a file
+    pub system_calendar: bool,
+    /// Holds reminders back while one of the calendars shows a meeting
+    pub defer_reminders_when_busy: bool,
+    /// File the habit calendar was last written to, for the OS calendar to subscribe to
+    pub habit_calendar_path: Option<String>,
+}
+
+/// A time range taken by a calendar event, as Unix timestamps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusySlot {
+    pub start: i64,
+    pub end: i64,
+    pub summary: Option<String>,
+}
+
+/// Busy slots fetched for the reminder scheduler, with the time they were fetched
+#[derive(Default)]
+pub struct CalendarState(Mutex<Option<(i64, Vec<BusySlot>)>>);
+
+#[derive(Default)]
+struct Event {
+    start: Option<i64>,
+    end: Option<i64>,
+    duration_secs: Option<i64>,
+    all_day: bool,
+    transparent: bool,
+    summary: Option<String>,
+    rule: Option<String>,
+    excluded: Vec<i64>,
+}
+
+/// Parses a DATE or DATE-TIME value. Times without a `Z` suffix, including those with a
+/// `TZID`, are read as local time.
+fn parse_time(value: &str) -> Option<(i64, bool)> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        let midnight = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?;
+        return Some((midnight.timestamp(), true));
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).timestamp(), false));
+    }
+
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((
+        Local.from_local_datetime(&time).earliest()?.timestamp(),
+        false,
+    ))
+}
+
+/// Parses durations such as `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Option<i64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in value.trim_start_matches(['+', 'P']).chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += amount
+                    * match unit {
+                        'W' => 7 * 86_400,
+                        'D' => 86_400,
+                        'H' => 3_600,
+                        'M' => 60,
+                        'S' => 1,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    Some(seconds)
+}
+
+fn parse_events(ics: &str) -> Vec<Event> {
+    // Folded lines continue with a leading space or tab
+    let unfolded = ics
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = name.split(';').next().unwrap_or(name).to_ascii_uppercase();
+
+        match (property.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => current = Some(Event::default()),
+            ("END", Some(_)) if value == "VEVENT" => events.extend(current.take()),
+            ("DTSTART", Some(event)) => {
+                if let Some((start, all_day)) = parse_time(value) {
+                    event.start = Some(start);
+                    event.all_day = all_day;
+                }
+            }
+            ("DTEND", Some(event)) => event.end = parse_time(value).map(|(end, _)| end),
+            ("DURATION", Some(event)) => event.duration_secs = parse_duration(value),
+            ("TRANSP", Some(event)) => event.transparent = value == "TRANSPARENT",
+            ("SUMMARY", Some(event)) => event.summary = Some(value.replace("\\,", ",")),
+            ("RRULE", Some(event)) => event.rule = Some(value.to_string()),
+            ("EXDATE", Some(event)) => event.excluded.extend(
+                value
+                    .split(',')
+                    .filter_map(|v| parse_time(v).map(|(t, _)| t)),
+            ),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    // BYDAY entries may carry an ordinal such as `1MO`, which is ignored here
+    match code.trim_start_matches(|c: char| c.is_ascii_digit() || c == '-' || c == '+') {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Start times of an event up to `until`. Daily and weekly rules are expanded; other
+/// frequencies only yield the first occurrence.
+fn occurrences(event: &Event, start: i64, until: i64) -> Vec<i64> {
+    let Some(rule) = &event.rule else {
+        return vec![start];
+    };
+
+    let parts: Vec<(&str, &str)> = rule.split(';').filter_map(|p| p.split_once('=')).collect();
+    let get = |key: &str| parts.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+    let step_days = match get("FREQ") {
+        Some("DAILY") => 1,
+        Some("WEEKLY") => 7,
+        _ => return vec![start],
+    };
+    let interval: i64 = get("INTERVAL")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1)
+        .max(1);
+    let count: Option<usize> = get("COUNT").and_then(|v| v.parse().ok());
+    let rule_until = get("UNTIL").and_then(parse_time).map(|(t, _)| t);
+    let until = rule_until.map_or(until, |rule_until| rule_until.min(until));
+
+    let Some(first) = Local.timestamp_opt(start, 0).single() else {
+        return vec![start];
+    };
+    let days: Vec<Weekday> = match get("BYDAY") {
+        Some(days) if step_days == 7 => days.split(',').filter_map(weekday).collect(),
+        _ => vec![first.weekday()],
+    };
+    let week_start =
+        first.date_naive() - Duration::days(i64::from(first.weekday().num_days_from_monday()));
+
+    let mut found = Vec::new();
+    let mut period = 0;
+    'outer: while found.len() < MAX_OCCURRENCES {
+        let candidates: Vec<NaiveDate> = if step_days == 7 {
+            let week = week_start + Duration::days(period * 7 * interval);
+            days.iter()
+                .map(|day| week + Duration::days(i64::from(day.num_days_from_monday())))
+                .collect()
+        } else {
+            vec![first.date_naive() + Duration::days(period * interval)]
+        };
+
+        for date in candidates {
+            let Some(occurrence) = Local
+                .from_local_datetime(&date.and_time(first.time()))
+                .earliest()
+                .map(|t| t.timestamp())
+            else {
+                continue;
+            };
+            if occurrence < start {
+                continue;
+            }
+            if occurrence > until || count.is_some_and(|count| found.len() >= count) {
+                break 'outer;
+            }
+            found.push(occurrence);
+        }
+
+        period += 1;
+    }
+
+    found
+}
+
+fn busy_slots(ics: &str, from: i64, to: i64) -> Vec<BusySlot> {
+    parse_events(ics)
+        .into_iter()
+        // All-day entries are mostly birthdays and holidays, not meetings
+        .filter(|event| !event.transparent && !event.all_day)
+        .flat_map(|event| {
+            let Some(start) = event.start else {
+                return Vec::new();
+            };
+            let length = event
+                .end
+                .map(|end| end - start)
+                .or(event.duration_secs)
+                .unwrap_or(0);
+
+            occurrences(&event, start, to)
+                .into_iter()
+                .filter(|occurrence| !event.excluded.contains(occurrence))
+                .filter(|occurrence| occurrence + length > from)
+                .map(|occurrence| BusySlot {
+                    start: occurrence,
+                    end: occurrence + length,
+                    summary: event.summary.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+async fn read_source(app: &AppHandle, source: &str) -> Result<String, String> {
+    let url = match source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => source.to_string(),
+    };
+
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return std::fs::read_to_string(source)
+            .map_err(|e| format!("Failed to read calendar '{}': {}", source, e));
+    }
+
+    let response = crate::http_client::client(app)
+        .get(&url)
+        .timeout(crate::http_client::REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch calendar: {}", e))?;
+
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read calendar: {}", e))
+}
+
+/// A reminder as a recurring event in the system calendar
+#[derive(Debug, Clone, Serialize)]
+struct HabitBlock {
+    title: String,
+    notes: Option<String>,
+    /// First occurrence, as a Unix timestamp
+    start: i64,
+    duration_secs: i64,
+    /// Weekdays it repeats on, 0 = Monday; every day when empty
+    days: Vec<u8>,
+}
+
+/// Blocks for the reminders, starting at their next day on or after today. The platforms
+/// count the start as an occurrence even when the rule would skip that day.
+fn habit_blocks(reminders: &[crate::reminders::Reminder]) -> Vec<HabitBlock> {
+    let today = Local::now().date_naive();
+
+    reminders
+        .iter()
+        .filter_map(|reminder| {
+            let Ok(time) = crate::reminders::parse_time(&reminder.time) else {
+                tracing::warn!(
+                    "Skipping reminder '{}' with invalid time",
+                    reminder.habit_name
+                );
+                return None;
+            };
+            let date = (0..7)
+                .map(|offset| today + Duration::days(offset))
+                .find(|date| {
+                    reminder.days.is_empty()
+                        || reminder
+                            .days
+                            .contains(&(date.weekday().num_days_from_monday() as u8))
+                })?;
+            let start = Local
+                .from_local_datetime(&date.and_time(time))
+                .earliest()?
+                .timestamp();
+
+            Some(HabitBlock {
+                title: reminder.habit_name.clone(),
+                notes: reminder.body.clone(),
+                start,
+                duration_secs: HABIT_BLOCK_SECS,
+                days: reminder.days.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The calendars on this device: EventKit on macOS and iOS, the appointment store on
+/// Windows and the calendar provider on Android. Habit blocks go to a calendar of the app's
+/// own, which is replaced on every sync and left out of busy times. Everything here blocks
+/// the calling thread.
+mod system {
+    use super::{BusySlot, HabitBlock};
+
+    #[cfg(any(target_vendor = "apple", windows, target_os = "android"))]
+    use super::HABIT_CALENDAR_NAME;
+
+    pub const AVAILABLE: bool = cfg!(any(target_vendor = "apple", windows, target_os = "android"));
+
+    #[cfg(target_vendor = "apple")]
+    fn event_store() -> Result<objc2::rc::Retained<objc2_event_kit::EKEventStore>, String> {
+        use block2::RcBlock;
+        use objc2::runtime::{Bool, NSObjectProtocol};
+        use objc2::sel;
+        use objc2_event_kit::{EKAuthorizationStatus, EKEntityType, EKEventStore};
+        use objc2_foundation::NSError;
+        use std::sync::mpsc;
+
+        let store = unsafe { EKEventStore::new() };
+        match unsafe { EKEventStore::authorizationStatusForEntityType(EKEntityType::Event) } {
+            EKAuthorizationStatus::FullAccess => return Ok(store),
+            EKAuthorizationStatus::NotDetermined => {}
+            _ => {
+                return Err(
+                    "Allow Lotus Routine to access your calendars in the system settings"
+                        .to_string(),
+                )
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        // Called once, on a queue of the framework's own, when the user answers
+        let completion = RcBlock::new(move |granted: Bool, _error: *mut NSError| {
+            let _ = sender.send(granted.as_bool());
+        });
+        if store.respondsToSelector(sel!(requestFullAccessToEventsWithCompletion:)) {
+            unsafe { store.requestFullAccessToEventsWithCompletion(RcBlock::as_ptr(&completion)) };
+        } else {
+            // Before macOS 14 and iOS 17
+            #[allow(deprecated)]
+            unsafe {
+                store.requestAccessToEntityType_completion(
+                    EKEntityType::Event,
+                    RcBlock::as_ptr(&completion),
+                )
+            };
+        }
+
+        match receiver.recv() {
+            Ok(true) => Ok(store),
+            Ok(false) => Err("Calendar access was not allowed".to_string()),
+            Err(e) => Err(format!("Failed to request calendar access: {}", e)),
+        }
+    }
+
+    #[cfg(target_vendor = "apple")]
+    pub fn busy_slots(from: i64, to: i64) -> Result<Vec<BusySlot>, String> {
+        use objc2_event_kit::EKEventAvailability;
+        use objc2_foundation::NSDate;
+
+        let store = event_store()?;
+        let events = unsafe {
+            let predicate = store.predicateForEventsWithStartDate_endDate_calendars(
+                &NSDate::dateWithTimeIntervalSince1970(from as f64),
+                &NSDate::dateWithTimeIntervalSince1970(to as f64),
+                None,
+            );
+            store.eventsMatchingPredicate(&predicate)
+        };
+
+        Ok(events
+            .iter()
+            .filter(|event| unsafe {
+                !event.isAllDay()
+                    && event.availability() != EKEventAvailability::Free
+                    && event
+                        .calendar()
+                        .is_none_or(|calendar| calendar.title().to_string() != HABIT_CALENDAR_NAME)
+            })
+            .map(|event| unsafe {
+                BusySlot {
+                    start: event.startDate().timeIntervalSince1970() as i64,
+                    end: event.endDate().timeIntervalSince1970() as i64,
+                    summary: Some(event.title().to_string()),
+                }
+            })
+            .collect())
+    }
+
+    #[cfg(target_vendor = "apple")]
+    fn recurrence_rule(days: &[u8]) -> objc2::rc::Retained<objc2_event_kit::EKRecurrenceRule> {
+        use objc2::AllocAnyThread;
+        use objc2_event_kit::{
+            EKRecurrenceDayOfWeek, EKRecurrenceFrequency, EKRecurrenceRule, EKWeekday,
+        };
+        use objc2_foundation::NSArray;
+
+        if days.is_empty() {
+            return unsafe {
+                EKRecurrenceRule::initRecurrenceWithFrequency_interval_end(
+                    EKRecurrenceRule::alloc(),
+                    EKRecurrenceFrequency::Daily,
+                    1,
+                    None,
+                )
+            };
+        }
+
+        // EventKit counts from Sunday = 1
+        let days: Vec<_> = days
+            .iter()
+            .map(|day| unsafe {
+                EKRecurrenceDayOfWeek::dayOfWeek(EKWeekday(isize::from((day + 1) % 7 + 1)))
+            })
+            .collect();
+        unsafe {
+            EKRecurrenceRule::initRecurrenceWithFrequency_interval_daysOfTheWeek_daysOfTheMonth_monthsOfTheYear_weeksOfTheYear_daysOfTheYear_setPositions_end(
+                EKRecurrenceRule::alloc(),
+                EKRecurrenceFrequency::Weekly,
+                1,
+                Some(&NSArray::from_retained_slice(&days)),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+        }
+    }
+
+    #[cfg(target_vendor = "apple")]
+    pub fn write_blocks(blocks: &[HabitBlock]) -> Result<(), String> {
+        use objc2_event_kit::{
+            EKCalendar, EKEntityType, EKEvent, EKEventAvailability, EKSourceType, EKSpan,
+        };
+        use objc2_foundation::{NSDate, NSError, NSString};
+
+        fn failed(action: &str) -> impl Fn(objc2::rc::Retained<NSError>) -> String + '_ {
+            move |e| format!("Failed to {}: {}", action, e.localizedDescription())
+        }
+
+        let store = event_store()?;
+        unsafe {
+            for calendar in store.calendarsForEntityType(EKEntityType::Event).iter() {
+                if calendar.title().to_string() == HABIT_CALENDAR_NAME
+                    && calendar.allowsContentModifications()
+                {
+                    store
+                        .removeCalendar_commit_error(&calendar, false)
+                        .map_err(failed("replace the habit calendar"))?;
+                }
+            }
+
+            // Next to the default calendar, so it syncs the same way, e.g. through iCloud
+            let source = store
+                .defaultCalendarForNewEvents()
+                .and_then(|calendar| calendar.source())
+                .or_else(|| {
+                    store
+                        .sources()
+                        .iter()
+                        .find(|source| source.sourceType() == EKSourceType::Local)
+                })
+                .ok_or_else(|| "No calendar account to add the habit calendar to".to_string())?;
+            let calendar =
+                EKCalendar::calendarForEntityType_eventStore(EKEntityType::Event, &store);
+            calendar.setTitle(&NSString::from_str(HABIT_CALENDAR_NAME));
+            calendar.setSource(Some(&source));
+            store
+                .saveCalendar_commit_error(&calendar, false)
+                .map_err(failed("create the habit calendar"))?;
+
+            for block in blocks {
+                let event = EKEvent::eventWithEventStore(&store);
+                event.setCalendar(Some(&calendar));
+                event.setTitle(Some(&NSString::from_str(&block.title)));
+                if let Some(notes) = &block.notes {
+                    event.setNotes(Some(&NSString::from_str(notes)));
+                }
+                event.setStartDate(Some(&NSDate::dateWithTimeIntervalSince1970(
+                    block.start as f64,
+                )));
+                event.setEndDate(Some(&NSDate::dateWithTimeIntervalSince1970(
+                    (block.start + block.duration_secs) as f64,
+                )));
+                event.setAvailability(EKEventAvailability::Free);
+                event.addRecurrenceRule(&recurrence_rule(&block.days));
+                store
+                    .saveEvent_span_commit_error(&event, EKSpan::FutureEvents, false)
+                    .map_err(failed("add a habit block"))?;
+            }
+
+            store.commit().map_err(failed("save the habit calendar"))
+        }
+    }
+
+    /// 100-nanosecond ticks between 1601, where Windows times start, and 1970
+    #[cfg(windows)]
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+    #[cfg(windows)]
+    const TICKS_PER_SECOND: i64 = 10_000_000;
+
+    #[cfg(windows)]
+    fn date_time(timestamp: i64) -> windows::Foundation::DateTime {
+        windows::Foundation::DateTime {
+            UniversalTime: timestamp * TICKS_PER_SECOND + UNIX_EPOCH_TICKS,
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn busy_slots(from: i64, to: i64) -> Result<Vec<BusySlot>, String> {
+        use windows::ApplicationModel::Appointments::{
+            AppointmentBusyStatus, AppointmentManager, AppointmentProperties,
+            AppointmentStoreAccessType, FindAppointmentsOptions,
+        };
+        use windows::Foundation::TimeSpan;
+
+        let read = || -> windows::core::Result<Vec<BusySlot>> {
+            let store = AppointmentManager::RequestStoreAsync(
+                AppointmentStoreAccessType::AllCalendarsReadOnly,
+            )?
+            .get()?;
+            let mut own = Vec::new();
+            for calendar in store.FindAppointmentCalendarsAsync()?.get()? {
+                if calendar.DisplayName()? == HABIT_CALENDAR_NAME {
+                    own.push(calendar.LocalId()?);
+                }
+            }
+
+            let options = FindAppointmentsOptions::new()?;
+            let properties = options.FetchProperties()?;
+            for property in [
+                AppointmentProperties::StartTime()?,
+                AppointmentProperties::Duration()?,
+                AppointmentProperties::Subject()?,
+                AppointmentProperties::AllDay()?,
+                AppointmentProperties::BusyStatus()?,
+            ] {
+                properties.Append(&property)?;
+            }
+            let appointments = store
+                .FindAppointmentsAsyncWithOptions(
+                    date_time(from),
+                    TimeSpan {
+                        Duration: (to - from) * TICKS_PER_SECOND,
+                    },
+                    &options,
+                )?
+                .get()?;
+
+            let mut slots = Vec::new();
+            for appointment in appointments {
+                if appointment.AllDay()?
+                    || appointment.BusyStatus()? == AppointmentBusyStatus::Free
+                    || own.contains(&appointment.CalendarId()?)
+                {
+                    continue;
+                }
+                let start =
+                    (appointment.StartTime()?.UniversalTime - UNIX_EPOCH_TICKS) / TICKS_PER_SECOND;
+                slots.push(BusySlot {
+                    start,
+                    end: start + appointment.Duration()?.Duration / TICKS_PER_SECOND,
+                    summary: Some(appointment.Subject()?.to_string()),
+                });
+            }
+            Ok(slots)
+        };
+
+        read().map_err(|e| format!("Failed to read the Windows calendar: {}", e.message()))
+    }
+
+    #[cfg(windows)]
+    pub fn write_blocks(blocks: &[HabitBlock]) -> Result<(), String> {
+        use windows::core::HSTRING;
+        use windows::ApplicationModel::Appointments::{
+            Appointment, AppointmentBusyStatus, AppointmentDaysOfWeek, AppointmentManager,
+            AppointmentRecurrence, AppointmentRecurrenceUnit, AppointmentStoreAccessType,
+        };
+        use windows::Foundation::TimeSpan;
+
+        let write = || -> windows::core::Result<()> {
+            let store = AppointmentManager::RequestStoreAsync(
+                AppointmentStoreAccessType::AppCalendarsReadWrite,
+            )?
+            .get()?;
+            for calendar in store.FindAppointmentCalendarsAsync()?.get()? {
+                if calendar.DisplayName()? == HABIT_CALENDAR_NAME {
+                    calendar.DeleteAsync()?.get()?;
+                }
+            }
+            let calendar = store
+                .CreateAppointmentCalendarAsync(&HSTRING::from(HABIT_CALENDAR_NAME))?
+                .get()?;
+
+            for block in blocks {
+                let recurrence = AppointmentRecurrence::new()?;
+                recurrence.SetInterval(1)?;
+                if block.days.is_empty() {
+                    recurrence.SetUnit(AppointmentRecurrenceUnit::Daily)?;
+                } else {
+                    // Windows sets one bit per day, starting with Sunday
+                    let days = block
+                        .days
+                        .iter()
+                        .fold(0, |days, day| days | 1 << ((day + 1) % 7));
+                    recurrence.SetUnit(AppointmentRecurrenceUnit::Weekly)?;
+                    recurrence.SetDaysOfWeek(AppointmentDaysOfWeek(days))?;
+                }
+
+                let appointment = Appointment::new()?;
+                appointment.SetSubject(&HSTRING::from(&block.title))?;
+                if let Some(notes) = &block.notes {
+                    appointment.SetDetails(&HSTRING::from(notes))?;
+                }
+                appointment.SetStartTime(date_time(block.start))?;
+                appointment.SetDuration(TimeSpan {
+                    Duration: block.duration_secs * TICKS_PER_SECOND,
+                })?;
+                appointment.SetBusyStatus(AppointmentBusyStatus::Free)?;
+                appointment.SetRecurrence(&recurrence)?;
+                calendar.SaveAppointmentAsync(&appointment)?.get()?;
+            }
+            Ok(())
+        };
+
+        write().map_err(|e| format!("Failed to write the Windows calendar: {}", e.message()))
+    }
+
+    /// Calls the Kotlin `CalendarBridge` helper, which is only visible through the app's
+    /// class loader, once calendar access has been granted.
+    #[cfg(target_os = "android")]
+    fn call_bridge<T>(
+        call: impl FnOnce(
+            &mut jni::JNIEnv,
+            &jni::objects::JObject,
+            &jni::objects::JClass,
+        ) -> jni::errors::Result<T>,
+    ) -> Result<T, String> {
+        use jni::objects::{JClass, JObject, JString, JValue};
+
+        let context = ndk_context::android_context();
+        let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+            .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+        let activity = unsafe { JObject::from_raw(context.context().cast()) };
+        let mut env = vm
+            .attach_current_thread()
+            .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+        let class = env
+            .call_method(
+                &activity,
+                "getClassLoader",
+                "()Ljava/lang/ClassLoader;",
+                &[],
+            )
+            .and_then(|loader| loader.l())
+            .and_then(|loader| {
+                let name = env.new_string("com.th3void.lotusroutine.CalendarBridge")?;
+                env.call_method(
+                    &loader,
+                    "loadClass",
+                    "(Ljava/lang/String;)Ljava/lang/Class;",
+                    &[JValue::from(&name)],
+                )
+            })
+            .and_then(|class| class.l())
+            .map(JClass::from);
+        // The answer to the permission prompt arrives after this returns, so the user
+        // tries again afterwards
+        let granted = class.as_ref().map_err(|_| ()).and_then(|class| {
+            env.call_static_method(
+                class,
+                "ensureAccess",
+                "(Landroid/app/Activity;)Z",
+                &[JValue::from(&activity)],
+            )
+            .and_then(|granted| granted.z())
+            .map_err(|_| ())
+        });
+
+        let result = match (class, granted) {
+            (Ok(_), Ok(false)) => return Err("Allow calendar access, then try again".to_string()),
+            (Ok(class), Ok(true)) => call(&mut env, &activity, &class),
+            (Err(e), _) => Err(e),
+            (Ok(_), Err(())) => Err(jni::errors::Error::JavaException),
+        };
+        match result {
+            Ok(value) => Ok(value),
+            Err(jni::errors::Error::JavaException) => {
+                let message = env.exception_occurred().and_then(|exception| {
+                    env.exception_clear()?;
+                    let message: JString = env
+                        .call_method(&exception, "getMessage", "()Ljava/lang/String;", &[])?
+                        .l()?
+                        .into();
+                    let message: String = env.get_string(&message)?.into();
+                    Ok(message)
+                });
+                let _ = env.exception_clear();
+                Err(message.unwrap_or_else(|_| "The calendar reported an error".to_string()))
+            }
+            Err(e) => {
+                let _ = env.exception_clear();
+                Err(format!("Failed to reach the calendar: {}", e))
+            }
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    pub fn busy_slots(from: i64, to: i64) -> Result<Vec<BusySlot>, String> {
+        use jni::objects::{JString, JValue};
+
+        let json = call_bridge(|env, activity, class| {
+            let name = env.new_string(HABIT_CALENDAR_NAME)?;
+            let json: JString = env
+                .call_static_method(
+                    class,
+                    "busySlots",
+                    "(Landroid/app/Activity;Ljava/lang/String;JJ)Ljava/lang/String;",
+                    &[
+                        JValue::from(activity),
+                        JValue::from(&name),
+                        JValue::Long(from),
+                        JValue::Long(to),
+                    ],
+                )?
+                .l()?
+                .into();
+            let json: String = env.get_string(&json)?.into();
+            Ok(json)
+        })?;
+
+        serde_json::from_str(&json).map_err(|e| format!("Failed to read calendar events: {}", e))
+    }
+
+    #[cfg(target_os = "android")]
+    pub fn write_blocks(blocks: &[HabitBlock]) -> Result<(), String> {
+        use jni::objects::JValue;
+
+        let json = serde_json::to_string(blocks)
+            .map_err(|e| format!("Failed to serialize habit blocks: {}", e))?;
+        call_bridge(|env, activity, class| {
+            let name = env.new_string(HABIT_CALENDAR_NAME)?;
+            let json = env.new_string(&json)?;
+            env.call_static_method(
+                class,
+                "writeBlocks",
+                "(Landroid/app/Activity;Ljava/lang/String;Ljava/lang/String;)V",
+                &[
+                    JValue::from(activity),
+                    JValue::from(&name),
+                    JValue::from(&json),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    #[cfg(not(any(target_vendor = "apple", windows, target_os = "android")))]
+    pub fn busy_slots(_from: i64, _to: i64) -> Result<Vec<BusySlot>, String> {
+        Err("The system calendar is not available on this platform".to_string())
+    }
+
+    #[cfg(not(any(target_vendor = "apple", windows, target_os = "android")))]
+    pub fn write_blocks(_blocks: &[HabitBlock]) -> Result<(), String> {
+        Err("The system calendar is not available on this platform".to_string())
+    }
+}
+
+async fn collect_busy_slots(
+    app: &AppHandle,
+    from: i64,
+    to: i64,
+) -> Result<Vec<BusySlot>, AppError> {
+    let config: CalendarConfig = crate::read_state_file_blocking(app, CALENDAR_FILE).await?;
+
+    let mut slots = Vec::new();
+    for source in &config.sources {
+        match read_source(app, source).await {
+            Ok(ics) => slots.extend(busy_slots(&ics, from, to)),
+            // One unreachable calendar should not hide the others
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    if config.system_calendar && system::AVAILABLE {
+        match tauri::async_runtime::spawn_blocking(move || system::busy_slots(from, to)).await {
+            Ok(Ok(system_slots)) => slots.extend(system_slots),
+            Ok(Err(e)) => tracing::warn!("Failed to read the system calendar: {}", e),
+            Err(e) => tracing::warn!("Failed to read the system calendar: {}", e),
+        }
+    }
+
+    slots.sort_by_key(|slot| slot.start);
+    Ok(slots)
+}
+
+/// Whether a calendar event is under way, for holding reminders back during meetings.
+/// Calendars are fetched at most every few minutes; failures count as free.
+pub async fn is_busy_now(app: &AppHandle) -> bool {
+    let config: CalendarConfig = crate::read_state_file_blocking(app, CALENDAR_FILE)
+        .await
+        .unwrap_or_default();
+    if !config.defer_reminders_when_busy || (config.sources.is_empty() && !config.system_calendar) {
+        return false;
+    }
+
+    let now = Utc::now().timestamp();
+    let state = app.state::<CalendarState>();
+    let cached = state
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .filter(|(fetched_at, _)| now - fetched_at < BUSY_CACHE_SECS);
+
+    let slots = match cached {
+        Some((_, slots)) => slots,
+        None => {
+            let slots = collect_busy_slots(app, now, now + BUSY_CACHE_SECS * 2)
+                .await
+                .unwrap_or_default();
+            *state.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((now, slots.clone()));
+            slots
+        }
+    };
+
+    slots.iter().any(|slot| slot.start <= now && now < slot.end)
+}
+
+fn parse_bound(value: &str) -> Result<i64, AppError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| time.timestamp())
+        .ok_or_else(|| AppError::invalid_input(format!("Invalid date '{}'", value)))
+}
+
+#[tauri::command]
+pub async fn get_calendar_config(app: AppHandle) -> Result<CalendarConfig, AppError> {
+    crate::read_state_file_blocking(&app, CALENDAR_FILE).await
+}
+
+#[tauri::command]
+pub async fn set_calendar_config(
+    app: AppHandle,
+    state: State<'_, CalendarState>,
+    config: CalendarConfig,
+) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    crate::write_state_file_blocking(&app, CALENDAR_FILE, &config).await?;
+    *state.0.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    Ok(())
+}
+
+/// Busy times between `start` and `end` (RFC 3339 timestamps or `YYYY-MM-DD` dates)
+/// across the configured calendars.
+#[tauri::command]
+pub async fn get_calendar_busy_slots(
+    app: AppHandle,
+    start: String,
+    end: String,
+) -> Result<Vec<BusySlot>, AppError> {
+    let (from, to) = (parse_bound(&start)?, parse_bound(&end)?);
+    if to <= from {
+        return Err(AppError::invalid_input(
+            "Calendar range must end after it starts",
+        ));
+    }
+
+    collect_busy_slots(&app, from, to).await
+}
+
+/// Writes habits and reminders to an `.ics` file the OS calendar can subscribe to, reusing
+/// the last location when `path` is not given. With the system calendar turned on and no
+/// `path`, the reminders are written there as recurring 15-minute blocks instead. Returns
+/// the file or the system calendar written.
+#[tauri::command]
+pub async fn sync_habits_to_calendar(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let mut config: CalendarConfig = crate::read_state_file_blocking(&app, CALENDAR_FILE).await?;
+    if path.is_none() && config.system_calendar {
+        if !system::AVAILABLE {
+            return Err(AppError::unsupported(
+                "The system calendar is not available on this platform",
+            ));
+        }
+
+        let blocks = habit_blocks(&crate::reminders::reminders(&app).await?);
+        tauri::async_runtime::spawn_blocking(move || system::write_blocks(&blocks))
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to write habit calendar: {}", e)))?
+            .map_err(AppError::internal)?;
+        return Ok(HABIT_CALENDAR_NAME.to_string());
+    }
+
+    let path = path
+        .or_else(|| config.habit_calendar_path.clone())
+        .ok_or_else(|| AppError::invalid_input("No habit calendar file chosen yet"))?;
+
+    let habits = crate::habits::all_habits(&app, false).await?;
+    let reminders = crate::reminders::reminders(&app).await?;
+    let calendar = crate::ical::render(&habits, &reminders);
+    let target = path.clone();
+    crate::storage::run_blocking(move || {
+        std::fs::write(&target, calendar)
+            .map_err(|e| AppError::storage(format!("Failed to write habit calendar: {}", e)))
+    })
+    .await?;
+
+    if config.habit_calendar_path.as_deref() != Some(path.as_str()) {
+        config.habit_calendar_path = Some(path.clone());
+        crate::write_state_file_blocking(&app, CALENDAR_FILE, &config).await?;
+    }
+
+    Ok(path)
+}
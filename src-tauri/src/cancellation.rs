@@ -0,0 +1,102 @@
+use futures_util::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{AppError, ErrorKind};
+
+/// Operations in progress that the frontend can cancel, keyed by the request ID it
+/// passed in
+#[derive(Default)]
+pub struct RequestRegistry {
+    requests: Mutex<HashMap<String, (u64, AbortHandle)>>,
+    /// Tells registrations apart when a request ID is reused after a cancellation
+    next_token: AtomicU64,
+}
+
+/// Removes a request from the registry however its operation ends.
+struct Registration<'a> {
+    registry: &'a RequestRegistry,
+    request_id: String,
+    token: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut requests = self
+            .registry
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if requests
+            .get(&self.request_id)
+            .is_some_and(|(token, _)| *token == self.token)
+        {
+            requests.remove(&self.request_id);
+        }
+    }
+}
+
+/// Runs `operation`, letting [`cancel_request`] abort it under `request_id`. Aborting
+/// drops the operation along with any network request it is waiting on.
+pub async fn cancellable<T, E: Into<AppError>>(
+    app: &AppHandle,
+    request_id: Option<String>,
+    operation: impl Future<Output = Result<T, E>>,
+) -> Result<T, AppError> {
+    let Some(request_id) = request_id else {
+        return operation.await.map_err(Into::into);
+    };
+
+    let registry = app.state::<RequestRegistry>().inner();
+    let (handle, abort_registration) = AbortHandle::new_pair();
+    let token = registry.next_token.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut requests = registry.requests.lock().unwrap_or_else(|e| e.into_inner());
+        if requests.contains_key(&request_id) {
+            return Err(AppError::invalid_state(format!(
+                "Request {} is already in progress",
+                request_id
+            )));
+        }
+        requests.insert(request_id.clone(), (token, handle));
+    }
+
+    let _registration = Registration {
+        registry,
+        request_id: request_id.clone(),
+        token,
+    };
+    match Abortable::new(operation, abort_registration).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(_) => Err(AppError::new(
+            ErrorKind::Cancelled,
+            format!("Request {} was cancelled", request_id),
+        )
+        .with_context(request_id)),
+    }
+}
+
+/// Aborts the operation started with `request_id`, which then fails with a cancellation
+/// error. Returns whether one was in progress.
+#[tauri::command]
+pub async fn cancel_request(
+    registry: State<'_, RequestRegistry>,
+    request_id: String,
+) -> Result<bool, AppError> {
+    let request = registry
+        .requests
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&request_id);
+
+    Ok(match request {
+        Some((_, handle)) => {
+            handle.abort();
+            true
+        }
+        None => false,
+    })
+}
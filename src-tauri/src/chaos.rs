@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{AppError, FetchError};
+
+/// Fault-injection settings for the fetch layer. All probabilities are in `0.0..=1.0`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    pub enabled: bool,
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub drop_probability: f64,
+    pub error_probability: f64,
+    pub truncate_probability: f64,
+}
+
+#[derive(Default)]
+pub struct ChaosState(RwLock<ChaosConfig>);
+
+impl ChaosState {
+    fn config(&self) -> ChaosConfig {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn roll(probability: f64) -> bool {
+    probability > 0.0 && fastrand::f64() < probability
+}
+
+/// Applies latency, dropped requests, and server errors before a request is sent.
+pub async fn before_request(app: &AppHandle) -> Result<(), FetchError> {
+    let config = app.state::<ChaosState>().config();
+    if !config.enabled {
+        return Ok(());
+    }
+
+    if config.max_latency_ms > 0 {
+        let min = config.min_latency_ms.min(config.max_latency_ms);
+        let latency = fastrand::u64(min..=config.max_latency_ms);
+        tokio::time::sleep(std::time::Duration::from_millis(latency)).await;
+    }
+
+    if roll(config.drop_probability) {
+        return Err(FetchError::Network {
+            message: "Network request failed: connection dropped (chaos mode)".to_string(),
+        });
+    }
+
+    if roll(config.error_probability) {
+        return Err(FetchError::from_response(
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            r#"{"message":"Injected server error (chaos mode)"}"#,
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Cuts a response body short to exercise partial-read handling.
+pub fn mangle_body(app: &AppHandle, mut body: String) -> String {
+    let config = app.state::<ChaosState>().config();
+
+    if config.enabled && !body.is_empty() && roll(config.truncate_probability) {
+        let mut cut = fastrand::usize(0..body.len());
+        while !body.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        body.truncate(cut);
+    }
+
+    body
+}
+
+#[tauri::command]
+pub async fn set_chaos_config(
+    state: State<'_, ChaosState>,
+    config: ChaosConfig,
+) -> Result<(), AppError> {
+    if !cfg!(debug_assertions) {
+        return Err(AppError::unsupported(
+            "Chaos mode is only available in debug builds",
+        ));
+    }
+
+    let probabilities = [
+        config.drop_probability,
+        config.error_probability,
+        config.truncate_probability,
+    ];
+    if probabilities.iter().any(|p| !(0.0..=1.0).contains(p)) {
+        return Err(AppError::invalid_input(
+            "Chaos probabilities must be between 0 and 1",
+        ));
+    }
+
+    *state.0.write().unwrap_or_else(|e| e.into_inner()) = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_chaos_config(state: State<'_, ChaosState>) -> Result<ChaosConfig, AppError> {
+    Ok(state.config())
+}
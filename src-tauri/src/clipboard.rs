@@ -0,0 +1,69 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::image::Image;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::error::AppError;
+
+/// Clipboard contents; writes take exactly one of the fields
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardContent {
+    pub text: Option<String>,
+    /// PNG image, base64-encoded or as a `data:` URL when copying, e.g. a streak card drawn
+    /// on a canvas. Always plain base64 when read.
+    pub image: Option<String>,
+}
+
+fn encode_png(image: &Image<'_>) -> Result<String, AppError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(image.rgba()))
+        .map_err(|e| AppError::internal(format!("Failed to encode PNG: {}", e)))?;
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// Puts text or an image on the system clipboard. Images can only be copied on desktop.
+#[tauri::command]
+pub async fn copy_to_clipboard(app: AppHandle, content: ClipboardContent) -> Result<(), AppError> {
+    let clipboard = app.clipboard();
+
+    match (content.text, content.image) {
+        (Some(text), None) => clipboard.write_text(text),
+        (None, Some(image)) => {
+            let bytes = crate::share::decode_image(&image)?;
+            let image = Image::from_bytes(&bytes)
+                .map_err(|e| AppError::invalid_input(format!("Invalid image: {}", e)))?;
+            clipboard.write_image(&image)
+        }
+        (None, None) => return Err(AppError::invalid_input("Nothing to copy")),
+        (Some(_), Some(_)) => {
+            return Err(AppError::invalid_input(
+                "Copy either text or an image, not both",
+            ))
+        }
+    }
+    .map_err(|e| AppError::internal(format!("Failed to write to clipboard: {}", e)))
+}
+
+/// Returns the text and image currently on the clipboard, whichever are present.
+#[tauri::command]
+pub async fn read_clipboard(app: AppHandle) -> Result<ClipboardContent, AppError> {
+    let clipboard = app.clipboard();
+
+    // Reads fail when the clipboard holds nothing of that kind, and image reads always
+    // fail on mobile
+    let text = clipboard.read_text().ok().filter(|text| !text.is_empty());
+    let image = match clipboard.read_image() {
+        Ok(image) => Some(encode_png(&image)?),
+        Err(_) => None,
+    };
+
+    Ok(ClipboardContent { text, image })
+}
@@ -0,0 +1,222 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+
+use crate::cache_store::with_store_blocking;
+use crate::error::AppError;
+use crate::outbox::PendingMutation;
+
+/// Emitted with the new [`SyncConflict`] when a queued change is set aside for the user
+pub const SYNC_CONFLICT_EVENT: &str = "sync-conflict";
+
+/// A queued change the server refused because its copy changed in the meantime
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub id: i64,
+    pub method: String,
+    pub url: String,
+    pub cache_key: Option<String>,
+    /// Body of the queued change
+    pub local: Option<serde_json::Value>,
+    /// Current server copy, if it could be fetched when the conflict was detected
+    pub server: Option<serde_json::Value>,
+    pub created_at: i64,
+    pub detected_at: i64,
+}
+
+/// How the user settled a conflict
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "choice", rename_all = "lowercase")]
+pub enum ConflictResolution {
+    /// Send the queued change again, overwriting the server copy
+    Local,
+    /// Keep the server copy and drop the queued change
+    Server,
+    /// Send a merged body instead of the queued one
+    Merged { body: serde_json::Value },
+}
+
+/// Moves a conflicting mutation out of the queue, keeping the server copy next to it.
+pub async fn record(app: &AppHandle, mutation: &PendingMutation) -> Result<(), AppError> {
+    let method = crate::outbox::parse_method(&mutation.method)?;
+
+    let server = if method == reqwest::Method::DELETE || method == reqwest::Method::POST {
+        None
+    } else {
+        match crate::send_online_request(
+            app,
+            reqwest::Method::GET,
+            &mutation.url,
+            mutation.headers.as_ref(),
+            None,
+        )
+        .await
+        {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!("Failed to fetch server copy for conflict: {}", e);
+                None
+            }
+        }
+    };
+
+    let conflict = SyncConflict {
+        id: 0,
+        method: mutation.method.clone(),
+        url: mutation.url.clone(),
+        cache_key: mutation.cache_key.clone(),
+        local: mutation.body.clone(),
+        server,
+        created_at: mutation.created_at,
+        detected_at: chrono::Utc::now().timestamp(),
+    };
+
+    // Resolving replays the change with the session's token at that time
+    let mut headers = mutation.headers.clone();
+    if let Some(headers) = &mut headers {
+        crate::outbox::strip_credentials(headers);
+    }
+    let headers = headers
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize headers: {}", e)))?;
+    let body = conflict
+        .local
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize body: {}", e)))?;
+    let server_data = conflict
+        .server
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize server data: {}", e)))?;
+
+    let stored = conflict.clone();
+    let id = with_store_blocking(app, move |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO conflicts
+                    (method, url, headers, body, cache_key, server_data, created_at, detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    stored.method,
+                    stored.url,
+                    headers,
+                    body,
+                    stored.cache_key,
+                    server_data,
+                    stored.created_at,
+                    stored.detected_at
+                ],
+            )
+            .map_err(|e| format!("Failed to record conflict: {}", e))?;
+        Ok(store.connection().last_insert_rowid())
+    })
+    .await
+    .map_err(AppError::storage)?;
+
+    let conflict = SyncConflict { id, ..conflict };
+    if let Err(e) = app.emit(SYNC_CONFLICT_EVENT, &conflict) {
+        tracing::warn!("Failed to emit sync conflict: {}", e);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_conflicts(app: AppHandle) -> Result<Vec<SyncConflict>, AppError> {
+    with_store_blocking(&app, |store| {
+        let mut statement = store
+            .connection()
+            .prepare(
+                "SELECT id, method, url, cache_key, body, server_data, created_at, detected_at
+                 FROM conflicts ORDER BY id",
+            )
+            .map_err(|e| format!("Failed to query conflicts: {}", e))?;
+
+        let rows = statement
+            .query_map([], |row| {
+                let body: Option<String> = row.get(4)?;
+                let server: Option<String> = row.get(5)?;
+                Ok(SyncConflict {
+                    id: row.get(0)?,
+                    method: row.get(1)?,
+                    url: row.get(2)?,
+                    cache_key: row.get(3)?,
+                    local: body.and_then(|b| serde_json::from_str(&b).ok()),
+                    server: server.and_then(|s| serde_json::from_str(&s).ok()),
+                    created_at: row.get(6)?,
+                    detected_at: row.get(7)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query conflicts: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read conflicts: {}", e))
+    })
+    .await
+    .map_err(AppError::storage)
+}
+
+/// Settles a conflict; local and merged choices are queued again without preconditions.
+#[tauri::command]
+pub async fn resolve_conflict(
+    app: AppHandle,
+    id: i64,
+    resolution: ConflictResolution,
+) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let (method, url, headers, body) = with_store_blocking(&app, move |store| {
+        store
+            .connection()
+            .query_row(
+                "SELECT method, url, headers, body FROM conflicts WHERE id = ?1",
+                params![id],
+                |row| {
+                    let headers: Option<String> = row.get(2)?;
+                    let body: Option<String> = row.get(3)?;
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        headers
+                            .and_then(|h| serde_json::from_str::<HashMap<String, String>>(&h).ok()),
+                        body.and_then(|b| serde_json::from_str::<serde_json::Value>(&b).ok()),
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read conflict: {}", e))
+    })
+    .await
+    .map_err(AppError::storage)?
+    .ok_or_else(|| AppError::not_found(format!("Unknown conflict: {}", id)))?;
+
+    let body = match resolution {
+        ConflictResolution::Local => Some(body),
+        ConflictResolution::Server => None,
+        ConflictResolution::Merged { body } => Some(Some(body)),
+    };
+
+    if let Some(body) = body {
+        crate::outbox::enqueue(&app, &method, &url, headers.as_ref(), body.as_ref(), None).await?;
+    }
+
+    with_store_blocking(&app, move |store| {
+        store
+            .connection()
+            .execute("DELETE FROM conflicts WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to remove conflict: {}", e))?;
+        Ok(())
+    })
+    .await
+    .map_err(AppError::storage)?;
+
+    crate::outbox::schedule_flush(&app);
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use chrono::Datelike;
+use tauri::menu::{AboutMetadata, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::{AppHandle, LogicalPosition, Manager, Webview, WebviewWindow, Window, Wry};
+
+use crate::error::AppError;
+use crate::i18n::{text, text_with};
+
+const BACK_ID: &str = "context-back";
+const FORWARD_ID: &str = "context-forward";
+const REFRESH_ID: &str = "context-refresh";
+
+/// Replaces the webview's own menu with the native one on right click
+const CONTEXT_MENU_SCRIPT: &str = r#"
+    (function() {
+        if (window.__tauriContextMenu) return;
+        window.__tauriContextMenu = true;
+
+        document.addEventListener('contextmenu', (e) => {
+            e.preventDefault();
+            window.__TAURI_INTERNALS__
+                .invoke('show_context_menu', { x: e.clientX, y: e.clientY })
+                .catch(() => {});
+        });
+    })();
+"#;
+
+/// Details shown by the About item, also used by the application menu.
+pub fn about_metadata(app: &AppHandle) -> AboutMetadata<'_> {
+    let year = chrono::Local::now().year();
+
+    AboutMetadata {
+        name: Some(app.package_info().name.clone()),
+        version: Some(app.package_info().version.to_string()),
+        comments: Some(text(app, "about.comments")),
+        copyright: Some(text_with(
+            app,
+            "about.copyright",
+            &[("year", &year.to_string())],
+        )),
+        icon: app.default_window_icon().cloned(),
+        ..Default::default()
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let back = MenuItem::with_id(app, BACK_ID, text(app, "context.back"), true, None::<&str>)?;
+    let forward = MenuItem::with_id(
+        app,
+        FORWARD_ID,
+        text(app, "context.forward"),
+        true,
+        None::<&str>,
+    )?;
+    let refresh = MenuItem::with_id(
+        app,
+        REFRESH_ID,
+        text(app, "context.refresh"),
+        true,
+        None::<&str>,
+    )?;
+
+    Menu::with_items(
+        app,
+        &[
+            &back,
+            &forward,
+            &refresh,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::copy(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::about(
+                app,
+                Some(&text(app, "context.about")),
+                Some(about_metadata(app)),
+            )?,
+        ],
+    )
+}
+
+fn handle_menu_event(window: &WebviewWindow, event: MenuEvent) {
+    let script = match event.id.as_ref() {
+        BACK_ID => "window.history.back()".to_string(),
+        FORWARD_ID => "window.history.forward()".to_string(),
+        REFRESH_ID => {
+            let url = crate::website_url(window.app_handle());
+            match serde_json::to_string(&url) {
+                Ok(url) => format!("window.location.href = {}", url),
+                Err(e) => {
+                    tracing::warn!("Failed to encode website URL: {}", e);
+                    return;
+                }
+            }
+        }
+        _ => return,
+    };
+
+    if let Err(e) = window.eval(script) {
+        tracing::warn!("Failed to run context menu action: {}", e);
+    }
+}
+
+/// Routes the context menu's items to the main window.
+pub fn watch(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_menu_event(move |_, event| handle_menu_event(&handle, event));
+}
+
+/// Hooks right clicks on a freshly loaded page up to the native menu.
+pub fn apply_to_page(webview: &Webview) {
+    if let Err(e) = webview.eval(CONTEXT_MENU_SCRIPT) {
+        tracing::warn!("Failed to install context menu: {}", e);
+    }
+}
+
+/// Opens the native context menu at a point in the page, in CSS pixels.
+#[tauri::command]
+pub async fn show_context_menu(
+    app: AppHandle,
+    window: Window,
+    x: f64,
+    y: f64,
+) -> Result<(), AppError> {
+    let menu = build_menu(&app)
+        .map_err(|e| AppError::internal(format!("Failed to build context menu: {}", e)))?;
+    window
+        .popup_menu_at(&menu, LogicalPosition::new(x, y))
+        .map_err(|e| AppError::internal(format!("Failed to show context menu: {}", e)))
+}
@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+const CRASH_DIR: &str = "crashes";
+const CRASH_REPORT_ROUTE: &str = "/api/crash-reports";
+
+/// Log lines saved with each report
+const LOG_TAIL_LINES: usize = 200;
+
+/// Reports kept on disk; the oldest are deleted when a new one is written
+const MAX_REPORTS: usize = 20;
+
+/// What the app knew when it panicked, saved on this device until the user chooses to
+/// send it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub created_at: i64,
+    pub message: String,
+    /// Source file, line and column of the panic
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub app_version: String,
+    pub os: String,
+    /// Kernel release on Linux and Android; not collected elsewhere
+    pub os_version: Option<String>,
+    pub arch: String,
+    /// Last lines of the log before the panic, oldest first
+    pub log_tail: Vec<String>,
+    /// When the report was sent, if it has been
+    pub submitted_at: Option<i64>,
+}
+
+/// Where crash reports go and whether they are written. The flag mirrors the setting so
+/// the panic hook never waits on the settings lock, which the panicking thread may hold.
+pub struct CrashReporter {
+    dir: PathBuf,
+    enabled: AtomicBool,
+}
+
+impl CrashReporter {
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid crash report id: {}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Report files, oldest first. Ids start with the time of the crash, so name order
+    /// is age order.
+    fn files(&self) -> Result<Vec<PathBuf>, String> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read crash reports: {}", e)),
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    fn load(&self, id: &str) -> Result<CrashReport, AppError> {
+        let path = self.path(id).map_err(AppError::invalid_input)?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AppError::not_found(format!("Unknown crash report: {}", id)))
+            }
+            Err(e) => {
+                return Err(AppError::storage(format!(
+                    "Failed to read crash report: {}",
+                    e
+                )))
+            }
+        };
+
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::corrupt_data(format!("Failed to parse crash report: {}", e))
+                .with_context(id.to_string())
+        })
+    }
+
+    fn save(&self, report: &CrashReport) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create crash report directory: {}", e))?;
+        let json = serde_json::to_vec_pretty(report)
+            .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+        crate::storage::write_atomic(&self.path(&report.id)?, &json)
+            .map_err(|e| format!("Failed to write crash report: {}", e))
+    }
+
+    fn prune(&self) -> Result<(), String> {
+        let files = self.files()?;
+        for path in &files[..files.len().saturating_sub(MAX_REPORTS)] {
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to delete old crash report: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+/// Kernel release on Linux and Android; `None` elsewhere.
+pub fn os_version() -> Option<String> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .ok()
+            .map(|release| release.trim().to_string())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        None
+    }
+}
+
+fn build_report(app: &AppHandle, info: &PanicHookInfo<'_>) -> CrashReport {
+    let now = chrono::Utc::now();
+    let log_tail = app
+        .try_state::<crate::logging::LogState>()
+        .and_then(|state| crate::logging::recent_lines(&state, LOG_TAIL_LINES).ok())
+        .unwrap_or_default();
+
+    CrashReport {
+        id: format!("{}-{:08x}", now.format("%Y%m%d-%H%M%S"), fastrand::u32(..)),
+        created_at: now.timestamp(),
+        message: panic_message(info),
+        location: info.location().map(|location| location.to_string()),
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: Backtrace::force_capture().to_string(),
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        os_version: os_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        log_tail,
+        submitted_at: None,
+    }
+}
+
+/// Logs every panic and, once the user has opted in, saves a crash report for it under
+/// the app log directory. Nothing is sent until `submit_crash_report` is called.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get log directory: {}", e))?
+        .join(CRASH_DIR);
+    app.manage(CrashReporter {
+        dir,
+        enabled: AtomicBool::new(crate::settings::current(app).crash_reporting),
+    });
+
+    let app = app.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        tracing::error!(
+            "Panic at {}: {}",
+            info.location()
+                .map(|location| location.to_string())
+                .unwrap_or_default(),
+            panic_message(info)
+        );
+
+        let reporter = app.state::<CrashReporter>();
+        if reporter.enabled.load(Ordering::Relaxed) {
+            let report = build_report(&app, info);
+            match reporter.save(&report).and_then(|_| reporter.prune()) {
+                Ok(()) => tracing::info!("Saved crash report {}", report.id),
+                Err(e) => tracing::warn!("Failed to save crash report: {}", e),
+            }
+        }
+
+        default_hook(info);
+    }));
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_crash_reporting(state: State<'_, SettingsState>) -> Result<bool, AppError> {
+    Ok(state.get().crash_reporting)
+}
+
+/// Turns saving crash reports on or off. Turning it off keeps saved reports; see
+/// `delete_crash_report`.
+#[tauri::command]
+pub async fn set_crash_reporting(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    reporter: State<'_, CrashReporter>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.crash_reporting = enabled)?;
+    reporter.set_enabled(enabled);
+    Ok(())
+}
+
+/// Saved crash reports, newest first.
+#[tauri::command]
+pub async fn list_crash_reports(
+    reporter: State<'_, CrashReporter>,
+) -> Result<Vec<CrashReport>, AppError> {
+    let mut reports = Vec::new();
+    for path in reporter.files().map_err(AppError::storage)?.iter().rev() {
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match reporter.load(id) {
+            Ok(report) => reports.push(report),
+            Err(e) => tracing::warn!("Skipping crash report {}: {}", path.display(), e),
+        }
+    }
+    Ok(reports)
+}
+
+/// Sends a saved crash report to the website, as the user agreed to, and marks it as
+/// submitted.
+#[tauri::command]
+pub async fn submit_crash_report(
+    app: AppHandle,
+    reporter: State<'_, CrashReporter>,
+    id: String,
+) -> Result<CrashReport, AppError> {
+    let mut report = reporter.load(&id)?;
+
+    let url = reqwest::Url::parse(&crate::website_url(&app))
+        .and_then(|base| base.join(CRASH_REPORT_ROUTE))
+        .map_err(|e| AppError::corrupt_data(format!("Invalid crash report URL: {}", e)))?;
+    crate::http_client::client(&app)
+        .post(url)
+        .timeout(crate::http_client::REQUEST_TIMEOUT)
+        .json(&report)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| AppError::from_reqwest(&e, format!("Failed to send crash report: {}", e)))?;
+
+    report.submitted_at = Some(chrono::Utc::now().timestamp());
+    reporter.save(&report).map_err(AppError::storage)?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn delete_crash_report(
+    reporter: State<'_, CrashReporter>,
+    id: String,
+) -> Result<(), AppError> {
+    let path = reporter.path(&id).map_err(AppError::invalid_input)?;
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AppError::storage(format!(
+            "Failed to delete crash report: {}",
+            e
+        ))),
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::Write;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Data to export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CsvSource {
+    /// Records of a cached document; `field` is a dot-separated path to the array when
+    /// it is not the document itself or its `entries`/`data`/`items`
+    Cache { key: String, field: Option<String> },
+    /// The local habit store, one row per habit
+    Habits,
+    /// The local habit store, one row per completed day
+    HabitCompletions,
+}
+
+/// A column of the export: the dot-separated field it reads and its header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumn {
+    pub field: String,
+    /// Defaults to `field`
+    pub header: Option<String>,
+}
+
+/// Flattens nested objects into dot-separated keys; arrays are kept as JSON text.
+fn flatten(prefix: &str, value: &Value, row: &mut Map<String, Value>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, row);
+            }
+        }
+        value => {
+            let key = if prefix.is_empty() { "value" } else { prefix };
+            row.insert(key.to_string(), value.clone());
+        }
+    }
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Quotes a field when it holds a separator, quote, or line break, doubling inner quotes.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+async fn records(app: &AppHandle, source: &CsvSource) -> Result<Vec<Value>, AppError> {
+    match source {
+        CsvSource::Cache { key, field } => {
+            let cached = crate::load_local_data(app.clone(), key.clone())
+                .await?
+                .ok_or_else(|| AppError::not_found(format!("No cached data for '{}'", key)))?;
+
+            let records = match field {
+                Some(field) => field
+                    .split('.')
+                    .try_fold(&cached.data, |value, part| value.get(part))
+                    .and_then(Value::as_array),
+                None => crate::document_records(&cached.data),
+            };
+
+            records.cloned().ok_or_else(|| {
+                AppError::invalid_input(format!("Cached data for '{}' has no list of records", key))
+            })
+        }
+        CsvSource::Habits | CsvSource::HabitCompletions => {
+            let habits = if crate::demo::is_demo(app) {
+                Vec::new()
+            } else {
+                crate::habits::all_habits(app, true).await?
+            };
+
+            if matches!(source, CsvSource::Habits) {
+                return habits
+                    .into_iter()
+                    .map(|mut habit| {
+                        habit.completions.clear();
+                        serde_json::to_value(habit).map_err(|e| {
+                            AppError::internal(format!("Failed to serialize habit: {}", e))
+                        })
+                    })
+                    .collect();
+            }
+
+            Ok(habits
+                .iter()
+                .flat_map(|habit| {
+                    habit.completions.iter().map(|date| {
+                        serde_json::json!({
+                            "habit_id": habit.id,
+                            "habit_name": habit.name,
+                            "date": date,
+                        })
+                    })
+                })
+                .collect())
+        }
+    }
+}
+
+/// Writes the records of `source` to a CSV file and returns the number of rows.
+///
+/// Without `columns`, every field found in the records becomes a column, in order of
+/// first appearance.
+#[tauri::command]
+pub async fn export_csv(
+    app: AppHandle,
+    source: CsvSource,
+    path: String,
+    columns: Option<Vec<CsvColumn>>,
+) -> Result<u64, AppError> {
+    let rows: Vec<Map<String, Value>> = records(&app, &source)
+        .await?
+        .iter()
+        .map(|record| {
+            let mut row = Map::new();
+            flatten("", record, &mut row);
+            row
+        })
+        .collect();
+
+    let columns = match columns {
+        Some(columns) if !columns.is_empty() => columns,
+        _ => {
+            let mut fields: Vec<String> = Vec::new();
+            for key in rows.iter().flat_map(|row| row.keys()) {
+                if !fields.contains(key) {
+                    fields.push(key.clone());
+                }
+            }
+            fields
+                .into_iter()
+                .map(|field| CsvColumn {
+                    field,
+                    header: None,
+                })
+                .collect()
+        }
+    };
+
+    let mut csv = String::new();
+    let header: Vec<String> = columns
+        .iter()
+        .map(|column| escape(column.header.as_deref().unwrap_or(&column.field)))
+        .collect();
+    csv.push_str(&header.join(","));
+    csv.push_str("\r\n");
+
+    for row in &rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| escape(&cell(row.get(&column.field))))
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+
+    crate::storage::run_blocking(move || {
+        let mut file = std::fs::File::create(&path)
+            .map_err(|e| AppError::storage(format!("Failed to create CSV file: {}", e)))?;
+        file.write_all(csv.as_bytes())
+            .map_err(|e| AppError::storage(format!("Failed to write CSV file: {}", e)))
+    })
+    .await?;
+
+    Ok(rows.len() as u64)
+}
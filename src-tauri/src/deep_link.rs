@@ -0,0 +1,90 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// Custom URL scheme registered for the app, e.g. `lotus-routine://habit/123`
+const DEEP_LINK_SCHEME: &str = "lotus-routine";
+
+/// Emitted with a [`DeepLink`] when a link arrives while the app is running
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLink {
+    pub url: String,
+    /// Website route the link points at, e.g. `/habit/123`
+    pub path: String,
+}
+
+/// Maps `lotus-routine://habit/123?x=1` to the website route `/habit/123?x=1`.
+fn parse(url: &Url) -> Option<DeepLink> {
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return None;
+    }
+
+    let mut path = format!(
+        "/{}{}",
+        url.host_str().unwrap_or_default(),
+        url.path().trim_end_matches('/')
+    );
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+
+    Some(DeepLink {
+        url: url.to_string(),
+        path,
+    })
+}
+
+/// Website URL for a link, refusing anything that would leave the website origin.
+fn website_url(app: &AppHandle, link: &DeepLink) -> Option<Url> {
+    let base = Url::parse(&crate::website_url(app)).ok()?;
+    let target = base.join(&link.path).ok()?;
+    (target.origin() == base.origin()).then_some(target)
+}
+
+fn handle_running(app: &AppHandle, urls: Vec<Url>) {
+    #[cfg(mobile)]
+    let urls: Vec<Url> = urls
+        .into_iter()
+        .filter(|url| !crate::oauth::handle_deep_link(app, url))
+        .collect();
+
+    for link in urls.iter().filter_map(parse) {
+        #[cfg(desktop)]
+        crate::tray::show_main_window(app);
+
+        if let Err(e) = app.emit(DEEP_LINK_EVENT, &link) {
+            tracing::warn!("Failed to emit deep link: {}", e);
+        }
+    }
+}
+
+/// Opens the link that launched the app and forwards later links to the webview.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    // Installed bundles register the scheme themselves; dev builds have to do it at runtime
+    #[cfg(all(debug_assertions, any(windows, target_os = "linux")))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("Failed to register deep link scheme: {}", e);
+    }
+
+    let launch_link = app
+        .deep_link()
+        .get_current()
+        .map_err(|e| format!("Failed to read launch deep link: {}", e))?
+        .and_then(|urls| urls.iter().find_map(parse));
+
+    if let Some(target) = launch_link.as_ref().and_then(|link| website_url(app, link)) {
+        if let Some(window) = app.get_webview_window("main") {
+            crate::app_lock::navigate(&window, target)
+                .map_err(|e| format!("Failed to open deep link: {}", e))?;
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link()
+        .on_open_url(move |event| handle_running(&handle, event.urls()));
+
+    Ok(())
+}
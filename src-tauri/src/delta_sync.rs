@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::cache_store::with_store_blocking;
+use crate::error::FetchError;
+use crate::retry::RetryOptions;
+use crate::{FetchResult, OnlineResponse};
+
+/// Describes how a resource supports incremental fetching.
+///
+/// The full document and each delta carry the records under `items_field` and the
+/// cursor to resume from under `cursor_field`; deltas also list removed ids under
+/// `deleted_field`. A server that cannot serve a delta for the cursor sets
+/// `reset_field` to `true` or answers `410 Gone`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeltaOptions {
+    /// Query parameter the stored cursor is sent in
+    pub cursor_param: String,
+    pub cursor_field: String,
+    pub items_field: String,
+    pub id_field: String,
+    pub deleted_field: String,
+    pub reset_field: String,
+}
+
+impl Default for DeltaOptions {
+    fn default() -> Self {
+        Self {
+            cursor_param: "since".to_string(),
+            cursor_field: "cursor".to_string(),
+            items_field: "items".to_string(),
+            id_field: "id".to_string(),
+            deleted_field: "deleted".to_string(),
+            reset_field: "reset".to_string(),
+        }
+    }
+}
+
+fn cursor_of(document: &Value, options: &DeltaOptions) -> Option<String> {
+    match document.get(&options.cursor_field)? {
+        Value::String(cursor) => Some(cursor.clone()),
+        Value::Number(cursor) => Some(cursor.to_string()),
+        _ => None,
+    }
+}
+
+/// Applies a delta to the cached document: changed records replace the cached ones
+/// with the same id, new ones are appended, and deleted ids are dropped.
+fn merge(cached: &mut Value, delta: &Value, options: &DeltaOptions) -> Result<(), String> {
+    let deleted = delta
+        .get(&options.deleted_field)
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let changed = delta
+        .get(&options.items_field)
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let items = cached
+        .get_mut(&options.items_field)
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| {
+            format!(
+                "Cached document has no '{}' array to merge into",
+                options.items_field
+            )
+        })?;
+
+    items.retain(|item| {
+        item.get(&options.id_field)
+            .is_none_or(|id| !deleted.contains(id))
+    });
+
+    for record in changed {
+        let id = record
+            .get(&options.id_field)
+            .cloned()
+            .ok_or_else(|| format!("Delta record is missing its '{}' field", options.id_field))?;
+
+        match items
+            .iter_mut()
+            .find(|item| item.get(&options.id_field) == Some(&id))
+        {
+            Some(existing) => *existing = record,
+            None => items.push(record),
+        }
+    }
+
+    if let (Some(document), Some(cursor)) =
+        (cached.as_object_mut(), delta.get(&options.cursor_field))
+    {
+        document.insert(options.cursor_field.clone(), cursor.clone());
+    }
+
+    Ok(())
+}
+
+/// Stores the cursor of a freshly fetched full document so the next refresh can be a delta.
+pub async fn remember_cursor(app: &AppHandle, key: &str, document: &Value, options: &DeltaOptions) {
+    let Some(cursor) = cursor_of(document, options) else {
+        return;
+    };
+
+    let owned_key = key.to_string();
+    let saved =
+        with_store_blocking(app, move |store| store.set_sync_cursor(&owned_key, &cursor)).await;
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save sync cursor for '{}': {}", key, e);
+    }
+}
+
+/// Fetches only what changed since the stored cursor and merges it into the cached copy.
+///
+/// Returns `None` when a full fetch is needed instead: there is no cursor or cached
+/// copy yet, the server asked for a reset, or the delta could not be merged.
+pub async fn refresh(
+    app: &AppHandle,
+    key: &str,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    retry: &RetryOptions,
+    options: &DeltaOptions,
+) -> Result<Option<FetchResult>, FetchError> {
+    let owned_key = key.to_string();
+    let cached = with_store_blocking(app, move |store| {
+        let Some(cursor) = store.sync_cursor(&owned_key)? else {
+            return Ok(None);
+        };
+        Ok(store.load(&owned_key)?.map(|entry| (cursor, entry.data)))
+    })
+    .await?;
+    let Some((cursor, mut document)) = cached else {
+        return Ok(None);
+    };
+
+    let query = HashMap::from([(options.cursor_param.clone(), cursor)]);
+    let delta_url = crate::with_query(url, Some(&query))?;
+
+    let delta = match crate::perform_request(
+        app,
+        reqwest::Method::GET,
+        &delta_url,
+        headers,
+        None,
+        None,
+        Some(retry),
+    )
+    .await
+    {
+        Ok(OnlineResponse::Fresh { data, .. }) => data,
+        Ok(OnlineResponse::NotModified) => return Ok(None),
+        Err(FetchError::Http { status: 410, .. }) => {
+            tracing::info!("Server expired the sync cursor for '{}'", key);
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if delta.get(&options.reset_field).and_then(Value::as_bool) == Some(true) {
+        tracing::info!("Server reset delta sync for '{}'", key);
+        return Ok(None);
+    }
+
+    if let Err(e) = merge(&mut document, &delta, options) {
+        tracing::warn!("Falling back to a full fetch of '{}': {}", key, e);
+        return Ok(None);
+    }
+
+    let expires_at = crate::store_local_data(app, key, &document, None, Some(url))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to save data locally: {}", e);
+            None
+        });
+    remember_cursor(app, key, &document, options).await;
+
+    Ok(Some(FetchResult::online(document, expires_at)))
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::cache_store::CacheEntryInfo;
+use crate::error::{AppError, ErrorKind};
+use crate::FetchResult;
+
+/// Emitted whenever demo mode is entered or left
+pub const DEMO_MODE_CHANGED_EVENT: &str = "demo-mode-changed";
+
+const SAMPLE_DATA: &str = include_str!("../assets/demo/sample-data.json");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoModeStatus {
+    pub demo: bool,
+}
+
+/// Ephemeral store backing guest mode. Nothing in it ever reaches disk or the network.
+#[derive(Default)]
+pub struct DemoState {
+    active: AtomicBool,
+    entries: RwLock<HashMap<String, FetchResult>>,
+}
+
+impl DemoState {
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn load(&self, key: &str) -> Option<FetchResult> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+    }
+
+    pub fn save(&self, key: String, data: serde_json::Value) {
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, demo_result(data));
+    }
+
+    pub fn remove(&self, key: Option<&str>) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        match key {
+            Some(key) => {
+                entries.remove(key);
+            }
+            None => entries.clear(),
+        }
+    }
+
+    pub fn timestamps(&self) -> HashMap<String, i64> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.timestamp))
+            .collect()
+    }
+
+    pub fn entries(&self) -> Vec<CacheEntryInfo> {
+        let mut entries: Vec<CacheEntryInfo> = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(key, entry)| CacheEntryInfo {
+                key: key.clone(),
+                scope: String::new(),
+                size: serde_json::to_vec(&entry.data).map_or(0, |json| json.len() as u64),
+                created_at: entry.timestamp,
+                updated_at: entry.timestamp,
+                last_accessed: entry.timestamp,
+                expires_at: None,
+                ttl_secs: None,
+                source_url: None,
+                compressed: false,
+                encrypted: false,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+}
+
+fn demo_result(data: serde_json::Value) -> FetchResult {
+    FetchResult {
+        data,
+        source: "demo".to_string(),
+        timestamp: chrono::Utc::now().timestamp(),
+        expires_at: None,
+        is_stale: false,
+    }
+}
+
+pub fn is_demo(app: &AppHandle) -> bool {
+    app.state::<DemoState>().is_active()
+}
+
+/// Guard for write, sync, and account paths that must stay inert in guest mode.
+pub fn ensure_not_demo(app: &AppHandle) -> Result<(), AppError> {
+    if is_demo(app) {
+        return Err(AppError::new(
+            ErrorKind::GuestMode,
+            "This action is not available in demo mode",
+        ));
+    }
+    Ok(())
+}
+
+fn emit_status(app: &AppHandle, demo: bool) {
+    if let Err(e) = app.emit(DEMO_MODE_CHANGED_EVENT, DemoModeStatus { demo }) {
+        tracing::warn!("Failed to emit demo mode change: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn enter_demo_mode(app: AppHandle, state: State<'_, DemoState>) -> Result<(), AppError> {
+    let sample: HashMap<String, serde_json::Value> = serde_json::from_str(SAMPLE_DATA)
+        .map_err(|e| AppError::internal(format!("Failed to parse demo data: {}", e)))?;
+
+    {
+        let mut entries = state.entries.write().unwrap_or_else(|e| e.into_inner());
+        *entries = sample
+            .into_iter()
+            .map(|(key, data)| (key, demo_result(data)))
+            .collect();
+    }
+
+    state.active.store(true, Ordering::SeqCst);
+    emit_status(&app, true);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn exit_demo_mode(app: AppHandle, state: State<'_, DemoState>) -> Result<(), AppError> {
+    state.active.store(false, Ordering::SeqCst);
+    state.remove(None);
+    emit_status(&app, false);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn is_demo_mode(state: State<'_, DemoState>) -> Result<bool, AppError> {
+    Ok(state.is_active())
+}
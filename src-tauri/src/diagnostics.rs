@@ -0,0 +1,177 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::AppError;
+use crate::settings::Settings;
+
+/// Log lines included in a bundle
+const LOG_LINES: usize = 2000;
+
+const REDACTED: &str = "[redacted]";
+
+/// One file of a diagnostics bundle, shown in full before anything is exported
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsFile {
+    pub name: String,
+    /// What the file holds, for the preview
+    pub description: String,
+    pub size: u64,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PlatformInfo {
+    app_version: String,
+    tauri_version: String,
+    webview_version: Option<String>,
+    os: String,
+    os_version: Option<String>,
+    arch: String,
+    created_at: i64,
+}
+
+fn platform_info(app: &AppHandle) -> PlatformInfo {
+    PlatformInfo {
+        app_version: app.package_info().version.to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        webview_version: tauri::webview_version().ok(),
+        os: std::env::consts::OS.to_string(),
+        os_version: crate::crash::os_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+    }
+}
+
+/// Drops credentials, query strings and fragments from a URL, which may carry tokens.
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut url) => {
+            if !url.username().is_empty() || url.password().is_some() {
+                let _ = url.set_username(REDACTED);
+                let _ = url.set_password(None);
+            }
+            if url.query().is_some() {
+                url.set_query(Some(REDACTED));
+            }
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => REDACTED.to_string(),
+    }
+}
+
+/// Settings without anything that identifies the user's accounts. The proxy password is
+/// never in the settings; it stays in the keychain.
+fn redacted_settings(mut settings: Settings) -> Settings {
+    settings.base_url = settings.base_url.as_deref().map(redact_url);
+    settings.proxy.url = settings.proxy.url.as_deref().map(redact_url);
+    if settings.proxy.username.is_some() {
+        settings.proxy.username = Some(REDACTED.to_string());
+    }
+    settings
+}
+
+fn json_file<T: Serialize>(
+    name: &str,
+    description: &str,
+    value: &T,
+) -> Result<DiagnosticsFile, AppError> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::internal(format!("Failed to serialize {}: {}", name, e)))?;
+    Ok(text_file(name, description, content))
+}
+
+fn text_file(name: &str, description: &str, content: String) -> DiagnosticsFile {
+    DiagnosticsFile {
+        name: name.to_string(),
+        description: description.to_string(),
+        size: content.len() as u64,
+        content,
+    }
+}
+
+/// Gathers everything a bundle holds. Parts that cannot be collected are replaced by a
+/// note saying why, so one failure does not hold back the rest.
+async fn collect(app: &AppHandle) -> Result<Vec<DiagnosticsFile>, AppError> {
+    let logs = match app.try_state::<crate::logging::LogState>() {
+        Some(state) => crate::logging::recent_lines(&state, LOG_LINES)
+            .map(|lines| lines.join("\n"))
+            .unwrap_or_else(|e| e),
+        None => "Logging is not initialized".to_string(),
+    };
+    let cache_metrics = match crate::cache_metrics::current(app).await {
+        Ok(metrics) => serde_json::to_value(metrics)
+            .map_err(|e| AppError::internal(format!("Failed to serialize cache metrics: {}", e)))?,
+        Err(e) => serde_json::json!({ "error": e }),
+    };
+    let network = crate::probe_network_status(app).await;
+
+    Ok(vec![
+        json_file(
+            "platform.json",
+            "App, webview and operating system versions",
+            &platform_info(app),
+        )?,
+        text_file(
+            "logs.txt",
+            &format!("The last {} lines of the app log", LOG_LINES),
+            logs,
+        ),
+        json_file(
+            "cache-metrics.json",
+            "Cache size, cached keys and hit rates",
+            &cache_metrics,
+        )?,
+        json_file(
+            "network.json",
+            "Whether the internet and the website could be reached just now",
+            &network,
+        )?,
+        json_file(
+            "settings.json",
+            "App settings, with website and proxy addresses and the proxy username redacted",
+            &redacted_settings(crate::settings::current(app)),
+        )?,
+    ])
+}
+
+fn write_zip(path: &str, files: &[DiagnosticsFile]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for file in files {
+        zip.start_file(file.name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to bundle: {}", file.name, e))?;
+        zip.write_all(file.content.as_bytes())
+            .map_err(|e| format!("Failed to write {} to bundle: {}", file.name, e))?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish diagnostics bundle: {}", e))?;
+    Ok(())
+}
+
+/// Shows exactly what `export_diagnostics` would put in the bundle right now.
+#[tauri::command]
+pub async fn preview_diagnostics(app: AppHandle) -> Result<Vec<DiagnosticsFile>, AppError> {
+    collect(&app).await
+}
+
+/// Writes recent logs, cache metrics, a fresh network check, redacted settings and
+/// platform information to a zip file at `path` for a support request. Returns the
+/// names of the files in the bundle.
+#[tauri::command]
+pub async fn export_diagnostics(app: AppHandle, path: String) -> Result<Vec<String>, AppError> {
+    let files = collect(&app).await?;
+    let names = files.iter().map(|file| file.name.clone()).collect();
+
+    crate::storage::run_blocking(move || write_zip(&path, &files))
+        .await
+        .map_err(AppError::storage)?;
+    Ok(names)
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// Whether the system's Do Not Disturb or Focus mode is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DndState {
+    Off,
+    On,
+    /// The platform does not say, e.g. on iOS
+    Unknown,
+}
+
+/// What happens to reminders that come due while Do Not Disturb is on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DndBehavior {
+    /// Held back and fired once Do Not Disturb ends, if that is within a few hours
+    #[default]
+    Defer,
+    /// Skipped for the day
+    Suppress,
+    /// Fired as usual
+    Ignore,
+}
+
+/// Asks the shell whether notifications are accepted right now. It reports Focus
+/// Assist's automatic rules (full-screen apps, presenting, quiet hours) rather than
+/// every manual setting.
+#[cfg(windows)]
+mod platform {
+    use windows::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS, QUNS_NOT_PRESENT,
+    };
+
+    use super::DndState;
+
+    pub fn state() -> DndState {
+        match unsafe { SHQueryUserNotificationState() } {
+            Ok(QUNS_ACCEPTS_NOTIFICATIONS | QUNS_NOT_PRESENT) => DndState::Off,
+            Ok(_) => DndState::On,
+            Err(_) => DndState::Unknown,
+        }
+    }
+}
+
+/// Reads the Focus assertions macOS keeps while a Focus is turned on by hand or from
+/// Control Center. Focus schedules are not recorded there.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::DndState;
+
+    pub fn state() -> DndState {
+        let Some(home) = std::env::var_os("HOME") else {
+            return DndState::Unknown;
+        };
+        let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+        let Ok(contents) = std::fs::read(path) else {
+            return DndState::Unknown;
+        };
+        let Ok(assertions) = serde_json::from_slice::<serde_json::Value>(&contents) else {
+            return DndState::Unknown;
+        };
+
+        let active = assertions["data"].as_array().is_some_and(|data| {
+            data.iter().any(|entry| {
+                entry["storeAssertionRecords"]
+                    .as_array()
+                    .is_some_and(|records| !records.is_empty())
+            })
+        });
+        if active {
+            DndState::On
+        } else {
+            DndState::Off
+        }
+    }
+}
+
+/// Asks the notification server, which KDE and others let users silence, and falls back
+/// to GNOME's banner setting.
+#[cfg(target_os = "linux")]
+mod platform {
+    use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+    use dbus::blocking::Connection;
+    use std::process::Command;
+    use std::time::Duration;
+
+    use super::DndState;
+
+    const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+    fn notifications_inhibited() -> Option<bool> {
+        let connection = Connection::new_session().ok()?;
+        connection
+            .with_proxy(
+                "org.freedesktop.Notifications",
+                "/org/freedesktop/Notifications",
+                DBUS_TIMEOUT,
+            )
+            .get("org.freedesktop.Notifications", "Inhibited")
+            .ok()
+    }
+
+    fn gnome_banners_hidden() -> Option<bool> {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "true" => Some(false),
+            "false" => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn state() -> DndState {
+        match notifications_inhibited().or_else(gnome_banners_hidden) {
+            Some(true) => DndState::On,
+            Some(false) => DndState::Off,
+            None => DndState::Unknown,
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+mod platform {
+    use jni::objects::{JObject, JValue};
+    use jni::JNIEnv;
+
+    use super::DndState;
+
+    // NotificationManager.INTERRUPTION_FILTER_UNKNOWN and INTERRUPTION_FILTER_ALL
+    const FILTER_UNKNOWN: i32 = 0;
+    const FILTER_ALL: i32 = 1;
+
+    fn interruption_filter(env: &mut JNIEnv, activity: &JObject) -> jni::errors::Result<i32> {
+        let name = env.new_string("notification")?;
+        let manager = env
+            .call_method(
+                activity,
+                "getSystemService",
+                "(Ljava/lang/String;)Ljava/lang/Object;",
+                &[JValue::from(&name)],
+            )?
+            .l()?;
+        env.call_method(&manager, "getCurrentInterruptionFilter", "()I", &[])?
+            .i()
+    }
+
+    pub fn state() -> DndState {
+        let context = ndk_context::android_context();
+        let Ok(vm) = (unsafe { jni::JavaVM::from_raw(context.vm().cast()) }) else {
+            return DndState::Unknown;
+        };
+        let activity = unsafe { JObject::from_raw(context.context().cast()) };
+        let Ok(mut env) = vm.attach_current_thread() else {
+            return DndState::Unknown;
+        };
+
+        match interruption_filter(&mut env, &activity) {
+            Ok(FILTER_UNKNOWN) => DndState::Unknown,
+            Ok(FILTER_ALL) => DndState::Off,
+            Ok(_) => DndState::On,
+            Err(e) => {
+                if let jni::errors::Error::JavaException = e {
+                    let _ = env.exception_clear();
+                }
+                tracing::warn!("Failed to read Do Not Disturb state: {}", e);
+                DndState::Unknown
+            }
+        }
+    }
+}
+
+/// iOS only shares the Focus state with apps holding a special entitlement.
+#[cfg(not(any(
+    windows,
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android"
+)))]
+mod platform {
+    use super::DndState;
+
+    pub fn state() -> DndState {
+        DndState::Unknown
+    }
+}
+
+/// The current state. Blocks briefly on Linux, where it is asked over D-Bus.
+pub fn state() -> DndState {
+    platform::state()
+}
+
+/// Whether alerts should be held back right now: Do Not Disturb is on and the settings
+/// do not ignore it.
+pub fn holds_alerts(app: &AppHandle) -> bool {
+    crate::settings::current(app).dnd_behavior != DndBehavior::Ignore && state() == DndState::On
+}
+
+pub async fn current_state() -> DndState {
+    tauri::async_runtime::spawn_blocking(state)
+        .await
+        .unwrap_or(DndState::Unknown)
+}
+
+#[tauri::command]
+pub async fn get_dnd_state() -> Result<DndState, AppError> {
+    Ok(current_state().await)
+}
+
+#[tauri::command]
+pub async fn get_dnd_behavior(state: State<'_, SettingsState>) -> Result<DndBehavior, AppError> {
+    Ok(state.get().dnd_behavior)
+}
+
+#[tauri::command]
+pub async fn set_dnd_behavior(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    behavior: DndBehavior,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.dnd_behavior = behavior)?;
+    Ok(())
+}
@@ -0,0 +1,507 @@
+use futures_util::future::{AbortHandle, Abortable};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::AsyncWriteExt;
+
+use crate::error::AppError;
+use crate::middleware::MiddlewareRegistry;
+use crate::progress::ProgressReporter;
+
+/// Longest wait for the response or the next chunk before a download counts as stalled.
+/// Downloads have no overall timeout, since large files may take a while on slow links.
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Minimum gap between progress events of one download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+}
+
+/// A file being streamed to disk. Progress events carry the download's `id` as their
+/// `operation_id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Download {
+    pub id: String,
+    pub url: String,
+    pub dest: String,
+    pub status: DownloadStatus,
+    pub downloaded_bytes: u64,
+    /// `None` if the server did not say how large the file is
+    pub total_bytes: Option<u64>,
+    /// Expected SHA-256 of the complete file, hex-encoded
+    pub sha256: Option<String>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+struct Entry {
+    download: Download,
+    headers: Option<HashMap<String, String>>,
+    /// ETag or Last-Modified of the partial content, so a resumed request only continues
+    /// it if the file on the server is unchanged
+    validator: Option<String>,
+    /// The running transfer, tagged so a stopped one cannot overwrite a newer one's status
+    task: Option<(u64, AbortHandle)>,
+}
+
+/// Downloads started since launch. Files land in `<dest>.partial` until they are
+/// complete and verified, so an interrupted download never leaves a truncated file at
+/// the destination.
+#[derive(Default)]
+pub struct DownloadManager {
+    downloads: Mutex<HashMap<String, Entry>>,
+    next_id: AtomicU64,
+}
+
+impl DownloadManager {
+    fn update(&self, id: &str, change: impl FnOnce(&mut Entry)) {
+        let mut downloads = self.downloads.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = downloads.get_mut(id) {
+            change(entry);
+            entry.download.updated_at = chrono::Utc::now().timestamp();
+        }
+    }
+
+    fn get(&self, id: &str) -> Result<Download, AppError> {
+        self.downloads
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .map(|entry| entry.download.clone())
+            .ok_or_else(|| AppError::not_found(format!("Unknown download: {}", id)))
+    }
+}
+
+fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".partial");
+    dest.with_file_name(name)
+}
+
+/// `bytes <start>-<end>/<total>` as `(start, total)`, the total being `None` if unknown.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// Validator the server can compare the partial content against. Weak ETags are not
+/// allowed in `If-Range`.
+fn resume_validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    header(reqwest::header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(reqwest::header::LAST_MODIFIED))
+}
+
+fn file_sha256(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open download: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read download: {}", e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Fetches the rest of the file into its `.partial` file, continuing from what is
+/// already there when the server supports range requests, then verifies it and moves it
+/// to the destination.
+async fn transfer(app: &AppHandle, id: &str, progress: &ProgressReporter) -> Result<(), String> {
+    let manager = app.state::<DownloadManager>();
+    let (url, dest, headers, validator, sha256) = {
+        let downloads = manager.downloads.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = downloads
+            .get(id)
+            .ok_or_else(|| format!("Unknown download: {}", id))?;
+        (
+            entry.download.url.clone(),
+            PathBuf::from(&entry.download.dest),
+            entry.headers.clone(),
+            entry.validator.clone(),
+            entry.download.sha256.clone(),
+        )
+    };
+    let partial = partial_path(&dest);
+
+    // Without a validator there is no telling whether the partial content still matches
+    // the file on the server, so it is fetched again from the start
+    let offset = match &validator {
+        Some(_) => tokio::fs::metadata(&partial)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0),
+        None => 0,
+    };
+
+    let client = crate::http_client::client(app);
+    let mut request = client.get(&url).header(reqwest::header::ACCEPT, "*/*");
+    if let Some(headers) = &headers {
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+    }
+    if let (Some(validator), true) = (&validator, offset > 0) {
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .header(reqwest::header::IF_RANGE, validator);
+    }
+
+    let mut request = request
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+    let registry = app.state::<MiddlewareRegistry>();
+    registry.apply_request(&mut request)?;
+    let request_url = request.url().clone();
+
+    let mut response = tokio::time::timeout(STALL_TIMEOUT, client.execute(request))
+        .await
+        .map_err(|_| "Download timed out waiting for the server".to_string())?
+        .map_err(|e| format!("Download failed: {}", e))?;
+    registry.apply_response(&reqwest::Method::GET, &request_url, &response);
+
+    let status = response.status();
+    let content_range = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range);
+
+    // A 206 continues the partial content; a 200 means the server sent the whole file,
+    // because it ignored the range or the file changed since
+    let (mut downloaded, total, append) = match (status, content_range) {
+        (reqwest::StatusCode::PARTIAL_CONTENT, Some((start, total))) if start == offset => {
+            (offset, total, true)
+        }
+        (reqwest::StatusCode::OK, _) => (0, response.content_length(), false),
+        (status, _) => return Err(format!("Download failed with status {}", status)),
+    };
+
+    let validator = resume_validator(&response);
+    manager.update(id, |entry| {
+        entry.validator = validator;
+        entry.download.downloaded_bytes = downloaded;
+        entry.download.total_bytes = total;
+    });
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&partial)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+
+    progress.report("downloading", downloaded, total);
+    let mut last_report = Instant::now();
+
+    while let Some(chunk) = tokio::time::timeout(STALL_TIMEOUT, response.chunk())
+        .await
+        .map_err(|_| "Download stalled".to_string())?
+        .map_err(|e| format!("Download failed: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            manager.update(id, |entry| entry.download.downloaded_bytes = downloaded);
+            progress.report("downloading", downloaded, total);
+            last_report = Instant::now();
+        }
+    }
+
+    file.sync_all()
+        .await
+        .map_err(|e| format!("Failed to write download: {}", e))?;
+    drop(file);
+    manager.update(id, |entry| entry.download.downloaded_bytes = downloaded);
+
+    if total.is_some_and(|total| total != downloaded) {
+        return Err(format!(
+            "Download ended after {} of {} bytes",
+            downloaded,
+            total.unwrap_or_default()
+        ));
+    }
+
+    if let Some(expected) = sha256 {
+        progress.report("verifying", downloaded, total);
+        let check = partial.clone();
+        let actual = crate::storage::run_blocking(move || file_sha256(&check)).await?;
+        if !actual.eq_ignore_ascii_case(&expected) {
+            // Resuming would only append to the bad content
+            let _ = tokio::fs::remove_file(&partial).await;
+            manager.update(id, |entry| {
+                entry.validator = None;
+                entry.download.downloaded_bytes = 0;
+            });
+            return Err(format!(
+                "Checksum mismatch: expected {}, got {}",
+                expected, actual
+            ));
+        }
+    }
+
+    tokio::fs::rename(&partial, &dest)
+        .await
+        .map_err(|e| format!("Failed to move download to {}: {}", dest.display(), e))
+}
+
+/// Starts a transfer for the download in the background, replacing any running one.
+fn spawn(app: &AppHandle, id: &str) {
+    let manager = app.state::<DownloadManager>();
+    let (handle, registration) = AbortHandle::new_pair();
+    let token = manager.next_id.fetch_add(1, Ordering::Relaxed);
+
+    manager.update(id, |entry| {
+        if let Some((_, running)) = entry.task.replace((token, handle)) {
+            running.abort();
+        }
+        entry.download.status = DownloadStatus::Downloading;
+        entry.download.error = None;
+    });
+
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let progress = ProgressReporter::new(&app, Some(id.clone()));
+        let Ok(result) = Abortable::new(transfer(&app, &id, &progress), registration).await else {
+            // Paused or cancelled, which updates the status itself
+            return;
+        };
+
+        let manager = app.state::<DownloadManager>();
+        let mut finished = None;
+        manager.update(&id, |entry| {
+            if entry.task.as_ref().map(|(current, _)| *current) != Some(token) {
+                return;
+            }
+            entry.task = None;
+            match result {
+                Ok(()) => entry.download.status = DownloadStatus::Completed,
+                Err(e) => {
+                    tracing::warn!("Download of {} failed: {}", entry.download.url, e);
+                    entry.download.status = DownloadStatus::Failed;
+                    entry.download.error = Some(e);
+                }
+            }
+            finished = Some(entry.download.clone());
+        });
+
+        if let Some(download) = finished {
+            let stage = match download.status {
+                DownloadStatus::Completed => "completed",
+                _ => "failed",
+            };
+            progress.finish(
+                stage,
+                download.downloaded_bytes,
+                download.total_bytes,
+                download.error,
+            );
+        }
+    });
+}
+
+/// Stops the running transfer, leaving its partial file in place.
+fn stop(entry: &mut Entry) -> bool {
+    match entry.task.take() {
+        Some((_, handle)) => {
+            handle.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Streams `url` to the absolute path `dest` in the background and returns the new
+/// download. Progress is reported under the download's id; `sha256`, if given, is
+/// checked before the file is moved into place. An existing file at `dest` is replaced.
+#[tauri::command]
+pub async fn download_file(
+    app: AppHandle,
+    url: String,
+    dest: String,
+    sha256: Option<String>,
+    headers: Option<HashMap<String, String>>,
+) -> Result<Download, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let parsed = tauri::Url::parse(&url)
+        .map_err(|e| AppError::invalid_input(format!("Invalid download URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::invalid_input(format!(
+            "Unsupported download URL: {}",
+            url
+        )));
+    }
+    let dest_path = PathBuf::from(&dest);
+    if !dest_path.is_absolute() || dest_path.file_name().is_none() {
+        return Err(AppError::invalid_input(format!(
+            "Download destination must be an absolute file path: {}",
+            dest
+        )));
+    }
+    if let Some(sha256) = &sha256 {
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(AppError::invalid_input(format!(
+                "Invalid SHA-256 checksum: {}",
+                sha256
+            )));
+        }
+    }
+
+    let manager = app.state::<DownloadManager>();
+    let now = chrono::Utc::now().timestamp();
+    let download = Download {
+        id: format!(
+            "download-{}",
+            manager.next_id.fetch_add(1, Ordering::Relaxed)
+        ),
+        url,
+        dest,
+        status: DownloadStatus::Downloading,
+        downloaded_bytes: 0,
+        total_bytes: None,
+        sha256,
+        error: None,
+        started_at: now,
+        updated_at: now,
+    };
+
+    {
+        let mut downloads = manager.downloads.lock().unwrap_or_else(|e| e.into_inner());
+        if downloads.values().any(|entry| {
+            entry.download.dest == download.dest
+                && entry.download.status != DownloadStatus::Completed
+        }) {
+            return Err(AppError::invalid_state(format!(
+                "A download to {} is already in progress or paused",
+                download.dest
+            )));
+        }
+        downloads.insert(
+            download.id.clone(),
+            Entry {
+                download: download.clone(),
+                headers,
+                validator: None,
+                task: None,
+            },
+        );
+    }
+
+    spawn(&app, &download.id);
+    Ok(download)
+}
+
+/// Stops a download, keeping what has arrived so far for [`resume_download`].
+#[tauri::command]
+pub async fn pause_download(app: AppHandle, id: String) -> Result<Download, AppError> {
+    let manager = app.state::<DownloadManager>();
+    let mut paused = false;
+    manager.update(&id, |entry| {
+        if stop(entry) {
+            entry.download.status = DownloadStatus::Paused;
+            paused = true;
+        }
+    });
+
+    let download = manager.get(&id)?;
+    if paused {
+        ProgressReporter::new(&app, Some(id)).finish(
+            "paused",
+            download.downloaded_bytes,
+            download.total_bytes,
+            None,
+        );
+    }
+    Ok(download)
+}
+
+/// Continues a paused or failed download where it left off, if the server allows it.
+#[tauri::command]
+pub async fn resume_download(app: AppHandle, id: String) -> Result<Download, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let download = app.state::<DownloadManager>().get(&id)?;
+    if !matches!(
+        download.status,
+        DownloadStatus::Paused | DownloadStatus::Failed
+    ) {
+        return Err(AppError::invalid_state(format!(
+            "Download {} is not paused",
+            id
+        )));
+    }
+
+    spawn(&app, &id);
+    app.state::<DownloadManager>().get(&id)
+}
+
+/// Stops a download and removes it from the list along with its partial file. A
+/// completed download's file is kept. Returns whether the download existed.
+#[tauri::command]
+pub async fn cancel_download(
+    manager: State<'_, DownloadManager>,
+    id: String,
+) -> Result<bool, AppError> {
+    let entry = manager
+        .downloads
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    let Some(mut entry) = entry else {
+        return Ok(false);
+    };
+
+    stop(&mut entry);
+    if entry.download.status != DownloadStatus::Completed {
+        let partial = partial_path(Path::new(&entry.download.dest));
+        if let Err(e) = tokio::fs::remove_file(&partial).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove {}: {}", partial.display(), e);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Lists the downloads started since launch, newest first.
+#[tauri::command]
+pub async fn list_downloads(
+    manager: State<'_, DownloadManager>,
+) -> Result<Vec<Download>, AppError> {
+    let mut downloads: Vec<Download> = manager
+        .downloads
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .map(|entry| entry.download.clone())
+        .collect();
+    downloads.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+
+    Ok(downloads)
+}
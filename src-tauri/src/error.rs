@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error surfaced by the online fetch commands.
+///
+/// Serialized with a `kind` tag so the frontend can tell server-reported failures
+/// (with the server's own message) from local ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FetchError {
+    Http {
+        status: u16,
+        message: String,
+        code: Option<String>,
+        retry_after_secs: Option<u64>,
+    },
+    /// The request never got a response: connection failure, timeout, or dropped socket
+    Network { message: String },
+    /// The first-party server presented a certificate without a pinned public key, which
+    /// points at an intercepting proxy on the network
+    CertificatePin { host: String, message: String },
+    /// A local failure, e.g. the cache could not be read or the response was not JSON;
+    /// `code` says which, as in [`AppError`]
+    Other {
+        #[serde(flatten)]
+        code: ErrorKind,
+        message: String,
+    },
+}
+
+impl FetchError {
+    /// Whether the same request may succeed later, as opposed to being rejected outright.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Network { .. } => true,
+            FetchError::Http { status, .. } => *status >= 500 || *status == 408 || *status == 429,
+            FetchError::CertificatePin { .. } => false,
+            FetchError::Other { code, .. } => code.is_retryable(),
+        }
+    }
+
+    /// Builds an HTTP error from a non-success response body, preferring the message
+    /// from a structured JSON error over the generic status text.
+    pub fn from_response(
+        status: reqwest::StatusCode,
+        body: &str,
+        retry_after_secs: Option<u64>,
+    ) -> Self {
+        let parsed = serde_json::from_str::<serde_json::Value>(body).ok();
+
+        let message = parsed
+            .as_ref()
+            .and_then(|json| {
+                ["message", "error_description", "msg", "detail", "error"]
+                    .iter()
+                    .find_map(|field| json.get(field).and_then(|v| v.as_str()))
+            })
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| {
+                format!(
+                    "HTTP error: {} - {}",
+                    status,
+                    status.canonical_reason().unwrap_or("Unknown")
+                )
+            });
+
+        let code = parsed.as_ref().and_then(|json| match json.get("code") {
+            Some(serde_json::Value::String(code)) => Some(code.clone()),
+            Some(serde_json::Value::Number(code)) => Some(code.to_string()),
+            _ => None,
+        });
+
+        FetchError::Http {
+            status: status.as_u16(),
+            message,
+            code,
+            retry_after_secs,
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Http {
+                status, message, ..
+            } => write!(f, "HTTP {}: {}", status, message),
+            FetchError::Network { message }
+            | FetchError::CertificatePin { message, .. }
+            | FetchError::Other { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for FetchError {
+    fn from(message: String) -> Self {
+        FetchError::Other {
+            code: ErrorKind::Internal,
+            message,
+        }
+    }
+}
+
+/// What went wrong, serialized as the `code` of an [`AppError`] along with any details
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The website or network could not be reached
+    Offline,
+    /// The server answered with an error status
+    Http {
+        status: u16,
+        /// Error code from the server's JSON body, if it sent one
+        server_code: Option<String>,
+        retry_after_secs: Option<u64>,
+    },
+    /// See [`FetchError::CertificatePin`]
+    CertificatePin {
+        host: String,
+    },
+    /// Stopped by `cancel_request` or a similar command
+    Cancelled,
+    /// Refused because guest mode keeps the real profile untouched
+    GuestMode,
+    InvalidInput,
+    /// The call does not fit the current state, e.g. starting something already running
+    InvalidState,
+    NotFound,
+    /// The user could not be verified, e.g. a failed biometric check
+    AuthenticationFailed,
+    /// Refused while the app lock is on, until the user unlocks the app
+    Locked,
+    /// Stored data could not be read back, e.g. a damaged file or one written by a newer
+    /// version of the app
+    CorruptData,
+    /// Reading or writing local files or the database failed
+    Storage,
+    /// Not available on this platform or in this build
+    Unsupported,
+    Internal,
+}
+
+impl ErrorKind {
+    fn is_retryable(&self) -> bool {
+        match self {
+            ErrorKind::Offline => true,
+            ErrorKind::Http { status, .. } => *status >= 500 || *status == 408 || *status == 429,
+            _ => false,
+        }
+    }
+}
+
+/// Error returned by every command.
+///
+/// Serialized flat, as `{ code, message, retryable, context }` plus the details of the
+/// kind, so the frontend can branch on `code` and show `message` as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    #[serde(flatten)]
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Whether the same call may succeed if it is made again later
+    pub retryable: bool,
+    /// What the command was working on, e.g. a cache key or file name
+    pub context: Option<String>,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            retryable: kind.is_retryable(),
+            kind,
+            message: message.into(),
+            context: None,
+        }
+    }
+
+    pub fn offline(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Offline, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidState, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn corrupt_data(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::CorruptData, message)
+    }
+
+    pub fn storage(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Storage, message)
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Unsupported, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+
+    /// For a failed `reqwest` call: `http` when the server answered with an error status,
+    /// `corrupt_data` when its response could not be decoded, and `offline` otherwise.
+    pub fn from_reqwest(error: &reqwest::Error, message: impl Into<String>) -> Self {
+        let kind = match error.status() {
+            Some(status) => ErrorKind::Http {
+                status: status.as_u16(),
+                server_code: None,
+                retry_after_secs: None,
+            },
+            None if error.is_decode() => ErrorKind::CorruptData,
+            None if error.is_builder() => ErrorKind::InvalidInput,
+            None => ErrorKind::Offline,
+        };
+        Self::new(kind, message)
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Lets helpers that return plain messages use `?` on functions returning [`AppError`].
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.message
+    }
+}
+
+impl From<FetchError> for AppError {
+    fn from(error: FetchError) -> Self {
+        let message = error.to_string();
+        let kind = match error {
+            FetchError::Http {
+                status,
+                code,
+                retry_after_secs,
+                ..
+            } => ErrorKind::Http {
+                status,
+                server_code: code,
+                retry_after_secs,
+            },
+            FetchError::Network { .. } => ErrorKind::Offline,
+            FetchError::CertificatePin { host, .. } => ErrorKind::CertificatePin { host },
+            FetchError::Other { code, .. } => code,
+        };
+        Self::new(kind, message)
+    }
+}
+
+impl From<AppError> for FetchError {
+    fn from(error: AppError) -> Self {
+        let message = error.message;
+        match error.kind {
+            ErrorKind::Offline => FetchError::Network { message },
+            ErrorKind::Http {
+                status,
+                server_code,
+                retry_after_secs,
+            } => FetchError::Http {
+                status,
+                message,
+                code: server_code,
+                retry_after_secs,
+            },
+            ErrorKind::CertificatePin { host } => FetchError::CertificatePin { host, message },
+            code => FetchError::Other { code, message },
+        }
+    }
+}
+
+/// Parses a `Retry-After` header given either as delay-seconds or an HTTP-date.
+pub fn parse_retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(delay.max(0) as u64)
+}
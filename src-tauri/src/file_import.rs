@@ -0,0 +1,353 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, DragDropEvent, Emitter, WebviewWindow, WindowEvent};
+
+use crate::backup::{BackupManifest, ImportSummary};
+use crate::error::AppError;
+use crate::habits::{HabitImportSummary, ImportedHabit};
+use crate::progress::ProgressReporter;
+
+pub const IMPORT_FILE_DROPPED_EVENT: &str = "import-file-dropped";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// JSON and CSV files are read whole, so larger ones are refused
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Habit names included in a preview
+const SAMPLE_SIZE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportKind {
+    /// Archive made by `export_backup`
+    Backup,
+    Json,
+    Csv,
+}
+
+impl ImportKind {
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ImportKind::Backup)
+        } else if name.ends_with(".json") {
+            Some(ImportKind::Json)
+        } else if name.ends_with(".csv") {
+            Some(ImportKind::Csv)
+        } else {
+            None
+        }
+    }
+}
+
+/// What importing a file would add
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImportPreview {
+    Backup {
+        manifest: BackupManifest,
+    },
+    Habits {
+        habits: u64,
+        completions: u64,
+        /// Names of the first few habits
+        sample: Vec<String>,
+    },
+}
+
+/// Payload of [`IMPORT_FILE_DROPPED_EVENT`]; `error` says why a file cannot be imported
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedFile {
+    pub path: String,
+    pub kind: Option<ImportKind>,
+    pub preview: Option<ImportPreview>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImportResult {
+    Backup(ImportSummary),
+    Habits(HabitImportSummary),
+}
+
+fn read_text(path: &Path) -> Result<String, AppError> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| AppError::storage(format!("Failed to read file: {}", e)))?
+        .len();
+    if size > MAX_FILE_BYTES {
+        return Err(AppError::invalid_input(format!(
+            "File is too large to import (limit is {} MB)",
+            MAX_FILE_BYTES / 1024 / 1024
+        )));
+    }
+
+    std::fs::read_to_string(path)
+        .map_err(|e| AppError::storage(format!("Failed to read file: {}", e)))
+}
+
+/// Habits from a JSON array such as `list_habits` returns, from a `habits` field, or from
+/// the usual `entries`/`data`/`items` wrappers.
+fn json_habits(text: &str) -> Result<Vec<ImportedHabit>, String> {
+    let document: Value = serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let records = document
+        .get("habits")
+        .and_then(Value::as_array)
+        .or_else(|| crate::document_records(&document))
+        .ok_or_else(|| "JSON file has no list of habits".to_string())?;
+
+    records
+        .iter()
+        .map(|record| {
+            serde_json::from_value(record.clone()).map_err(|e| format!("Invalid habit: {}", e))
+        })
+        .collect()
+}
+
+/// Splits CSV text into rows of fields, undoing the quoting `export_csv` applies. Blank
+/// lines are dropped.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+/// Completed days from a CSV with `habit_name` and `date` columns, as exported from the
+/// `habit_completions` source, or habits from one with a `name` column, as exported from
+/// the `habits` source.
+fn csv_habits(text: &str) -> Result<Vec<ImportedHabit>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let headers: Vec<String> = rows
+        .next()
+        .ok_or_else(|| "CSV file is empty".to_string())?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let field = |row: &[String], index: Option<usize>| {
+        index
+            .and_then(|index| row.get(index))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    if let (Some(habit_name), Some(date)) = (column("habit_name"), column("date")) {
+        let mut habits: Vec<ImportedHabit> = Vec::new();
+        let mut positions = HashMap::new();
+
+        for row in rows {
+            let name = field(&row, Some(habit_name)).unwrap_or_default();
+            let date = field(&row, Some(date))
+                .ok_or_else(|| format!("Missing date for habit '{}'", name))?;
+
+            let position = *positions.entry(name.to_lowercase()).or_insert_with(|| {
+                habits.push(ImportedHabit {
+                    name,
+                    ..Default::default()
+                });
+                habits.len() - 1
+            });
+            habits[position].completions.push(date);
+        }
+
+        return Ok(habits);
+    }
+
+    let name = column("name").ok_or_else(|| {
+        "CSV file needs a name column, or habit_name and date columns".to_string()
+    })?;
+    let description = column("description");
+    let color = column("color");
+
+    Ok(rows
+        .map(|row| ImportedHabit {
+            name: field(&row, Some(name)).unwrap_or_default(),
+            description: field(&row, description),
+            color: field(&row, color),
+            completions: Vec::new(),
+        })
+        .collect())
+}
+
+/// Reads the habits in a JSON or CSV file, trimming names and normalizing dates.
+fn load_habits(path: &Path, kind: ImportKind) -> Result<Vec<ImportedHabit>, AppError> {
+    let text = read_text(path)?;
+    let mut habits = match kind {
+        ImportKind::Csv => csv_habits(&text),
+        _ => json_habits(&text),
+    }
+    .map_err(AppError::invalid_input)?;
+
+    if habits.is_empty() {
+        return Err(AppError::invalid_input("File contains no habits"));
+    }
+
+    for habit in &mut habits {
+        habit.name = habit.name.trim().to_string();
+        if habit.name.is_empty() {
+            return Err(AppError::invalid_input("Every habit needs a name"));
+        }
+
+        habit.completions = habit
+            .completions
+            .iter()
+            .map(|date| {
+                NaiveDate::parse_from_str(date.trim(), DATE_FORMAT)
+                    .map(|date| date.format(DATE_FORMAT).to_string())
+                    .map_err(|e| {
+                        AppError::invalid_input(format!(
+                            "Invalid date '{}' for habit '{}': {}",
+                            date, habit.name, e
+                        ))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    Ok(habits)
+}
+
+fn preview(path: &Path, kind: ImportKind) -> Result<ImportPreview, String> {
+    if kind == ImportKind::Backup {
+        return Ok(ImportPreview::Backup {
+            manifest: crate::backup::read_manifest(&path.to_string_lossy())?,
+        });
+    }
+
+    let habits = load_habits(path, kind)?;
+    Ok(ImportPreview::Habits {
+        habits: habits.len() as u64,
+        completions: habits
+            .iter()
+            .map(|habit| habit.completions.len() as u64)
+            .sum(),
+        sample: habits
+            .into_iter()
+            .take(SAMPLE_SIZE)
+            .map(|habit| habit.name)
+            .collect(),
+    })
+}
+
+fn inspect(path: &Path) -> DroppedFile {
+    let kind = ImportKind::detect(path);
+    let result = match kind {
+        Some(kind) => preview(path, kind),
+        None => Err("Only .json, .csv and backup (.tar.gz) files can be imported".to_string()),
+    };
+
+    let (preview, error) = match result {
+        Ok(preview) => (Some(preview), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    DroppedFile {
+        path: path.to_string_lossy().into_owned(),
+        kind,
+        preview,
+        error,
+    }
+}
+
+/// Previews each file dropped onto the main window and announces it with
+/// [`IMPORT_FILE_DROPPED_EVENT`]. Nothing is imported until the frontend calls
+/// [`import_file`].
+pub fn watch(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+            return;
+        };
+
+        let window = handle.clone();
+        let paths = paths.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            for path in paths {
+                let file = inspect(&path);
+                if let Err(e) = window.emit(IMPORT_FILE_DROPPED_EVENT, &file) {
+                    tracing::warn!("Failed to emit {}: {}", IMPORT_FILE_DROPPED_EVENT, e);
+                }
+            }
+        });
+    });
+}
+
+/// Imports a dropped file into the active profile. Backups are restored as by
+/// `import_backup`; habits from JSON and CSV files are added to the local habits, merging
+/// completed days into habits that already exist under the same name.
+#[tauri::command]
+pub async fn import_file(
+    app: AppHandle,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<ImportResult, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let kind = ImportKind::detect(Path::new(&path)).ok_or_else(|| {
+        AppError::invalid_input("Only .json, .csv and backup (.tar.gz) files can be imported")
+    })?;
+    if kind == ImportKind::Backup {
+        return crate::backup::import_backup(app, path, operation_id)
+            .await
+            .map(ImportResult::Backup);
+    }
+
+    let progress = ProgressReporter::new(&app, operation_id);
+    progress.report("reading", 0, None);
+    let habits = crate::storage::run_blocking(move || load_habits(Path::new(&path), kind)).await?;
+
+    let total = habits.len() as u64;
+    progress.report("importing", 0, Some(total));
+    let summary = crate::habits::import_habits(&app, habits).await?;
+    progress.finish(
+        "importing",
+        total,
+        Some(total),
+        Some(format!(
+            "Imported {} habits and {} completed days",
+            total, summary.completions_imported
+        )),
+    );
+
+    Ok(ImportResult::Habits(summary))
+}
@@ -0,0 +1,107 @@
+//! Fixture-backed networking for end-to-end tests.
+//!
+//! Compiled only with the `fixtures` feature. Requests are answered from files listed in
+//! `manifest.json` inside the fixtures directory (`LOTUS_FIXTURES_DIR`, defaulting to the
+//! bundled `src-tauri/fixtures`), so WebDriver runs never touch the real backend.
+//! Set `LOTUS_FIXTURES=off` to run a fixtures build against the live site.
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::error::FetchError;
+
+#[derive(Debug, Deserialize)]
+struct FixtureRoute {
+    /// URL pattern where `*` matches any run of characters
+    url: String,
+    #[serde(default = "default_status")]
+    status: u16,
+    file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixtureManifest {
+    routes: Vec<FixtureRoute>,
+}
+
+struct FixtureSet {
+    dir: PathBuf,
+    routes: Vec<FixtureRoute>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn fixture_set() -> &'static Result<FixtureSet, String> {
+    static FIXTURES: OnceLock<Result<FixtureSet, String>> = OnceLock::new();
+
+    FIXTURES.get_or_init(|| {
+        let dir = std::env::var_os("LOTUS_FIXTURES_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+
+        let content = std::fs::read_to_string(dir.join("manifest.json"))
+            .map_err(|e| format!("Failed to read fixture manifest: {}", e))?;
+        let manifest: FixtureManifest = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse fixture manifest: {}", e))?;
+
+        Ok(FixtureSet {
+            dir,
+            routes: manifest.routes,
+        })
+    })
+}
+
+pub fn is_active() -> bool {
+    std::env::var("LOTUS_FIXTURES").map_or(true, |value| value != "off")
+}
+
+/// Serves `url` from the first matching fixture route.
+pub fn respond(url: &str) -> Result<serde_json::Value, FetchError> {
+    let fixtures = fixture_set().as_ref().map_err(|e| e.clone())?;
+
+    let route = fixtures
+        .routes
+        .iter()
+        .find(|route| matches_pattern(&route.url, url))
+        .ok_or_else(|| format!("No fixture matches {}", url))?;
+
+    let content = std::fs::read_to_string(fixtures.dir.join(&route.file))
+        .map_err(|e| format!("Failed to read fixture {}: {}", route.file, e))?;
+
+    let status = reqwest::StatusCode::from_u16(route.status)
+        .map_err(|e| format!("Invalid fixture status {}: {}", route.status, e))?;
+
+    if !status.is_success() {
+        return Err(FetchError::from_response(status, &content, None));
+    }
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse fixture {}: {}", route.file, e).into())
+}
+
+fn matches_pattern(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the pattern must match the whole URL
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::i18n::text;
+
+/// Emitted every second with the [`FocusStatus`] of a running session
+pub const TIMER_TICK_EVENT: &str = "timer-tick";
+
+/// Emitted with the final [`FocusStatus`] when a session runs out
+pub const TIMER_COMPLETE_EVENT: &str = "timer-complete";
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest session accepted, to catch unit mix-ups such as milliseconds
+const MAX_SESSION_SECS: u64 = 12 * 60 * 60;
+
+struct FocusSession {
+    id: String,
+    label: Option<String>,
+    duration_secs: u64,
+    started_at: i64,
+    /// Time counted before the current run, i.e. across earlier pauses
+    elapsed_before_secs: u64,
+    /// Start of the current run; `None` while paused
+    running_since: Option<i64>,
+    /// Paused because the user went idle rather than by the user
+    paused_for_idle: bool,
+}
+
+impl FocusSession {
+    fn elapsed_secs(&self, now: i64) -> u64 {
+        let current_run = self
+            .running_since
+            .map(|since| (now - since).max(0) as u64)
+            .unwrap_or(0);
+        (self.elapsed_before_secs + current_run).min(self.duration_secs)
+    }
+
+    fn status(&self, now: i64) -> FocusStatus {
+        let elapsed_secs = self.elapsed_secs(now);
+        FocusStatus {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            duration_secs: self.duration_secs,
+            elapsed_secs,
+            remaining_secs: self.duration_secs - elapsed_secs,
+            started_at: self.started_at,
+            paused: self.running_since.is_none(),
+        }
+    }
+}
+
+/// Snapshot of a focus session as seen by the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStatus {
+    pub id: String,
+    pub label: Option<String>,
+    pub duration_secs: u64,
+    pub elapsed_secs: u64,
+    pub remaining_secs: u64,
+    pub started_at: i64,
+    pub paused: bool,
+}
+
+/// The single active session. It lives in the native layer so it keeps counting while
+/// the webview reloads or is throttled in the background.
+#[derive(Default)]
+pub struct FocusState(Mutex<Option<FocusSession>>);
+
+impl FocusState {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<FocusSession>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn new_session_id() -> String {
+    std::iter::repeat_with(fastrand::alphanumeric)
+        .take(10)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn notify_complete(app: &AppHandle, status: &FocusStatus) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(text(app, "notification.focus_complete"))
+        .body(
+            status
+                .label
+                .clone()
+                .unwrap_or_else(|| text(app, "notification.focus_break")),
+        )
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+fn tick(app: &AppHandle) {
+    let now = chrono::Utc::now().timestamp();
+
+    let (status, finished) = {
+        let state = app.state::<FocusState>();
+        let mut session = state.lock();
+        let Some(status) = session
+            .as_ref()
+            .filter(|session| session.running_since.is_some())
+            .map(|session| session.status(now))
+        else {
+            return;
+        };
+
+        let finished = status.remaining_secs == 0;
+        if finished {
+            *session = None;
+        }
+        (status, finished)
+    };
+
+    if finished {
+        crate::focus_block::stop_for_session(app);
+        if let Err(e) = app.emit(TIMER_COMPLETE_EVENT, &status) {
+            tracing::warn!("Failed to emit timer completion: {}", e);
+        }
+        if let Err(e) = notify_complete(app, &status) {
+            tracing::warn!("Focus session notification failed: {}", e);
+        }
+        if !crate::dnd::holds_alerts(app) {
+            crate::audio::play_alert(app, &crate::settings::current(app).sound.timer_sound);
+        }
+    } else if let Err(e) = app.emit(TIMER_TICK_EVENT, &status) {
+        tracing::warn!("Failed to emit timer tick: {}", e);
+    }
+}
+
+/// Pauses the running session as of `since`, when the user went idle, so the time away
+/// is not counted. Returns the paused session, if one was running.
+pub fn pause_for_idle(app: &AppHandle, since: i64) -> Option<FocusStatus> {
+    let state = app.state::<FocusState>();
+    let mut session = state.lock();
+    let session = session
+        .as_mut()
+        .filter(|session| session.running_since.is_some())?;
+
+    session.elapsed_before_secs = session.elapsed_secs(since);
+    session.running_since = None;
+    session.paused_for_idle = true;
+    Some(session.status(chrono::Utc::now().timestamp()))
+}
+
+/// Resumes a session paused by [`pause_for_idle`]; sessions the user paused stay paused.
+pub fn resume_after_idle(app: &AppHandle) -> Option<FocusStatus> {
+    let state = app.state::<FocusState>();
+    let mut session = state.lock();
+    let session = session.as_mut().filter(|session| session.paused_for_idle)?;
+
+    let now = chrono::Utc::now().timestamp();
+    session.running_since = Some(now);
+    session.paused_for_idle = false;
+    Some(session.status(now))
+}
+
+/// Starts the background task that advances the active focus session.
+pub fn spawn_ticker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            tick(&app);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn start_focus_session(
+    app: AppHandle,
+    state: State<'_, FocusState>,
+    duration_secs: u64,
+    label: Option<String>,
+) -> Result<FocusStatus, AppError> {
+    if duration_secs == 0 || duration_secs > MAX_SESSION_SECS {
+        return Err(AppError::invalid_input(format!(
+            "Focus session length must be between 1 and {} seconds",
+            MAX_SESSION_SECS
+        )));
+    }
+
+    let mut session = state.lock();
+    if session.is_some() {
+        return Err(AppError::invalid_state("A focus session is already active"));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let started = FocusSession {
+        id: new_session_id(),
+        label,
+        duration_secs,
+        started_at: now,
+        elapsed_before_secs: 0,
+        running_since: Some(now),
+        paused_for_idle: false,
+    };
+    let status = started.status(now);
+    *session = Some(started);
+
+    crate::focus_block::start_for_session(&app);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn pause_focus_session(state: State<'_, FocusState>) -> Result<FocusStatus, AppError> {
+    let mut session = state.lock();
+    let session = session
+        .as_mut()
+        .ok_or_else(|| AppError::invalid_state("No active focus session"))?;
+
+    let now = chrono::Utc::now().timestamp();
+    if session.running_since.is_some() {
+        session.elapsed_before_secs = session.elapsed_secs(now);
+        session.running_since = None;
+    }
+
+    Ok(session.status(now))
+}
+
+#[tauri::command]
+pub async fn resume_focus_session(state: State<'_, FocusState>) -> Result<FocusStatus, AppError> {
+    let mut session = state.lock();
+    let session = session
+        .as_mut()
+        .ok_or_else(|| AppError::invalid_state("No active focus session"))?;
+
+    let now = chrono::Utc::now().timestamp();
+    if session.running_since.is_none() {
+        session.running_since = Some(now);
+        session.paused_for_idle = false;
+    }
+
+    Ok(session.status(now))
+}
+
+#[tauri::command]
+pub async fn cancel_focus_session(
+    app: AppHandle,
+    state: State<'_, FocusState>,
+) -> Result<(), AppError> {
+    state
+        .lock()
+        .take()
+        .ok_or_else(|| AppError::invalid_state("No active focus session"))?;
+
+    crate::focus_block::stop_for_session(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_active_session(
+    state: State<'_, FocusState>,
+) -> Result<Option<FocusStatus>, AppError> {
+    let now = chrono::Utc::now().timestamp();
+    Ok(state.lock().as_ref().map(|session| session.status(now)))
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Manager, State, Url, Wry};
+
+use crate::error::AppError;
+use crate::middleware::Middleware;
+use crate::settings::SettingsState;
+
+/// Emitted with a [`BlockHit`] whenever a page or request to a blocked domain is stopped
+pub const FOCUS_BLOCK_HIT_EVENT: &str = "focus-block-hit";
+
+/// Emitted with the new [`FocusBlockStatus`] when blocking starts or stops
+pub const FOCUS_BLOCK_CHANGED_EVENT: &str = "focus-block-changed";
+
+const MAX_BLOCKED_DOMAINS: usize = 500;
+
+/// Distracting domains, persisted with the other settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusBlockSettings {
+    /// Each domain also covers its subdomains, e.g. `reddit.com` covers `old.reddit.com`
+    pub blocked_domains: Vec<String>,
+    /// Blocks the domains for as long as a focus session is active
+    pub block_during_sessions: bool,
+}
+
+impl Default for FocusBlockSettings {
+    fn default() -> Self {
+        Self {
+            blocked_domains: Vec::new(),
+            block_during_sessions: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FocusBlockStatus {
+    pub active: bool,
+    pub domains: Vec<String>,
+    pub started_at: Option<i64>,
+    /// Navigations and requests stopped since blocking started
+    pub hits: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockHit {
+    /// The blocked domain the URL fell under
+    pub domain: String,
+    pub url: String,
+}
+
+struct ActiveBlock {
+    domains: Vec<String>,
+    started_at: i64,
+    hits: u64,
+    /// Started along with a focus session rather than by the user
+    for_session: bool,
+}
+
+/// The block in effect, if any. Blocking covers pages opened in the app's webviews and
+/// requests made through the native fetch layer.
+#[derive(Default)]
+pub struct FocusBlockState(RwLock<Option<ActiveBlock>>);
+
+impl FocusBlockState {
+    fn status(&self) -> FocusBlockStatus {
+        match self.0.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            Some(block) => FocusBlockStatus {
+                active: true,
+                domains: block.domains.clone(),
+                started_at: Some(block.started_at),
+                hits: block.hits,
+            },
+            None => FocusBlockStatus::default(),
+        }
+    }
+
+    /// The blocked domain `url` falls under, counting the hit.
+    fn check(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+
+        let mut block = self.0.write().unwrap_or_else(|e| e.into_inner());
+        let block = block.as_mut()?;
+        let domain = block
+            .domains
+            .iter()
+            .find(|domain| {
+                host == **domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })?
+            .clone();
+        block.hits += 1;
+        Some(domain)
+    }
+}
+
+/// Reduces input such as `https://www.Reddit.com/r/all` or `*.reddit.com` to the bare
+/// host, which also covers subdomains.
+fn normalize_domain(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    let host = match Url::parse(trimmed) {
+        Ok(url) if url.has_host() => url.host_str().unwrap_or_default().to_string(),
+        _ => trimmed
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+    let host = host
+        .trim_start_matches("*.")
+        .trim_start_matches("www.")
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+
+    let valid = host.contains('.')
+        && host.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid {
+        return Err(format!("Invalid domain: {}", input));
+    }
+
+    Ok(host)
+}
+
+pub fn normalize_domains(app: &AppHandle, domains: &[String]) -> Result<Vec<String>, String> {
+    if domains.len() > MAX_BLOCKED_DOMAINS {
+        return Err(format!(
+            "At most {} domains can be blocked",
+            MAX_BLOCKED_DOMAINS
+        ));
+    }
+
+    // Blocking the website itself would lock the user out of the app
+    let website = Url::parse(&crate::website_url(app))
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+
+    let mut normalized = Vec::with_capacity(domains.len());
+    for domain in domains {
+        let domain = normalize_domain(domain)?;
+        if website
+            .as_deref()
+            .is_some_and(|website| website == domain || website.ends_with(&format!(".{}", domain)))
+        {
+            return Err(format!(
+                "{} hosts Lotus Routine and cannot be blocked",
+                domain
+            ));
+        }
+        if !normalized.contains(&domain) {
+            normalized.push(domain);
+        }
+    }
+
+    Ok(normalized)
+}
+
+fn emit_status(app: &AppHandle) {
+    let status = app.state::<FocusBlockState>().status();
+    if let Err(e) = app.emit(FOCUS_BLOCK_CHANGED_EVENT, &status) {
+        tracing::warn!("Failed to emit focus block status: {}", e);
+    }
+}
+
+fn report_hit(app: &AppHandle, domain: String, url: &Url) {
+    tracing::info!("Blocked {} during focus", domain);
+    let hit = BlockHit {
+        domain,
+        url: url.to_string(),
+    };
+    if let Err(e) = app.emit(FOCUS_BLOCK_HIT_EVENT, &hit) {
+        tracing::warn!("Failed to emit focus block hit: {}", e);
+    }
+}
+
+/// Starts blocking `domains`, replacing any block in effect.
+fn start(app: &AppHandle, domains: Vec<String>, for_session: bool) -> FocusBlockStatus {
+    let state = app.state::<FocusBlockState>();
+    *state.0.write().unwrap_or_else(|e| e.into_inner()) = Some(ActiveBlock {
+        domains,
+        started_at: chrono::Utc::now().timestamp(),
+        hits: 0,
+        for_session,
+    });
+
+    emit_status(app);
+    state.status()
+}
+
+/// Lifts the block in effect, or only one started for a focus session.
+fn stop(app: &AppHandle, only_for_session: bool) -> bool {
+    let stopped = {
+        let state = app.state::<FocusBlockState>();
+        let mut block = state.0.write().unwrap_or_else(|e| e.into_inner());
+        if block
+            .as_ref()
+            .is_some_and(|block| block.for_session || !only_for_session)
+        {
+            block.take().is_some()
+        } else {
+            false
+        }
+    };
+
+    if stopped {
+        emit_status(app);
+    }
+    stopped
+}
+
+/// Starts blocking the configured domains when a focus session starts, if the settings
+/// ask for it. A block the user started stays as it is.
+pub fn start_for_session(app: &AppHandle) {
+    let settings = crate::settings::current(app).focus_block;
+    if !settings.block_during_sessions || settings.blocked_domains.is_empty() {
+        return;
+    }
+
+    let state = app.state::<FocusBlockState>();
+    if state.0.read().unwrap_or_else(|e| e.into_inner()).is_none() {
+        start(app, settings.blocked_domains, true);
+    }
+}
+
+/// Lifts the block started by [`start_for_session`] when the session ends.
+pub fn stop_for_session(app: &AppHandle) {
+    stop(app, true);
+}
+
+/// Plugin that stops webviews from navigating to blocked domains, including links
+/// followed in the website and windows it opens.
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri::plugin::Builder::new("focus-block")
+        .on_navigation(|webview, url| {
+            let app = webview.app_handle();
+            match app.state::<FocusBlockState>().check(url) {
+                Some(domain) => {
+                    report_hit(app, domain, url);
+                    false
+                }
+                None => true,
+            }
+        })
+        .build()
+}
+
+/// Fails requests made through the native fetch layer to blocked domains.
+pub struct FocusBlockMiddleware {
+    app: AppHandle,
+}
+
+impl FocusBlockMiddleware {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl Middleware for FocusBlockMiddleware {
+    fn name(&self) -> &str {
+        "focus-block"
+    }
+
+    fn on_request(&self, request: &mut reqwest::Request) -> Result<(), String> {
+        match self.app.state::<FocusBlockState>().check(request.url()) {
+            Some(domain) => {
+                report_hit(&self.app, domain.clone(), request.url());
+                Err(format!("{} is blocked during focus", domain))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Starts blocking distracting domains: the given ones, or the configured list.
+#[tauri::command]
+pub async fn start_focus_block(
+    app: AppHandle,
+    domains: Option<Vec<String>>,
+) -> Result<FocusBlockStatus, AppError> {
+    let domains = match domains {
+        Some(domains) => normalize_domains(&app, &domains).map_err(AppError::invalid_input)?,
+        None => crate::settings::current(&app).focus_block.blocked_domains,
+    };
+    if domains.is_empty() {
+        return Err(AppError::invalid_input("No domains to block"));
+    }
+
+    Ok(start(&app, domains, false))
+}
+
+/// Lifts the block. Returns whether one was in effect.
+#[tauri::command]
+pub async fn stop_focus_block(app: AppHandle) -> Result<bool, AppError> {
+    Ok(stop(&app, false))
+}
+
+#[tauri::command]
+pub async fn get_focus_block_status(
+    state: State<'_, FocusBlockState>,
+) -> Result<FocusBlockStatus, AppError> {
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn get_focus_block_settings(
+    state: State<'_, SettingsState>,
+) -> Result<FocusBlockSettings, AppError> {
+    Ok(state.get().focus_block)
+}
+
+/// Saves the blocked domains, normalized to bare hosts. A block in effect keeps its
+/// domains until it is restarted.
+#[tauri::command]
+pub async fn set_focus_block_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: FocusBlockSettings,
+) -> Result<FocusBlockSettings, AppError> {
+    let settings = FocusBlockSettings {
+        blocked_domains: normalize_domains(&app, &settings.blocked_domains)
+            .map_err(AppError::invalid_input)?,
+        ..settings
+    };
+
+    state.update(&app, |current| current.focus_block = settings.clone())?;
+    Ok(settings)
+}
@@ -0,0 +1,466 @@
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::cache_store::{with_store_blocking, CacheStore};
+use crate::demo::ensure_not_demo;
+use crate::error::AppError;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A habit tracked locally, optionally linked to its record on the website
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Habit {
+    /// Local id, stable even before the habit has been synced
+    pub id: String,
+    pub server_id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub archived: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Completed days in `YYYY-MM-DD` format, oldest first
+    pub completions: Vec<String>,
+}
+
+/// Fields to change in [`update_habit`]; absent fields are left alone and empty strings
+/// clear optional text
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HabitPatch {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub archived: Option<bool>,
+}
+
+/// A habit read from an exported file; imports match it to local habits by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportedHabit {
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    /// Completed days in `YYYY-MM-DD` format
+    pub completions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HabitImportSummary {
+    pub habits_created: u64,
+    /// Imported habits that already existed under the same name
+    pub habits_matched: u64,
+    /// Completed days added; days already recorded are not counted
+    pub completions_imported: u64,
+}
+
+fn new_habit_id() -> String {
+    std::iter::repeat_with(fastrand::alphanumeric)
+        .take(10)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn load_habit(store: &CacheStore, id: &str) -> Result<Option<Habit>, String> {
+    let habit = store
+        .connection()
+        .query_row(
+            "SELECT id, server_id, name, description, color, archived, created_at, updated_at
+             FROM habits WHERE id = ?1 AND deleted_at IS NULL",
+            params![id],
+            |row| {
+                Ok(Habit {
+                    id: row.get(0)?,
+                    server_id: row.get(1)?,
+                    name: row.get(2)?,
+                    description: row.get(3)?,
+                    color: row.get(4)?,
+                    archived: row.get(5)?,
+                    created_at: row.get(6)?,
+                    updated_at: row.get(7)?,
+                    completions: Vec::new(),
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read habit: {}", e))?;
+
+    habit
+        .map(|mut habit| {
+            habit.completions = completions(store, &habit.id)?;
+            Ok(habit)
+        })
+        .transpose()
+}
+
+fn completions(store: &CacheStore, habit_id: &str) -> Result<Vec<String>, String> {
+    let mut statement = store
+        .connection()
+        .prepare("SELECT date FROM habit_completions WHERE habit_id = ?1 ORDER BY date")
+        .map_err(|e| format!("Failed to query habit completions: {}", e))?;
+
+    let rows = statement
+        .query_map(params![habit_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query habit completions: {}", e))?;
+
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read habit completions: {}", e))
+}
+
+fn require_habit(store: &CacheStore, id: &str) -> Result<Habit, String> {
+    load_habit(store, id)?.ok_or_else(|| format!("Unknown habit: {}", id))
+}
+
+/// Runs `f` with the habit `id`, telling a missing habit apart from a failing store.
+async fn with_habit<T: Send + 'static>(
+    app: &AppHandle,
+    id: String,
+    f: impl FnOnce(&CacheStore, Habit) -> Result<T, String> + Send + 'static,
+) -> Result<T, AppError> {
+    let habit_id = id.clone();
+    with_store_blocking(app, move |store| match load_habit(store, &habit_id)? {
+        Some(habit) => f(store, habit).map(Some),
+        None => Ok(None),
+    })
+    .await
+    .map_err(AppError::storage)?
+    .ok_or_else(|| AppError::not_found(format!("Unknown habit: {}", id)))
+}
+
+/// Habits in `store` in creation order, for code already running on the blocking pool.
+pub fn read_habits(store: &CacheStore, include_archived: bool) -> Result<Vec<Habit>, String> {
+    let ids: Vec<String> = {
+        let mut statement = store
+            .connection()
+            .prepare(
+                "SELECT id FROM habits
+                 WHERE deleted_at IS NULL AND (?1 OR archived = 0)
+                 ORDER BY created_at",
+            )
+            .map_err(|e| format!("Failed to query habits: {}", e))?;
+
+        let rows = statement
+            .query_map(params![include_archived], |row| row.get(0))
+            .map_err(|e| format!("Failed to query habits: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read habits: {}", e))?
+    };
+
+    ids.iter().map(|id| require_habit(store, id)).collect()
+}
+
+/// Habits of the active profile in creation order, without any guest-mode filtering.
+pub async fn all_habits(app: &AppHandle, include_archived: bool) -> Result<Vec<Habit>, AppError> {
+    with_store_blocking(app, move |store| read_habits(store, include_archived))
+        .await
+        .map_err(AppError::storage)
+}
+
+#[tauri::command]
+pub async fn list_habits(
+    app: AppHandle,
+    include_archived: Option<bool>,
+) -> Result<Vec<Habit>, AppError> {
+    // Guest mode never shows the real profile's habits
+    if crate::demo::is_demo(&app) {
+        return Ok(Vec::new());
+    }
+
+    all_habits(&app, include_archived.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn create_habit(
+    app: AppHandle,
+    name: String,
+    description: Option<String>,
+    color: Option<String>,
+    server_id: Option<String>,
+) -> Result<Habit, AppError> {
+    ensure_not_demo(&app)?;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Habit name cannot be empty"));
+    }
+
+    let id = new_habit_id();
+    let now = chrono::Utc::now().timestamp();
+
+    let habit = with_store_blocking(&app, move |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO habits (id, server_id, name, description, color, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                params![
+                    id,
+                    non_empty(server_id),
+                    name,
+                    non_empty(description),
+                    non_empty(color),
+                    now
+                ],
+            )
+            .map_err(|e| format!("Failed to create habit: {}", e))?;
+
+        require_habit(store, &id)
+    })
+    .await
+    .map_err(AppError::storage)?;
+
+    crate::badge::refresh(&app);
+    crate::home_widget::refresh(&app);
+    Ok(habit)
+}
+
+#[tauri::command]
+pub async fn update_habit(
+    app: AppHandle,
+    id: String,
+    patch: HabitPatch,
+) -> Result<Habit, AppError> {
+    ensure_not_demo(&app)?;
+
+    if patch
+        .name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        return Err(AppError::invalid_input("Habit name cannot be empty"));
+    }
+
+    let habit = with_habit(&app, id.clone(), move |store, mut habit| {
+        if let Some(name) = patch.name {
+            habit.name = name.trim().to_string();
+        }
+        if patch.description.is_some() {
+            habit.description = non_empty(patch.description);
+        }
+        if patch.color.is_some() {
+            habit.color = non_empty(patch.color);
+        }
+        if let Some(archived) = patch.archived {
+            habit.archived = archived;
+        }
+
+        store
+            .connection()
+            .execute(
+                "UPDATE habits SET name = ?2, description = ?3, color = ?4, archived = ?5,
+                    updated_at = ?6
+                 WHERE id = ?1",
+                params![
+                    id,
+                    habit.name,
+                    habit.description,
+                    habit.color,
+                    habit.archived,
+                    chrono::Utc::now().timestamp()
+                ],
+            )
+            .map_err(|e| format!("Failed to update habit: {}", e))?;
+
+        require_habit(store, &id)
+    })
+    .await?;
+
+    crate::badge::refresh(&app);
+    crate::home_widget::refresh(&app);
+    Ok(habit)
+}
+
+/// Marks the habit done on `date` (today by default), or undoes that with `completed: false`.
+#[tauri::command]
+pub async fn complete_habit(
+    app: AppHandle,
+    id: String,
+    date: Option<String>,
+    completed: Option<bool>,
+) -> Result<Habit, AppError> {
+    ensure_not_demo(&app)?;
+
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, DATE_FORMAT).map_err(|e| {
+            AppError::invalid_input(format!("Invalid completion date '{}': {}", date, e))
+        })?,
+        None => Local::now().date_naive(),
+    }
+    .format(DATE_FORMAT)
+    .to_string();
+    let now = chrono::Utc::now().timestamp();
+
+    let habit = with_habit(&app, id.clone(), move |store, _| {
+        if completed.unwrap_or(true) {
+            store.connection().execute(
+                "INSERT OR IGNORE INTO habit_completions (habit_id, date, completed_at)
+                 VALUES (?1, ?2, ?3)",
+                params![id, date, now],
+            )
+        } else {
+            store.connection().execute(
+                "DELETE FROM habit_completions WHERE habit_id = ?1 AND date = ?2",
+                params![id, date],
+            )
+        }
+        .map_err(|e| format!("Failed to record habit completion: {}", e))?;
+
+        store
+            .connection()
+            .execute(
+                "UPDATE habits SET updated_at = ?2 WHERE id = ?1",
+                params![id, now],
+            )
+            .map_err(|e| format!("Failed to update habit: {}", e))?;
+
+        require_habit(store, &id)
+    })
+    .await?;
+
+    crate::badge::refresh(&app);
+    crate::home_widget::refresh(&app);
+    Ok(habit)
+}
+
+/// Deletes a habit. Synced habits keep a tombstone so the deletion can be sent later.
+#[tauri::command]
+pub async fn delete_habit(app: AppHandle, id: String) -> Result<(), AppError> {
+    ensure_not_demo(&app)?;
+
+    with_habit(&app, id.clone(), move |store, habit| {
+        let conn = store.connection();
+
+        conn.execute(
+            "DELETE FROM habit_completions WHERE habit_id = ?1",
+            params![id],
+        )
+        .map_err(|e| format!("Failed to delete habit completions: {}", e))?;
+
+        if habit.server_id.is_some() {
+            conn.execute(
+                "UPDATE habits SET deleted_at = ?2, updated_at = ?2 WHERE id = ?1",
+                params![id, chrono::Utc::now().timestamp()],
+            )
+        } else {
+            conn.execute("DELETE FROM habits WHERE id = ?1", params![id])
+        }
+        .map_err(|e| format!("Failed to delete habit: {}", e))?;
+
+        Ok(())
+    })
+    .await?;
+
+    crate::badge::refresh(&app);
+    crate::home_widget::refresh(&app);
+    Ok(())
+}
+
+/// Adds imported habits and their completed days in a single transaction. Names must be
+/// trimmed and non-empty and dates already in `YYYY-MM-DD` format.
+pub async fn import_habits(
+    app: &AppHandle,
+    habits: Vec<ImportedHabit>,
+) -> Result<HabitImportSummary, AppError> {
+    ensure_not_demo(app)?;
+    let now = chrono::Utc::now().timestamp();
+
+    let summary = with_store_blocking(app, move |store| {
+        let tx = store
+            .connection()
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start habit import: {}", e))?;
+
+        let mut summary = HabitImportSummary::default();
+        for habit in &habits {
+            let existing: Option<String> = tx
+                .query_row(
+                    "SELECT id FROM habits
+                     WHERE name = ?1 COLLATE NOCASE AND deleted_at IS NULL
+                     ORDER BY created_at LIMIT 1",
+                    params![habit.name],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| format!("Failed to query habits: {}", e))?;
+
+            let id = match existing {
+                Some(id) => {
+                    summary.habits_matched += 1;
+                    id
+                }
+                None => {
+                    let id = new_habit_id();
+                    tx.execute(
+                        "INSERT INTO habits (id, name, description, color, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                        params![
+                            id,
+                            habit.name,
+                            non_empty(habit.description.clone()),
+                            non_empty(habit.color.clone()),
+                            now
+                        ],
+                    )
+                    .map_err(|e| format!("Failed to import habit '{}': {}", habit.name, e))?;
+                    summary.habits_created += 1;
+                    id
+                }
+            };
+
+            for date in &habit.completions {
+                let added = tx
+                    .execute(
+                        "INSERT OR IGNORE INTO habit_completions (habit_id, date, completed_at)
+                         VALUES (?1, ?2, ?3)",
+                        params![id, date, now],
+                    )
+                    .map_err(|e| format!("Failed to import habit completion: {}", e))?;
+                summary.completions_imported += added as u64;
+            }
+
+            tx.execute(
+                "UPDATE habits SET updated_at = ?2 WHERE id = ?1",
+                params![id, now],
+            )
+            .map_err(|e| format!("Failed to update habit: {}", e))?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit habit import: {}", e))?;
+        Ok(summary)
+    })
+    .await
+    .map_err(AppError::storage)?;
+
+    crate::badge::refresh(app);
+    crate::home_widget::refresh(app);
+    Ok(summary)
+}
+
+/// Records the website's id for a local habit once it has been synced.
+#[tauri::command]
+pub async fn link_habit(app: AppHandle, id: String, server_id: String) -> Result<Habit, AppError> {
+    ensure_not_demo(&app)?;
+
+    with_habit(&app, id.clone(), move |store, _| {
+        store
+            .connection()
+            .execute(
+                "UPDATE habits SET server_id = ?2 WHERE id = ?1",
+                params![id, server_id],
+            )
+            .map_err(|e| format!("Failed to link habit: {}", e))?;
+
+        require_habit(store, &id)
+    })
+    .await
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Kind of tactile feedback, mirroring the iOS feedback generators
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HapticKind {
+    Light,
+    Medium,
+    Heavy,
+    Success,
+    Warning,
+    Error,
+}
+
+impl HapticKind {
+    /// Vibration pattern in milliseconds, alternating on and off, for devices without
+    /// feedback generators
+    #[cfg(target_os = "android")]
+    fn vibration_pattern(self) -> &'static [u32] {
+        match self {
+            HapticKind::Light => &[10],
+            HapticKind::Medium => &[20],
+            HapticKind::Heavy => &[40],
+            HapticKind::Success => &[15, 60, 15],
+            HapticKind::Warning => &[30, 80, 30],
+            HapticKind::Error => &[40, 60, 40, 60, 40],
+        }
+    }
+}
+
+#[cfg(target_os = "ios")]
+fn perform(app: &AppHandle, kind: HapticKind) -> Result<(), String> {
+    use objc2::MainThreadOnly;
+    use objc2_ui_kit::{
+        UIImpactFeedbackGenerator, UIImpactFeedbackStyle, UINotificationFeedbackGenerator,
+        UINotificationFeedbackType,
+    };
+
+    app.run_on_main_thread(move || {
+        let Some(mtm) = objc2::MainThreadMarker::new() else {
+            return;
+        };
+
+        let impact = |style| {
+            UIImpactFeedbackGenerator::initWithStyle(UIImpactFeedbackGenerator::alloc(mtm), style)
+                .impactOccurred()
+        };
+        let notification =
+            |feedback| UINotificationFeedbackGenerator::new(mtm).notificationOccurred(feedback);
+
+        match kind {
+            HapticKind::Light => impact(UIImpactFeedbackStyle::Light),
+            HapticKind::Medium => impact(UIImpactFeedbackStyle::Medium),
+            HapticKind::Heavy => impact(UIImpactFeedbackStyle::Heavy),
+            HapticKind::Success => notification(UINotificationFeedbackType::Success),
+            HapticKind::Warning => notification(UINotificationFeedbackType::Warning),
+            HapticKind::Error => notification(UINotificationFeedbackType::Error),
+        }
+    })
+    .map_err(|e| format!("Failed to trigger haptic feedback: {}", e))
+}
+
+/// Android's WebView forwards the Vibration API to the system vibrator, which needs the
+/// `VIBRATE` permission from the manifest.
+#[cfg(target_os = "android")]
+fn perform(app: &AppHandle, kind: HapticKind) -> Result<(), String> {
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window is not open".to_string())?;
+    let pattern = serde_json::to_string(kind.vibration_pattern())
+        .map_err(|e| format!("Failed to encode vibration pattern: {}", e))?;
+
+    window
+        .eval(format!(
+            "navigator.vibrate && navigator.vibrate({})",
+            pattern
+        ))
+        .map_err(|e| format!("Failed to trigger haptic feedback: {}", e))
+}
+
+/// Desktops have no haptics the app can drive, so feedback is silently skipped.
+#[cfg(desktop)]
+fn perform(_app: &AppHandle, _kind: HapticKind) -> Result<(), String> {
+    Ok(())
+}
+
+/// Plays tactile feedback on phones, e.g. after a check-in or a streak milestone.
+#[tauri::command]
+pub async fn haptic_feedback(app: AppHandle, kind: HapticKind) -> Result<(), AppError> {
+    perform(&app, kind).map_err(AppError::internal)
+}
@@ -0,0 +1,292 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::cache_store::CacheUsage;
+use crate::error::AppError;
+
+/// Free space below which storage is reported as running low
+const LOW_DISK_SPACE_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Missed runs after which a scheduler is no longer considered running
+const MISSED_RUNS: u32 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityHealth {
+    pub is_online: bool,
+    pub can_reach_website: bool,
+    pub captive_portal: bool,
+    /// Time until the website answered the probe, if it could be reached
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheHealth {
+    #[serde(flatten)]
+    pub usage: CacheUsage,
+    /// Entries found damaged and removed; see `get_corrupted_entries`
+    pub corrupted_entries: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueHealth {
+    pub pending: u64,
+    pub retrying: u64,
+    pub oldest_created_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub flushing: bool,
+}
+
+/// How a background scheduler fared on its latest run
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerStatus {
+    pub name: String,
+    pub interval_secs: u64,
+    pub last_run_at: i64,
+    /// Why the latest run failed, if it did
+    pub last_error: Option<String>,
+    /// Whether the scheduler ran within the last few intervals
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageHealth {
+    /// Data directory of the active profile
+    pub data_dir: String,
+    /// Space available to the app on the data directory's volume, if it could be read
+    pub free_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+}
+
+/// Everything the system status panel shows. Parts that could not be checked are `None`
+/// and explained in `issues`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub checked_at: i64,
+    pub connectivity: ConnectivityHealth,
+    pub cache: Option<CacheHealth>,
+    pub queue: Option<QueueHealth>,
+    pub schedulers: Vec<SchedulerStatus>,
+    pub storage: Option<StorageHealth>,
+    /// Problems found, as short sentences for the user; empty when all is well
+    pub issues: Vec<String>,
+}
+
+/// Latest run of each background scheduler, reported by the schedulers themselves
+#[derive(Default)]
+pub struct SchedulerHealth(Mutex<BTreeMap<&'static str, SchedulerStatus>>);
+
+/// Records a run of the scheduler `name`, which wakes up every `interval`.
+pub fn record_run(
+    app: &AppHandle,
+    name: &'static str,
+    interval: Duration,
+    result: &Result<(), String>,
+) {
+    let state = app.state::<SchedulerHealth>();
+    let mut schedulers = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    schedulers.insert(
+        name,
+        SchedulerStatus {
+            name: name.to_string(),
+            interval_secs: interval.as_secs(),
+            last_run_at: chrono::Utc::now().timestamp(),
+            last_error: result.as_ref().err().cloned(),
+            running: true,
+        },
+    );
+}
+
+fn scheduler_statuses(app: &AppHandle, now: i64) -> Vec<SchedulerStatus> {
+    let state = app.state::<SchedulerHealth>();
+    let schedulers = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    schedulers
+        .values()
+        .map(|status| SchedulerStatus {
+            running: now - status.last_run_at
+                <= (status.interval_secs * u64::from(MISSED_RUNS)) as i64,
+            ..status.clone()
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Free and total bytes of the volume holding `path`.
+    pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+
+        let block_size = stat.f_frsize as u64;
+        Some((
+            stat.f_bavail as u64 * block_size,
+            stat.f_blocks as u64 * block_size,
+        ))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::Path;
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    /// Free and total bytes of the volume holding `path`.
+    pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+        let mut free = 0u64;
+        let mut total = 0u64;
+        unsafe {
+            GetDiskFreeSpaceExW(
+                &HSTRING::from(path.as_os_str()),
+                Some(&mut free),
+                Some(&mut total),
+                None,
+            )
+        }
+        .ok()?;
+        Some((free, total))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::path::Path;
+
+    pub fn disk_space(_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+fn storage_health(data_dir: &Path) -> StorageHealth {
+    let space = platform::disk_space(data_dir);
+    StorageHealth {
+        data_dir: data_dir.to_string_lossy().into_owned(),
+        free_bytes: space.map(|(free, _)| free),
+        total_bytes: space.map(|(_, total)| total),
+    }
+}
+
+async fn cache_health(app: &AppHandle) -> Result<CacheHealth, String> {
+    crate::cache_store::with_store_blocking(app, |store| {
+        Ok(CacheHealth {
+            usage: store.usage()?,
+            corrupted_entries: store.corrupted_entries()?.len() as u64,
+        })
+    })
+    .await
+}
+
+/// Checks connectivity, the cache, the offline queue, the background schedulers and free
+/// disk space in one go, for a system status panel.
+#[tauri::command]
+pub async fn health_check(app: AppHandle) -> Result<HealthReport, AppError> {
+    let ((network, latency), cache, queue) = tokio::join!(
+        crate::probe_network(&app),
+        cache_health(&app),
+        crate::outbox::queue_status(&app),
+    );
+    let now = chrono::Utc::now().timestamp();
+    let mut issues = Vec::new();
+
+    if network.captive_portal {
+        issues.push("A Wi-Fi login page is blocking the connection".to_string());
+    } else if !network.is_online {
+        issues.push("No internet connection".to_string());
+    } else if !network.can_reach_website {
+        issues.push("The website cannot be reached".to_string());
+    }
+
+    let cache = match cache {
+        Ok(cache) => {
+            if cache.corrupted_entries > 0 {
+                issues.push(format!(
+                    "{} damaged cache entries were removed",
+                    cache.corrupted_entries
+                ));
+            }
+            Some(cache)
+        }
+        Err(e) => {
+            issues.push(format!("Cache could not be checked: {}", e));
+            None
+        }
+    };
+
+    let queue = match queue {
+        Ok(status) => {
+            if status.retrying > 0 {
+                issues.push(format!(
+                    "{} queued changes failed to send and will be retried",
+                    status.retrying
+                ));
+            }
+            Some(QueueHealth {
+                pending: status.pending,
+                retrying: status.retrying,
+                oldest_created_at: status.oldest_created_at,
+                last_error: status.last_error,
+                flushing: status.flushing,
+            })
+        }
+        Err(e) => {
+            issues.push(format!("Offline queue could not be checked: {}", e));
+            None
+        }
+    };
+
+    let schedulers = scheduler_statuses(&app, now);
+    for scheduler in &schedulers {
+        if !scheduler.running {
+            issues.push(format!("The {} scheduler has stopped", scheduler.name));
+        } else if let Some(error) = &scheduler.last_error {
+            issues.push(format!(
+                "The {} scheduler failed: {}",
+                scheduler.name, error
+            ));
+        }
+    }
+
+    let storage = match crate::profiles::active_data_dir(&app) {
+        Ok(dir) => {
+            let storage =
+                crate::storage::run_blocking(move || Ok::<_, AppError>(storage_health(&dir)))
+                    .await?;
+            if storage
+                .free_bytes
+                .is_some_and(|free| free < LOW_DISK_SPACE_BYTES)
+            {
+                issues.push("Disk space is running low".to_string());
+            }
+            Some(storage)
+        }
+        Err(e) => {
+            issues.push(format!("Storage could not be checked: {}", e));
+            None
+        }
+    };
+
+    Ok(HealthReport {
+        checked_at: now,
+        connectivity: ConnectivityHealth {
+            is_online: network.is_online,
+            can_reach_website: network.can_reach_website,
+            captive_portal: network.captive_portal,
+            latency_ms: latency.map(|latency| latency.as_millis() as u64),
+        },
+        cache,
+        queue,
+        schedulers,
+        storage,
+        issues,
+    })
+}
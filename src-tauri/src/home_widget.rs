@@ -0,0 +1,196 @@
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use tauri::AppHandle;
+
+use crate::cache_store::with_store;
+use crate::error::AppError;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// File the native widgets read the summary from
+#[cfg(mobile)]
+const WIDGET_DATA_FILE: &str = "widget-data.json";
+
+/// App group shared with the iOS widget extension; both targets need it in their
+/// entitlements
+#[cfg(target_os = "ios")]
+const APP_GROUP: &str = "group.com.th3void.lotusroutine";
+
+/// Path of the summary in the content provider, under the `<package>.widgetdata` authority
+#[cfg(target_os = "android")]
+const CONTENT_PATH: &str = "summary";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WidgetHabit {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub done_today: bool,
+    pub current_streak: u32,
+}
+
+/// Today's progress as shown by the home-screen widgets
+#[derive(Debug, Clone, Serialize)]
+pub struct WidgetData {
+    /// Local day the summary is for, in `YYYY-MM-DD` format
+    pub date: String,
+    pub completed: u32,
+    pub total: u32,
+    /// Longest streak still alive among the habits
+    pub best_streak: u32,
+    pub habits: Vec<WidgetHabit>,
+    pub updated_at: i64,
+}
+
+fn build(app: &AppHandle) -> Result<WidgetData, AppError> {
+    let today = Local::now().date_naive();
+    let date = today.format(DATE_FORMAT).to_string();
+
+    let habits = with_store(app, |store| crate::habits::read_habits(store, false))
+        .map_err(AppError::storage)?;
+    let habits: Vec<WidgetHabit> = habits
+        .into_iter()
+        .map(|habit| {
+            let days: BTreeSet<NaiveDate> = habit
+                .completions
+                .iter()
+                .filter_map(|day| NaiveDate::parse_from_str(day, DATE_FORMAT).ok())
+                .collect();
+
+            WidgetHabit {
+                done_today: days.contains(&today),
+                current_streak: crate::streaks::summarize(&days, today, 0).current_streak,
+                id: habit.id,
+                name: habit.name,
+                color: habit.color,
+            }
+        })
+        .collect();
+
+    Ok(WidgetData {
+        date,
+        completed: habits.iter().filter(|habit| habit.done_today).count() as u32,
+        total: habits.len() as u32,
+        best_streak: habits
+            .iter()
+            .map(|habit| habit.current_streak)
+            .max()
+            .unwrap_or(0),
+        habits,
+        updated_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+/// Writes the summary into the app group container, where the widget extension reads it.
+#[cfg(target_os = "ios")]
+fn publish(_app: &AppHandle, data: &[u8]) -> Result<(), String> {
+    use objc2_foundation::{NSFileManager, NSString};
+
+    let container = NSFileManager::defaultManager()
+        .containerURLForSecurityApplicationGroupIdentifier(&NSString::from_str(APP_GROUP))
+        .and_then(|url| url.path())
+        .ok_or_else(|| format!("App group {} is not available", APP_GROUP))?;
+
+    let path = std::path::Path::new(&container.to_string()).join(WIDGET_DATA_FILE);
+    crate::storage::write_atomic(&path, data)
+        .map_err(|e| format!("Failed to write widget data: {}", e))
+}
+
+/// Writes the summary to the app's data directory, which `WidgetDataProvider` serves,
+/// and tells observers of the provider that it changed.
+#[cfg(target_os = "android")]
+fn publish(app: &AppHandle, data: &[u8]) -> Result<(), String> {
+    use jni::objects::{JObject, JString, JValue};
+    use jni::JNIEnv;
+    use tauri::Manager;
+
+    fn notify_change(env: &mut JNIEnv, activity: &JObject) -> jni::errors::Result<()> {
+        let package: JString = env
+            .call_method(activity, "getPackageName", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let package: String = env.get_string(&package)?.into();
+
+        let uri = env.new_string(format!("content://{}.widgetdata/{}", package, CONTENT_PATH))?;
+        let uri = env
+            .call_static_method(
+                "android/net/Uri",
+                "parse",
+                "(Ljava/lang/String;)Landroid/net/Uri;",
+                &[JValue::from(&uri)],
+            )?
+            .l()?;
+        let resolver = env
+            .call_method(
+                activity,
+                "getContentResolver",
+                "()Landroid/content/ContentResolver;",
+                &[],
+            )?
+            .l()?;
+        env.call_method(
+            &resolver,
+            "notifyChange",
+            "(Landroid/net/Uri;Landroid/database/ContentObserver;)V",
+            &[JValue::from(&uri), JValue::from(&JObject::null())],
+        )?;
+        Ok(())
+    }
+
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get data directory: {}", e))?;
+    crate::storage::write_atomic(&dir.join(WIDGET_DATA_FILE), data)
+        .map_err(|e| format!("Failed to write widget data: {}", e))?;
+
+    let context = ndk_context::android_context();
+    let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+        .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+    let mut env = vm
+        .attach_current_thread()
+        .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+    notify_change(&mut env, &activity).map_err(|e| {
+        let _ = env.exception_clear();
+        format!("Failed to notify widget data observers: {}", e)
+    })
+}
+
+/// Builds today's summary and hands it to the native widgets. Desktop has no home-screen
+/// widgets, so there the summary is only returned.
+fn update(app: &AppHandle) -> Result<WidgetData, AppError> {
+    let data = build(app)?;
+
+    #[cfg(mobile)]
+    {
+        let json = serde_json::to_vec(&data)
+            .map_err(|e| AppError::internal(format!("Failed to serialize widget data: {}", e)))?;
+        publish(app, &json).map_err(AppError::storage)?;
+    }
+
+    Ok(data)
+}
+
+/// Updates the widgets on the blocking thread pool after local habits change or a sync
+/// finishes. Guest mode leaves them showing the real profile.
+pub fn refresh(app: &AppHandle) {
+    if crate::demo::is_demo(app) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = update(&app) {
+            tracing::warn!("Failed to update home-screen widget data: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn refresh_widget_data(app: AppHandle) -> Result<WidgetData, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    crate::storage::run_blocking(move || update(&app)).await
+}
@@ -0,0 +1,241 @@
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Timeout for each public endpoint probed by the internet connectivity check
+pub const INTERNET_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Timeout for the website reachability check
+pub const WEBSITE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout for API requests issued by the fetch layer
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Public-key pins for the first-party hosts. Shipped with each release, so a rotated
+/// pin-set reaches users through the updater; pinning is off while `pins` is empty.
+const CERTIFICATE_PINS: &str = include_str!("../certificate-pins.json");
+
+/// Marks TLS failures caused by a pin mismatch in the error chain
+const PIN_MISMATCH: &str = "certificate pin mismatch";
+
+#[derive(Debug, Deserialize)]
+struct PinSet {
+    hosts: Vec<String>,
+    /// SHA-256 hashes of a SubjectPublicKeyInfo, as `sha256/<base64>`
+    pins: Vec<String>,
+}
+
+/// Standard WebPKI validation plus, for pinned hosts, a check that some certificate in
+/// the chain carries one of the pinned public keys.
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    hosts: Vec<String>,
+    pins: Vec<Vec<u8>>,
+    enabled: Arc<AtomicBool>,
+}
+
+impl PinningVerifier {
+    fn matches_pin(&self, certificate: &CertificateDer<'_>) -> bool {
+        webpki::EndEntityCert::try_from(certificate).is_ok_and(|parsed| {
+            let hash = Sha256::digest(parsed.subject_public_key_info().as_ref());
+            self.pins.iter().any(|pin| pin.as_slice() == hash.as_slice())
+        })
+    }
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let host = server_name.to_str();
+        let pinned = self.enabled.load(Ordering::Relaxed)
+            && !self.pins.is_empty()
+            && self.hosts.iter().any(|pinned| pinned.eq_ignore_ascii_case(&host));
+        if !pinned {
+            return Ok(verified);
+        }
+
+        if std::iter::once(end_entity)
+            .chain(intermediates)
+            .any(|certificate| self.matches_pin(certificate))
+        {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(format!("{} for {}", PIN_MISMATCH, host)))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn tls_config(enabled: Arc<AtomicBool>) -> Result<rustls::ClientConfig, String> {
+    let pin_set: PinSet = serde_json::from_str(CERTIFICATE_PINS)
+        .map_err(|e| format!("Failed to parse certificate pins: {}", e))?;
+    let pins = pin_set
+        .pins
+        .iter()
+        .map(|pin| {
+            pin.strip_prefix("sha256/")
+                .and_then(|hash| base64::engine::general_purpose::STANDARD.decode(hash).ok())
+                .filter(|hash| hash.len() == 32)
+                .ok_or_else(|| format!("Invalid certificate pin '{}'", pin))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| format!("Failed to create certificate verifier: {}", e))?;
+
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinningVerifier {
+            inner,
+            hosts: pin_set.hosts,
+            pins,
+            enabled,
+        }))
+        .with_no_client_auth();
+
+    Ok(config)
+}
+
+/// Builds a client with the app's defaults, routed through `proxy`.
+fn build(
+    pinning: Arc<AtomicBool>,
+    proxy: &crate::proxy::ProxyConfig,
+    password: Option<&str>,
+) -> Result<reqwest::Client, String> {
+    let builder = reqwest::Client::builder()
+        .user_agent(concat!("LotusRoutine/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .use_preconfigured_tls(tls_config(pinning)?);
+
+    crate::proxy::configure(builder, proxy, password)?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Process-wide HTTP client, shared so connections and TLS sessions are reused.
+/// Per-call timeouts are set on each request.
+pub struct HttpClient {
+    client: RwLock<reqwest::Client>,
+    pinning: Arc<AtomicBool>,
+}
+
+impl HttpClient {
+    /// Client using the system proxy until the settings are loaded.
+    pub fn new() -> Result<Self, String> {
+        let pinning = Arc::new(AtomicBool::new(true));
+        let client = build(pinning.clone(), &Default::default(), None)?;
+
+        Ok(Self {
+            client: RwLock::new(client),
+            pinning,
+        })
+    }
+
+    /// Turns pin checks for the first-party hosts on or off for new connections.
+    pub fn set_pinning(&self, enabled: bool) {
+        self.pinning.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Replaces the shared client with one using `proxy`. Requests already under way
+    /// finish on the previous client.
+    pub fn set_proxy(
+        &self,
+        proxy: &crate::proxy::ProxyConfig,
+        password: Option<&str>,
+    ) -> Result<(), String> {
+        let client = build(self.pinning.clone(), proxy, password)?;
+        *self.client.write().unwrap_or_else(|e| e.into_inner()) = client;
+        Ok(())
+    }
+
+    /// Standalone client using `proxy`, for trying settings before saving them.
+    pub fn with_proxy(
+        &self,
+        proxy: &crate::proxy::ProxyConfig,
+        password: Option<&str>,
+    ) -> Result<reqwest::Client, String> {
+        build(self.pinning.clone(), proxy, password)
+    }
+}
+
+/// Handle to the shared client; cloning it is cheap.
+pub fn client(app: &AppHandle) -> reqwest::Client {
+    app.state::<HttpClient>()
+        .client
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// The pinned host whose certificate failed the pin check, if that is what broke
+/// the request.
+pub fn pin_failure(error: &reqwest::Error) -> Option<String> {
+    let mut source: Option<&dyn std::error::Error> = Some(error);
+    while let Some(error) = source {
+        let message = error.to_string();
+        if let Some(position) = message.find(PIN_MISMATCH) {
+            let host = message[position + PIN_MISMATCH.len()..]
+                .trim_start_matches(" for ")
+                .to_string();
+            return Some(host);
+        }
+        source = error.source();
+    }
+    None
+}
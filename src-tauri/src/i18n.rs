@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// Used for keys a translation is missing, and for locales without a translation
+const DEFAULT_LOCALE: &str = "en";
+
+/// Translations of the native UI strings, bundled into the binary
+const TRANSLATIONS: [(&str, &str); 4] = [
+    ("de", include_str!("../locales/de.json")),
+    ("en", include_str!("../locales/en.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleInfo {
+    /// Bundled translation in use
+    pub locale: String,
+    /// Locale reported by the operating system, e.g. `de-AT`
+    pub system_locale: Option<String>,
+    pub locale_override: Option<String>,
+    pub available: Vec<String>,
+}
+
+type Catalogs = HashMap<&'static str, HashMap<String, String>>;
+
+fn catalogs() -> &'static Catalogs {
+    static CATALOGS: OnceLock<Catalogs> = OnceLock::new();
+
+    CATALOGS.get_or_init(|| {
+        TRANSLATIONS
+            .iter()
+            .filter_map(|(locale, content)| match serde_json::from_str(content) {
+                Ok(strings) => Some((*locale, strings)),
+                Err(e) => {
+                    tracing::warn!("Failed to parse {} translations: {}", locale, e);
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// Bundled translation for a locale such as `fr-CA` or `pt_BR`, matched on its language.
+pub fn bundled_locale(locale: &str) -> Option<&'static str> {
+    let language = locale
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    TRANSLATIONS
+        .iter()
+        .map(|(bundled, _)| *bundled)
+        .find(|bundled| *bundled == language)
+}
+
+/// Translation used for native strings: the override from the settings if there is one,
+/// otherwise the operating system's locale.
+pub fn current_locale(app: &AppHandle) -> &'static str {
+    crate::settings::current(app)
+        .locale_override
+        .or_else(sys_locale::get_locale)
+        .and_then(|locale| bundled_locale(&locale))
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Native UI string `key` in the current locale, with each `{name}` placeholder replaced
+/// by the matching value in `args`.
+pub fn text_with(app: &AppHandle, key: &str, args: &[(&str, &str)]) -> String {
+    let catalogs = catalogs();
+    let template = [current_locale(app), DEFAULT_LOCALE]
+        .iter()
+        .find_map(|locale| catalogs.get(locale)?.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+pub fn text(app: &AppHandle, key: &str) -> String {
+    text_with(app, key, &[])
+}
+
+#[tauri::command]
+pub async fn get_locale(app: AppHandle) -> Result<LocaleInfo, AppError> {
+    Ok(LocaleInfo {
+        locale: current_locale(&app).to_string(),
+        system_locale: sys_locale::get_locale(),
+        locale_override: crate::settings::current(&app).locale_override,
+        available: TRANSLATIONS
+            .iter()
+            .map(|(locale, _)| locale.to_string())
+            .collect(),
+    })
+}
+
+/// Shows native strings in `locale` instead of the system language, or follows the system
+/// again when `None`.
+#[tauri::command]
+pub async fn set_locale_override(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    locale: Option<String>,
+) -> Result<(), AppError> {
+    if let Some(locale) = &locale {
+        if bundled_locale(locale).is_none() {
+            return Err(AppError::invalid_input(format!(
+                "No translation available for {}",
+                locale
+            )));
+        }
+    }
+
+    state.update(&app, |settings| settings.locale_override = locale)?;
+
+    #[cfg(desktop)]
+    {
+        crate::tray::refresh_menu(&app);
+        crate::app_menu::refresh(&app);
+    }
+    Ok(())
+}
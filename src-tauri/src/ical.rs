@@ -0,0 +1,150 @@
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::habits::Habit;
+use crate::reminders::Reminder;
+
+const PRODUCT_ID: &str = "-//Lotus Routine//Habits//EN";
+
+/// Domain part of the UIDs, keeping them stable across exports
+const UID_DOMAIN: &str = "lotus-routine";
+
+const WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// Reminders become events of this length
+const REMINDER_DURATION: &str = "PT15M";
+
+/// Escapes TEXT values as required by RFC 5545.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded so no line exceeds 75 octets.
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn push_reminder(ics: &mut String, reminder: &Reminder, stamp: &str) {
+    let Ok(time) = crate::reminders::parse_time(&reminder.time) else {
+        tracing::warn!(
+            "Skipping reminder '{}' with invalid time",
+            reminder.habit_name
+        );
+        return;
+    };
+    let start = Local::now().date_naive().and_time(time);
+
+    let rule = if reminder.days.is_empty() {
+        "FREQ=DAILY".to_string()
+    } else {
+        let days: Vec<&str> = reminder
+            .days
+            .iter()
+            .filter_map(|day| WEEKDAYS.get(usize::from(*day)).copied())
+            .collect();
+        format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+    };
+
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:reminder-{}@{}", reminder.id, UID_DOMAIN));
+    push_line(ics, &format!("DTSTAMP:{}", stamp));
+    // Floating time: the reminder follows the user's local clock wherever they are
+    push_line(ics, &format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+    push_line(ics, &format!("DURATION:{}", REMINDER_DURATION));
+    push_line(ics, &format!("RRULE:{}", rule));
+    push_line(ics, &format!("SUMMARY:{}", escape(&reminder.habit_name)));
+    if let Some(body) = &reminder.body {
+        push_line(ics, &format!("DESCRIPTION:{}", escape(body)));
+    }
+    push_line(ics, "END:VEVENT");
+}
+
+fn push_habit(ics: &mut String, habit: &Habit, stamp: &str) {
+    let created = Local
+        .timestamp_opt(habit.created_at, 0)
+        .single()
+        .map(|created| created.date_naive())
+        .unwrap_or_else(|| Local::now().date_naive());
+
+    push_line(ics, "BEGIN:VTODO");
+    push_line(ics, &format!("UID:habit-{}@{}", habit.id, UID_DOMAIN));
+    push_line(ics, &format!("DTSTAMP:{}", stamp));
+    push_line(
+        ics,
+        &format!("DTSTART;VALUE=DATE:{}", created.format("%Y%m%d")),
+    );
+    push_line(ics, "RRULE:FREQ=DAILY");
+    push_line(ics, &format!("SUMMARY:{}", escape(&habit.name)));
+    if let Some(description) = &habit.description {
+        push_line(ics, &format!("DESCRIPTION:{}", escape(description)));
+    }
+    if let Some(last) = habit
+        .completions
+        .last()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+    {
+        push_line(
+            ics,
+            &format!(
+                "X-LOTUS-LAST-COMPLETED;VALUE=DATE:{}",
+                last.format("%Y%m%d")
+            ),
+        );
+    }
+    push_line(ics, "END:VTODO");
+}
+
+/// Builds a calendar with the habits as daily to-dos and the reminders as recurring events.
+pub fn render(habits: &[Habit], reminders: &[Reminder]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, &format!("PRODID:{}", PRODUCT_ID));
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "X-WR-CALNAME:Lotus Routine");
+
+    for habit in habits {
+        push_habit(&mut ics, habit, &stamp);
+    }
+    for reminder in reminders {
+        push_reminder(&mut ics, reminder, &stamp);
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Writes the local habits and reminders to an iCalendar file and returns the number of
+/// components written.
+#[tauri::command]
+pub async fn export_ical(app: AppHandle, path: String) -> Result<u64, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let habits = crate::habits::all_habits(&app, false).await?;
+    let reminders = crate::reminders::reminders(&app).await?;
+
+    let ics = render(&habits, &reminders);
+    crate::storage::run_blocking(move || {
+        std::fs::write(&path, ics)
+            .map_err(|e| AppError::storage(format!("Failed to write calendar file: {}", e)))
+    })
+    .await?;
+
+    Ok((habits.len() + reminders.len()) as u64)
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::AppError;
+use crate::focus::FocusStatus;
+use crate::settings::SettingsState;
+
+/// Emitted with an [`IdleEvent`] once the user has been away for the idle threshold
+pub const USER_IDLE_EVENT: &str = "user-idle";
+
+/// Emitted with an [`IdleEvent`] when the user comes back after being idle
+pub const USER_ACTIVE_EVENT: &str = "user-active";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const MIN_THRESHOLD_SECS: u64 = 30;
+const MAX_THRESHOLD_SECS: u64 = 4 * 60 * 60;
+
+/// When the user counts as away, persisted with the other settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleSettings {
+    /// Seconds without keyboard or mouse input anywhere on the system
+    pub threshold_secs: u64,
+    /// Pauses a running focus session while the user is away
+    pub pause_focus_sessions: bool,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            threshold_secs: 5 * 60,
+            pause_focus_sessions: true,
+        }
+    }
+}
+
+impl IdleSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(MIN_THRESHOLD_SECS..=MAX_THRESHOLD_SECS).contains(&self.threshold_secs) {
+            return Err(AppError::invalid_input(format!(
+                "Idle threshold must be between {} and {} seconds",
+                MIN_THRESHOLD_SECS, MAX_THRESHOLD_SECS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdleStatus {
+    /// Seconds since the last input anywhere on the system
+    pub idle_secs: u64,
+    pub idle: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdleEvent {
+    /// How long the user has been away; for `user-active`, as of the last check before
+    /// they came back
+    pub idle_secs: u64,
+    /// The focus session paused or resumed along with the change, if any
+    pub focus_session: Option<FocusStatus>,
+}
+
+/// Seconds since the last input anywhere on the system, or `None` where that cannot be
+/// measured, e.g. on mobile or a Linux desktop without an idle monitor.
+fn system_idle_secs() -> Option<u64> {
+    platform::idle_time().map(|idle| idle.as_secs())
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    pub fn idle_time() -> Option<Duration> {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+            return None;
+        }
+
+        // Both are milliseconds since boot, wrapping after 49.7 days
+        let now = unsafe { GetTickCount() };
+        Some(Duration::from_millis(now.wrapping_sub(info.dwTime) as u64))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2_core_graphics::{CGEventSource, CGEventSourceStateID, CGEventType};
+    use std::time::Duration;
+
+    /// `kCGAnyInputEventType`
+    const ANY_INPUT_EVENT: CGEventType = CGEventType(u32::MAX);
+
+    pub fn idle_time() -> Option<Duration> {
+        let secs = CGEventSource::seconds_since_last_event_type(
+            CGEventSourceStateID::CombinedSessionState,
+            ANY_INPUT_EVENT,
+        );
+        Duration::try_from_secs_f64(secs).ok()
+    }
+}
+
+/// Asks the desktop's idle monitor over D-Bus: GNOME's Mutter, or the screen saver
+/// service that KDE and others provide. Both also work under Wayland.
+#[cfg(target_os = "linux")]
+mod platform {
+    use dbus::blocking::Connection;
+    use std::time::Duration;
+
+    const DBUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+    pub fn idle_time() -> Option<Duration> {
+        let connection = Connection::new_session().ok()?;
+
+        let mutter = connection.with_proxy(
+            "org.gnome.Mutter.IdleMonitor",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            DBUS_TIMEOUT,
+        );
+        let idle: Result<(u64,), _> =
+            mutter.method_call("org.gnome.Mutter.IdleMonitor", "GetIdletime", ());
+        if let Ok((millis,)) = idle {
+            return Some(Duration::from_millis(millis));
+        }
+
+        let screen_saver = connection.with_proxy(
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            DBUS_TIMEOUT,
+        );
+        let idle: Result<(u32,), _> =
+            screen_saver.method_call("org.freedesktop.ScreenSaver", "GetSessionIdleTime", ());
+        idle.ok()
+            .map(|(millis,)| Duration::from_millis(millis as u64))
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn idle_time() -> Option<Duration> {
+        None
+    }
+}
+
+/// Whether the user has gone without input for the idle threshold. Blocks briefly on
+/// Linux, where the idle time is asked over D-Bus.
+pub fn is_away(app: &AppHandle) -> bool {
+    let threshold_secs = crate::settings::current(app).idle.threshold_secs;
+    system_idle_secs().is_some_and(|idle_secs| idle_secs >= threshold_secs)
+}
+
+async fn poll_idle_secs() -> Option<u64> {
+    tauri::async_runtime::spawn_blocking(system_idle_secs)
+        .await
+        .ok()
+        .flatten()
+}
+
+fn emit(app: &AppHandle, event: &str, payload: &IdleEvent) {
+    if let Err(e) = app.emit(event, payload) {
+        tracing::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Starts the background task that watches for the user going idle and coming back.
+/// Stops right away where idle time cannot be measured.
+pub fn spawn_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        // How long the user had been away at the last poll while idle
+        let mut away_secs: Option<u64> = None;
+
+        loop {
+            interval.tick().await;
+
+            let Some(idle_secs) = poll_idle_secs().await else {
+                if away_secs.is_none() {
+                    tracing::warn!("System idle time is unavailable; idle detection is off");
+                    return;
+                }
+                continue;
+            };
+            let settings = crate::settings::current(&app).idle;
+
+            match away_secs {
+                None if idle_secs >= settings.threshold_secs => {
+                    let focus_session = if settings.pause_focus_sessions {
+                        let since = chrono::Utc::now().timestamp() - idle_secs as i64;
+                        crate::focus::pause_for_idle(&app, since)
+                    } else {
+                        None
+                    };
+
+                    emit(
+                        &app,
+                        USER_IDLE_EVENT,
+                        &IdleEvent {
+                            idle_secs,
+                            focus_session,
+                        },
+                    );
+                    away_secs = Some(idle_secs);
+                }
+                // Idle time restarts from zero on input, so a drop means the user is back
+                Some(previous) if idle_secs < previous => {
+                    let focus_session = crate::focus::resume_after_idle(&app);
+
+                    emit(
+                        &app,
+                        USER_ACTIVE_EVENT,
+                        &IdleEvent {
+                            idle_secs: previous,
+                            focus_session,
+                        },
+                    );
+                    away_secs = None;
+                }
+                Some(_) => away_secs = Some(idle_secs),
+                None => {}
+            }
+        }
+    });
+}
+
+/// Returns how long the system has gone without input. Fails on platforms where this
+/// cannot be measured.
+#[tauri::command]
+pub async fn get_idle_time(app: AppHandle) -> Result<IdleStatus, AppError> {
+    let idle_secs = poll_idle_secs()
+        .await
+        .ok_or_else(|| AppError::unsupported("Idle time is not available on this system"))?;
+
+    Ok(IdleStatus {
+        idle_secs,
+        idle: idle_secs >= crate::settings::current(&app).idle.threshold_secs,
+    })
+}
+
+#[tauri::command]
+pub async fn get_idle_settings(state: State<'_, SettingsState>) -> Result<IdleSettings, AppError> {
+    Ok(state.get().idle)
+}
+
+#[tauri::command]
+pub async fn set_idle_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: IdleSettings,
+) -> Result<(), AppError> {
+    settings.validate()?;
+
+    state.update(&app, |current| current.idle = settings)?;
+    Ok(())
+}
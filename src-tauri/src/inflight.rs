@@ -0,0 +1,161 @@
+use futures_util::future::{BoxFuture, Shared};
+use futures_util::FutureExt;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::FetchResult;
+
+type SharedFetch = Shared<BoxFuture<'static, Result<FetchResult, AppError>>>;
+
+struct Inflight {
+    fetch: SharedFetch,
+    /// Callers currently waiting for the result
+    waiters: usize,
+}
+
+/// What makes two fetches interchangeable
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FetchId {
+    key: String,
+    url: String,
+    /// Profile and cache scope the fetch runs for, which decide the session token sent
+    /// and where the response is cached
+    account: (String, String),
+    /// See [`headers_digest`]
+    headers: [u8; 32],
+}
+
+/// Fetches in progress
+#[derive(Default)]
+pub struct InflightFetches(Mutex<HashMap<FetchId, Inflight>>);
+
+/// One caller's interest in a shared fetch. Dropping the last one before the result is
+/// in, e.g. when the request is cancelled, drops the fetch and its network request.
+struct Waiter<'a> {
+    fetches: &'a InflightFetches,
+    id: FetchId,
+    fetch: SharedFetch,
+    done: bool,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        let mut inflight = self.fetches.0.lock().unwrap_or_else(|e| e.into_inner());
+
+        // A newer fetch may have replaced this one after it finished
+        let Some(current) = inflight.get_mut(&self.id) else {
+            return;
+        };
+        if !current.fetch.ptr_eq(&self.fetch) {
+            return;
+        }
+
+        current.waiters -= 1;
+        if self.done || current.waiters == 0 {
+            inflight.remove(&self.id);
+        }
+    }
+}
+
+/// SHA-256 of the request headers, with names lowercased and sorted so the same headers
+/// always hash the same
+fn headers_digest(headers: Option<&HashMap<String, String>>) -> [u8; 32] {
+    let mut headers: Vec<_> = headers
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    for (name, value) in headers {
+        // Header names and values cannot contain NUL, so it separates them unambiguously
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().into()
+}
+
+/// Runs `fetch` unless a fetch of the same key, URL and headers is already in progress
+/// for the same account, in which case the caller waits for that one and gets its result.
+/// Pages often ask for the same data from several components at mount, and this keeps
+/// that to one request and one cache write. The first caller's options apply to everyone
+/// who joins it.
+pub async fn coalesce(
+    app: &AppHandle,
+    key: &str,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    fetch: impl Future<Output = Result<FetchResult, AppError>> + Send + 'static,
+) -> Result<FetchResult, AppError> {
+    let scope = crate::cache_store::with_store_blocking(app, |store| Ok(store.scope().to_string()))
+        .await
+        .map_err(AppError::storage)?;
+    let id = FetchId {
+        key: key.to_string(),
+        url: url.to_string(),
+        account: (
+            app.state::<crate::profiles::ProfileState>().active_id(),
+            scope,
+        ),
+        headers: headers_digest(headers),
+    };
+    let fetches = app.state::<InflightFetches>().inner();
+
+    let shared = {
+        let mut inflight = fetches.0.lock().unwrap_or_else(|e| e.into_inner());
+        let current = inflight.entry(id.clone()).or_insert_with(|| Inflight {
+            fetch: fetch.boxed().shared(),
+            waiters: 0,
+        });
+        current.waiters += 1;
+        current.fetch.clone()
+    };
+
+    let mut waiter = Waiter {
+        fetches,
+        id,
+        fetch: shared.clone(),
+        done: false,
+    };
+    let result = shared.await;
+    waiter.done = true;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn headers_digest_ignores_order_and_name_case() {
+        let first = headers(&[("Authorization", "Bearer a"), ("Accept", "text/csv")]);
+        let second = headers(&[("accept", "text/csv"), ("authorization", "Bearer a")]);
+
+        assert_eq!(headers_digest(Some(&first)), headers_digest(Some(&second)));
+    }
+
+    #[test]
+    fn headers_digest_tells_credentials_apart() {
+        let first = headers(&[("Authorization", "Bearer a")]);
+        let second = headers(&[("Authorization", "Bearer b")]);
+
+        assert_ne!(headers_digest(Some(&first)), headers_digest(Some(&second)));
+        assert_ne!(headers_digest(Some(&first)), headers_digest(None));
+        assert_eq!(headers_digest(Some(&HashMap::new())), headers_digest(None));
+    }
+}
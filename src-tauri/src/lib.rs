@@ -1,98 +1,438 @@
+mod anonymize;
+mod app_lock;
+mod app_cache;
+#[cfg(desktop)]
+mod app_menu;
+mod attachments;
+mod audio;
+mod auth;
+#[cfg(desktop)]
+mod autostart;
+mod backup;
+mod badge;
+mod barcode;
+mod cache_metrics;
+mod cache_store;
+mod calendar;
+mod cancellation;
+mod chaos;
+mod clipboard;
+#[cfg(desktop)]
+mod context_menu;
+mod conflicts;
+mod crash;
+mod csv_export;
+mod deep_link;
+mod delta_sync;
+mod demo;
+mod diagnostics;
+mod dnd;
+mod downloads;
+mod error;
+mod file_import;
+mod focus;
+mod focus_block;
+#[cfg(feature = "fixtures")]
+mod fixtures;
+mod habits;
+mod health;
+mod haptics;
+mod home_widget;
+mod http_client;
+mod i18n;
+mod ical;
+mod idle;
+mod inflight;
+mod logging;
+mod middleware;
+mod network_monitor;
+mod oauth;
+mod offline_page;
+mod outbox;
+mod prefetch;
+mod print;
+mod profiles;
+mod progress;
+mod proxy;
+mod recording;
+mod reminders;
+mod report;
+mod retry;
+mod scanner;
+mod secrets;
+mod session;
+mod settings;
+mod share;
+#[cfg(desktop)]
+mod shortcuts;
+mod speech;
+mod storage;
+mod streaks;
+mod sync_schedule;
+mod theme;
+mod thumbnails;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod updates;
+mod uploads;
+mod usage;
+mod websocket;
+#[cfg(desktop)]
+mod widget;
+#[cfg(desktop)]
+mod window_state;
+
 use tauri::{
     Manager, WebviewWindow
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use tauri::AppHandle;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use tokio::sync::Semaphore;
+
+use error::{parse_retry_after, AppError, FetchError};
+use delta_sync::DeltaOptions;
+use demo::DemoState;
+use middleware::{MiddlewareInfo, MiddlewareRegistry};
+use progress::ProgressReporter;
+use retry::RetryOptions;
+
+/// Website loaded when no custom base URL is configured
+const DEFAULT_WEBSITE_URL: &str = "https://accounted.th3void.com";
+
+/// Plain-HTTP endpoint answering 204, used to spot captive portals. Opening it in a
+/// browser lands on the portal's login page while one is active.
+const CAPTIVE_PORTAL_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+/// Simultaneous requests issued by `fetch_many_with_fallback` unless the caller asks otherwise
+const DEFAULT_CONCURRENT_FETCHES: usize = 4;
+
+/// Upper bound on the concurrency a caller may request for a batch fetch
+const MAX_CONCURRENT_FETCHES: usize = 16;
 
-const WEBSITE_URL: &str = "https://accounted.th3void.com";
+/// How many times a throttled (429/503) request is retried after its `Retry-After` delay
+const MAX_RETRY_AFTER_ATTEMPTS: u32 = 2;
+
+/// Longest `Retry-After` delay the fetch layer will wait out before giving up
+const MAX_RETRY_AFTER_SECS: u64 = 30;
+
+/// Smallest cache size limit accepted from the frontend
+const MIN_CACHE_BYTES: u64 = 1024 * 1024;
 
 /// Network connectivity status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NetworkStatus {
     pub is_online: bool,
     pub can_reach_website: bool,
+    /// A captive portal (hotel or airport Wi-Fi login) is answering in place of the internet
+    pub captive_portal: bool,
 }
 
 /// Data fetch result with source information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchResult {
     pub data: serde_json::Value,
-    pub source: String, // "online" or "local"
+    pub source: String, // "online", "online-not-modified", "local" or "demo"
     pub timestamp: i64,
+    pub expires_at: Option<i64>,
+    pub is_stale: bool,
+}
+
+impl FetchResult {
+    fn online(data: serde_json::Value, expires_at: Option<i64>) -> Self {
+        Self {
+            data,
+            source: "online".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            expires_at,
+            is_stale: false,
+        }
+    }
+}
+
+/// Outcome of a request that may have been sent with cache validators
+enum OnlineResponse {
+    Fresh {
+        data: serde_json::Value,
+        validators: cache_store::Validators,
+    },
+    /// The server confirmed the cached copy is current (`304 Not Modified`)
+    NotModified,
+}
+
+/// Body of an outgoing request
+#[derive(Clone, Copy)]
+enum RequestBody<'a> {
+    Json(&'a serde_json::Value),
+    /// Raw bytes, such as an uploaded attachment
+    Bytes {
+        data: &'a [u8],
+        content_type: &'a str,
+    },
+    /// A file streamed from disk as `multipart/form-data`
+    Multipart {
+        upload: &'a uploads::FileUpload,
+        progress: &'a ProgressReporter,
+    },
+}
+
+/// Outcome of [`send_request`]: the server's response, or the id of the queued mutation
+/// when the server could not be reached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendOutcome {
+    pub data: Option<serde_json::Value>,
+    pub queued_id: Option<i64>,
+}
+
+/// A single entry of a batch fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchRequest {
+    pub key: String,
+    pub url: String,
+    pub headers: Option<HashMap<String, String>>,
+    pub max_age_secs: Option<u64>,
+    #[serde(default)]
+    pub retry: Option<RetryOptions>,
+    #[serde(default)]
+    pub delta: Option<DeltaOptions>,
+}
+
+/// Per-item outcome of a batch fetch; exactly one of `result`/`error` is set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFetchItem {
+    pub key: String,
+    pub result: Option<FetchResult>,
+    pub error: Option<AppError>,
+}
+
+/// Base URL of the website for the active profile, e.g. a staging or self-hosted deployment
+fn website_url(app: &AppHandle) -> String {
+    settings::current(app)
+        .base_url
+        .unwrap_or_else(|| DEFAULT_WEBSITE_URL.to_string())
+}
+
+/// Root URL of a custom URI scheme registered by the app. Windows and Android serve these
+/// from `http://<scheme>.localhost`.
+fn custom_scheme_url(scheme: &str) -> reqwest::Url {
+    #[cfg(any(windows, target_os = "android"))]
+    let url = format!("http://{}.localhost/", scheme);
+    #[cfg(not(any(windows, target_os = "android")))]
+    let url = format!("{}://localhost/", scheme);
+
+    reqwest::Url::parse(&url).expect("custom scheme URL is valid")
 }
 
 #[tauri::command]
-async fn retry_connection(window: WebviewWindow) -> Result<(), String> {
-    window.eval(&format!("window.location.href = '{}'", WEBSITE_URL))
-        .map_err(|e| e.to_string())
+async fn retry_connection(app: AppHandle, window: WebviewWindow) -> Result<(), AppError> {
+    let url = serde_json::to_string(&website_url(&app)).map_err(|e| AppError::internal(e.to_string()))?;
+    window.eval(format!("window.location.href = {}", url))
+        .map_err(|e| AppError::internal(e.to_string()))
 }
 
 #[tauri::command]
-async fn check_network_status() -> Result<NetworkStatus, String> {
-    let is_online = check_internet_connectivity().await;
-    let can_reach_website = if is_online {
-        check_website_connectivity().await
+async fn check_network_status(app: AppHandle) -> Result<NetworkStatus, AppError> {
+    let network_status = probe_network_status(&app).await;
+
+    // Connectivity is back: replay anything queued while offline
+    if network_status.can_reach_website {
+        outbox::schedule_flush(&app);
+    }
+
+    Ok(network_status)
+}
+
+async fn probe_network_status(app: &AppHandle) -> NetworkStatus {
+    probe_network(app).await.0
+}
+
+/// Checks connectivity like [`probe_network_status`], also returning how long the website
+/// took to answer when it could be reached.
+async fn probe_network(app: &AppHandle) -> (NetworkStatus, Option<Duration>) {
+    let (captive_portal, reachable) =
+        tokio::join!(check_captive_portal(app), check_internet_connectivity(app));
+    // A portal answers every request itself, so reaching a probe host proves nothing
+    let is_online = reachable && !captive_portal;
+    let latency = if is_online {
+        website_latency(app).await
     } else {
-        false
+        None
     };
 
-    Ok(NetworkStatus {
+    let status = NetworkStatus {
         is_online,
-        can_reach_website,
-    })
+        can_reach_website: latency.is_some(),
+        captive_portal,
+    };
+    (status, latency)
 }
 
-async fn check_internet_connectivity() -> bool {
+/// Whether a captive portal intercepts plain HTTP: the probe URL answers 204 with an empty
+/// body, while a portal redirects it to its login page. Failed probes count as no portal;
+/// the other checks report those as offline.
+async fn check_captive_portal(app: &AppHandle) -> bool {
+    #[cfg(feature = "fixtures")]
+    if fixtures::is_active() {
+        return false;
+    }
+
+    match http_client::client(app)
+        .get(CAPTIVE_PORTAL_PROBE_URL)
+        .timeout(http_client::INTERNET_PROBE_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            response.status().is_success() && response.status() != reqwest::StatusCode::NO_CONTENT
+        }
+        Err(_) => false,
+    }
+}
+
+async fn check_internet_connectivity(app: &AppHandle) -> bool {
+    #[cfg(feature = "fixtures")]
+    if fixtures::is_active() {
+        return true;
+    }
+
     let test_urls = vec![
         "https://www.google.com",
         "https://1.1.1.1",
         "https://8.8.8.8",
     ];
 
+    let client = http_client::client(app);
     for url in test_urls {
-        if let Ok(client) = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(3))
-            .build()
-        {
-            if client.get(url).send().await.is_ok() {
-                return true;
-            }
+        let request = client.get(url).timeout(http_client::INTERNET_PROBE_TIMEOUT);
+        if request.send().await.is_ok() {
+            return true;
         }
     }
 
     false
 }
 
-async fn check_website_connectivity() -> bool {
-    if let Ok(client) = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-    {
-        if client.get(WEBSITE_URL).send().await.is_ok() {
-            return true;
+async fn check_website_connectivity(app: &AppHandle) -> bool {
+    website_latency(app).await.is_some()
+}
+
+/// Time until the website answered, or `None` if it could not be reached.
+async fn website_latency(app: &AppHandle) -> Option<Duration> {
+    #[cfg(feature = "fixtures")]
+    if fixtures::is_active() {
+        return Some(Duration::ZERO);
+    }
+
+    let started = Instant::now();
+    http_client::client(app)
+        .get(website_url(app))
+        .timeout(http_client::WEBSITE_PROBE_TIMEOUT)
+        .send()
+        .await
+        .ok()
+        .map(|_| started.elapsed())
+}
+
+fn get_data_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let data_dir = profiles::active_data_dir(app)?;
+
+    std::fs::create_dir_all(&data_dir)
+        .map_err(|e| AppError::storage(format!("Failed to create data directory: {}", e)))?;
+
+    Ok(data_dir)
+}
+
+/// Path of a state file (settings, schedules). These live in a `state` subdirectory,
+/// apart from the cache database.
+fn get_state_file_path(app: &AppHandle, name: &str) -> Result<PathBuf, AppError> {
+    let state_dir = get_data_dir(app)?.join("state");
+
+    std::fs::create_dir_all(&state_dir)
+        .map_err(|e| AppError::storage(format!("Failed to create state directory: {}", e)))?;
+
+    Ok(state_dir.join(format!("{}.json", name)))
+}
+
+/// Reads a state file, falling back to the type's default when it does not exist yet.
+fn read_state_file<T: serde::de::DeserializeOwned + Serialize + Default>(
+    app: &AppHandle,
+    name: &str,
+) -> Result<T, AppError> {
+    let file_path = get_state_file_path(app, name).map_err(|e| e.with_context(name))?;
+
+    if !file_path.exists() {
+        return Ok(T::default());
+    }
+
+    let content = std::fs::read_to_string(&file_path).map_err(|e| {
+        AppError::storage(format!("Failed to read {} file: {}", name, e)).with_context(name)
+    })?;
+
+    let decoded = storage::decode(name, &content)
+        .map_err(|e| AppError::corrupt_data(e).with_context(name))?;
+    if decoded.upgraded {
+        // Saved right away so the migrations run once, not on every load
+        let json_string = storage::encode(name, &decoded.value)
+            .map_err(|e| AppError::internal(e).with_context(name))?;
+        if let Err(e) = storage::write_atomic(&file_path, json_string.as_bytes()) {
+            tracing::warn!("Failed to save migrated {} file: {}", name, e);
         }
     }
 
-    false
+    Ok(decoded.value)
 }
 
-fn get_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+fn write_state_file<T: Serialize>(app: &AppHandle, name: &str, value: &T) -> Result<(), AppError> {
+    let file_path = get_state_file_path(app, name).map_err(|e| e.with_context(name))?;
+
+    let json_string = storage::encode(name, value)
+        .map_err(|e| AppError::internal(e).with_context(name))?;
+
+    write_encoded_state_file(&file_path, name, &json_string)
+}
 
-    std::fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+fn write_encoded_state_file(
+    file_path: &std::path::Path,
+    name: &str,
+    json_string: &str,
+) -> Result<(), AppError> {
+    storage::write_atomic(file_path, json_string.as_bytes()).map_err(|e| {
+        AppError::storage(format!("Failed to write {} file: {}", name, e)).with_context(name)
+    })
+}
 
-    Ok(app_data_dir)
+/// [`read_state_file`] for async code: the file is read on the blocking thread pool.
+async fn read_state_file_blocking<T>(app: &AppHandle, name: &str) -> Result<T, AppError>
+where
+    T: serde::de::DeserializeOwned + Serialize + Default + Send + 'static,
+{
+    let app = app.clone();
+    let name = name.to_string();
+    storage::run_blocking(move || read_state_file(&app, &name)).await
 }
 
-fn get_data_file_path(app: &AppHandle, key: &str) -> Result<PathBuf, String> {
-    let data_dir = get_data_dir(app)?;
-    Ok(data_dir.join(format!("{}.json", key)))
+/// [`write_state_file`] for async code: `value` is encoded here and written on the
+/// blocking thread pool.
+async fn write_state_file_blocking<T: Serialize>(
+    app: &AppHandle,
+    name: &str,
+    value: &T,
+) -> Result<(), AppError> {
+    let json_string = storage::encode(name, value)
+        .map_err(|e| AppError::internal(e).with_context(name))?;
+
+    let app = app.clone();
+    let name = name.to_string();
+    storage::run_blocking(move || {
+        let file_path = get_state_file_path(&app, &name).map_err(|e| e.with_context(&name))?;
+        write_encoded_state_file(&file_path, &name, &json_string)
+    })
+    .await
 }
 
 #[tauri::command]
@@ -100,129 +440,681 @@ async fn save_local_data(
     app: AppHandle,
     key: String,
     data: serde_json::Value,
-) -> Result<(), String> {
-    let file_path = get_data_file_path(&app, &key)?;
+    ttl_secs: Option<i64>,
+) -> Result<(), AppError> {
+    store_local_data(&app, &key, &data, ttl_secs, None).await?;
+    Ok(())
+}
 
-    let data_with_timestamp = serde_json::json!({
-        "data": data,
-        "timestamp": chrono::Utc::now().timestamp(),
-    });
+/// Writes `data`, fetched from `source_url` if it came from the network, to the active
+/// cache and returns when the entry goes stale, if ever.
+async fn store_local_data(
+    app: &AppHandle,
+    key: &str,
+    data: &serde_json::Value,
+    ttl_secs: Option<i64>,
+    source_url: Option<&str>,
+) -> Result<Option<i64>, AppError> {
+    let demo = app.state::<DemoState>();
+    if demo.is_active() {
+        demo.save(key.to_string(), data.clone());
+        return Ok(None);
+    }
 
-    let json_string = serde_json::to_string_pretty(&data_with_timestamp)
-        .map_err(|e| format!("Failed to serialize data: {}", e))?;
+    let key = key.to_string();
+    let data = data.clone();
+    let source_url = source_url.map(str::to_string);
+    cache_store::with_store_blocking(app, move |store| {
+        let now = chrono::Utc::now().timestamp();
+        store.save(&key, &data, now, ttl_secs, source_url.as_deref())
+    })
+    .await
+    .map_err(AppError::storage)
+}
 
-    std::fs::write(&file_path, json_string)
-        .map_err(|e| format!("Failed to write data file: {}", e))?;
+#[tauri::command]
+async fn set_cache_policy(
+    app: AppHandle,
+    key: String,
+    ttl_secs: Option<i64>,
+) -> Result<(), AppError> {
+    if ttl_secs.is_some_and(|ttl_secs| ttl_secs <= 0) {
+        return Err(AppError::invalid_input("Cache TTL must be positive"));
+    }
 
-    Ok(())
+    cache_store::with_store_blocking(&app, move |store| store.set_policy(&key, ttl_secs))
+        .await
+        .map_err(AppError::storage)
+}
+
+/// The list of records in a cached document: the document itself when it is an array,
+/// otherwise its `entries`, `data`, or `items` array.
+fn document_records(data: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+    match data {
+        serde_json::Value::Array(items) => Some(items),
+        serde_json::Value::Object(object) => ["entries", "data", "items"]
+            .iter()
+            .find_map(|field| object.get(*field).and_then(|v| v.as_array())),
+        _ => None,
+    }
 }
 
 #[tauri::command]
 async fn load_local_data(
     app: AppHandle,
     key: String,
-) -> Result<Option<FetchResult>, String> {
-    let file_path = get_data_file_path(&app, &key)?;
+) -> Result<Option<FetchResult>, AppError> {
+    let cached = read_local_data(&app, &key).await?;
 
-    if !file_path.exists() {
-        return Ok(None);
+    if !demo::is_demo(&app) {
+        match &cached {
+            Some(cached) => cache_metrics::record_hit(&app, &key, cached.is_stale),
+            None => cache_metrics::record_miss(&app, &key),
+        }
     }
 
-    let content = std::fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read data file: {}", e))?;
+    Ok(cached)
+}
 
-    let parsed: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse data file: {}", e))?;
+/// The cached copy of `key`, without counting the read in the cache metrics.
+async fn read_local_data(app: &AppHandle, key: &str) -> Result<Option<FetchResult>, AppError> {
+    let demo = app.state::<DemoState>();
+    if demo.is_active() {
+        return Ok(demo.load(key));
+    }
 
-    let timestamp = parsed
-        .get("timestamp")
-        .and_then(|t| t.as_i64())
-        .unwrap_or(0);
+    let key = key.to_string();
+    let entry = cache_store::with_store_blocking(app, move |store| store.load(&key))
+        .await
+        .map_err(AppError::storage)?;
 
-    let data = parsed
-        .get("data")
-        .cloned()
-        .unwrap_or(serde_json::Value::Null);
+    let now = chrono::Utc::now().timestamp();
 
-    Ok(Some(FetchResult {
-        data,
+    Ok(entry.map(|entry| FetchResult {
+        data: entry.data,
         source: "local".to_string(),
-        timestamp,
+        timestamp: entry.timestamp,
+        expires_at: entry.expires_at,
+        is_stale: entry.expires_at.is_some_and(|expires_at| expires_at <= now),
     }))
 }
 
+/// Passing `delta` switches to incremental fetching: after the first full download only
+/// the changes since the stored cursor are requested and merged into the cached copy.
+///
+/// With a `request_id`, the fetch can be aborted through `cancel_request`.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn fetch_data_with_fallback(
     app: AppHandle,
     key: String,
     url: String,
     headers: Option<HashMap<String, String>>,
-) -> Result<FetchResult, String> {
-    let network_status = check_network_status().await?;
+    max_age_secs: Option<u64>,
+    retry: Option<RetryOptions>,
+    query: Option<HashMap<String, String>>,
+    delta: Option<DeltaOptions>,
+    request_id: Option<String>,
+) -> Result<FetchResult, AppError> {
+    let retry = retry.unwrap_or_default();
+    retry.validate()?;
+    let url = with_query(&url, query.as_ref())?;
+
+    let operation = async {
+        if let Some(fresh) = load_fresh_local_data(&app, &key, max_age_secs).await {
+            return Ok(fresh);
+        }
 
-    if network_status.can_reach_website {
-        match fetch_online_data(&url, headers).await {
-            Ok(online_data) => {
-                if let Err(e) = save_local_data(app.clone(), key.clone(), online_data.clone()).await
-                {
-                    eprintln!("Warning: Failed to save data locally: {}", e);
+        let network_status = probe_network_status(&app).await;
+
+        fetch_with_fallback(
+            app.clone(),
+            key,
+            url,
+            headers,
+            network_status.can_reach_website,
+            max_age_secs.is_some(),
+            &retry,
+            delta.as_ref(),
+        )
+        .await
+    };
+    cancellation::cancellable(&app, request_id, operation).await
+}
+
+/// Fetches every request like `fetch_data_with_fallback`, sharing one connectivity probe.
+/// Items come back in the order of `requests`, whatever order the fetches finish in, so
+/// a key requested twice appears twice.
+#[tauri::command]
+async fn fetch_many_with_fallback(
+    app: AppHandle,
+    requests: Vec<FetchRequest>,
+    operation_id: Option<String>,
+    max_concurrency: Option<usize>,
+) -> Result<Vec<BatchFetchItem>, AppError> {
+    for request in &requests {
+        if let Some(retry) = &request.retry {
+            retry.validate()?;
+        }
+    }
+
+    let progress = ProgressReporter::new(&app, operation_id);
+    let total = requests.len() as u64;
+    progress.report("connectivity", 0, Some(total));
+
+    // One connectivity probe serves the whole batch
+    let network_status = probe_network_status(&app).await;
+    let concurrency = max_concurrency
+        .unwrap_or(DEFAULT_CONCURRENT_FETCHES)
+        .clamp(1, MAX_CONCURRENT_FETCHES);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let completed = Arc::new(AtomicU64::new(0));
+
+    let handles: Vec<_> = requests
+        .into_iter()
+        .map(|request| {
+            let app = app.clone();
+            let semaphore = semaphore.clone();
+            let completed = completed.clone();
+            let progress = progress.clone();
+            let can_reach_website = network_status.can_reach_website;
+
+            tauri::async_runtime::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let key = request.key.clone();
+
+                let outcome = match load_fresh_local_data(&app, &key, request.max_age_secs).await {
+                    Some(fresh) => Ok(fresh),
+                    None => {
+                        fetch_with_fallback(
+                            app,
+                            request.key,
+                            request.url,
+                            request.headers,
+                            can_reach_website,
+                            request.max_age_secs.is_some(),
+                            &request.retry.unwrap_or_default(),
+                            request.delta.as_ref(),
+                        )
+                        .await
+                    }
+                };
+
+                let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                progress.report("fetching", done, Some(total));
+
+                match outcome {
+                    Ok(result) => BatchFetchItem {
+                        key,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Err(e) => BatchFetchItem {
+                        key,
+                        result: None,
+                        error: Some(e),
+                    },
                 }
+            })
+        })
+        .collect();
 
-                return Ok(FetchResult {
-                    data: online_data,
-                    source: "online".to_string(),
-                    timestamp: chrono::Utc::now().timestamp(),
-                });
-            }
+    let results = join_in_order(handles).await?;
+    progress.finish("fetching", total, Some(total), None);
+
+    Ok(results)
+}
+
+/// Outputs of `handles` in the order given, however the tasks finish.
+async fn join_in_order<T>(
+    handles: Vec<tauri::async_runtime::JoinHandle<T>>,
+) -> Result<Vec<T>, AppError> {
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(
+            handle
+                .await
+                .map_err(|e| AppError::internal(format!("Batch fetch task failed: {}", e)))?,
+        );
+    }
+    Ok(results)
+}
+
+/// Returns the cached entry for `key` if it is younger than `max_age_secs`.
+async fn load_fresh_local_data(
+    app: &AppHandle,
+    key: &str,
+    max_age_secs: Option<u64>,
+) -> Option<FetchResult> {
+    let max_age = max_age_secs?;
+    let cached = read_local_data(app, key)
+        .await
+        .ok()
+        .flatten()
+        .filter(|cached| {
+            let age = chrono::Utc::now().timestamp() - cached.timestamp;
+            age >= 0 && (age as u64) < max_age
+        });
+
+    if !demo::is_demo(app) {
+        match &cached {
+            Some(cached) => cache_metrics::record_hit(app, key, cached.is_stale),
+            None => cache_metrics::record_miss(app, key),
+        }
+    }
+
+    cached
+}
+
+/// Fetches `url` when the website is reachable, caching the response under `key`,
+/// and falls back to the cached copy otherwise. Concurrent calls for the same key, URL
+/// and headers on behalf of the same account share a single fetch.
+///
+/// `force_online` attempts the network even when the connectivity probe failed, for
+/// callers whose cached copy is already known to be stale.
+#[allow(clippy::too_many_arguments)]
+async fn fetch_with_fallback(
+    app: AppHandle,
+    key: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    can_reach_website: bool,
+    force_online: bool,
+    retry: &RetryOptions,
+    delta: Option<&DeltaOptions>,
+) -> Result<FetchResult, AppError> {
+    let id_headers = headers.clone();
+    let fetch = fetch_or_fall_back(
+        app.clone(),
+        key.clone(),
+        url.clone(),
+        headers,
+        can_reach_website,
+        force_online,
+        retry.clone(),
+        delta.cloned(),
+    );
+    inflight::coalesce(&app, &key, &url, id_headers.as_ref(), fetch).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_or_fall_back(
+    app: AppHandle,
+    key: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    can_reach_website: bool,
+    force_online: bool,
+    retry: RetryOptions,
+    delta: Option<DeltaOptions>,
+) -> Result<FetchResult, AppError> {
+    let demo = demo::is_demo(&app);
+    let mut online_error = None;
+
+    // Guest mode never talks to the backend; the demo store answers instead
+    if !demo && (can_reach_website || force_online) {
+        match refresh_cached_data(&app, &key, &url, headers, &retry, delta.as_ref()).await {
+            Ok(result) => return Ok(result),
             Err(e) => {
-                eprintln!("Failed to fetch online data: {}", e);
+                tracing::warn!("Failed to fetch online data: {}", e);
+                online_error = Some(e);
             }
         }
     }
 
-    match load_local_data(app, key).await {
-        Ok(Some(local_data)) => Ok(local_data),
-        Ok(None) => Err("No data available online or locally".to_string()),
-        Err(e) => Err(format!("Failed to load local data: {}", e)),
+    match read_local_data(&app, &key).await {
+        Ok(Some(local_data)) => {
+            if !demo {
+                cache_metrics::record_fallback(&app, &key, local_data.is_stale);
+            }
+            Ok(local_data)
+        }
+        Ok(None) => {
+            if demo {
+                return Err(AppError::not_found("No demo data available").with_context(key));
+            }
+            cache_metrics::record_miss(&app, &key);
+
+            // Without a cached copy, why the network failed is the useful part
+            let error = match online_error {
+                Some(e) => AppError::from(e),
+                None => AppError::offline("No data available online or locally"),
+            };
+            Err(error.with_context(key))
+        }
+        Err(e) => {
+            Err(AppError::storage(format!("Failed to load local data: {}", e)).with_context(key))
+        }
     }
 }
 
-async fn fetch_online_data(
+/// Downloads `url` into the cache under `key`. The cached copy's `ETag`/`Last-Modified`
+/// are sent along, so an unchanged resource costs a `304` instead of the full payload.
+/// With `delta`, only changes since the stored cursor are fetched when possible.
+async fn refresh_cached_data(
+    app: &AppHandle,
+    key: &str,
     url: &str,
     headers: Option<HashMap<String, String>>,
-) -> Result<serde_json::Value, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    retry: &RetryOptions,
+    delta: Option<&DeltaOptions>,
+) -> Result<FetchResult, FetchError> {
+    if let Some(delta) = delta {
+        if let Some(result) =
+            delta_sync::refresh(app, key, url, headers.as_ref(), retry, delta).await?
+        {
+            return Ok(result);
+        }
+    }
+
+    let owned_key = key.to_string();
+    let cached_validators =
+        cache_store::with_store_blocking(app, move |store| store.validators(&owned_key))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read cache validators: {}", e);
+                None
+            });
+
+    let response = perform_request(
+        app,
+        reqwest::Method::GET,
+        url,
+        headers.as_ref(),
+        None,
+        cached_validators.as_ref(),
+        Some(retry),
+    )
+    .await?;
+
+    match response {
+        OnlineResponse::Fresh { data, validators } => {
+            let expires_at = store_local_data(app, key, &data, None, Some(url))
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to save data locally: {}", e);
+                    None
+                });
 
-    let mut request = client.get(url);
+            if !validators.is_empty() {
+                let key = key.to_string();
+                let saved = cache_store::with_store_blocking(app, move |store| {
+                    store.set_validators(&key, &validators)
+                })
+                .await;
+                if let Err(e) = saved {
+                    tracing::warn!("Failed to save cache validators: {}", e);
+                }
+            }
 
-    if let Some(headers_map) = headers {
-        for (key, value) in headers_map {
-            request = request.header(&key, &value);
+            if let Some(delta) = delta {
+                delta_sync::remember_cursor(app, key, &data, delta).await;
+            }
+
+            Ok(FetchResult::online(data, expires_at))
+        }
+        OnlineResponse::NotModified => {
+            let now = chrono::Utc::now().timestamp();
+            let owned_key = key.to_string();
+            let entry = cache_store::with_store_blocking(app, move |store| {
+                store.touch(&owned_key, now)?;
+                store.load(&owned_key)
+            })
+            .await?
+            .ok_or_else(|| format!("Cached copy of '{}' disappeared during refresh", key))?;
+
+            Ok(FetchResult {
+                data: entry.data,
+                source: "online-not-modified".to_string(),
+                timestamp: entry.timestamp,
+                expires_at: entry.expires_at,
+                is_stale: false,
+            })
         }
     }
+}
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Network request failed: {}", e))?;
+async fn fetch_online_data(
+    app: &AppHandle,
+    url: &str,
+    headers: Option<HashMap<String, String>>,
+) -> Result<serde_json::Value, FetchError> {
+    let retry = RetryOptions::default();
+    let response = perform_request(
+        app,
+        reqwest::Method::GET,
+        url,
+        headers.as_ref(),
+        None,
+        None,
+        Some(&retry),
+    )
+    .await?;
+
+    match response {
+        OnlineResponse::Fresh { data, .. } => Ok(data),
+        OnlineResponse::NotModified => Ok(serde_json::Value::Null),
+    }
+}
 
-    if !response.status().is_success() {
-        return Err(format!(
-            "HTTP error: {} - {}",
-            response.status(),
-            response.status().canonical_reason().unwrap_or("Unknown")
-        ));
+/// Sends a request through the middleware chain and parses the JSON response, as
+/// described at [`success_data`]. Not retried, since it may not be idempotent.
+async fn send_online_request(
+    app: &AppHandle,
+    method: reqwest::Method,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    body: Option<RequestBody<'_>>,
+) -> Result<serde_json::Value, FetchError> {
+    match perform_request(app, method, url, headers, body, None, None).await? {
+        OnlineResponse::Fresh { data, .. } => Ok(data),
+        // Only conditional requests can come back unmodified
+        OnlineResponse::NotModified => Ok(serde_json::Value::Null),
     }
+}
 
-    let json: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse JSON response: {}", e))?;
+fn header_string(response: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Data of a success response; an empty body yields `null`. A write the server accepted
+/// counts as delivered whatever it sends back, so a body that is not JSON comes back as a
+/// string rather than as an error that would get the write sent again.
+fn success_data(method: &reqwest::Method, body: String) -> Result<serde_json::Value, FetchError> {
+    if body.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
 
-    Ok(json)
+    match serde_json::from_str(&body) {
+        Ok(data) => Ok(data),
+        Err(_) if !method.is_safe() => Ok(serde_json::Value::String(body)),
+        Err(e) => {
+            Err(AppError::corrupt_data(format!("Failed to parse JSON response: {}", e)).into())
+        }
+    }
+}
+
+/// [`send_online_request`], optionally made conditional on the given cache validators
+/// and retried with backoff on transient failures.
+async fn perform_request(
+    app: &AppHandle,
+    method: reqwest::Method,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    body: Option<RequestBody<'_>>,
+    validators: Option<&cache_store::Validators>,
+    retry: Option<&RetryOptions>,
+) -> Result<OnlineResponse, FetchError> {
+    chaos::before_request(app).await?;
+
+    #[cfg(feature = "fixtures")]
+    if fixtures::is_active() {
+        return fixtures::respond(url).map(|data| OnlineResponse::Fresh {
+            data,
+            validators: cache_store::Validators::default(),
+        });
+    }
+
+    oauth::refresh_if_expiring(app).await;
+
+    let client = http_client::client(app);
+
+    let registry = app.state::<MiddlewareRegistry>();
+    let mut attempt = 0;
+    let mut failures = 0;
+    let mut reauthenticated = false;
+
+    loop {
+        let mut request = client
+            .request(method.clone(), url)
+            .timeout(http_client::REQUEST_TIMEOUT);
+
+        if let Some(headers_map) = headers {
+            for (key, value) in headers_map {
+                request = request.header(key, value);
+            }
+        }
+
+        match body {
+            Some(RequestBody::Json(body)) => request = request.json(body),
+            Some(RequestBody::Bytes { data, content_type }) => {
+                request = request
+                    .header(reqwest::header::CONTENT_TYPE, content_type)
+                    .body(data.to_vec());
+            }
+            Some(RequestBody::Multipart { upload, progress }) => {
+                let multipart = uploads::MultipartBody::open(upload, progress).await?;
+                request = request
+                    .timeout(uploads::UPLOAD_TIMEOUT)
+                    .header(reqwest::header::CONTENT_TYPE, multipart.content_type)
+                    .header(reqwest::header::CONTENT_LENGTH, multipart.length)
+                    .body(multipart.body);
+            }
+            None => {}
+        }
+
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let mut request = request
+            .build()
+            .map_err(|e| format!("Failed to build request: {}", e))?;
+
+        // Only a token the middleware added can be refreshed; the caller's own is left alone
+        let caller_authorization = request
+            .headers()
+            .contains_key(reqwest::header::AUTHORIZATION);
+        registry.apply_request(&mut request)?;
+        let session_authorization = request
+            .headers()
+            .get(reqwest::header::AUTHORIZATION)
+            .filter(|_| !caller_authorization)
+            .cloned();
+
+        let request_url = request.url().clone();
+
+        let response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(host) = http_client::pin_failure(&e) {
+                    return Err(FetchError::CertificatePin {
+                        message: format!(
+                            "The certificate presented by {} does not match the pinned keys; the network may be intercepting secure traffic",
+                            host
+                        ),
+                        host,
+                    });
+                }
+
+                failures += 1;
+                let transient = e.is_timeout() || e.is_connect();
+                if let Some(retry) = retry.filter(|retry| transient && retry.allows_retry(failures)) {
+                    tokio::time::sleep(retry.delay(failures)).await;
+                    continue;
+                }
+
+                return Err(FetchError::Network {
+                    message: format!("Network request failed: {}", e),
+                });
+            }
+        };
+
+        registry.apply_response(&method, &request_url, &response);
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED && !reauthenticated {
+            if let Some(sent) = &session_authorization {
+                reauthenticated = true;
+                if oauth::refresh_after_unauthorized(app, sent).await {
+                    continue;
+                }
+            }
+        }
+
+        if status == reqwest::StatusCode::NOT_MODIFIED && validators.is_some() {
+            return Ok(OnlineResponse::NotModified);
+        }
+
+        if status.is_success() {
+            let validators = cache_store::Validators {
+                etag: header_string(&response, reqwest::header::ETAG),
+                last_modified: header_string(&response, reqwest::header::LAST_MODIFIED),
+            };
+
+            let body = match response.text().await {
+                Ok(body) => body,
+                // The write went through; only its answer was lost
+                Err(e) if !method.is_safe() => {
+                    tracing::warn!("Failed to read response body of {} {}: {}", method, url, e);
+                    String::new()
+                }
+                Err(e) => return Err(format!("Failed to read response body: {}", e).into()),
+            };
+            let body = chaos::mangle_body(app, body);
+            let data = success_data(&method, body)?;
+
+            return Ok(OnlineResponse::Fresh { data, validators });
+        }
+
+        let retry_after_secs = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+
+        let throttled = status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE;
+
+        // Wait out short server-requested delays; longer ones are left to the caller
+        if let Some(delay) = retry_after_secs {
+            if throttled && attempt < MAX_RETRY_AFTER_ATTEMPTS && delay <= MAX_RETRY_AFTER_SECS {
+                attempt += 1;
+                tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+                continue;
+            }
+        }
+
+        if status.is_server_error() {
+            failures += 1;
+            if let Some(retry) = retry.filter(|retry| retry.allows_retry(failures)) {
+                tokio::time::sleep(retry.delay(failures)).await;
+                continue;
+            }
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        return Err(FetchError::from_response(status, &body, retry_after_secs));
+    }
 }
 
 #[tauri::command]
@@ -231,180 +1123,420 @@ async fn force_refresh_data(
     key: String,
     url: String,
     headers: Option<HashMap<String, String>>,
-) -> Result<FetchResult, String> {
-    let network_status = check_network_status().await?;
+    retry: Option<RetryOptions>,
+    request_id: Option<String>,
+) -> Result<FetchResult, AppError> {
+    let retry = retry.unwrap_or_default();
+    retry.validate()?;
+
+    if demo::is_demo(&app) {
+        return load_local_data(app, key)
+            .await?
+            .ok_or_else(|| AppError::not_found("No demo data available for this key"));
+    }
+
+    let operation = async {
+        let network_status = probe_network_status(&app).await;
+
+        if !network_status.can_reach_website {
+            return Err(FetchError::Network {
+                message: "Cannot reach website. Please check your internet connection."
+                    .to_string(),
+            });
+        }
 
-    if !network_status.can_reach_website {
-        return Err("Cannot reach website. Please check your internet connection.".to_string());
+        refresh_cached_data(&app, &key, &url, headers, &retry, None).await
+    };
+    cancellation::cancellable(&app, request_id, operation).await
+}
+
+/// Appends `query` to `url`, keeping any parameters already present.
+fn with_query(url: &str, query: Option<&HashMap<String, String>>) -> Result<String, AppError> {
+    let Some(query) = query.filter(|query| !query.is_empty()) else {
+        return Ok(url.to_string());
+    };
+
+    let mut url = reqwest::Url::parse(url)
+        .map_err(|e| AppError::invalid_input(format!("Invalid URL: {}", e)).with_context(url))?;
+    url.query_pairs_mut().extend_pairs(query);
+    Ok(url.to_string())
+}
+
+/// Sends an arbitrary API request through the native layer.
+///
+/// Mutations that fail because the server is unreachable or temporarily unavailable are
+/// queued for replay unless `queue_on_failure` is `false`; rejected ones return the error.
+/// `cache_key` names the cached document being changed so a conflicting server-side
+/// change can be detected when the queue is replayed.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn send_request(
+    app: AppHandle,
+    method: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    query: Option<HashMap<String, String>>,
+    body: Option<serde_json::Value>,
+    queue_on_failure: Option<bool>,
+    cache_key: Option<String>,
+) -> Result<SendOutcome, AppError> {
+    demo::ensure_not_demo(&app)?;
+
+    let method = reqwest::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+        .map_err(|e| AppError::invalid_input(format!("Invalid HTTP method: {}", e)))?;
+    let url = with_query(&url, query.as_ref())?;
+
+    // Only reads are safe to repeat automatically
+    let retry = (method == reqwest::Method::GET).then(RetryOptions::default);
+
+    let outcome = perform_request(
+        &app,
+        method.clone(),
+        &url,
+        headers.as_ref(),
+        body.as_ref().map(RequestBody::Json),
+        None,
+        retry.as_ref(),
+    )
+    .await;
+
+    match outcome {
+        Ok(OnlineResponse::Fresh { data, .. }) => Ok(SendOutcome {
+            data: Some(data),
+            queued_id: None,
+        }),
+        Ok(OnlineResponse::NotModified) => Ok(SendOutcome {
+            data: None,
+            queued_id: None,
+        }),
+        Err(e) if e.is_transient()
+            && method != reqwest::Method::GET
+            && queue_on_failure.unwrap_or(true) =>
+        {
+            tracing::info!("Queueing {} {} after failure: {}", method, url, e);
+            let id = outbox::enqueue(
+                &app,
+                method.as_str(),
+                &url,
+                headers.as_ref(),
+                body.as_ref(),
+                cache_key.as_deref(),
+            )
+            .await?;
+            Ok(SendOutcome {
+                data: None,
+                queued_id: Some(id),
+            })
+        }
+        Err(e) => Err(e.into()),
     }
+}
 
-    let online_data = fetch_online_data(&url, headers).await?;
+#[tauri::command]
+async fn list_middleware(
+    registry: State<'_, MiddlewareRegistry>,
+) -> Result<Vec<MiddlewareInfo>, AppError> {
+    Ok(registry.list())
+}
 
-    save_local_data(app.clone(), key.clone(), online_data.clone()).await?;
+#[tauri::command]
+async fn clear_local_cache(app: AppHandle, key: Option<String>) -> Result<(), AppError> {
+    let demo = app.state::<DemoState>();
+    if demo.is_active() {
+        demo.remove(key.as_deref());
+        return Ok(());
+    }
 
-    Ok(FetchResult {
-        data: online_data,
-        source: "online".to_string(),
-        timestamp: chrono::Utc::now().timestamp(),
+    cache_store::with_store_blocking(&app, move |store| match &key {
+        Some(specific_key) => store.remove(specific_key),
+        None => store.clear(),
     })
+    .await
+    .map_err(AppError::storage)
 }
 
 #[tauri::command]
-async fn clear_local_cache(app: AppHandle, key: Option<String>) -> Result<(), String> {
-    let data_dir = get_data_dir(&app)?;
-
-    if let Some(specific_key) = key {
-        let file_path = get_data_file_path(&app, &specific_key)?;
-        if file_path.exists() {
-            std::fs::remove_file(&file_path)
-                .map_err(|e| format!("Failed to remove data file: {}", e))?;
-        }
-    } else {
-        let entries = std::fs::read_dir(&data_dir)
-            .map_err(|e| format!("Failed to read data directory: {}", e))?;
-
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                    if let Err(e) = std::fs::remove_file(&path) {
-                        eprintln!("Warning: Failed to remove file {:?}: {}", path, e);
-                    }
-                }
-            }
-        }
+async fn get_cache_info(app: AppHandle) -> Result<HashMap<String, i64>, AppError> {
+    let demo = app.state::<DemoState>();
+    if demo.is_active() {
+        return Ok(demo.timestamps());
     }
 
-    Ok(())
+    cache_store::with_store_blocking(&app, |store| store.timestamps())
+        .await
+        .map_err(AppError::storage)
 }
 
+/// Every cache entry with its scope, size, times, freshness window and origin, for a
+/// cache management screen.
 #[tauri::command]
-async fn get_cache_info(app: AppHandle) -> Result<HashMap<String, i64>, String> {
-    let data_dir = get_data_dir(&app)?;
-    let mut cache_info = HashMap::new();
-
-    let entries = std::fs::read_dir(&data_dir)
-        .map_err(|e| format!("Failed to read data directory: {}", e))?;
-
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Some(file_name) = path.file_stem().and_then(|s| s.to_str()) {
-                    if let Ok(content) = std::fs::read_to_string(&path) {
-                        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&content) {
-                            if let Some(timestamp) = parsed.get("timestamp").and_then(|t| t.as_i64()) {
-                                cache_info.insert(file_name.to_string(), timestamp);
-                            }
-                        }
-                    }
-                }
-            }
+async fn list_cache_entries(app: AppHandle) -> Result<Vec<cache_store::CacheEntryInfo>, AppError> {
+    let demo = app.state::<DemoState>();
+    if demo.is_active() {
+        return Ok(demo.entries());
+    }
+
+    cache_store::with_store_blocking(&app, |store| store.entries())
+        .await
+        .map_err(AppError::storage)
+}
+
+/// Account whose cache entries are in use, or `None` for the shared ones.
+#[tauri::command]
+async fn get_active_scope(app: AppHandle) -> Result<Option<String>, AppError> {
+    cache_store::with_store_blocking(&app, |store| {
+        Ok(Some(store.scope().to_string()).filter(|scope| scope != cache_store::SHARED_SCOPE))
+    })
+    .await
+    .map_err(AppError::storage)
+}
+
+/// Points cache reads and writes at `account_id`'s own entries when an account signs in,
+/// or back at the shared entries with `None`.
+#[tauri::command]
+async fn set_active_scope(app: AppHandle, account_id: Option<String>) -> Result<(), AppError> {
+    let scope = match account_id {
+        Some(account_id) if account_id.trim().is_empty() => {
+            return Err(AppError::invalid_input("Account id must not be empty"));
         }
+        Some(account_id) => account_id,
+        None => cache_store::SHARED_SCOPE.to_string(),
+    };
+
+    cache_store::with_store_mut_blocking(&app, move |store| store.set_scope(&scope))
+        .await
+        .map_err(AppError::storage)
+}
+
+/// Deletes everything cached for `account_id`, e.g. when it signs out. Returns how many
+/// entries were removed.
+#[tauri::command]
+async fn purge_cache_scope(app: AppHandle, account_id: String) -> Result<u64, AppError> {
+    demo::ensure_not_demo(&app)?;
+
+    cache_store::with_store_blocking(&app, move |store| store.purge_scope(&account_id))
+        .await
+        .map_err(AppError::storage)
+}
+
+#[tauri::command]
+async fn get_base_url(app: AppHandle) -> Result<String, AppError> {
+    Ok(website_url(&app))
+}
+
+/// Checks that `url` is an http(s) URL and drops any trailing slash.
+fn normalize_base_url(url: &str) -> Result<String, AppError> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| AppError::invalid_input(format!("Invalid base URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "https" | "http") || parsed.host_str().is_none() {
+        return Err(AppError::invalid_input("Base URL must be an http(s) URL"));
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+/// Loads the website from the current base URL in the main window. Returns that URL.
+fn load_website(app: &AppHandle) -> Result<String, AppError> {
+    let base_url = website_url(app);
+    if let Some(window) = app.get_webview_window("main") {
+        let target = base_url
+            .parse()
+            .map_err(|e| AppError::corrupt_data(format!("Invalid base URL: {}", e)))?;
+        window
+            .navigate(target)
+            .map_err(|e| AppError::internal(format!("Failed to load {}: {}", base_url, e)))?;
+    }
+
+    Ok(base_url)
+}
+
+/// Points the app at another deployment of the website, or back at the default with `None`.
+#[tauri::command]
+async fn set_base_url(
+    app: AppHandle,
+    state: State<'_, settings::SettingsState>,
+    url: Option<String>,
+) -> Result<String, AppError> {
+    let url = url.as_deref().map(normalize_base_url).transpose()?;
+
+    state.update(&app, |settings| settings.base_url = url)?;
+    load_website(&app)
+}
+
+#[tauri::command]
+async fn get_cache_usage(app: AppHandle) -> Result<cache_store::CacheUsage, AppError> {
+    cache_store::with_store_blocking(&app, |store| store.usage())
+        .await
+        .map_err(AppError::storage)
+}
+
+/// Lists cache entries that were removed after failing their integrity check.
+#[tauri::command]
+async fn get_corrupted_entries(
+    app: AppHandle,
+) -> Result<Vec<cache_store::CorruptedEntry>, AppError> {
+    cache_store::with_store_blocking(&app, |store| store.corrupted_entries())
+        .await
+        .map_err(AppError::storage)
+}
+
+/// Changes the cache size limit, evicting least recently used entries if needed.
+#[tauri::command]
+async fn set_cache_size_limit(
+    app: AppHandle,
+    state: tauri::State<'_, settings::SettingsState>,
+    max_bytes: u64,
+) -> Result<cache_store::CacheUsage, AppError> {
+    if max_bytes < MIN_CACHE_BYTES {
+        return Err(AppError::invalid_input(format!(
+            "Cache limit must be at least {} bytes",
+            MIN_CACHE_BYTES
+        )));
     }
 
-    Ok(cache_info)
+    state.update(&app, |settings| settings.max_cache_bytes = max_bytes)?;
+    cache_store::with_store_mut_blocking(&app, move |store| {
+        store.set_max_bytes(max_bytes)?;
+        store.usage()
+    })
+    .await
+    .map_err(AppError::storage)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let context = tauri::generate_context!();
 
-    tauri::Builder::default()
+
+    let builder = tauri::Builder::default();
+
+    // Must be registered first so a second launch exits before other plugins start
+    #[cfg(desktop)]
+    let builder =
+        builder.plugin(tauri_plugin_single_instance::init(tray::handle_second_instance));
+
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(focus_block::plugin())
+        .plugin(app_lock::plugin())
+        .register_uri_scheme_protocol(offline_page::OFFLINE_SCHEME, offline_page::protocol)
+        .register_uri_scheme_protocol(app_lock::LOCK_SCHEME, app_lock::protocol)
+        .register_asynchronous_uri_scheme_protocol(app_cache::APP_CACHE_SCHEME, app_cache::protocol)
+        .manage(MiddlewareRegistry::with_defaults())
+        .manage(chaos::ChaosState::default())
+        .manage(DemoState::default())
+        .manage(outbox::OutboxState::default())
+        .manage(websocket::WebSocketState::default())
+        .manage(focus::FocusState::default())
+        .manage(focus_block::FocusBlockState::default())
+        .manage(calendar::CalendarState::default())
+        .manage(badge::BadgeState::default())
+        .manage(recording::RecordingState::default())
+        .manage(cache_metrics::CacheMetricsState::default())
+        .manage(inflight::InflightFetches::default())
+        .manage(cancellation::RequestRegistry::default())
+        .manage(downloads::DownloadManager::default())
+        .manage(health::SchedulerHealth::default())
+        .manage(session::SessionState::default())
+        .manage(oauth::RefreshLock::default())
         .setup(|app| {
-            let main_window = app.get_webview_window("main").unwrap();
+            app.manage(http_client::HttpClient::new()?);
+
+            let profile_state = profiles::ProfileState::load(app.handle())?;
+            app.manage(profile_state);
+
+            let settings_state = settings::SettingsState::load(app.handle())?;
+            app.state::<http_client::HttpClient>()
+                .set_pinning(settings_state.get().certificate_pinning);
+            app.manage(settings_state);
+            if let Err(e) = proxy::apply(app.handle()) {
+                tracing::warn!("Failed to apply proxy settings: {}", e);
+            }
+
+            let app_lock_state = app_lock::AppLockState::load(app.handle())?;
+            app.manage(app_lock_state);
+
+            let log_state = logging::init(app.handle())?;
+            app.manage(log_state);
+            crash::init(app.handle())?;
+
+            let cache_state = cache_store::CacheState::open(app.handle())?;
+            app.manage(cache_state);
+
+            #[cfg(mobile)]
+            app.manage(oauth::PendingLogin::default());
+
+            prefetch::spawn_scheduler(app.handle().clone());
+            sync_schedule::spawn_scheduler(app.handle().clone());
+            reminders::spawn_scheduler(app.handle().clone());
+            focus::spawn_ticker(app.handle().clone());
+            app_lock::spawn_idle_timer(app.handle().clone());
+            network_monitor::spawn(app.handle().clone());
+            badge::spawn_updater(app.handle().clone());
+            idle::spawn_monitor(app.handle().clone());
+            usage::spawn_tracker(app.handle().clone());
 
             #[cfg(desktop)]
             {
-                main_window.eval(r#"
-                    // Disable default context menu
-                    document.addEventListener('contextmenu', function(e) {
-                        e.preventDefault();
-
-                        // Create custom context menu
-                        const contextMenu = document.createElement('div');
-                        contextMenu.id = 'custom-context-menu';
-                        contextMenu.style.cssText = `
-                            position: fixed;
-                            top: ${e.clientY}px;
-                            left: ${e.clientX}px;
-                            background: #2d2d2d;
-                            border: 1px solid #555;
-                            border-radius: 6px;
-                            box-shadow: 0 4px 12px rgba(0,0,0,0.3);
-                            z-index: 10000;
-                            min-width: 150px;
-                            padding: 4px 0;
-                            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
-                        `;
-
-                        const items = [
-                            { text: '⬅️ Back', action: () => window.history.back() },
-                            { text: '🔄 Refresh', action: () => window.location.href = 'https://accounted.th3void.com' },
-                            { text: 'ℹ️ About', action: () => {
-                                const currentYear = new Date().getFullYear();
-                                const aboutMessage = `Lotus Routine - Your Accountability Hub\nTrack your progress, compete with friends, and build lasting habits.\nVersion: 1.0.0\n© ${currentYear} th3void. All rights reserved.`;
-                                alert(aboutMessage);
-                            }},
-
-                        ];
-
-                        items.forEach(item => {
-                            const menuItem = document.createElement('div');
-                            menuItem.textContent = item.text;
-                            menuItem.style.cssText = `
-                                padding: 8px 16px;
-                                cursor: pointer;
-                                color: #ffffff;
-                                font-size: 14px;
-                                transition: background-color 0.2s;
-                            `;
+                tray::create(app.handle())?;
+                app_menu::create(app.handle())?;
 
-                            menuItem.addEventListener('mouseenter', () => {
-                                menuItem.style.backgroundColor = '#404040';
-                            });
-
-                            menuItem.addEventListener('mouseleave', () => {
-                                menuItem.style.backgroundColor = 'transparent';
-                            });
-
-                            menuItem.addEventListener('click', () => {
-                                try {
-                                    item.action();
-                                    contextMenu.remove();
-                                } catch (error) {
-                                    console.error('Menu action error:', error);
-                                    contextMenu.remove();
-                                }
-                            });
+                app.handle()
+                    .plugin(tauri_plugin_updater::Builder::new().build())?;
+                app.manage(updates::UpdateState::default());
+                app.manage(widget::WidgetState::default());
+                app.manage(window_state::WindowGeometryState::default());
 
-                            contextMenu.appendChild(menuItem);
-                        });
+                app.handle().plugin(shortcuts::plugin())?;
+                shortcuts::apply(app.handle());
 
-                        // Remove existing context menu
-                        const existing = document.getElementById('custom-context-menu');
-                        if (existing) existing.remove();
+                app.handle().plugin(autostart::plugin())?;
+            }
 
-                        // Add to document
-                        document.body.appendChild(contextMenu);
+            app.state::<MiddlewareRegistry>().register(
+                10,
+                Arc::new(auth::AuthHeaderMiddleware::new(app.handle().clone())),
+            );
+            app.state::<MiddlewareRegistry>().register(
+                5,
+                Arc::new(focus_block::FocusBlockMiddleware::new(app.handle().clone())),
+            );
+
+            // Created here rather than from the config so that it opens on the lock page,
+            // without loading the website first, when the app starts locked
+            let mut window_config = app
+                .config()
+                .app
+                .windows
+                .first()
+                .cloned()
+                .ok_or("The main window is missing from the config")?;
+            window_config.url = tauri::WebviewUrl::External(app_lock::start_url(
+                app.handle(),
+                website_url(app.handle()).parse()?,
+            ));
+            let main_window =
+                tauri::WebviewWindowBuilder::from_config(app.handle(), &window_config)?.build()?;
 
-                        // Close on click outside
-                        const closeMenu = (e) => {
-                            if (!contextMenu.contains(e.target)) {
-                                contextMenu.remove();
-                                document.removeEventListener('click', closeMenu);
-                            }
-                        };
+            deep_link::init(app.handle())?;
+            theme::watch(&main_window);
+            session::watch(&main_window);
+            app_lock::watch(&main_window);
+            file_import::watch(&main_window);
 
-                        setTimeout(() => {
-                            document.addEventListener('click', closeMenu);
-                        }, 100);
-                    });
-                "#)?;
+            #[cfg(desktop)]
+            {
+                window_state::restore(&main_window);
+                // After restoring, since maximizing can show the window again
+                autostart::apply_launch_visibility(app.handle());
+                tray::handle_window_events(&main_window);
+
+                context_menu::watch(&main_window);
             }
 
 
@@ -440,56 +1572,14 @@ pub fn run() {
                         "#);
                     }
                 });
-
-                // Add mobile-specific print handling using iframe approach
-                let _ = main_window.eval(r#"
-                    // Override window.open for mobile to use iframe printing instead
-                    const originalWindowOpen = window.open;
-                    window.open = function(url, name, specs) {
-                        if (name === '' && specs === 'height=500, width=500') {
-                            // Use iframe approach like the Barcode component
-                            const printFrame = document.createElement('iframe');
-                            printFrame.style.display = 'none';
-                            document.body.appendChild(printFrame);
-
-                            // Create a mock window object that uses iframe
-                            const mockWindow = {
-                                document: {
-                                    write: function(content) {
-                                        printFrame.contentDocument.body.innerHTML = content;
-                                    },
-                                    close: function() {
-                                        document.body.removeChild(printFrame);
-                                    }
-                                },
-                                print: function() {
-                                    printFrame.contentWindow.print();
-                                    // Clean up after a short delay
-                                    setTimeout(() => {
-                                        if (printFrame.parentNode) {
-                                            document.body.removeChild(printFrame);
-                                        }
-                                    }, 1000);
-                                },
-                                closed: false,
-                                close: function() {
-                                    this.closed = true;
-                                    if (printFrame.parentNode) {
-                                        document.body.removeChild(printFrame);
-                                    }
-                                }
-                            };
-
-                            return mockWindow;
-                        }
-                        return originalWindowOpen.call(this, url, name, specs);
-                    };
-                "#);
             }
 
             Ok(())
         })
-        .on_page_load(|window, _| {
+        .on_page_load(|window, payload| {
+            offline_page::handle_page_load(window, payload);
+            session::handle_page_load(window, payload);
+
             let css = r#"
                 ::-webkit-scrollbar {
                     display: none !important;
@@ -546,7 +1636,7 @@ pub fn run() {
                 }
             "#;
 
-            let _ = window.eval(&format!(
+            let _ = window.eval(format!(
                 r#"
                 const style = document.createElement('style');
                 style.id = 'tauri-sidebar-fix';
@@ -555,17 +1645,230 @@ pub fn run() {
                 "#,
                 css
             ));
+
+            theme::apply_to_page(window);
+            app_lock::apply_to_page(window);
+            #[cfg(desktop)]
+            context_menu::apply_to_page(window);
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(app_lock::guard(tauri::generate_handler![
             retry_connection,
+            get_base_url,
+            set_base_url,
             check_network_status,
+            health::health_check,
+            i18n::get_locale,
+            i18n::set_locale_override,
+            app_cache::prefetch_assets,
+            app_cache::get_app_cache_usage,
+            app_cache::clear_app_cache,
+            theme::get_system_theme,
+            badge::set_badge_count,
+            haptics::haptic_feedback,
+            share::share,
+            home_widget::refresh_widget_data,
+            barcode::generate_barcode,
+            scanner::scan_barcode,
+            clipboard::copy_to_clipboard,
+            clipboard::read_clipboard,
+            file_import::import_file,
+            attachments::store_attachment,
+            attachments::get_attachment,
+            attachments::delete_attachment,
+            thumbnails::generate_thumbnail,
+            #[cfg(desktop)]
+            context_menu::show_context_menu,
+            app_lock::get_app_lock,
+            app_lock::configure_app_lock,
+            app_lock::lock_app,
+            app_lock::unlock_app,
+            app_lock::record_app_activity,
             save_local_data,
             load_local_data,
             fetch_data_with_fallback,
+            fetch_many_with_fallback,
+            send_request,
             force_refresh_data,
+            cancellation::cancel_request,
+            downloads::download_file,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::cancel_download,
+            downloads::list_downloads,
+            uploads::upload_file,
             clear_local_cache,
             get_cache_info,
-        ])
-        .run(context)
-        .expect("error while running Lotus Routine application");
+            get_cache_usage,
+            list_cache_entries,
+            get_active_scope,
+            set_active_scope,
+            purge_cache_scope,
+            get_corrupted_entries,
+            cache_metrics::get_cache_metrics,
+            diagnostics::preview_diagnostics,
+            diagnostics::export_diagnostics,
+            set_cache_size_limit,
+            backup::export_backup,
+            backup::import_backup,
+            set_cache_policy,
+            list_middleware,
+            auth::set_session_token,
+            oauth::start_oauth_login,
+            oauth::refresh_auth,
+            session::save_session,
+            session::restore_session,
+            secrets::store_secret,
+            secrets::get_secret,
+            secrets::delete_secret,
+            chaos::set_chaos_config,
+            chaos::get_chaos_config,
+            profiles::list_profiles,
+            profiles::add_profile,
+            profiles::create_profile,
+            profiles::switch_profile,
+            profiles::remove_profile,
+            demo::enter_demo_mode,
+            demo::exit_demo_mode,
+            demo::is_demo_mode,
+            settings::get_data_saver,
+            settings::set_data_saver,
+            settings::get_update_channel,
+            settings::set_update_channel,
+            crash::get_crash_reporting,
+            crash::set_crash_reporting,
+            crash::list_crash_reports,
+            crash::submit_crash_report,
+            crash::delete_crash_report,
+            logging::get_recent_logs,
+            logging::set_log_level,
+            #[cfg(desktop)]
+            updates::check_for_update,
+            #[cfg(desktop)]
+            updates::download_update,
+            #[cfg(desktop)]
+            updates::install_update,
+            #[cfg(desktop)]
+            shortcuts::get_shortcut,
+            #[cfg(desktop)]
+            shortcuts::register_shortcut,
+            #[cfg(desktop)]
+            shortcuts::unregister_shortcut,
+            #[cfg(desktop)]
+            tray::get_close_behavior,
+            #[cfg(desktop)]
+            tray::set_close_behavior,
+            #[cfg(desktop)]
+            autostart::get_autostart,
+            #[cfg(desktop)]
+            autostart::set_autostart,
+            #[cfg(desktop)]
+            window_state::reset_window_state,
+            #[cfg(desktop)]
+            widget::toggle_widget_window,
+            #[cfg(desktop)]
+            widget::get_widget_config,
+            #[cfg(desktop)]
+            widget::set_widget_click_through,
+            prefetch::register_prefetch,
+            prefetch::unregister_prefetch,
+            prefetch::list_prefetch,
+            sync_schedule::set_sync_schedule,
+            sync_schedule::get_sync_schedule,
+            sync_schedule::clear_sync_schedule,
+            sync_schedule::sync_now,
+            reminders::schedule_reminder,
+            reminders::cancel_reminder,
+            reminders::list_reminders,
+            print::print_html,
+            report::generate_report,
+            csv_export::export_csv,
+            anonymize::export_anonymized,
+            ical::export_ical,
+            calendar::get_calendar_config,
+            calendar::set_calendar_config,
+            calendar::get_calendar_busy_slots,
+            calendar::sync_habits_to_calendar,
+            focus::start_focus_session,
+            focus::pause_focus_session,
+            focus::resume_focus_session,
+            focus::cancel_focus_session,
+            focus::get_active_session,
+            audio::play_sound,
+            audio::list_sounds,
+            audio::import_sound,
+            audio::delete_sound,
+            audio::get_sound_settings,
+            audio::set_sound_settings,
+            speech::speak,
+            speech::list_voices,
+            speech::get_speech_settings,
+            speech::set_speech_settings,
+            recording::start_recording,
+            recording::stop_recording,
+            idle::get_idle_time,
+            idle::get_idle_settings,
+            idle::set_idle_settings,
+            usage::get_usage_summary,
+            usage::get_usage_settings,
+            usage::set_usage_settings,
+            usage::clear_usage_data,
+            dnd::get_dnd_state,
+            dnd::get_dnd_behavior,
+            dnd::set_dnd_behavior,
+            focus_block::start_focus_block,
+            focus_block::stop_focus_block,
+            focus_block::get_focus_block_status,
+            focus_block::get_focus_block_settings,
+            focus_block::set_focus_block_settings,
+            storage::get_storage_version,
+            streaks::compute_streaks,
+            streaks::get_streak_summary,
+            habits::list_habits,
+            habits::create_habit,
+            habits::update_habit,
+            habits::complete_habit,
+            habits::delete_habit,
+            habits::link_habit,
+            outbox::queue_mutation,
+            outbox::get_pending_mutations,
+            outbox::get_queue_status,
+            outbox::flush_queue,
+            websocket::ws_connect,
+            websocket::ws_send,
+            websocket::ws_disconnect,
+            conflicts::get_conflicts,
+            conflicts::resolve_conflict,
+            settings::get_conflict_strategy,
+            settings::set_conflict_strategy,
+            settings::get_certificate_pinning,
+            settings::set_certificate_pinning,
+            settings::get_settings,
+            settings::update_settings,
+            proxy::get_proxy_config,
+            proxy::set_proxy_config,
+            proxy::test_proxy,
+        ]))
+        .build(context)
+        .expect("error while building Lotus Routine application")
+        .run(session::handle_run_event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn batch_results_follow_the_request_order() {
+        // Later items finish first
+        let handles = (0..4u64)
+            .map(|index| {
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(40 - index * 10)).await;
+                    index
+                })
+            })
+            .collect();
+
+        assert_eq!(join_in_order(handles).await.unwrap(), vec![0, 1, 2, 3]);
+    }
 }
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use tauri::{AppHandle, Manager, State};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+use crate::error::AppError;
+
+const LOG_FILE_PREFIX: &str = "lotus-routine";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Upper bound on lines returned to the in-app log viewer
+const MAX_RECENT_LINES: usize = 5000;
+
+/// Keeps the background log writer alive and allows changing the level at runtime
+pub struct LogState {
+    dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+    _guard: WorkerGuard,
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|_| format!("Invalid log level: {}", level))
+}
+
+/// Installs the global subscriber, writing to stderr and to daily rotated files in the
+/// app log directory.
+pub fn init(app: &AppHandle) -> Result<LogState, String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get log directory: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to create log file: {}", e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let level = parse_level(&crate::settings::current(app).log_level).unwrap_or(LevelFilter::INFO);
+    let (level_layer, level_handle) = reload::Layer::new(level);
+
+    tracing_subscriber::registry()
+        .with(level_layer)
+        .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(writer))
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))?;
+
+    Ok(LogState {
+        dir,
+        level: level_handle,
+        _guard: guard,
+    })
+}
+
+/// Sets the level from the active profile's settings.
+pub fn apply(app: &AppHandle) -> Result<(), String> {
+    let filter = parse_level(&crate::settings::current(app).log_level)?;
+    app.state::<LogState>()
+        .level
+        .modify(|current| *current = filter)
+        .map_err(|e| format!("Failed to change log level: {}", e))
+}
+
+/// Log files, oldest first. Rotated names end in the date, so name order is age order.
+fn log_files(state: &LogState) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&state.dir)
+        .map_err(|e| format!("Failed to read log directory: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+
+    files.sort();
+    Ok(files)
+}
+
+/// The last `lines` lines across the log files, oldest first.
+pub fn recent_lines(state: &LogState, lines: usize) -> Result<Vec<String>, String> {
+    let wanted = lines.min(MAX_RECENT_LINES);
+    let mut recent: Vec<String> = Vec::with_capacity(wanted);
+
+    for path in log_files(state)?.iter().rev() {
+        if recent.len() >= wanted {
+            break;
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read log file: {}", e))?;
+        let needed = wanted - recent.len();
+        let file_lines: Vec<&str> = content.lines().collect();
+        let start = file_lines.len().saturating_sub(needed);
+
+        // Older files go in front of what has been collected so far
+        recent.splice(0..0, file_lines[start..].iter().map(|line| line.to_string()));
+    }
+
+    Ok(recent)
+}
+
+#[tauri::command]
+pub async fn get_recent_logs(
+    state: State<'_, LogState>,
+    lines: usize,
+) -> Result<Vec<String>, AppError> {
+    recent_lines(&state, lines).map_err(AppError::storage)
+}
+
+#[tauri::command]
+pub async fn set_log_level(
+    app: AppHandle,
+    state: State<'_, LogState>,
+    level: String,
+) -> Result<(), AppError> {
+    let filter = parse_level(&level).map_err(AppError::invalid_input)?;
+
+    state
+        .level
+        .modify(|current| *current = filter)
+        .map_err(|e| AppError::internal(format!("Failed to change log level: {}", e)))?;
+
+    app.state::<crate::settings::SettingsState>()
+        .update(&app, |settings| settings.log_level = filter.to_string().to_lowercase())?;
+    Ok(())
+}
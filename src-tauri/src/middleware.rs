@@ -0,0 +1,124 @@
+use reqwest::header::{HeaderValue, ACCEPT};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// A hook invoked around every request issued by the native fetch layer.
+///
+/// Features (auth injection, signing, logging, caching policy) implement this trait
+/// and register it with the [`MiddlewareRegistry`] instead of touching headers ad hoc.
+pub trait Middleware: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Inspect or modify the outgoing request. Returning an error aborts the request.
+    fn on_request(&self, _request: &mut reqwest::Request) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Observe the response before the body is consumed.
+    fn on_response(&self, _method: &reqwest::Method, _url: &reqwest::Url, _response: &reqwest::Response) {}
+}
+
+/// Public description of a registered hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiddlewareInfo {
+    pub name: String,
+    pub order: i32,
+}
+
+struct RegisteredMiddleware {
+    order: i32,
+    middleware: Arc<dyn Middleware>,
+}
+
+/// Ordered collection of middleware, kept in managed state.
+///
+/// Hooks run in ascending `order` for requests and in the same order for responses.
+#[derive(Default)]
+pub struct MiddlewareRegistry {
+    hooks: RwLock<Vec<RegisteredMiddleware>>,
+}
+
+impl MiddlewareRegistry {
+    pub fn with_defaults() -> Self {
+        let registry = Self::default();
+        registry.register(0, Arc::new(AcceptJsonMiddleware));
+        registry.register(1000, Arc::new(LoggingMiddleware));
+        registry
+    }
+
+    pub fn register(&self, order: i32, middleware: Arc<dyn Middleware>) {
+        let mut hooks = self.hooks.write().unwrap_or_else(|e| e.into_inner());
+        hooks.push(RegisteredMiddleware { order, middleware });
+        // Stable sort keeps registration order for hooks sharing the same order
+        hooks.sort_by_key(|hook| hook.order);
+    }
+
+    pub fn list(&self) -> Vec<MiddlewareInfo> {
+        let hooks = self.hooks.read().unwrap_or_else(|e| e.into_inner());
+        hooks
+            .iter()
+            .map(|hook| MiddlewareInfo {
+                name: hook.middleware.name().to_string(),
+                order: hook.order,
+            })
+            .collect()
+    }
+
+    pub fn apply_request(&self, request: &mut reqwest::Request) -> Result<(), String> {
+        for middleware in self.snapshot() {
+            middleware
+                .on_request(request)
+                .map_err(|e| format!("Middleware '{}' rejected request: {}", middleware.name(), e))?;
+        }
+        Ok(())
+    }
+
+    pub fn apply_response(
+        &self,
+        method: &reqwest::Method,
+        url: &reqwest::Url,
+        response: &reqwest::Response,
+    ) {
+        for middleware in self.snapshot() {
+            middleware.on_response(method, url, response);
+        }
+    }
+
+    // Clone the hook list so no lock is held while hooks run
+    fn snapshot(&self) -> Vec<Arc<dyn Middleware>> {
+        let hooks = self.hooks.read().unwrap_or_else(|e| e.into_inner());
+        hooks.iter().map(|hook| hook.middleware.clone()).collect()
+    }
+}
+
+/// Asks for JSON unless the caller already chose a representation
+struct AcceptJsonMiddleware;
+
+impl Middleware for AcceptJsonMiddleware {
+    fn name(&self) -> &str {
+        "accept-json"
+    }
+
+    fn on_request(&self, request: &mut reqwest::Request) -> Result<(), String> {
+        request
+            .headers_mut()
+            .entry(ACCEPT)
+            .or_insert(HeaderValue::from_static("application/json"));
+        Ok(())
+    }
+}
+
+/// Logs every completed request in debug builds
+struct LoggingMiddleware;
+
+impl Middleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    fn on_response(&self, method: &reqwest::Method, url: &reqwest::Url, response: &reqwest::Response) {
+        if cfg!(debug_assertions) {
+            tracing::info!("{} {} -> {}", method, url, response.status());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::NetworkStatus;
+
+/// Emitted with the new [`NetworkStatus`] whenever connectivity changes
+pub const NETWORK_STATUS_CHANGED_EVENT: &str = "network-status-changed";
+
+/// Emitted with a [`CaptivePortal`] when a captive portal starts intercepting traffic,
+/// so the frontend can offer to open its login page
+pub const CAPTIVE_PORTAL_DETECTED_EVENT: &str = "captive-portal-detected";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptivePortal {
+    /// Page to open in the browser to reach the portal's login
+    pub url: String,
+}
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Consecutive identical probes required before a change is reported, so a single
+/// dropped probe on a flaky connection does not flip the UI offline
+const DEBOUNCE_SAMPLES: u32 = 2;
+
+/// Starts the background task that watches connectivity and notifies the webview.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut reported: Option<NetworkStatus> = None;
+        let mut candidate: Option<NetworkStatus> = None;
+        let mut candidate_samples = 0;
+
+        loop {
+            interval.tick().await;
+
+            let status = crate::probe_network_status(&app).await;
+
+            if reported.as_ref() == Some(&status) {
+                candidate = None;
+                candidate_samples = 0;
+                continue;
+            }
+
+            if candidate.as_ref() == Some(&status) {
+                candidate_samples += 1;
+            } else {
+                candidate = Some(status.clone());
+                candidate_samples = 1;
+            }
+
+            // The first reading is reported immediately so listeners get an initial state
+            if reported.is_some() && candidate_samples < DEBOUNCE_SAMPLES {
+                continue;
+            }
+
+            let came_online = status.can_reach_website
+                && !reported.as_ref().is_some_and(|r| r.can_reach_website);
+
+            if let Err(e) = app.emit(NETWORK_STATUS_CHANGED_EVENT, &status) {
+                tracing::warn!("Failed to emit network status: {}", e);
+            }
+
+            if status.captive_portal && !reported.as_ref().is_some_and(|r| r.captive_portal) {
+                let portal = CaptivePortal {
+                    url: crate::CAPTIVE_PORTAL_PROBE_URL.to_string(),
+                };
+                if let Err(e) = app.emit(CAPTIVE_PORTAL_DETECTED_EVENT, &portal) {
+                    tracing::warn!("Failed to emit captive portal event: {}", e);
+                }
+            }
+
+            if came_online {
+                crate::outbox::schedule_flush(&app);
+            }
+            crate::websocket::set_online(&app, status.can_reach_website);
+
+            reported = Some(status);
+            candidate = None;
+            candidate_samples = 0;
+        }
+    });
+}
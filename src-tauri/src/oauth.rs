@@ -0,0 +1,542 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Url};
+
+use crate::error::{AppError, ErrorKind};
+use crate::profiles::scoped_secret_key;
+use crate::secrets::{self, REFRESH_TOKEN_KEY, SESSION_TOKEN_KEY};
+
+/// Public client id of the native apps; they cannot keep a secret, which is what PKCE is for
+pub const CLIENT_ID: &str = "lotus-routine-app";
+
+const AUTHORIZE_ROUTE: &str = "/oauth/authorize";
+pub const TOKEN_ROUTE: &str = "/oauth/token";
+
+/// Where the provider, expiry and scope of the current login are kept; the tokens
+/// themselves live in the keychain
+pub const OAUTH_FILE: &str = "oauth";
+
+/// How long the user has to finish signing in in the browser
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How long before it expires the access token is refreshed, so requests under way do
+/// not race the expiry
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Held while the tokens are refreshed, so requests rejected together refresh only once
+#[derive(Default)]
+pub struct RefreshLock(tokio::sync::Mutex<()>);
+
+/// The login, without its tokens
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OAuthSession {
+    pub provider: Option<String>,
+    /// When the access token expires, if the server said
+    pub expires_at: Option<i64>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_in: Option<i64>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// URL-safe random string from `bytes` bytes of the OS random source.
+fn random_token(bytes: usize) -> Result<String, AppError> {
+    let mut buffer = vec![0u8; bytes];
+    getrandom::getrandom(&mut buffer)
+        .map_err(|e| AppError::internal(format!("Failed to generate random data: {}", e)))?;
+    Ok(URL_SAFE_NO_PAD.encode(buffer))
+}
+
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Sign-in route on the website. Refused unless the website is one of the hosts trusted
+/// with the session token, since the code and refresh token are sent there.
+pub fn endpoint(app: &AppHandle, route: &str) -> Result<Url, AppError> {
+    let url = Url::parse(&crate::website_url(app))
+        .and_then(|base| base.join(route))
+        .map_err(|e| AppError::corrupt_data(format!("Invalid sign-in URL: {}", e)))?;
+    if !crate::auth::is_trusted_url(&url) {
+        return Err(AppError::unsupported(format!(
+            "Sign-in is not available for {}",
+            url.origin().ascii_serialization()
+        )));
+    }
+
+    Ok(url)
+}
+
+#[cfg(desktop)]
+mod redirect {
+    use std::net::Ipv4Addr;
+    use tauri::{AppHandle, Url};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::error::AppError;
+
+    const CALLBACK_PATH: &str = "/callback";
+
+    /// Largest request read from the browser; the redirect fits easily
+    const MAX_REQUEST_BYTES: usize = 16 * 1024;
+
+    /// Loopback listener receiving the browser's redirect, as RFC 8252 recommends for
+    /// desktop apps
+    pub struct Redirect {
+        pub uri: String,
+        listener: TcpListener,
+    }
+
+    impl Redirect {
+        pub async fn listen(_app: &AppHandle) -> Result<Self, AppError> {
+            let failed = |e: std::io::Error| {
+                AppError::internal(format!("Failed to listen for the sign-in redirect: {}", e))
+            };
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+                .await
+                .map_err(failed)?;
+            let port = listener.local_addr().map_err(failed)?.port();
+
+            Ok(Self {
+                uri: format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH),
+                listener,
+            })
+        }
+
+        /// Waits for the browser to load the callback URL and returns it. The page left in
+        /// the browser tells the user to go back to the app.
+        pub async fn wait(self, app: &AppHandle) -> Result<Url, AppError> {
+            loop {
+                let (mut stream, _) = self.listener.accept().await.map_err(|e| {
+                    AppError::internal(format!("Failed to receive the sign-in redirect: {}", e))
+                })?;
+
+                // Browsers also ask for things like a favicon; only the callback counts
+                let Some(url) = read_callback(&mut stream).await else {
+                    respond(&mut stream, "404 Not Found", "").await;
+                    continue;
+                };
+
+                let key = if url.query_pairs().any(|(name, _)| name == "code") {
+                    "oauth.signed_in"
+                } else {
+                    "oauth.failed"
+                };
+                let page = format!(
+                    "<!doctype html><meta charset=\"utf-8\"><title>Lotus Routine</title><p>{}</p>",
+                    crate::i18n::text(app, key)
+                );
+                respond(&mut stream, "200 OK", &page).await;
+
+                crate::tray::show_main_window(app);
+                return Ok(url);
+            }
+        }
+    }
+
+    async fn read_callback(stream: &mut TcpStream) -> Option<Url> {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut buffer).await.ok()?;
+            if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+                return None;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        let request = String::from_utf8_lossy(&request);
+        let target = request
+            .lines()
+            .next()?
+            .strip_prefix("GET ")?
+            .split(' ')
+            .next()?;
+        let url = Url::parse(&format!("http://127.0.0.1{}", target)).ok()?;
+        (url.path() == CALLBACK_PATH).then_some(url)
+    }
+
+    async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        if let Err(e) = stream.write_all(response.as_bytes()).await {
+            tracing::warn!("Failed to answer the sign-in redirect: {}", e);
+        }
+    }
+}
+
+#[cfg(mobile)]
+mod redirect {
+    use std::sync::Mutex;
+    use tauri::{AppHandle, Manager, Url};
+    use tokio::sync::oneshot;
+
+    use crate::error::{AppError, ErrorKind};
+
+    /// Mobile apps get the redirect as a deep link
+    const REDIRECT_URI: &str = "lotus-routine://oauth/callback";
+
+    /// Login waiting for its redirect; starting another one abandons it
+    #[derive(Default)]
+    pub struct PendingLogin(Mutex<Option<oneshot::Sender<Url>>>);
+
+    pub struct Redirect {
+        pub uri: String,
+        receiver: oneshot::Receiver<Url>,
+    }
+
+    impl Redirect {
+        pub async fn listen(app: &AppHandle) -> Result<Self, AppError> {
+            let (sender, receiver) = oneshot::channel();
+            *app.state::<PendingLogin>()
+                .0
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some(sender);
+
+            Ok(Self {
+                uri: REDIRECT_URI.to_string(),
+                receiver,
+            })
+        }
+
+        pub async fn wait(self, _app: &AppHandle) -> Result<Url, AppError> {
+            self.receiver.await.map_err(|_| {
+                AppError::new(ErrorKind::Cancelled, "Sign-in was replaced by a newer one")
+            })
+        }
+    }
+
+    /// Hands a sign-in redirect to the login waiting for it. Returns whether `url` was
+    /// one, so it is not treated as a link into the website.
+    pub fn handle_deep_link(app: &AppHandle, url: &Url) -> bool {
+        if !url.as_str().starts_with(REDIRECT_URI) {
+            return false;
+        }
+
+        let pending = app
+            .state::<PendingLogin>()
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        match pending {
+            Some(sender) => {
+                let _ = sender.send(url.clone());
+            }
+            None => tracing::warn!("Ignoring a sign-in redirect nobody is waiting for"),
+        }
+        true
+    }
+}
+
+#[cfg(mobile)]
+pub use redirect::{handle_deep_link, PendingLogin};
+
+/// The authorization code from the redirect, once its `state` matches the login's.
+fn authorization_code(redirect: &Url, state: &str) -> Result<String, AppError> {
+    let param = |name: &str| {
+        redirect
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    if param("state").as_deref() != Some(state) {
+        return Err(AppError::new(
+            ErrorKind::AuthenticationFailed,
+            "Sign-in redirect does not belong to this login",
+        ));
+    }
+
+    if let Some(error) = param("error") {
+        let kind = if error == "access_denied" {
+            ErrorKind::Cancelled
+        } else {
+            ErrorKind::AuthenticationFailed
+        };
+        let description = param("error_description").unwrap_or(error);
+        return Err(AppError::new(
+            kind,
+            format!("Sign-in failed: {}", description),
+        ));
+    }
+
+    param("code").ok_or_else(|| {
+        AppError::new(
+            ErrorKind::AuthenticationFailed,
+            "Sign-in redirect has no authorization code",
+        )
+    })
+}
+
+/// Posts a token request to the website, for the first login and for refreshes.
+pub async fn request_tokens(
+    app: &AppHandle,
+    form: &[(&str, &str)],
+) -> Result<TokenResponse, AppError> {
+    let response = crate::http_client::client(app)
+        .post(endpoint(app, TOKEN_ROUTE)?)
+        .timeout(crate::http_client::REQUEST_TIMEOUT)
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| {
+            AppError::from_reqwest(&e, format!("Failed to reach the sign-in server: {}", e))
+        })?;
+
+    let status = response.status();
+    if status.is_client_error() {
+        let message = match response.json::<TokenError>().await {
+            Ok(error) => error.error_description.unwrap_or(error.error),
+            Err(_) => status.to_string(),
+        };
+        return Err(AppError::new(
+            ErrorKind::AuthenticationFailed,
+            format!("Sign-in was refused: {}", message),
+        ));
+    }
+
+    response
+        .error_for_status()
+        .map_err(|e| AppError::from_reqwest(&e, format!("Sign-in server failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| AppError::corrupt_data(format!("Invalid sign-in response: {}", e)))
+}
+
+/// Keeps the tokens in the keychain, where the request layer picks up the access token,
+/// and records the rest of the login.
+pub async fn save_tokens(
+    app: &AppHandle,
+    provider: Option<String>,
+    tokens: TokenResponse,
+) -> Result<OAuthSession, AppError> {
+    secrets::write_secret(
+        &scoped_secret_key(app, SESSION_TOKEN_KEY),
+        &tokens.access_token,
+    )?;
+    // A refresh may not rotate the refresh token; the previous one then stays valid
+    if let Some(refresh_token) = &tokens.refresh_token {
+        secrets::write_secret(&scoped_secret_key(app, REFRESH_TOKEN_KEY), refresh_token)?;
+    }
+
+    let session = OAuthSession {
+        provider,
+        expires_at: tokens
+            .expires_in
+            .map(|expires_in| chrono::Utc::now().timestamp() + expires_in),
+        scope: tokens.scope,
+    };
+    crate::write_state_file_blocking(app, OAUTH_FILE, &session).await?;
+    Ok(session)
+}
+
+/// Forgets the refresh token and the login record, e.g. when the frontend sets a session
+/// token of its own.
+pub async fn forget(app: &AppHandle) -> Result<(), AppError> {
+    secrets::remove_secret(&scoped_secret_key(app, REFRESH_TOKEN_KEY))?;
+    crate::write_state_file_blocking(app, OAUTH_FILE, &OAuthSession::default()).await
+}
+
+fn has_refresh_token(app: &AppHandle) -> bool {
+    match secrets::read_secret(&scoped_secret_key(app, REFRESH_TOKEN_KEY)) {
+        Ok(token) => token.is_some(),
+        Err(e) => {
+            tracing::warn!("Failed to read refresh token: {}", e);
+            false
+        }
+    }
+}
+
+/// Trades the refresh token for new tokens. A refused refresh token is forgotten, since
+/// only signing in again can help then.
+async fn refresh(app: &AppHandle) -> Result<OAuthSession, AppError> {
+    let key = scoped_secret_key(app, REFRESH_TOKEN_KEY);
+    let Some(refresh_token) = secrets::read_secret(&key)? else {
+        return Err(AppError::new(
+            ErrorKind::AuthenticationFailed,
+            "Not signed in with a refresh token",
+        ));
+    };
+    let session: OAuthSession = crate::read_state_file_blocking(app, OAUTH_FILE).await?;
+
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+        ("client_id", CLIENT_ID),
+    ];
+    let mut tokens = match request_tokens(app, &form).await {
+        Err(e) if matches!(e.kind, ErrorKind::AuthenticationFailed) => {
+            if let Err(e) = secrets::remove_secret(&key) {
+                tracing::warn!("Failed to remove refused refresh token: {}", e);
+            }
+            return Err(e);
+        }
+        result => result?,
+    };
+    // Leaving out the scope means it did not change
+    tokens.scope = tokens.scope.or(session.scope);
+
+    let session = save_tokens(app, session.provider, tokens).await?;
+    tracing::info!("Refreshed the access token");
+    Ok(session)
+}
+
+async fn expiring(app: &AppHandle) -> bool {
+    match crate::read_state_file_blocking::<OAuthSession>(app, OAUTH_FILE).await {
+        Ok(session) => session.expires_at.is_some_and(|expires_at| {
+            expires_at - chrono::Utc::now().timestamp() <= REFRESH_MARGIN_SECS
+        }),
+        Err(e) => {
+            tracing::warn!("Failed to read sign-in state: {}", e);
+            false
+        }
+    }
+}
+
+/// Refreshes the access token before a request if it is about to expire. Failures are
+/// only logged; the request then goes out with the token it has.
+pub async fn refresh_if_expiring(app: &AppHandle) {
+    if !expiring(app).await {
+        return;
+    }
+
+    let lock = app.state::<RefreshLock>();
+    let _guard = lock.0.lock().await;
+    // Another request may have refreshed while this one waited
+    if !expiring(app).await || !has_refresh_token(app) {
+        return;
+    }
+    if let Err(e) = refresh(app).await {
+        tracing::warn!("Failed to refresh the access token: {}", e.message);
+    }
+}
+
+/// Called when a request carrying `sent` as its `Authorization` header got a 401. Returns
+/// whether there is a newer token to retry with, refreshing it unless another request
+/// already did.
+pub async fn refresh_after_unauthorized(app: &AppHandle, sent: &HeaderValue) -> bool {
+    let lock = app.state::<RefreshLock>();
+    let _guard = lock.0.lock().await;
+
+    match secrets::read_secret(&scoped_secret_key(app, SESSION_TOKEN_KEY)) {
+        Ok(Some(token)) if sent.to_str().ok() != Some(format!("Bearer {}", token).as_str()) => {
+            return true;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!("Failed to read session token: {}", e);
+            return false;
+        }
+    }
+
+    if !has_refresh_token(app) {
+        return false;
+    }
+    match refresh(app).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!("Failed to refresh the access token: {}", e.message);
+            false
+        }
+    }
+}
+
+#[allow(deprecated)] // The shell plugin's opener is the one this app ships with
+fn open_in_browser(app: &AppHandle, url: &Url) -> Result<(), AppError> {
+    use tauri_plugin_shell::ShellExt;
+
+    app.shell()
+        .open(url.as_str(), None)
+        .map_err(|e| AppError::internal(format!("Failed to open the browser: {}", e)))
+}
+
+/// Signs in through `provider` (e.g. `google`) in the system browser rather than the
+/// webview, which some identity providers refuse to work in. The website's authorization
+/// server redirects back to a loopback port on desktop or to the app's deep link on
+/// mobile, and the code is exchanged with PKCE. The tokens go to the keychain and are
+/// never returned to the page.
+#[tauri::command]
+pub async fn start_oauth_login(app: AppHandle, provider: String) -> Result<OAuthSession, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    if provider.is_empty()
+        || !provider
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(AppError::invalid_input(format!(
+            "Invalid sign-in provider: {}",
+            provider
+        )));
+    }
+
+    let verifier = random_token(32)?;
+    let state = random_token(16)?;
+    let redirect = redirect::Redirect::listen(&app).await?;
+    let redirect_uri = redirect.uri.clone();
+
+    let mut url = endpoint(&app, AUTHORIZE_ROUTE)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", CLIENT_ID)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("code_challenge", &code_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256")
+        .append_pair("state", &state)
+        .append_pair("provider", &provider);
+    open_in_browser(&app, &url)?;
+
+    let callback = tokio::time::timeout(LOGIN_TIMEOUT, redirect.wait(&app))
+        .await
+        .map_err(|_| AppError::new(ErrorKind::Cancelled, "Sign-in timed out"))??;
+    let code = authorization_code(&callback, &state)?;
+
+    let tokens = request_tokens(
+        &app,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", CLIENT_ID),
+            ("code_verifier", &verifier),
+        ],
+    )
+    .await?;
+
+    // A provider without refresh tokens must not leave the previous login's behind
+    forget(&app).await?;
+    save_tokens(&app, Some(provider), tokens).await
+}
+
+/// Gets a new access token with the stored refresh token right away. Requests to the
+/// website do this by themselves when the token is about to expire or is rejected.
+#[tauri::command]
+pub async fn refresh_auth(app: AppHandle) -> Result<OAuthSession, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let lock = app.state::<RefreshLock>();
+    let _guard = lock.0.lock().await;
+    refresh(&app).await
+}
@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::webview::{PageLoadEvent, PageLoadPayload};
+use tauri::{Manager, Runtime, UriSchemeContext, Url, Webview};
+
+/// Custom scheme serving the bundled offline page, so it loads without the network and
+/// can still call commands
+pub const OFFLINE_SCHEME: &str = "lotus-offline";
+
+const OFFLINE_PAGE: &str = include_str!("../assets/offline.html");
+
+pub fn protocol<R: Runtime>(
+    _context: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+) -> Response<Cow<'static, [u8]>> {
+    let builder = Response::builder();
+    let response = if request.uri().path() == "/" {
+        builder
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Cow::Borrowed(OFFLINE_PAGE.as_bytes()))
+    } else {
+        builder
+            .status(StatusCode::NOT_FOUND)
+            .body(Cow::Borrowed(&[][..]))
+    };
+
+    response.unwrap_or_else(|e| {
+        tracing::warn!("Failed to build offline page response: {}", e);
+        Response::new(Cow::Borrowed(&[][..]))
+    })
+}
+
+/// Replaces the platform's error page when the website failed to load: with the cached
+/// copy of the web app if there is one, and the offline page otherwise. Webviews do not
+/// report load failures, so a finished load of the website is followed by a reachability
+/// check.
+pub fn handle_page_load(webview: &Webview, payload: &PageLoadPayload<'_>) {
+    if webview.label() != "main" || payload.event() != PageLoadEvent::Finished {
+        return;
+    }
+
+    let app = webview.app_handle().clone();
+    let Ok(website) = Url::parse(&crate::website_url(&app)) else {
+        return;
+    };
+    if payload.url().origin() != website.origin() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        if crate::check_website_connectivity(&app).await {
+            return;
+        }
+
+        let Some(window) = app.get_webview_window("main") else {
+            return;
+        };
+
+        let url = if crate::app_cache::has_cached_app(&app).await {
+            tracing::info!("Website unreachable, opening the cached web app");
+            crate::custom_scheme_url(crate::app_cache::APP_CACHE_SCHEME)
+        } else {
+            tracing::info!("Website unreachable, showing offline page");
+            crate::custom_scheme_url(OFFLINE_SCHEME)
+        };
+        if let Err(e) = window.navigate(url) {
+            tracing::warn!("Failed to open offline page: {}", e);
+        }
+    });
+}
@@ -0,0 +1,789 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+use crate::cache_store::{with_store, with_store_blocking, CacheStore};
+use crate::error::{AppError, FetchError};
+use crate::progress::ProgressReporter;
+use crate::RequestBody;
+use crate::settings::ConflictStrategy;
+use crate::uploads::FileUpload;
+
+/// Operation id used for progress events of flushes that were not started by the frontend
+const AUTO_FLUSH_OPERATION_ID: &str = "mutation-queue";
+
+/// Request headers that carry credentials. Copies of queued mutations kept outside the
+/// queue, in backups and sync conflicts, leave them out; a replay gets the current
+/// session's token instead.
+const CREDENTIAL_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "x-auth-token",
+];
+
+/// Removes the [`CREDENTIAL_HEADERS`] from `headers`, whatever their case.
+pub fn strip_credentials(headers: &mut HashMap<String, String>) {
+    headers.retain(|name, _| {
+        !CREDENTIAL_HEADERS
+            .iter()
+            .any(|credential| name.eq_ignore_ascii_case(credential))
+    });
+}
+
+/// A write request stored while offline, replayed in insertion order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingMutation {
+    pub id: i64,
+    pub method: String,
+    pub url: String,
+    pub headers: Option<HashMap<String, String>>,
+    pub body: Option<serde_json::Value>,
+    pub created_at: i64,
+    /// Cached document the change applies to
+    #[serde(default)]
+    pub cache_key: Option<String>,
+    /// Validators of that document when the change was made, sent as preconditions so
+    /// the server can report a conflict if its copy has moved on
+    #[serde(default)]
+    pub base_etag: Option<String>,
+    #[serde(default)]
+    pub base_last_modified: Option<String>,
+    /// Hash of a stored attachment sent as the raw request body instead of `body`
+    #[serde(default)]
+    pub attachment: Option<String>,
+    /// File on disk sent as `multipart/form-data` instead of `body`
+    #[serde(default)]
+    pub upload: Option<FileUpload>,
+    /// Replays that failed because the website was unreachable or unavailable; the
+    /// mutation stays queued and is tried again on the next flush
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_attempt_at: Option<i64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl PendingMutation {
+    /// Headers to send, with the base version attached as preconditions if requested.
+    fn request_headers(&self, preconditions: bool) -> Option<HashMap<String, String>> {
+        let mut headers = self.headers.clone();
+
+        if preconditions {
+            if let Some(etag) = &self.base_etag {
+                headers
+                    .get_or_insert_with(HashMap::new)
+                    .insert("If-Match".to_string(), etag.clone());
+            } else if let Some(last_modified) = &self.base_last_modified {
+                headers
+                    .get_or_insert_with(HashMap::new)
+                    .insert("If-Unmodified-Since".to_string(), last_modified.clone());
+            }
+        }
+
+        headers
+    }
+
+    fn has_base_version(&self) -> bool {
+        self.base_etag.is_some() || self.base_last_modified.is_some()
+    }
+}
+
+/// What will be sent once the website is reachable again
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub pending: u64,
+    /// Queued mutations that have already failed at least once
+    pub retrying: u64,
+    pub oldest_created_at: Option<i64>,
+    /// When a replay last failed, and why
+    pub last_attempt_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub flushing: bool,
+    /// In the order they will be sent
+    pub mutations: Vec<PendingMutation>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlushSummary {
+    pub sent: u64,
+    /// Mutations the server rejected permanently (4xx); they are dropped from the queue
+    pub rejected: u64,
+    /// Mutations that conflicted with newer server data and were not sent
+    pub conflicts: u64,
+    pub remaining: u64,
+}
+
+/// Prevents overlapping flushes from replaying the same mutation twice
+#[derive(Default)]
+pub struct OutboxState {
+    flushing: AtomicBool,
+}
+
+pub(crate) fn parse_method(method: &str) -> Result<reqwest::Method, AppError> {
+    match method.to_ascii_uppercase().as_str() {
+        "POST" => Ok(reqwest::Method::POST),
+        "PUT" => Ok(reqwest::Method::PUT),
+        "PATCH" => Ok(reqwest::Method::PATCH),
+        "DELETE" => Ok(reqwest::Method::DELETE),
+        other => Err(AppError::invalid_input(format!(
+            "Unsupported mutation method: {}",
+            other
+        ))),
+    }
+}
+
+pub async fn pending_mutations(app: &AppHandle) -> Result<Vec<PendingMutation>, AppError> {
+    with_store_blocking(app, read_pending)
+        .await
+        .map_err(AppError::storage)
+}
+
+/// [`pending_mutations`] for code already on the blocking pool
+pub fn read_pending(store: &CacheStore) -> Result<Vec<PendingMutation>, String> {
+    let mut statement = store
+        .connection()
+        .prepare(
+            "SELECT id, method, url, headers, body, created_at, cache_key, base_etag,
+                base_last_modified, attachment, upload, attempts, last_attempt_at,
+                last_error
+             FROM mutations ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to query mutation queue: {}", e))?;
+
+    let rows = statement
+        .query_map([], |row| {
+            let headers: Option<String> = row.get(3)?;
+            let body: Option<String> = row.get(4)?;
+            let upload: Option<String> = row.get(10)?;
+            Ok(PendingMutation {
+                id: row.get(0)?,
+                method: row.get(1)?,
+                url: row.get(2)?,
+                headers: headers.and_then(|h| serde_json::from_str(&h).ok()),
+                body: body.and_then(|b| serde_json::from_str(&b).ok()),
+                created_at: row.get(5)?,
+                cache_key: row.get(6)?,
+                base_etag: row.get(7)?,
+                base_last_modified: row.get(8)?,
+                attachment: row.get(9)?,
+                upload: upload.and_then(|u| serde_json::from_str(&u).ok()),
+                attempts: row.get(11)?,
+                last_attempt_at: row.get(12)?,
+                last_error: row.get(13)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query mutation queue: {}", e))?;
+
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read mutation queue: {}", e))
+}
+
+/// Notes a failed replay of a mutation that stays queued.
+async fn record_failure(app: &AppHandle, id: i64, error: String) -> Result<(), AppError> {
+    with_store_blocking(app, move |store| {
+        store
+            .connection()
+            .execute(
+                "UPDATE mutations
+                 SET attempts = attempts + 1, last_attempt_at = ?2, last_error = ?3
+                 WHERE id = ?1",
+                params![id, chrono::Utc::now().timestamp(), error],
+            )
+            .map_err(|e| format!("Failed to update queued mutation: {}", e))?;
+        Ok(())
+    })
+    .await
+    .map_err(AppError::storage)
+}
+
+async fn remove_mutation(app: &AppHandle, id: i64) -> Result<(), AppError> {
+    with_store_blocking(app, move |store| delete_mutation(store, id))
+        .await
+        .map_err(AppError::storage)
+}
+
+fn delete_mutation(store: &CacheStore, id: i64) -> Result<(), String> {
+    store
+        .connection()
+        .execute("DELETE FROM mutations WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to remove queued mutation: {}", e))?;
+    Ok(())
+}
+
+/// Stores a write request for replay once the website is reachable again.
+///
+/// When `cache_key` names the cached document being changed, its current validators
+/// are kept so a conflicting server-side change can be detected on replay.
+pub async fn enqueue(
+    app: &AppHandle,
+    method: &str,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    body: Option<&serde_json::Value>,
+    cache_key: Option<&str>,
+) -> Result<i64, AppError> {
+    let method = parse_method(method)?;
+
+    let headers = headers
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize headers: {}", e)))?;
+    let body = body
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize body: {}", e)))?;
+    let url = url.to_string();
+    let cache_key = cache_key.map(str::to_string);
+
+    with_store_blocking(app, move |store| {
+        let base = match &cache_key {
+            Some(key) => store.validators(key)?.unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        store
+            .connection()
+            .execute(
+                "INSERT INTO mutations
+                    (method, url, headers, body, created_at, cache_key, base_etag,
+                     base_last_modified)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    method.as_str(),
+                    url,
+                    headers,
+                    body,
+                    chrono::Utc::now().timestamp(),
+                    cache_key,
+                    base.etag,
+                    base.last_modified
+                ],
+            )
+            .map_err(|e| format!("Failed to queue mutation: {}", e))?;
+        Ok(store.connection().last_insert_rowid())
+    })
+    .await
+    .map_err(AppError::storage)
+}
+
+/// Queues an upload of a stored attachment, sent as the raw request body.
+pub fn enqueue_upload(
+    app: &AppHandle,
+    method: &str,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    hash: &str,
+) -> Result<i64, AppError> {
+    let method = parse_method(method)?;
+    let headers = headers
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize headers: {}", e)))?;
+
+    with_store(app, |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO mutations (method, url, headers, created_at, attachment)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    method.as_str(),
+                    url,
+                    headers,
+                    chrono::Utc::now().timestamp(),
+                    hash
+                ],
+            )
+            .map_err(|e| format!("Failed to queue upload: {}", e))?;
+        Ok(store.connection().last_insert_rowid())
+    })
+    .map_err(AppError::storage)
+}
+
+/// Queues a multipart upload of a file on disk, read when the queue is flushed.
+pub async fn enqueue_file_upload(
+    app: &AppHandle,
+    method: &str,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    upload: &FileUpload,
+) -> Result<i64, AppError> {
+    let method = parse_method(method)?;
+    let headers = headers
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| AppError::internal(format!("Failed to serialize headers: {}", e)))?;
+    let upload = serde_json::to_string(upload)
+        .map_err(|e| AppError::internal(format!("Failed to serialize upload: {}", e)))?;
+    let url = url.to_string();
+
+    with_store_blocking(app, move |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO mutations (method, url, headers, created_at, upload)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    method.as_str(),
+                    url,
+                    headers,
+                    chrono::Utc::now().timestamp(),
+                    upload
+                ],
+            )
+            .map_err(|e| format!("Failed to queue upload: {}", e))?;
+        Ok(store.connection().last_insert_rowid())
+    })
+    .await
+    .map_err(AppError::storage)
+}
+
+/// Drops queued uploads of an attachment that is being deleted.
+pub fn remove_uploads(app: &AppHandle, hash: &str) -> Result<(), AppError> {
+    with_store(app, |store| {
+        store
+            .connection()
+            .execute("DELETE FROM mutations WHERE attachment = ?1", params![hash])
+            .map_err(|e| format!("Failed to remove queued uploads: {}", e))?;
+        Ok(())
+    })
+    .map_err(AppError::storage)
+}
+
+/// Appends mutations from a backup behind anything already queued, keeping their
+/// original creation times.
+pub fn restore(app: &AppHandle, mutations: &[PendingMutation]) -> Result<u64, AppError> {
+    let methods = mutations
+        .iter()
+        .map(|mutation| parse_method(&mutation.method))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::corrupt_data(e.message))?;
+
+    with_store(app, |store| {
+        let tx = store
+            .connection()
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start mutation import: {}", e))?;
+
+        for (mutation, method) in mutations.iter().zip(methods) {
+            let headers = mutation
+                .headers
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| format!("Failed to serialize headers: {}", e))?;
+            let body = mutation
+                .body
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| format!("Failed to serialize body: {}", e))?;
+            let upload = mutation
+                .upload
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| format!("Failed to serialize upload: {}", e))?;
+
+            tx.execute(
+                "INSERT INTO mutations
+                    (method, url, headers, body, created_at, cache_key, base_etag,
+                     base_last_modified, attachment, upload, attempts, last_attempt_at,
+                     last_error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    method.as_str(),
+                    mutation.url,
+                    headers,
+                    body,
+                    mutation.created_at,
+                    mutation.cache_key,
+                    mutation.base_etag,
+                    mutation.base_last_modified,
+                    mutation.attachment,
+                    upload,
+                    mutation.attempts,
+                    mutation.last_attempt_at,
+                    mutation.last_error
+                ],
+            )
+            .map_err(|e| format!("Failed to queue mutation: {}", e))?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit mutation import: {}", e))?;
+        Ok(mutations.len() as u64)
+    })
+    .map_err(AppError::storage)
+}
+
+/// Replays queued mutations in order, stopping at the first transient failure so later
+/// writes never overtake earlier ones.
+async fn flush(app: &AppHandle, progress: &ProgressReporter) -> Result<FlushSummary, AppError> {
+    let state = app.state::<OutboxState>();
+    if state.flushing.swap(true, Ordering::SeqCst) {
+        return Err(AppError::invalid_state("A queue flush is already in progress"));
+    }
+
+    let result = replay(app, progress).await;
+    state.flushing.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn send_mutation(
+    app: &AppHandle,
+    mutation: &PendingMutation,
+    preconditions: bool,
+) -> Result<serde_json::Value, FetchError> {
+    let method = parse_method(&mutation.method)?;
+    let attachment = mutation
+        .attachment
+        .as_deref()
+        .map(|hash| crate::attachments::read(app, hash))
+        .transpose()?;
+
+    // Progress of uploads replayed from the queue is covered by the flush's own events
+    let progress = ProgressReporter::new(app, None);
+
+    let body = match (&attachment, &mutation.upload) {
+        (Some((attachment, data)), _) => Some(RequestBody::Bytes {
+            data,
+            content_type: &attachment.content_type,
+        }),
+        (None, Some(upload)) => Some(RequestBody::Multipart {
+            upload,
+            progress: &progress,
+        }),
+        (None, None) => mutation.body.as_ref().map(RequestBody::Json),
+    };
+
+    crate::send_online_request(
+        app,
+        method,
+        &mutation.url,
+        mutation.request_headers(preconditions).as_ref(),
+        body,
+    )
+    .await
+}
+
+/// `409 Conflict` or `412 Precondition Failed`: the server copy changed since the
+/// mutation was queued
+fn is_conflict(error: &FetchError) -> bool {
+    matches!(error, FetchError::Http { status: 409 | 412, .. })
+}
+
+/// Failed replays that stay queued: the website was unreachable, asked to be tried
+/// again later, or could not be verified. Any other answer is final, and a success of
+/// any kind means the server has the change, so sending it again would duplicate it.
+fn keeps_queued(error: &FetchError) -> bool {
+    error.is_transient() || matches!(error, FetchError::CertificatePin { .. })
+}
+
+async fn replay(app: &AppHandle, progress: &ProgressReporter) -> Result<FlushSummary, AppError> {
+    let pending = pending_mutations(app).await?;
+    let strategy = crate::settings::current(app).conflict_strategy;
+    let total = pending.len() as u64;
+    let mut summary = FlushSummary {
+        remaining: total,
+        ..Default::default()
+    };
+
+    progress.report("flushing", 0, Some(total));
+
+    for mutation in pending {
+        if let Some(hash) = mutation
+            .attachment
+            .as_deref()
+            .filter(|hash| !crate::attachments::exists(app, hash))
+        {
+            tracing::warn!(
+                "Dropping queued upload {} {}: attachment {} is gone",
+                mutation.method, mutation.url, hash
+            );
+            summary.rejected += 1;
+            remove_mutation(app, mutation.id).await?;
+            summary.remaining -= 1;
+            progress.report("flushing", total - summary.remaining, Some(total));
+            continue;
+        }
+
+        if let Some(upload) = mutation
+            .upload
+            .as_ref()
+            .filter(|upload| !std::path::Path::new(&upload.path).is_file())
+        {
+            tracing::warn!(
+                "Dropping queued upload {} {}: {} is gone",
+                mutation.method, mutation.url, upload.path
+            );
+            summary.rejected += 1;
+            remove_mutation(app, mutation.id).await?;
+            summary.remaining -= 1;
+            progress.report("flushing", total - summary.remaining, Some(total));
+            continue;
+        }
+
+        let mut outcome = send_mutation(app, &mutation, true).await;
+
+        if strategy == ConflictStrategy::LastWriteWins
+            && mutation.has_base_version()
+            && outcome.as_ref().is_err_and(is_conflict)
+        {
+            tracing::info!(
+                "Overwriting newer server data with queued {} {}",
+                mutation.method, mutation.url
+            );
+            outcome = send_mutation(app, &mutation, false).await;
+        }
+
+        match outcome {
+            Ok(_) => {
+                if let Some(hash) = &mutation.attachment {
+                    crate::attachments::mark_uploaded(app, hash);
+                }
+                summary.sent += 1;
+            }
+            Err(e) if is_conflict(&e) && strategy != ConflictStrategy::LastWriteWins => {
+                if strategy == ConflictStrategy::Ask {
+                    crate::conflicts::record(app, &mutation).await?;
+                } else {
+                    tracing::info!(
+                        "Discarding queued {} {} in favour of newer server data",
+                        mutation.method, mutation.url
+                    );
+                }
+                summary.conflicts += 1;
+            }
+            Err(e) if keeps_queued(&e) => {
+                tracing::info!("Queue flush paused: {}", e);
+                record_failure(app, mutation.id, e.to_string()).await?;
+                break;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Dropping queued mutation {} {}: {}",
+                    mutation.method, mutation.url, e
+                );
+                summary.rejected += 1;
+            }
+        }
+
+        remove_mutation(app, mutation.id).await?;
+        summary.remaining -= 1;
+        progress.report("flushing", total - summary.remaining, Some(total));
+    }
+
+    progress.finish(
+        "flushing",
+        total - summary.remaining,
+        Some(total),
+        Some(format!(
+            "Sent {}, rejected {}, conflicts {}, remaining {}",
+            summary.sent, summary.rejected, summary.conflicts, summary.remaining
+        )),
+    );
+
+    Ok(summary)
+}
+
+/// Starts a background flush if anything is queued and no flush is running.
+pub fn schedule_flush(app: &AppHandle) {
+    if crate::demo::is_demo(app) || app.state::<OutboxState>().flushing.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match pending_mutations(&app).await {
+            Ok(pending) if !pending.is_empty() => {}
+            _ => return,
+        }
+
+        let progress = ProgressReporter::new(&app, Some(AUTO_FLUSH_OPERATION_ID.to_string()));
+        if let Err(e) = flush(&app, &progress).await {
+            tracing::warn!("Automatic queue flush failed: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn queue_mutation(
+    app: AppHandle,
+    method: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    body: Option<serde_json::Value>,
+    cache_key: Option<String>,
+) -> Result<i64, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    enqueue(
+        &app,
+        &method,
+        &url,
+        headers.as_ref(),
+        body.as_ref(),
+        cache_key.as_deref(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_pending_mutations(app: AppHandle) -> Result<Vec<PendingMutation>, AppError> {
+    pending_mutations(&app).await
+}
+
+pub async fn queue_status(app: &AppHandle) -> Result<QueueStatus, AppError> {
+    let flushing = app.state::<OutboxState>().flushing.load(Ordering::SeqCst);
+    let mutations = pending_mutations(app).await?;
+
+    let last_failed = mutations
+        .iter()
+        .filter(|mutation| mutation.last_attempt_at.is_some())
+        .max_by_key(|mutation| mutation.last_attempt_at);
+
+    Ok(QueueStatus {
+        pending: mutations.len() as u64,
+        retrying: mutations.iter().filter(|mutation| mutation.attempts > 0).count() as u64,
+        oldest_created_at: mutations.iter().map(|mutation| mutation.created_at).min(),
+        last_attempt_at: last_failed.and_then(|mutation| mutation.last_attempt_at),
+        last_error: last_failed.and_then(|mutation| mutation.last_error.clone()),
+        flushing,
+        mutations,
+    })
+}
+
+/// Summarizes the queue, for showing what will sync on reconnect.
+#[tauri::command]
+pub async fn get_queue_status(app: AppHandle) -> Result<QueueStatus, AppError> {
+    queue_status(&app).await
+}
+
+#[tauri::command]
+pub async fn flush_queue(
+    app: AppHandle,
+    operation_id: Option<String>,
+) -> Result<FlushSummary, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    if !crate::probe_network_status(&app).await.can_reach_website {
+        return Err(AppError::offline(
+            "Cannot reach website. Queued changes will be sent when back online.",
+        ));
+    }
+
+    let progress = ProgressReporter::new(
+        &app,
+        Some(operation_id.unwrap_or_else(|| AUTO_FLUSH_OPERATION_ID.to_string())),
+    );
+    flush(&app, &progress).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers one request with `response` and returns the URL it listens on
+    async fn serve_once(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/habits", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        url
+    }
+
+    fn queue_one(store: &CacheStore) -> i64 {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO mutations (method, url, body, created_at)
+                 VALUES ('POST', 'https://example.com/habits', '{}', 0)",
+                [],
+            )
+            .unwrap();
+        store.connection().last_insert_rowid()
+    }
+
+    #[tokio::test]
+    async fn accepted_writes_without_json_leave_the_queue() {
+        let responses = [
+            "HTTP/1.1 204 No Content\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 8\r\n\r\naccepted",
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let store = CacheStore::open(&dir.path().join("cache.db"), u64::MAX).unwrap();
+
+        for response in responses {
+            let id = queue_one(&store);
+            let response = reqwest::Client::new()
+                .post(serve_once(response).await)
+                .json(&serde_json::json!({}))
+                .send()
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+
+            let outcome =
+                crate::success_data(&reqwest::Method::POST, response.text().await.unwrap());
+            match outcome {
+                Err(e) if keeps_queued(&e) => panic!("kept a delivered write queued: {}", e),
+                Err(e) => panic!("rejected a delivered write: {}", e),
+                Ok(_) => delete_mutation(&store, id).unwrap(),
+            }
+
+            assert!(read_pending(&store).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn credentials_are_stripped_in_any_case() {
+        let mut headers = HashMap::from([
+            ("Authorization".to_string(), "Bearer secret".to_string()),
+            ("COOKIE".to_string(), "session=secret".to_string()),
+            ("x-api-key".to_string(), "secret".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ]);
+
+        strip_credentials(&mut headers);
+
+        assert_eq!(
+            headers,
+            HashMap::from([("Content-Type".to_string(), "application/json".to_string())])
+        );
+    }
+
+    #[test]
+    fn only_unanswered_replays_stay_queued() {
+        let network = FetchError::Network {
+            message: "timed out".to_string(),
+        };
+        let http = |status| FetchError::Http {
+            status,
+            message: String::new(),
+            code: None,
+            retry_after_secs: None,
+        };
+
+        assert!(keeps_queued(&network));
+        assert!(keeps_queued(&http(503)));
+        assert!(keeps_queued(&http(429)));
+        assert!(!keeps_queued(&http(400)));
+        assert!(!keeps_queued(&FetchError::from(AppError::corrupt_data(
+            "Failed to parse JSON response"
+        ))));
+        assert!(!keeps_queued(&FetchError::from(AppError::storage(
+            "Failed to read queued upload"
+        ))));
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+const PREFETCH_FILE: &str = "prefetch";
+
+/// How often the background runner looks for due prefetches
+const PREFETCH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A key refreshed in the background at fixed local times of day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefetchEntry {
+    pub key: String,
+    pub url: String,
+    pub headers: Option<HashMap<String, String>>,
+    /// Local times of day in `HH:MM` format
+    pub times: Vec<String>,
+    pub last_run: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PrefetchSchedule {
+    entries: Vec<PrefetchEntry>,
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| format!("Invalid prefetch time '{}': {}", time, e))
+}
+
+/// Latest scheduled slot at or before `now` that has not run yet.
+fn is_due(entry: &PrefetchEntry, now: chrono::DateTime<Local>) -> bool {
+    entry.times.iter().filter_map(|t| parse_time(t).ok()).any(|time| {
+        let Some(slot) = Local
+            .from_local_datetime(&now.date_naive().and_time(time))
+            .earliest()
+        else {
+            return false;
+        };
+
+        slot <= now && entry.last_run.is_none_or(|last_run| last_run < slot.timestamp())
+    })
+}
+
+async fn run_due_prefetches(app: &AppHandle) -> Result<(), String> {
+    if crate::demo::is_demo(app) || crate::settings::current(app).data_saver {
+        return Ok(());
+    }
+
+    let mut schedule: PrefetchSchedule =
+        crate::read_state_file_blocking(app, PREFETCH_FILE).await?;
+    let now = Local::now();
+
+    let due: Vec<usize> = (0..schedule.entries.len())
+        .filter(|&i| is_due(&schedule.entries[i], now))
+        .collect();
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    if !crate::probe_network_status(app).await.can_reach_website {
+        return Ok(());
+    }
+
+    for i in due {
+        let entry = &mut schedule.entries[i];
+
+        match crate::fetch_online_data(app, &entry.url, entry.headers.clone()).await {
+            Ok(data) => {
+                crate::store_local_data(app, &entry.key, &data, None, Some(&entry.url)).await?;
+                entry.last_run = Some(now.timestamp());
+            }
+            Err(e) => tracing::warn!("Scheduled prefetch of '{}' failed: {}", entry.key, e),
+        }
+    }
+
+    crate::write_state_file_blocking(app, PREFETCH_FILE, &schedule)
+        .await
+        .map_err(String::from)
+}
+
+/// Starts the background runner that executes scheduled prefetches.
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PREFETCH_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let result = run_due_prefetches(&app).await;
+            if let Err(e) = &result {
+                tracing::warn!("Prefetch scheduler run failed: {}", e);
+            }
+            crate::health::record_run(&app, "prefetch", PREFETCH_CHECK_INTERVAL, &result);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn register_prefetch(
+    app: AppHandle,
+    key: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    times: Vec<String>,
+) -> Result<(), AppError> {
+    for time in &times {
+        parse_time(time).map_err(AppError::invalid_input)?;
+    }
+
+    let mut schedule: PrefetchSchedule =
+        crate::read_state_file_blocking(&app, PREFETCH_FILE).await?;
+    schedule.entries.retain(|entry| entry.key != key);
+    schedule.entries.push(PrefetchEntry {
+        key,
+        url,
+        headers,
+        times,
+        last_run: None,
+    });
+
+    crate::write_state_file_blocking(&app, PREFETCH_FILE, &schedule).await
+}
+
+#[tauri::command]
+pub async fn unregister_prefetch(app: AppHandle, key: String) -> Result<(), AppError> {
+    let mut schedule: PrefetchSchedule =
+        crate::read_state_file_blocking(&app, PREFETCH_FILE).await?;
+    schedule.entries.retain(|entry| entry.key != key);
+    crate::write_state_file_blocking(&app, PREFETCH_FILE, &schedule).await
+}
+
+#[tauri::command]
+pub async fn list_prefetch(app: AppHandle) -> Result<Vec<PrefetchEntry>, AppError> {
+    let schedule: PrefetchSchedule = crate::read_state_file_blocking(&app, PREFETCH_FILE).await?;
+    Ok(schedule.entries)
+}
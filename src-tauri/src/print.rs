@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Page setup applied through CSS `@page` rules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrintOptions {
+    /// Title of the print window, used by some platforms as the document name
+    pub title: Option<String>,
+    pub landscape: bool,
+    pub margin_mm: Option<f64>,
+}
+
+/// Adds the page setup to `content`, inside its `<head>` when it has one.
+fn with_page_style(content: &str, options: &PrintOptions) -> String {
+    let mut rules = Vec::new();
+    if options.landscape {
+        rules.push("size: landscape;".to_string());
+    }
+    if let Some(margin) = options.margin_mm {
+        rules.push(format!("margin: {}mm;", margin));
+    }
+
+    if rules.is_empty() {
+        return content.to_string();
+    }
+
+    let style = format!("<style>@page {{ {} }}</style>", rules.join(" "));
+    match content.find("</head>") {
+        Some(index) => format!("{}{}{}", &content[..index], style, &content[index..]),
+        None => format!("{}{}", style, content),
+    }
+}
+
+/// Loads the document into its own window and opens the native print dialog for it. The
+/// window stays open as a preview until the user closes it.
+#[cfg(desktop)]
+fn print_document(app: &AppHandle, document: &str, options: &PrintOptions) -> Result<(), String> {
+    use base64::Engine;
+    use tauri::webview::PageLoadEvent;
+    use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+    let url = format!(
+        "data:text/html;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(document)
+    )
+    .parse()
+    .map_err(|e| format!("Failed to prepare print document: {}", e))?;
+
+    let label = format!(
+        "print-{}",
+        std::iter::repeat_with(fastrand::alphanumeric)
+            .take(8)
+            .collect::<String>()
+            .to_lowercase()
+    );
+
+    WebviewWindowBuilder::new(app, label, WebviewUrl::External(url))
+        .title(options.title.as_deref().unwrap_or("Print"))
+        .inner_size(800.0, 1000.0)
+        .on_page_load(|window, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                if let Err(e) = window.print() {
+                    tracing::warn!("Failed to open print dialog: {}", e);
+                }
+            }
+        })
+        .build()
+        .map_err(|e| format!("Failed to open print window: {}", e))?;
+
+    Ok(())
+}
+
+/// Mobile apps have a single webview, so the document is printed from a hidden frame in
+/// it; the system dialog there also offers saving as PDF.
+#[cfg(mobile)]
+fn print_document(app: &AppHandle, document: &str, _options: &PrintOptions) -> Result<(), String> {
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window is not available".to_string())?;
+    let document = serde_json::to_string(document)
+        .map_err(|e| format!("Failed to prepare print document: {}", e))?;
+
+    window
+        .eval(&format!(
+            r#"(function() {{
+                const frame = document.createElement('iframe');
+                frame.style.display = 'none';
+                frame.srcdoc = {};
+                frame.onload = function() {{
+                    frame.contentWindow.print();
+                    setTimeout(function() {{ frame.remove(); }}, 1000);
+                }};
+                document.body.appendChild(frame);
+            }})();"#,
+            document
+        ))
+        .map_err(|e| format!("Failed to print: {}", e))
+}
+
+/// Prints an HTML document through the platform print dialog.
+#[tauri::command]
+pub async fn print_html(
+    app: AppHandle,
+    content: String,
+    options: Option<PrintOptions>,
+) -> Result<(), AppError> {
+    let options = options.unwrap_or_default();
+    let document = with_page_style(&content, &options);
+    print_document(&app, &document, &options).map_err(AppError::internal)
+}
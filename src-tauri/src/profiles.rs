@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::demo::ensure_not_demo;
+use crate::error::AppError;
+use crate::secrets::{self, REFRESH_TOKEN_KEY, SESSION_TOKEN_KEY};
+
+/// Profile that owns the data written before profiles existed
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// Emitted with the newly active [`Profile`] after a switch
+pub const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+/// Index of the profiles, under `profiles/` in the app data root
+const PROFILE_INDEX_FILE: &str = "index";
+
+/// A local profile with its own cache, settings, secrets, and queues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileList {
+    pub profiles: Vec<Profile>,
+    pub active: String,
+}
+
+pub struct ProfileState(RwLock<ProfileList>);
+
+impl ProfileState {
+    /// Reads the profile index, creating it with the default profile on first launch.
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        let index_path = index_path(app)?;
+
+        let list = match std::fs::read_to_string(&index_path) {
+            Ok(content) => crate::storage::decode(PROFILE_INDEX_FILE, &content)?.value,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ProfileList {
+                profiles: vec![Profile {
+                    id: DEFAULT_PROFILE_ID.to_string(),
+                    name: "Default".to_string(),
+                    created_at: chrono::Utc::now().timestamp(),
+                }],
+                active: DEFAULT_PROFILE_ID.to_string(),
+            },
+            Err(e) => return Err(format!("Failed to read profile index: {}", e)),
+        };
+
+        Ok(Self(RwLock::new(list)))
+    }
+
+    pub fn active_id(&self) -> String {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).active.clone()
+    }
+
+    pub fn snapshot(&self) -> ProfileList {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn app_data_root(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::storage(format!("Failed to get app data directory: {}", e)))
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(app_data_root(app)?
+        .join("profiles")
+        .join(format!("{}.json", PROFILE_INDEX_FILE)))
+}
+
+/// The default profile keeps using the app data root so existing caches stay in place.
+fn profile_dir(root: &Path, profile_id: &str) -> PathBuf {
+    if profile_id == DEFAULT_PROFILE_ID {
+        root.to_path_buf()
+    } else {
+        root.join("profiles").join(profile_id)
+    }
+}
+
+/// Data directory of the active profile
+pub fn active_data_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let profile_id = app.state::<ProfileState>().active_id();
+    Ok(profile_dir(&app_data_root(app)?, &profile_id))
+}
+
+/// Namespaces a keychain entry name under the active profile.
+pub fn scoped_secret_key(app: &AppHandle, key: &str) -> String {
+    secret_key_for(&app.state::<ProfileState>().active_id(), key)
+}
+
+fn secret_key_for(profile_id: &str, key: &str) -> String {
+    if profile_id == DEFAULT_PROFILE_ID {
+        key.to_string()
+    } else {
+        format!("{}/{}", profile_id, key)
+    }
+}
+
+fn save_index(app: &AppHandle, list: &ProfileList) -> Result<(), AppError> {
+    let index_path = index_path(app)?;
+    if let Some(parent) = index_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            AppError::storage(format!("Failed to create profiles directory: {}", e))
+        })?;
+    }
+
+    let json_string =
+        crate::storage::encode(PROFILE_INDEX_FILE, list).map_err(AppError::internal)?;
+
+    crate::storage::write_atomic(&index_path, json_string.as_bytes())
+        .map_err(|e| AppError::storage(format!("Failed to write profile index: {}", e)))
+}
+
+fn new_profile_id() -> String {
+    std::iter::repeat_with(fastrand::alphanumeric)
+        .take(10)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, ProfileState>) -> Result<ProfileList, AppError> {
+    Ok(state.snapshot())
+}
+
+#[tauri::command]
+pub async fn add_profile(
+    app: AppHandle,
+    state: State<'_, ProfileState>,
+    name: String,
+) -> Result<Profile, AppError> {
+    ensure_not_demo(&app)?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Profile name cannot be empty"));
+    }
+
+    let profile = Profile {
+        id: new_profile_id(),
+        name: name.to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+
+    {
+        let mut list = state.0.write().unwrap_or_else(|e| e.into_inner());
+        list.profiles.push(profile.clone());
+        save_index(&app, &list)?;
+    }
+
+    #[cfg(desktop)]
+    crate::tray::refresh_menu(&app);
+
+    Ok(profile)
+}
+
+/// Same as [`add_profile`]
+#[tauri::command]
+pub async fn create_profile(
+    app: AppHandle,
+    state: State<'_, ProfileState>,
+    name: String,
+) -> Result<Profile, AppError> {
+    add_profile(app, state, name).await
+}
+
+/// Makes `id` the active profile and points profile-scoped state at its data.
+pub fn activate_profile(app: &AppHandle, id: &str) -> Result<Profile, AppError> {
+    ensure_not_demo(app)?;
+    // Another profile may not have the lock turned on
+    crate::app_lock::ensure_unlocked(app)?;
+
+    let profile = {
+        let state = app.state::<ProfileState>();
+        let mut list = state.0.write().unwrap_or_else(|e| e.into_inner());
+        let profile = list
+            .profiles
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found(format!("Unknown profile: {}", id)))?;
+
+        list.active = profile.id.clone();
+        save_index(app, &list)?;
+        profile
+    };
+
+    crate::settings::reload(app)?;
+    crate::cache_store::reopen(app).map_err(AppError::storage)?;
+    crate::cache_metrics::reset(app);
+
+    #[cfg(desktop)]
+    crate::shortcuts::apply(app);
+    #[cfg(desktop)]
+    crate::app_menu::refresh(app);
+    crate::badge::refresh(app);
+    crate::home_widget::refresh(app);
+
+    if let Err(e) = app.emit(PROFILE_CHANGED_EVENT, &profile) {
+        tracing::warn!("Failed to emit profile change: {}", e);
+    }
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, id: String) -> Result<Profile, AppError> {
+    let profile = activate_profile(&app, &id)?;
+
+    #[cfg(desktop)]
+    crate::tray::refresh_menu(&app);
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn remove_profile(
+    app: AppHandle,
+    state: State<'_, ProfileState>,
+    id: String,
+) -> Result<(), AppError> {
+    ensure_not_demo(&app)?;
+
+    if id == DEFAULT_PROFILE_ID {
+        return Err(AppError::invalid_state("The default profile cannot be removed"));
+    }
+
+    {
+        let mut list = state.0.write().unwrap_or_else(|e| e.into_inner());
+        if list.active == id {
+            return Err(AppError::invalid_state(
+                "Switch to another profile before removing this one",
+            ));
+        }
+
+        let before = list.profiles.len();
+        list.profiles.retain(|p| p.id != id);
+        if list.profiles.len() == before {
+            return Err(AppError::not_found(format!("Unknown profile: {}", id)));
+        }
+
+        save_index(&app, &list)?;
+    }
+
+    #[cfg(desktop)]
+    crate::tray::refresh_menu(&app);
+
+    let dir = profile_dir(&app_data_root(&app)?, &id);
+    for key in secrets::web_secret_keys_in(&dir) {
+        secrets::remove_secret(&secret_key_for(&id, &key))?;
+    }
+
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| AppError::storage(format!("Failed to remove profile data: {}", e)))?;
+    }
+
+    secrets::remove_secret(&secret_key_for(&id, REFRESH_TOKEN_KEY))?;
+    secrets::remove_secret(&secret_key_for(&id, crate::session::SESSION_BACKUP_KEY))?;
+    secrets::remove_secret(&secret_key_for(&id, SESSION_TOKEN_KEY))
+}
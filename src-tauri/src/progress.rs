@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying progress updates for long-running commands
+pub const PROGRESS_EVENT: &str = "operation-progress";
+
+/// Progress update for a single invocation of a long-running command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub stage: String,
+    pub current: u64,
+    pub total: Option<u64>,
+    pub done: bool,
+    pub message: Option<String>,
+    /// Set while guest mode is active so listeners can tell sample activity apart
+    pub demo: bool,
+}
+
+/// Emits progress for one operation, keyed by the id the frontend passed in.
+///
+/// Commands accept an optional `operation_id`; without one the reporter is a no-op,
+/// so existing callers keep working unchanged.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    app: AppHandle,
+    operation_id: Option<String>,
+}
+
+impl ProgressReporter {
+    pub fn new(app: &AppHandle, operation_id: Option<String>) -> Self {
+        Self {
+            app: app.clone(),
+            operation_id,
+        }
+    }
+
+    pub fn report(&self, stage: &str, current: u64, total: Option<u64>) {
+        self.emit(stage, current, total, false, None);
+    }
+
+    pub fn finish(&self, stage: &str, current: u64, total: Option<u64>, message: Option<String>) {
+        self.emit(stage, current, total, true, message);
+    }
+
+    fn emit(
+        &self,
+        stage: &str,
+        current: u64,
+        total: Option<u64>,
+        done: bool,
+        message: Option<String>,
+    ) {
+        let Some(operation_id) = &self.operation_id else {
+            return;
+        };
+
+        let event = ProgressEvent {
+            operation_id: operation_id.clone(),
+            stage: stage.to_string(),
+            current,
+            total,
+            done,
+            message,
+            demo: crate::demo::is_demo(&self.app),
+        };
+
+        if let Err(e) = self.app.emit(PROGRESS_EVENT, event) {
+            tracing::warn!("Failed to emit progress event: {}", e);
+        }
+    }
+}
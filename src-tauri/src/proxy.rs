@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::AppError;
+use crate::http_client::HttpClient;
+use crate::profiles::scoped_secret_key;
+use crate::secrets;
+use crate::settings::SettingsState;
+
+/// Keychain entry holding the password of the manual proxy
+const PROXY_PASSWORD_KEY: &str = "proxy_password";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Proxy from the OS settings or the `HTTPS_PROXY`/`NO_PROXY` environment variables
+    #[default]
+    System,
+    /// Direct connections, ignoring any system proxy
+    None,
+    Manual,
+}
+
+/// How the shared HTTP client reaches the network. The password lives in the keychain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    /// Manual proxy, e.g. `http://proxy.corp:8080` or `socks5://127.0.0.1:1080`
+    pub url: Option<String>,
+    pub username: Option<String>,
+    /// Hosts reached directly in manual mode: names, `.domain` suffixes, IPs or CIDR ranges
+    pub bypass: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTestResult {
+    pub ok: bool,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Applies `config` to a client builder.
+pub fn configure(
+    builder: reqwest::ClientBuilder,
+    config: &ProxyConfig,
+    password: Option<&str>,
+) -> Result<reqwest::ClientBuilder, String> {
+    match config.mode {
+        ProxyMode::System => Ok(builder),
+        ProxyMode::None => Ok(builder.no_proxy()),
+        ProxyMode::Manual => {
+            let url = config
+                .url
+                .as_deref()
+                .ok_or_else(|| "Manual proxy needs a URL".to_string())?;
+            let mut url = reqwest::Url::parse(url)
+                .map_err(|e| format!("Invalid proxy URL '{}': {}", url, e))?;
+            if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                return Err(format!("Unsupported proxy scheme '{}'", url.scheme()));
+            }
+
+            // Credentials in the URL work for both HTTP (Basic auth) and SOCKS5 proxies
+            if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+                url.set_username(username)
+                    .and_then(|_| url.set_password(password))
+                    .map_err(|_| "Proxy URL cannot carry credentials".to_string())?;
+            }
+
+            let proxy = reqwest::Proxy::all(url)
+                .map_err(|e| format!("Invalid proxy: {}", e))?
+                .no_proxy(reqwest::NoProxy::from_string(&config.bypass.join(",")));
+
+            Ok(builder.proxy(proxy))
+        }
+    }
+}
+
+fn stored_password(app: &AppHandle) -> Result<Option<String>, AppError> {
+    secrets::read_secret(&scoped_secret_key(app, PROXY_PASSWORD_KEY))
+}
+
+/// Routes the shared client through the active profile's proxy settings.
+pub fn apply(app: &AppHandle) -> Result<(), AppError> {
+    let config = crate::settings::current(app).proxy;
+    let password = stored_password(app)?;
+
+    app.state::<HttpClient>()
+        .set_proxy(&config, password.as_deref())
+        .map_err(AppError::invalid_input)
+}
+
+#[tauri::command]
+pub async fn get_proxy_config(state: State<'_, SettingsState>) -> Result<ProxyConfig, AppError> {
+    Ok(state.get().proxy)
+}
+
+/// Saves the proxy settings and applies them to new requests. `password` replaces the
+/// stored one when given; an empty string removes it.
+#[tauri::command]
+pub async fn set_proxy_config(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    config: ProxyConfig,
+    password: Option<String>,
+) -> Result<(), AppError> {
+    let password = match password {
+        Some(password) => Some(password).filter(|p| !p.is_empty()),
+        None => stored_password(&app)?,
+    };
+
+    // Fails on an unusable URL before anything is saved
+    app.state::<HttpClient>()
+        .set_proxy(&config, password.as_deref())
+        .map_err(AppError::invalid_input)?;
+
+    let key = scoped_secret_key(&app, PROXY_PASSWORD_KEY);
+    match &password {
+        Some(password) => secrets::write_secret(&key, password)?,
+        None => secrets::remove_secret(&key)?,
+    }
+
+    state.update(&app, |settings| settings.proxy = config)?;
+    Ok(())
+}
+
+/// Requests the website through `config` (the saved settings when not given) and reports
+/// whether it answered.
+#[tauri::command]
+pub async fn test_proxy(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    config: Option<ProxyConfig>,
+    password: Option<String>,
+) -> Result<ProxyTestResult, AppError> {
+    let config = config.unwrap_or_else(|| state.get().proxy);
+    let password = match password {
+        Some(password) => Some(password),
+        None => stored_password(&app)?,
+    };
+
+    let client = app
+        .state::<HttpClient>()
+        .with_proxy(&config, password.as_deref())
+        .map_err(AppError::invalid_input)?;
+
+    let started = Instant::now();
+    let response = client
+        .get(crate::website_url(&app))
+        .timeout(crate::http_client::WEBSITE_PROBE_TIMEOUT)
+        .send()
+        .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(match response {
+        Ok(response) => ProxyTestResult {
+            // Proxies report their own failures as 407 or 502
+            ok: !matches!(response.status().as_u16(), 407 | 502),
+            status: Some(response.status().as_u16()),
+            latency_ms,
+            error: None,
+        },
+        Err(e) => ProxyTestResult {
+            ok: false,
+            status: None,
+            latency_ms,
+            error: Some(format!("{}", e)),
+        },
+    })
+}
@@ -0,0 +1,724 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+
+use crate::attachments::{Attachment, AttachmentUpload};
+use crate::error::AppError;
+
+/// Recordings stop on their own after this long, which keeps them within the attachment
+/// size limit
+const MAX_RECORDING: Duration = Duration::from_secs(30 * 60);
+
+/// A finished voice memo, kept in the attachment store
+#[derive(Debug, Clone, Serialize)]
+pub struct Recording {
+    #[serde(flatten)]
+    pub attachment: Attachment,
+    /// Location of the audio file on disk
+    pub path: String,
+    pub duration_ms: u64,
+}
+
+/// The microphone capture in progress, if any
+#[derive(Default)]
+pub struct RecordingState(Mutex<Option<capture::Capture>>);
+
+/// Microphone input resampled to 48 kHz mono and encoded as Opus in an Ogg container,
+/// which desktop webviews can play back directly.
+#[cfg(desktop)]
+mod capture {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SampleFormat, SizedSample};
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+    use std::sync::mpsc;
+    use tauri::AppHandle;
+
+    pub const CONTENT_TYPE: &str = "audio/ogg; codecs=opus";
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// 20 ms, the usual Opus frame length
+    const FRAME_SAMPLES: usize = 960;
+
+    /// Plenty for speech, and about 180 KB per minute
+    const BITRATE: i32 = 24_000;
+
+    /// Largest Opus packet the encoder may produce
+    const MAX_PACKET_BYTES: usize = 4000;
+
+    const STREAM_SERIAL: u32 = 0x4c52_5643;
+
+    enum Message {
+        Samples(Vec<f32>),
+        Stop,
+    }
+
+    /// Linear resampling to 48 kHz; plain but good enough for speech.
+    struct Resampler {
+        step: f64,
+        position: f64,
+        pending: Vec<f32>,
+    }
+
+    impl Resampler {
+        fn new(input_rate: u32) -> Self {
+            Self {
+                step: input_rate as f64 / SAMPLE_RATE as f64,
+                position: 0.0,
+                pending: Vec::new(),
+            }
+        }
+
+        fn push(&mut self, input: &[f32], output: &mut Vec<f32>) {
+            self.pending.extend_from_slice(input);
+
+            while (self.position as usize) + 1 < self.pending.len() {
+                let index = self.position as usize;
+                let fraction = (self.position - index as f64) as f32;
+                output.push(
+                    self.pending[index]
+                        + (self.pending[index + 1] - self.pending[index]) * fraction,
+                );
+                self.position += self.step;
+            }
+
+            let consumed = (self.position as usize).min(self.pending.len());
+            self.pending.drain(..consumed);
+            self.position -= consumed as f64;
+        }
+    }
+
+    struct Encoder {
+        opus: *mut audiopus_sys::OpusEncoder,
+        writer: PacketWriter<Vec<u8>>,
+        resampler: Resampler,
+        /// Resampled audio not yet making up a whole frame
+        buffer: Vec<f32>,
+        /// Samples of decoder delay the player skips at the start
+        pre_skip: u64,
+        /// Samples of actual audio received, at 48 kHz
+        samples: u64,
+        max_samples: u64,
+        encoded_samples: u64,
+        /// Each packet is written once the next one exists, so the last can end the stream
+        pending: Option<Vec<u8>>,
+    }
+
+    impl Encoder {
+        fn new(input_rate: u32, max_samples: u64) -> Result<Self, String> {
+            let mut error = 0;
+            let opus = unsafe {
+                audiopus_sys::opus_encoder_create(
+                    SAMPLE_RATE as i32,
+                    1,
+                    audiopus_sys::OPUS_APPLICATION_VOIP,
+                    &mut error,
+                )
+            };
+            if opus.is_null() || error != audiopus_sys::OPUS_OK {
+                return Err(format!("Failed to create Opus encoder (error {})", error));
+            }
+
+            let mut lookahead: i32 = 0;
+            unsafe {
+                audiopus_sys::opus_encoder_ctl(
+                    opus,
+                    audiopus_sys::OPUS_SET_BITRATE_REQUEST,
+                    BITRATE,
+                );
+                audiopus_sys::opus_encoder_ctl(
+                    opus,
+                    audiopus_sys::OPUS_GET_LOOKAHEAD_REQUEST,
+                    &mut lookahead as *mut i32,
+                );
+            }
+
+            let mut encoder = Self {
+                opus,
+                writer: PacketWriter::new(Vec::new()),
+                resampler: Resampler::new(input_rate),
+                buffer: Vec::new(),
+                pre_skip: lookahead.max(0) as u64,
+                samples: 0,
+                max_samples,
+                encoded_samples: 0,
+                pending: None,
+            };
+            encoder.write_headers()?;
+            Ok(encoder)
+        }
+
+        /// The identification and comment headers, each on a page of its own (RFC 7845).
+        fn write_headers(&mut self) -> Result<(), String> {
+            let mut head = b"OpusHead".to_vec();
+            head.push(1);
+            head.push(1);
+            head.extend_from_slice(&(self.pre_skip as u16).to_le_bytes());
+            head.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+            head.extend_from_slice(&0i16.to_le_bytes());
+            head.push(0);
+
+            let vendor = b"Lotus Routine";
+            let mut tags = b"OpusTags".to_vec();
+            tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+            tags.extend_from_slice(vendor);
+            tags.extend_from_slice(&0u32.to_le_bytes());
+
+            for header in [head, tags] {
+                self.writer
+                    .write_packet(
+                        header.into_boxed_slice(),
+                        STREAM_SERIAL,
+                        PacketWriteEndInfo::EndPage,
+                        0,
+                    )
+                    .map_err(|e| format!("Failed to write recording: {}", e))?;
+            }
+            Ok(())
+        }
+
+        fn push(&mut self, input: &[f32]) -> Result<(), String> {
+            let start = self.buffer.len();
+            self.resampler.push(input, &mut self.buffer);
+
+            let room = self.max_samples.saturating_sub(self.samples) as usize;
+            self.buffer
+                .truncate(start + (self.buffer.len() - start).min(room));
+            self.samples += (self.buffer.len() - start) as u64;
+
+            while self.buffer.len() >= FRAME_SAMPLES {
+                let frame: Vec<f32> = self.buffer.drain(..FRAME_SAMPLES).collect();
+                self.encode(&frame)?;
+            }
+            Ok(())
+        }
+
+        fn encode(&mut self, frame: &[f32]) -> Result<(), String> {
+            let mut packet = vec![0u8; MAX_PACKET_BYTES];
+            let length = unsafe {
+                audiopus_sys::opus_encode_float(
+                    self.opus,
+                    frame.as_ptr(),
+                    FRAME_SAMPLES as i32,
+                    packet.as_mut_ptr(),
+                    MAX_PACKET_BYTES as i32,
+                )
+            };
+            if length < 0 {
+                return Err(format!("Failed to encode recording (error {})", length));
+            }
+            packet.truncate(length as usize);
+
+            if let Some(previous) = self.pending.replace(packet) {
+                self.encoded_samples += FRAME_SAMPLES as u64;
+                self.writer
+                    .write_packet(
+                        previous.into_boxed_slice(),
+                        STREAM_SERIAL,
+                        PacketWriteEndInfo::NormalPacket,
+                        // The silence flushed at the end does not count
+                        self.pre_skip + self.encoded_samples.min(self.samples),
+                    )
+                    .map_err(|e| format!("Failed to write recording: {}", e))?;
+            }
+            Ok(())
+        }
+
+        /// Flushes the encoder's delay with silence and ends the stream. The last page's
+        /// position tells players where the actual audio stops.
+        fn finish(mut self) -> Result<(Vec<u8>, u64), String> {
+            let padding = FRAME_SAMPLES - self.buffer.len() % FRAME_SAMPLES;
+            let mut tail = std::mem::take(&mut self.buffer);
+            tail.resize(tail.len() + padding + self.pre_skip as usize, 0.0);
+            for frame in tail.chunks(FRAME_SAMPLES) {
+                let mut frame = frame.to_vec();
+                frame.resize(FRAME_SAMPLES, 0.0);
+                self.encode(&frame)?;
+            }
+
+            let last = self.pending.take().unwrap_or_default();
+            self.writer
+                .write_packet(
+                    last.into_boxed_slice(),
+                    STREAM_SERIAL,
+                    PacketWriteEndInfo::EndStream,
+                    self.pre_skip + self.samples,
+                )
+                .map_err(|e| format!("Failed to write recording: {}", e))?;
+
+            let duration_ms = self.samples * 1000 / SAMPLE_RATE as u64;
+            let writer = std::mem::replace(&mut self.writer, PacketWriter::new(Vec::new()));
+            Ok((writer.into_inner(), duration_ms))
+        }
+    }
+
+    impl Drop for Encoder {
+        fn drop(&mut self) {
+            unsafe { audiopus_sys::opus_encoder_destroy(self.opus) };
+        }
+    }
+
+    fn build_stream<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        sender: mpsc::Sender<Message>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let channels = config.channels.max(1) as usize;
+        device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mono = data
+                    .chunks(channels)
+                    .map(|frame| {
+                        frame
+                            .iter()
+                            .map(|&sample| sample.to_sample::<f32>())
+                            .sum::<f32>()
+                            / channels as f32
+                    })
+                    .collect();
+                let _ = sender.send(Message::Samples(mono));
+            },
+            |e| tracing::warn!("Microphone stream failed: {}", e),
+            None,
+        )
+    }
+
+    fn open(sender: mpsc::Sender<Message>) -> Result<(cpal::Stream, u32), String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| "No microphone found".to_string())?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to open microphone: {}", e))?;
+        let format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        let stream = match format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, sender),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, sender),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, sender),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, sender),
+            format => return Err(format!("Unsupported microphone sample format: {}", format)),
+        }
+        .map_err(|e| format!("Failed to open microphone: {}", e))?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start microphone: {}", e))?;
+
+        Ok((stream, config.sample_rate.0))
+    }
+
+    pub struct Capture {
+        sender: mpsc::Sender<Message>,
+        worker: std::thread::JoinHandle<Result<(Vec<u8>, u64), String>>,
+    }
+
+    impl Capture {
+        /// Opens the microphone on a thread of its own, since audio streams cannot move
+        /// between threads, and encodes the audio there as it arrives.
+        pub async fn start(_app: &AppHandle) -> Result<Self, String> {
+            let (sender, messages) = mpsc::channel();
+            let (opened, result) = mpsc::channel();
+            let stream_sender = sender.clone();
+            let max_samples = super::MAX_RECORDING.as_secs() * SAMPLE_RATE as u64;
+
+            let worker = std::thread::Builder::new()
+                .name("recording".to_string())
+                .spawn(move || {
+                    let started = open(stream_sender)
+                        .and_then(|(stream, rate)| Ok((stream, Encoder::new(rate, max_samples)?)));
+                    let (stream, mut encoder) = match started {
+                        Ok(started) => {
+                            let _ = opened.send(Ok(()));
+                            started
+                        }
+                        Err(e) => {
+                            let _ = opened.send(Err(e.clone()));
+                            return Err(e);
+                        }
+                    };
+
+                    while let Ok(Message::Samples(samples)) = messages.recv() {
+                        encoder.push(&samples)?;
+                    }
+                    drop(stream);
+
+                    // Audio captured before the stream closed
+                    while let Ok(Message::Samples(samples)) = messages.try_recv() {
+                        encoder.push(&samples)?;
+                    }
+                    encoder.finish()
+                })
+                .map_err(|e| format!("Failed to start recording: {}", e))?;
+
+            result
+                .recv()
+                .map_err(|_| "Recording stopped unexpectedly".to_string())??;
+            Ok(Self { sender, worker })
+        }
+
+        /// Stops the microphone and returns the encoded audio and its length in ms.
+        pub async fn finish(self, _app: &AppHandle) -> Result<(Vec<u8>, u64), String> {
+            let _ = self.sender.send(Message::Stop);
+            tauri::async_runtime::spawn_blocking(move || self.worker.join())
+                .await
+                .map_err(|e| format!("Failed to finish recording: {}", e))?
+                .map_err(|_| "Recording stopped unexpectedly".to_string())?
+        }
+    }
+}
+
+/// AVAudioRecorder writing AAC in an MPEG-4 container.
+#[cfg(target_os = "ios")]
+mod capture {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::AllocAnyThread;
+    use objc2_avf_audio::{
+        AVAudioRecorder, AVAudioSession, AVAudioSessionCategoryPlayAndRecord, AVEncoderBitRateKey,
+        AVFormatIDKey, AVNumberOfChannelsKey, AVSampleRateKey,
+    };
+    use objc2_foundation::{NSDictionary, NSNumber, NSString, NSURL};
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use tauri::{AppHandle, Manager};
+
+    pub const CONTENT_TYPE: &str = "audio/mp4";
+
+    /// `kAudioFormatMPEG4AAC`
+    const FORMAT_AAC: u32 = u32::from_be_bytes(*b"aac ");
+
+    thread_local! {
+        /// The recorder lives on the main thread, where it was created
+        static RECORDER: RefCell<Option<Retained<AVAudioRecorder>>> = const { RefCell::new(None) };
+    }
+
+    fn begin(path: &str) -> Result<(), String> {
+        let failed = |e: Retained<objc2_foundation::NSError>| {
+            format!("Failed to start recording: {}", e.localizedDescription())
+        };
+
+        unsafe {
+            let session = AVAudioSession::sharedInstance();
+            let category = AVAudioSessionCategoryPlayAndRecord
+                .ok_or_else(|| "Recording is not available".to_string())?;
+            session.setCategory_error(category).map_err(failed)?;
+            session.setActive_error(true).map_err(failed)?;
+
+            let (Some(format), Some(rate), Some(channels), Some(bitrate)) = (
+                AVFormatIDKey,
+                AVSampleRateKey,
+                AVNumberOfChannelsKey,
+                AVEncoderBitRateKey,
+            ) else {
+                return Err("Recording is not available".to_string());
+            };
+            let values = [
+                NSNumber::new_u32(FORMAT_AAC),
+                NSNumber::new_f64(44_100.0),
+                NSNumber::new_u32(1),
+                NSNumber::new_u32(64_000),
+            ];
+            let objects: Vec<&AnyObject> = values.iter().map(|value| value.as_ref()).collect();
+            let settings: Retained<NSDictionary<NSString, AnyObject>> =
+                NSDictionary::from_slices(&[format, rate, channels, bitrate], &objects);
+
+            let url = NSURL::fileURLWithPath(&NSString::from_str(path));
+            let recorder = AVAudioRecorder::initWithURL_settings_error(
+                AVAudioRecorder::alloc(),
+                &url,
+                &settings,
+            )
+            .map_err(failed)?;
+            if !recorder.recordForDuration(super::MAX_RECORDING.as_secs_f64()) {
+                return Err(
+                    "Could not start recording; allow microphone access in Settings".to_string(),
+                );
+            }
+
+            RECORDER.with(|current| *current.borrow_mut() = Some(recorder));
+        }
+        Ok(())
+    }
+
+    /// Stops the recorder and returns how long it ran, in ms.
+    fn end() -> u64 {
+        let Some(recorder) = RECORDER.with(|current| current.borrow_mut().take()) else {
+            return 0;
+        };
+
+        unsafe {
+            let duration = recorder.currentTime();
+            recorder.stop();
+            let _ = AVAudioSession::sharedInstance().setActive_error(false);
+            (duration * 1000.0) as u64
+        }
+    }
+
+    pub struct Capture {
+        path: PathBuf,
+    }
+
+    impl Capture {
+        pub async fn start(app: &AppHandle) -> Result<Self, String> {
+            let dir = app
+                .path()
+                .app_cache_dir()
+                .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+            let path = dir.join("voice-memo.m4a");
+
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            let target = path.to_string_lossy().into_owned();
+            app.run_on_main_thread(move || {
+                let _ = sender.send(begin(&target));
+            })
+            .map_err(|e| format!("Failed to start recording: {}", e))?;
+            receiver
+                .await
+                .map_err(|_| "Recording stopped unexpectedly".to_string())??;
+
+            Ok(Self { path })
+        }
+
+        pub async fn finish(self, app: &AppHandle) -> Result<(Vec<u8>, u64), String> {
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            app.run_on_main_thread(move || {
+                let _ = sender.send(end());
+            })
+            .map_err(|e| format!("Failed to finish recording: {}", e))?;
+            let duration_ms = receiver
+                .await
+                .map_err(|_| "Recording stopped unexpectedly".to_string())?;
+
+            let data = std::fs::read(&self.path)
+                .map_err(|e| format!("Failed to read recording: {}", e))?;
+            let _ = std::fs::remove_file(&self.path);
+            Ok((data, duration_ms))
+        }
+    }
+}
+
+/// MediaRecorder writing AAC in an MPEG-4 container, driven through JNI.
+#[cfg(target_os = "android")]
+mod capture {
+    use jni::objects::{GlobalRef, JObject, JValue};
+    use jni::JNIEnv;
+    use std::path::PathBuf;
+    use std::time::Instant;
+    use tauri::{AppHandle, Manager};
+
+    pub const CONTENT_TYPE: &str = "audio/mp4";
+
+    const RECORD_AUDIO: &str = "android.permission.RECORD_AUDIO";
+    const PERMISSION_GRANTED: i32 = 0;
+    const PERMISSION_REQUEST_CODE: i32 = 4207;
+
+    // MediaRecorder.AudioSource.MIC, OutputFormat.MPEG_4 and AudioEncoder.AAC
+    const SOURCE_MIC: i32 = 1;
+    const FORMAT_MPEG_4: i32 = 2;
+    const ENCODER_AAC: i32 = 3;
+
+    fn with_env<T>(
+        work: impl FnOnce(&mut JNIEnv, &JObject) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let context = ndk_context::android_context();
+        let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+            .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+        let activity = unsafe { JObject::from_raw(context.context().cast()) };
+        let mut env = vm
+            .attach_current_thread()
+            .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+        let result = work(&mut env, &activity);
+        if env.exception_check().unwrap_or(false) {
+            let _ = env.exception_clear();
+        }
+        result
+    }
+
+    /// Asks for the microphone permission when it has not been granted yet. The answer
+    /// arrives after this returns, so the user starts the recording again afterwards.
+    fn ensure_permission(env: &mut JNIEnv, activity: &JObject) -> jni::errors::Result<bool> {
+        let permission = env.new_string(RECORD_AUDIO)?;
+        let granted = env
+            .call_method(
+                activity,
+                "checkSelfPermission",
+                "(Ljava/lang/String;)I",
+                &[JValue::from(&permission)],
+            )?
+            .i()?;
+        if granted == PERMISSION_GRANTED {
+            return Ok(true);
+        }
+
+        let permissions = env.new_object_array(1, "java/lang/String", &permission)?;
+        env.call_method(
+            activity,
+            "requestPermissions",
+            "([Ljava/lang/String;I)V",
+            &[
+                JValue::from(&permissions),
+                JValue::Int(PERMISSION_REQUEST_CODE),
+            ],
+        )?;
+        Ok(false)
+    }
+
+    fn begin(env: &mut JNIEnv, path: &str) -> jni::errors::Result<GlobalRef> {
+        let recorder = env.new_object("android/media/MediaRecorder", "()V", &[])?;
+        let path = env.new_string(path)?;
+
+        for (method, value) in [
+            ("setAudioSource", SOURCE_MIC),
+            ("setOutputFormat", FORMAT_MPEG_4),
+            ("setAudioEncoder", ENCODER_AAC),
+            ("setAudioChannels", 1),
+            ("setAudioSamplingRate", 44_100),
+            ("setAudioEncodingBitRate", 64_000),
+            ("setMaxDuration", super::MAX_RECORDING.as_millis() as i32),
+        ] {
+            env.call_method(&recorder, method, "(I)V", &[JValue::Int(value)])?;
+        }
+        env.call_method(
+            &recorder,
+            "setOutputFile",
+            "(Ljava/lang/String;)V",
+            &[JValue::from(&path)],
+        )?;
+        env.call_method(&recorder, "prepare", "()V", &[])?;
+        env.call_method(&recorder, "start", "()V", &[])?;
+
+        env.new_global_ref(recorder)
+    }
+
+    pub struct Capture {
+        recorder: GlobalRef,
+        path: PathBuf,
+        started: Instant,
+    }
+
+    impl Capture {
+        pub async fn start(app: &AppHandle) -> Result<Self, String> {
+            let dir = app
+                .path()
+                .app_cache_dir()
+                .map_err(|e| format!("Failed to resolve cache directory: {}", e))?;
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+            let path = dir.join("voice-memo.m4a");
+            let target = path.to_string_lossy().into_owned();
+
+            let recorder = tauri::async_runtime::spawn_blocking(move || {
+                with_env(|env, activity| {
+                    let granted = ensure_permission(env, activity)
+                        .map_err(|e| format!("Failed to check microphone access: {}", e))?;
+                    if !granted {
+                        return Err(
+                            "Allow microphone access, then start the recording again".to_string()
+                        );
+                    }
+
+                    begin(env, &target).map_err(|e| format!("Failed to start recording: {}", e))
+                })
+            })
+            .await
+            .map_err(|e| format!("Failed to start recording: {}", e))??;
+
+            Ok(Self {
+                recorder,
+                path,
+                started: Instant::now(),
+            })
+        }
+
+        pub async fn finish(self, _app: &AppHandle) -> Result<(Vec<u8>, u64), String> {
+            let duration_ms = self.started.elapsed().as_millis() as u64;
+            let recorder = self.recorder;
+
+            tauri::async_runtime::spawn_blocking(move || {
+                with_env(|env, _| {
+                    // stop() throws when nothing was captured yet
+                    let stopped = env.call_method(&recorder, "stop", "()V", &[]);
+                    if stopped.is_err() {
+                        let _ = env.exception_clear();
+                    }
+                    let _ = env.call_method(&recorder, "release", "()V", &[]);
+                    stopped
+                        .map(drop)
+                        .map_err(|_| "The recording is too short".to_string())
+                })
+            })
+            .await
+            .map_err(|e| format!("Failed to finish recording: {}", e))??;
+
+            let data = std::fs::read(&self.path)
+                .map_err(|e| format!("Failed to read recording: {}", e))?;
+            let _ = std::fs::remove_file(&self.path);
+            Ok((
+                data,
+                duration_ms.min(super::MAX_RECORDING.as_millis() as u64),
+            ))
+        }
+    }
+}
+
+/// Starts capturing the microphone for a voice memo. Only one recording runs at a time.
+#[tauri::command]
+pub async fn start_recording(
+    app: AppHandle,
+    state: State<'_, RecordingState>,
+) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let mut active = state.0.lock().await;
+    if active.is_some() {
+        return Err(AppError::invalid_state(
+            "A recording is already in progress",
+        ));
+    }
+
+    *active = Some(
+        capture::Capture::start(&app)
+            .await
+            .map_err(AppError::internal)?,
+    );
+    Ok(())
+}
+
+/// Stops the recording and stores it as an attachment, which works offline. With
+/// `upload`, the memo is also queued for upload like any other attachment.
+#[tauri::command]
+pub async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, RecordingState>,
+    upload: Option<AttachmentUpload>,
+) -> Result<Recording, AppError> {
+    let capture = state
+        .0
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| AppError::invalid_state("No recording in progress"))?;
+
+    let (data, duration_ms) = capture.finish(&app).await.map_err(AppError::internal)?;
+    let attachment = crate::attachments::save(&app, &data, capture::CONTENT_TYPE)?;
+    if let Some(upload) = upload {
+        crate::attachments::queue_upload(&app, &attachment.hash, &upload)?;
+    }
+
+    let path = crate::attachments::attachment_path(&app, &attachment.hash)?;
+    Ok(Recording {
+        attachment,
+        path: path.to_string_lossy().into_owned(),
+        duration_ms,
+    })
+}
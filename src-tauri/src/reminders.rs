@@ -0,0 +1,271 @@
+use chrono::{Datelike, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::dnd::{DndBehavior, DndState};
+use crate::error::AppError;
+use crate::i18n::{text, text_with};
+
+const REMINDERS_FILE: &str = "reminders";
+
+/// How often the background runner looks for due reminders
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Slots older than this are skipped instead of firing late, e.g. after the app was closed
+const MAX_REMINDER_DELAY_SECS: i64 = 15 * 60;
+
+/// Reminders deferred by Do Not Disturb may fire this long after their slot
+const MAX_DND_DELAY_SECS: i64 = 3 * 60 * 60;
+
+/// A daily or weekly reminder for a habit, shown as an OS notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: String,
+    pub habit_name: String,
+    pub body: Option<String>,
+    /// Local time of day in `HH:MM` format
+    pub time: String,
+    /// Weekdays to fire on, 0 = Monday; fires every day when empty
+    #[serde(default)]
+    pub days: Vec<u8>,
+    /// Sound played when it fires; the reminder sound from the settings when `None`
+    #[serde(default)]
+    pub sound: Option<String>,
+    /// Reads the reminder aloud instead of playing a sound
+    #[serde(default)]
+    pub announce: bool,
+    /// Fires even while Do Not Disturb is on
+    #[serde(default)]
+    pub break_through_dnd: bool,
+    pub last_fired: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReminderSchedule {
+    reminders: Vec<Reminder>,
+}
+
+pub fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| format!("Invalid reminder time '{}': {}", time, e))
+}
+
+fn new_reminder_id() -> String {
+    std::iter::repeat_with(fastrand::alphanumeric)
+        .take(10)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Today's slot if it passed at most `max_delay_secs` ago and has not fired yet.
+fn is_due(reminder: &Reminder, now: chrono::DateTime<Local>, max_delay_secs: i64) -> bool {
+    let weekday = now.weekday().num_days_from_monday() as u8;
+    if !reminder.days.is_empty() && !reminder.days.contains(&weekday) {
+        return false;
+    }
+
+    let Ok(time) = parse_time(&reminder.time) else {
+        return false;
+    };
+    let Some(slot) = Local
+        .from_local_datetime(&now.date_naive().and_time(time))
+        .earliest()
+    else {
+        return false;
+    };
+
+    slot <= now
+        && now.timestamp() - slot.timestamp() <= max_delay_secs
+        && reminder.last_fired.is_none_or(|last_fired| last_fired < slot.timestamp())
+}
+
+fn notify(app: &AppHandle, reminder: &Reminder) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(&reminder.habit_name)
+        .body(
+            reminder
+                .body
+                .clone()
+                .unwrap_or_else(|| text(app, "notification.reminder")),
+        )
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+/// Fires the reminders that are due. While Do Not Disturb is on, reminders that do not
+/// break through it are deferred, keeping their ids in `deferred`, or skipped.
+async fn fire_due_reminders(
+    app: &AppHandle,
+    dnd: DndState,
+    deferred: &mut HashSet<String>,
+) -> Result<(), String> {
+    if crate::demo::is_demo(app) {
+        return Ok(());
+    }
+
+    let mut schedule: ReminderSchedule =
+        crate::read_state_file_blocking(app, REMINDERS_FILE).await?;
+    let now = Local::now();
+    let behavior = crate::settings::current(app).dnd_behavior;
+    let holding = dnd == DndState::On && behavior != DndBehavior::Ignore;
+    let mut changed = false;
+    let mut sound = None;
+
+    for reminder in schedule.reminders.iter_mut() {
+        let max_delay_secs = if deferred.contains(&reminder.id) {
+            MAX_DND_DELAY_SECS
+        } else {
+            MAX_REMINDER_DELAY_SECS
+        };
+        if !is_due(reminder, now, max_delay_secs) {
+            continue;
+        }
+
+        if holding && !reminder.break_through_dnd {
+            if behavior == DndBehavior::Defer {
+                deferred.insert(reminder.id.clone());
+            } else {
+                reminder.last_fired = Some(now.timestamp());
+                changed = true;
+            }
+            continue;
+        }
+
+        if let Err(e) = notify(app, reminder) {
+            tracing::warn!("Reminder '{}' failed: {}", reminder.habit_name, e);
+        }
+        reminder.last_fired = Some(now.timestamp());
+        deferred.remove(&reminder.id);
+        changed = true;
+
+        if reminder.announce {
+            let announcement = reminder.body.clone().unwrap_or_else(|| {
+                text_with(app, "speech.reminder", &[("habit", &reminder.habit_name)])
+            });
+            crate::speech::announce(app, announcement);
+            continue;
+        }
+
+        // Reminders due together play a single sound rather than a pile-up
+        if sound.is_none() {
+            sound = Some(
+                reminder
+                    .sound
+                    .clone()
+                    .unwrap_or_else(|| crate::settings::current(app).sound.reminder_sound),
+            );
+        }
+    }
+
+    if let Some(sound) = sound {
+        crate::audio::play_alert(app, &sound);
+    }
+
+    if !changed {
+        return Ok(());
+    }
+    crate::write_state_file_blocking(app, REMINDERS_FILE, &schedule)
+        .await
+        .map_err(String::from)
+}
+
+/// Starts the background runner that shows due reminders.
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+        let mut deferred = HashSet::new();
+        let mut today = Local::now().date_naive();
+
+        loop {
+            interval.tick().await;
+
+            // Deferrals only ever cover the day's slots
+            if Local::now().date_naive() != today {
+                today = Local::now().date_naive();
+                deferred.clear();
+            }
+
+            // Due reminders keep until the meeting ends, within the usual delay limit
+            let result = if crate::calendar::is_busy_now(&app).await {
+                Ok(())
+            } else {
+                let dnd = crate::dnd::current_state().await;
+                fire_due_reminders(&app, dnd, &mut deferred).await
+            };
+            if let Err(e) = &result {
+                tracing::warn!("Reminder scheduler run failed: {}", e);
+            }
+            crate::health::record_run(&app, "reminders", REMINDER_CHECK_INTERVAL, &result);
+        }
+    });
+}
+
+/// Creates a reminder, or replaces the one with the same `id`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn schedule_reminder(
+    app: AppHandle,
+    id: Option<String>,
+    habit_name: String,
+    body: Option<String>,
+    time: String,
+    days: Option<Vec<u8>>,
+    sound: Option<String>,
+    announce: Option<bool>,
+    break_through_dnd: Option<bool>,
+) -> Result<Reminder, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    parse_time(&time).map_err(AppError::invalid_input)?;
+    if let Some(sound) = &sound {
+        crate::audio::check_sound(&app, sound)?;
+    }
+
+    let days = days.unwrap_or_default();
+    if let Some(day) = days.iter().find(|&&day| day > 6) {
+        return Err(AppError::invalid_input(format!("Invalid reminder weekday: {}", day)));
+    }
+
+    let reminder = Reminder {
+        id: id.unwrap_or_else(new_reminder_id),
+        habit_name,
+        body,
+        time,
+        days,
+        sound,
+        announce: announce.unwrap_or(false),
+        break_through_dnd: break_through_dnd.unwrap_or(false),
+        last_fired: None,
+    };
+
+    let mut schedule: ReminderSchedule =
+        crate::read_state_file_blocking(&app, REMINDERS_FILE).await?;
+    schedule.reminders.retain(|r| r.id != reminder.id);
+    schedule.reminders.push(reminder.clone());
+    crate::write_state_file_blocking(&app, REMINDERS_FILE, &schedule).await?;
+
+    Ok(reminder)
+}
+
+#[tauri::command]
+pub async fn cancel_reminder(app: AppHandle, id: String) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let mut schedule: ReminderSchedule =
+        crate::read_state_file_blocking(&app, REMINDERS_FILE).await?;
+    schedule.reminders.retain(|r| r.id != id);
+    crate::write_state_file_blocking(&app, REMINDERS_FILE, &schedule).await
+}
+
+pub async fn reminders(app: &AppHandle) -> Result<Vec<Reminder>, AppError> {
+    let schedule: ReminderSchedule = crate::read_state_file_blocking(app, REMINDERS_FILE).await?;
+    Ok(schedule.reminders)
+}
+
+#[tauri::command]
+pub async fn list_reminders(app: AppHandle) -> Result<Vec<Reminder>, AppError> {
+    reminders(&app).await
+}
@@ -0,0 +1,396 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::error::AppError;
+use crate::habits::Habit;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+const REGULAR_FONT: Name = Name(b"F1");
+const BOLD_FONT: Name = Name(b"F2");
+
+const HEATMAP_CELL: f32 = 22.0;
+
+/// Column offsets of the per-habit table
+const TABLE_COLUMNS: [(f32, &str); 5] = [
+    (0.0, "Habit"),
+    (230.0, "Done"),
+    (290.0, "Rate"),
+    (350.0, "Current streak"),
+    (430.0, "Longest streak"),
+];
+
+/// Habit names longer than this are cut to keep the table columns apart
+const MAX_NAME_CHARS: usize = 40;
+
+/// Period covered by a report
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportRange {
+    /// Monday to Sunday of the week containing the end date
+    Week,
+    /// The calendar month containing the end date
+    Month,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportOptions {
+    /// Destination file; a save dialog is shown when unset
+    pub path: Option<String>,
+    /// Day the report is for in `YYYY-MM-DD` format; today when unset. Later days of
+    /// the period are left out.
+    pub end_date: Option<String>,
+    /// Missed days tolerated inside a streak, as in `compute_streaks`
+    pub grace_days: u32,
+    pub include_archived: bool,
+}
+
+/// Inclusive first and last day reported on
+fn period(range: ReportRange, end: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = match range {
+        ReportRange::Week => end - Duration::days(i64::from(end.weekday().num_days_from_monday())),
+        ReportRange::Month => end.with_day(1).unwrap_or(end),
+    };
+    (start, end)
+}
+
+fn completed_days(habit: &Habit) -> BTreeSet<NaiveDate> {
+    habit
+        .completions
+        .iter()
+        .filter_map(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+        .collect()
+}
+
+/// Maps text onto the WinAnsi encoding of the standard PDF fonts; characters outside
+/// Latin-1 become `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match u32::from(c) {
+            code @ (0x20..=0x7e | 0xa0..=0xff) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Content streams of the pages written so far, with a cursor moving down the page
+struct Layout {
+    pages: Vec<Content>,
+    current: Content,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: Content::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn fits(&self, height: f32) -> bool {
+        self.y - height >= MARGIN
+    }
+
+    fn new_page(&mut self) {
+        self.pages
+            .push(std::mem::replace(&mut self.current, Content::new()));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Moves the cursor down by `height`, starting a new page if it would not fit.
+    fn advance(&mut self, height: f32) {
+        if !self.fits(height) {
+            self.new_page();
+        }
+        self.y -= height;
+    }
+
+    fn text_at(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { BOLD_FONT } else { REGULAR_FONT };
+        self.current
+            .set_fill_rgb(0.1, 0.1, 0.1)
+            .begin_text()
+            .set_font(font, size)
+            .next_line(x, y)
+            .show(Str(&encode(text)))
+            .end_text();
+    }
+
+    fn line(&mut self, size: f32, bold: bool, text: &str) {
+        self.advance(size * 1.6);
+        self.text_at(MARGIN, self.y, size, bold, text);
+    }
+
+    fn finish(mut self) -> Vec<Content> {
+        self.pages.push(self.current);
+        self.pages
+    }
+}
+
+fn percent(part: u32, whole: u32) -> String {
+    if whole == 0 {
+        return "-".to_string();
+    }
+    format!("{:.0}%", f64::from(part) * 100.0 / f64::from(whole))
+}
+
+/// Calendar grid of the period, each day shaded by the share of habits completed on it.
+fn draw_heatmap(
+    layout: &mut Layout,
+    habits: &[(Habit, BTreeSet<NaiveDate>)],
+    start: NaiveDate,
+    end: NaiveDate,
+) {
+    let first_monday = start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+    let weeks = ((end - first_monday).num_days() / 7 + 1) as f32;
+
+    layout.line(13.0, true, "Completion heatmap");
+    layout.advance(6.0 + 14.0 + weeks * HEATMAP_CELL);
+
+    let top = layout.y + weeks * HEATMAP_CELL;
+    for (column, label) in ["M", "T", "W", "T", "F", "S", "S"].iter().enumerate() {
+        layout.text_at(
+            MARGIN + column as f32 * HEATMAP_CELL + 8.0,
+            top + 4.0,
+            8.0,
+            false,
+            label,
+        );
+    }
+
+    let mut day = start;
+    while day <= end {
+        let offset = (day - first_monday).num_days();
+        let x = MARGIN + (offset % 7) as f32 * HEATMAP_CELL;
+        let y = top - (offset / 7 + 1) as f32 * HEATMAP_CELL;
+
+        let done = habits
+            .iter()
+            .filter(|(_, days)| days.contains(&day))
+            .count();
+        let share = if habits.is_empty() {
+            0.0
+        } else {
+            done as f32 / habits.len() as f32
+        };
+
+        // From light grey to the app's purple
+        layout
+            .current
+            .set_fill_rgb(
+                0.92 - 0.42 * share,
+                0.92 - 0.72 * share,
+                0.92 - 0.12 * share,
+            )
+            .rect(x + 1.0, y + 1.0, HEATMAP_CELL - 2.0, HEATMAP_CELL - 2.0)
+            .fill_nonzero();
+        layout.text_at(x + 4.0, y + 4.0, 7.0, false, &day.day().to_string());
+
+        day += Duration::days(1);
+    }
+}
+
+fn draw_habit_table(
+    layout: &mut Layout,
+    habits: &[(Habit, BTreeSet<NaiveDate>)],
+    start: NaiveDate,
+    end: NaiveDate,
+    grace_days: u32,
+) {
+    let days_in_period = ((end - start).num_days() + 1) as u32;
+
+    layout.line(13.0, true, "Habits");
+    layout.advance(4.0);
+
+    let header = |layout: &mut Layout| {
+        layout.advance(16.0);
+        for (x, title) in TABLE_COLUMNS {
+            layout.text_at(MARGIN + x, layout.y, 10.0, true, title);
+        }
+    };
+    header(layout);
+
+    for (habit, days) in habits {
+        // Repeat the header on every page the table spills onto
+        if !layout.fits(16.0) {
+            layout.new_page();
+            header(layout);
+        }
+        layout.advance(16.0);
+
+        let done = days.range(start..=end).count() as u32;
+        let streaks = crate::streaks::summarize(days, end, grace_days);
+        let name: String = habit.name.chars().take(MAX_NAME_CHARS).collect();
+
+        let cells = [
+            name,
+            done.to_string(),
+            percent(done, days_in_period),
+            streaks.current_streak.to_string(),
+            streaks.longest_streak.to_string(),
+        ];
+        for ((x, _), cell) in TABLE_COLUMNS.iter().zip(cells) {
+            layout.text_at(MARGIN + x, layout.y, 10.0, false, &cell);
+        }
+    }
+}
+
+fn render(habits: &[Habit], range: ReportRange, end: NaiveDate, grace_days: u32) -> Vec<u8> {
+    let (start, end) = period(range, end);
+    let habits: Vec<_> = habits
+        .iter()
+        .map(|habit| (habit.clone(), completed_days(habit)))
+        .collect();
+
+    let days_in_period = ((end - start).num_days() + 1) as u32;
+    let done: u32 = habits
+        .iter()
+        .map(|(_, days)| days.range(start..=end).count() as u32)
+        .sum();
+
+    let mut layout = Layout::new();
+    layout.line(20.0, true, "Lotus Routine progress report");
+    layout.line(
+        11.0,
+        false,
+        &format!(
+            "{} to {}",
+            start.format("%B %-d, %Y"),
+            end.format("%B %-d, %Y")
+        ),
+    );
+    layout.advance(10.0);
+    layout.line(11.0, false, &format!("Habits: {}", habits.len()));
+    layout.line(
+        11.0,
+        false,
+        &format!(
+            "Check-ins: {} of {} possible ({})",
+            done,
+            days_in_period * habits.len() as u32,
+            percent(done, days_in_period * habits.len() as u32)
+        ),
+    );
+    layout.advance(16.0);
+
+    draw_heatmap(&mut layout, &habits, start, end);
+    layout.advance(24.0);
+    draw_habit_table(&mut layout, &habits, start, end, grace_days);
+
+    write_pdf(layout.finish())
+}
+
+fn write_pdf(pages: Vec<Content>) -> Vec<u8> {
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let page_ids: Vec<Ref> = (0..pages.len() as i32)
+        .map(|i| Ref::new(5 + i * 2))
+        .collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    for (page_id, content) in page_ids.iter().zip(pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources()
+            .fonts()
+            .pair(REGULAR_FONT, regular_id)
+            .pair(BOLD_FONT, bold_id);
+        page.finish();
+
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.type1_font(regular_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    pdf.finish()
+}
+
+async fn choose_path(app: &AppHandle, file_name: &str) -> Result<Option<PathBuf>, AppError> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("PDF", &["pdf"])
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+
+    match receiver.await.ok().flatten() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| AppError::invalid_input(format!("Invalid report path: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// Renders a progress report for the local habits to a PDF file. Returns where it was
+/// saved, or `None` if the save dialog was cancelled.
+#[tauri::command]
+pub async fn generate_report(
+    app: AppHandle,
+    range: ReportRange,
+    options: Option<ReportOptions>,
+) -> Result<Option<String>, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let options = options.unwrap_or_default();
+    let end = match &options.end_date {
+        Some(date) => NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|e| {
+            AppError::invalid_input(format!("Invalid report date '{}': {}", date, e))
+        })?,
+        None => Local::now().date_naive(),
+    };
+
+    let path = match &options.path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let file_name = format!("lotus-routine-report-{}.pdf", end.format(DATE_FORMAT));
+            match choose_path(&app, &file_name).await? {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let habits = crate::habits::all_habits(&app, options.include_archived).await?;
+    let pdf = render(&habits, range, end, options.grace_days);
+
+    let written = path.to_string_lossy().into_owned();
+    crate::storage::run_blocking(move || {
+        std::fs::write(&path, pdf)
+            .map_err(|e| AppError::storage(format!("Failed to write report: {}", e)))
+    })
+    .await?;
+
+    Ok(Some(written))
+}
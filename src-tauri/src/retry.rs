@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::AppError;
+
+/// Longest delay between two attempts, whatever the backoff computes
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Exponential backoff for idempotent fetches. Only timeouts, connection failures, and
+/// 5xx responses are retried; other errors are returned immediately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryOptions {
+    /// Total attempts including the first one; `1` disables retrying
+    pub attempts: u32,
+    /// Delay before the first retry, doubled for each one after it
+    pub base_delay_ms: u64,
+    /// Fraction of each delay (`0.0..=1.0`) randomly shaved off so clients spread out
+    pub jitter: f64,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay_ms: 500,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryOptions {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.attempts == 0 || self.attempts > MAX_ATTEMPTS {
+            return Err(AppError::invalid_input(format!(
+                "Retry attempts must be between 1 and {}",
+                MAX_ATTEMPTS
+            )));
+        }
+
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(AppError::invalid_input(
+                "Retry jitter must be between 0.0 and 1.0",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Whether another attempt is allowed after `failures` failed ones.
+    pub fn allows_retry(&self, failures: u32) -> bool {
+        failures < self.attempts
+    }
+
+    /// Delay before retry number `retry` (starting at 1).
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(1u64 << retry.saturating_sub(1).min(16))
+            .min(MAX_BACKOFF_MS);
+        let jitter = (backoff as f64 * self.jitter * fastrand::f64()) as u64;
+
+        Duration::from_millis(backoff - jitter)
+    }
+}
@@ -0,0 +1,358 @@
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Opens Google's code scanner, which draws its own camera UI and needs no camera
+/// permission, and waits for it on the calling thread.
+#[cfg(target_os = "android")]
+fn scan_blocking() -> Result<Option<String>, String> {
+    use jni::objects::{JClass, JObject, JString, JValue};
+    use jni::JNIEnv;
+
+    // Play services classes are only visible through the app's class loader
+    fn load_class<'local>(
+        env: &mut JNIEnv<'local>,
+        activity: &JObject,
+        name: &str,
+    ) -> jni::errors::Result<JClass<'local>> {
+        let loader = env
+            .call_method(activity, "getClassLoader", "()Ljava/lang/ClassLoader;", &[])?
+            .l()?;
+        let name = env.new_string(name)?;
+        Ok(env
+            .call_method(
+                &loader,
+                "loadClass",
+                "(Ljava/lang/String;)Ljava/lang/Class;",
+                &[JValue::from(&name)],
+            )?
+            .l()?
+            .into())
+    }
+
+    /// `None` when the code has no text payload, e.g. a binary QR code
+    fn start_scan(env: &mut JNIEnv, activity: &JObject) -> jni::errors::Result<Option<String>> {
+        let scanning = load_class(
+            env,
+            activity,
+            "com.google.mlkit.vision.codescanner.GmsBarcodeScanning",
+        )?;
+        let scanner = env
+            .call_static_method(
+                &scanning,
+                "getClient",
+                "(Landroid/content/Context;)Lcom/google/mlkit/vision/codescanner/GmsBarcodeScanner;",
+                &[JValue::from(activity)],
+            )?
+            .l()?;
+        let task = env
+            .call_method(
+                &scanner,
+                "startScan",
+                "()Lcom/google/android/gms/tasks/Task;",
+                &[],
+            )?
+            .l()?;
+
+        let tasks = load_class(env, activity, "com.google.android.gms.tasks.Tasks")?;
+        let barcode = env
+            .call_static_method(
+                &tasks,
+                "await",
+                "(Lcom/google/android/gms/tasks/Task;)Ljava/lang/Object;",
+                &[JValue::from(&task)],
+            )?
+            .l()?;
+
+        let value: JString = env
+            .call_method(&barcode, "getRawValue", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        if value.is_null() {
+            return Ok(None);
+        }
+        let value: String = env.get_string(&value)?.into();
+        Ok(Some(value))
+    }
+
+    /// Clears the pending Java exception and tells whether it was the user backing out
+    fn take_cancellation(env: &mut JNIEnv) -> jni::errors::Result<bool> {
+        let exception = env.exception_occurred()?;
+        env.exception_clear()?;
+        env.is_instance_of(&exception, "java/util/concurrent/CancellationException")
+    }
+
+    let context = ndk_context::android_context();
+    let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+        .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+    let mut env = vm
+        .attach_current_thread()
+        .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+    match start_scan(&mut env, &activity) {
+        Ok(Some(value)) => Ok(Some(value)),
+        Ok(None) => Err("The scanned code does not contain text".to_string()),
+        Err(jni::errors::Error::JavaException) => match take_cancellation(&mut env) {
+            Ok(true) => Ok(None),
+            _ => Err("Failed to scan barcode: the scanner reported an error".to_string()),
+        },
+        Err(e) => {
+            let _ = env.exception_clear();
+            Err(format!("Failed to scan barcode: {}", e))
+        }
+    }
+}
+
+#[cfg(target_os = "ios")]
+mod capture {
+    use std::cell::RefCell;
+
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, NSObject, NSObjectProtocol, ProtocolObject};
+    use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadMarker, MainThreadOnly};
+    use objc2_av_foundation::{
+        AVAuthorizationStatus, AVCaptureConnection, AVCaptureDevice, AVCaptureDeviceInput,
+        AVCaptureMetadataOutput, AVCaptureMetadataOutputObjectsDelegate, AVCaptureSession,
+        AVCaptureVideoPreviewLayer, AVLayerVideoGravityResizeAspectFill, AVMediaTypeVideo,
+        AVMetadataMachineReadableCodeObject, AVMetadataObject, AVMetadataObjectTypeCode128Code,
+        AVMetadataObjectTypeEAN13Code, AVMetadataObjectTypeQRCode,
+    };
+    use objc2_core_foundation::{CGPoint, CGRect, CGSize};
+    use objc2_foundation::{ns_string, NSArray};
+    use objc2_ui_kit::{
+        UIButton, UIButtonType, UIControlEvents, UIControlState, UIModalPresentationStyle,
+        UIViewController,
+    };
+    use tokio::sync::oneshot;
+
+    pub type Sender = oneshot::Sender<Result<Option<String>, String>>;
+
+    pub struct ScanIvars {
+        session: Retained<AVCaptureSession>,
+        controller: RefCell<Option<Retained<UIViewController>>>,
+        sender: RefCell<Option<Sender>>,
+    }
+
+    define_class!(
+        // SAFETY: NSObject has no subclassing requirements and ScanDelegate does not
+        // implement Drop.
+        #[unsafe(super(NSObject))]
+        #[thread_kind = MainThreadOnly]
+        #[name = "LotusRoutineScanDelegate"]
+        #[ivars = ScanIvars]
+        pub struct ScanDelegate;
+
+        impl ScanDelegate {
+            #[unsafe(method(cancel))]
+            fn cancel(&self) {
+                self.finish(Ok(None));
+            }
+        }
+
+        unsafe impl NSObjectProtocol for ScanDelegate {}
+
+        unsafe impl AVCaptureMetadataOutputObjectsDelegate for ScanDelegate {
+            #[unsafe(method(captureOutput:didOutputMetadataObjects:fromConnection:))]
+            fn capture_output(
+                &self,
+                _output: &AVCaptureMetadataOutput,
+                objects: &NSArray<AVMetadataObject>,
+                _connection: &AVCaptureConnection,
+            ) {
+                let value = objects.iter().find_map(|object| {
+                    object
+                        .downcast_ref::<AVMetadataMachineReadableCodeObject>()
+                        .and_then(|code| unsafe { code.stringValue() })
+                });
+                if let Some(value) = value {
+                    self.finish(Ok(Some(value.to_string())));
+                }
+            }
+        }
+    );
+
+    thread_local! {
+        /// Capture outputs and buttons hold their delegate and target weakly, so the
+        /// current scan is kept alive here until the next one replaces it.
+        static CURRENT: RefCell<Option<Retained<ScanDelegate>>> = const { RefCell::new(None) };
+    }
+
+    impl ScanDelegate {
+        fn finish(&self, result: Result<Option<String>, String>) {
+            let ivars = self.ivars();
+            let Some(sender) = ivars.sender.take() else {
+                return;
+            };
+
+            unsafe { ivars.session.stopRunning() };
+            if let Some(controller) = ivars.controller.take() {
+                controller.dismissViewControllerAnimated_completion(true, None);
+            }
+            let _ = sender.send(result);
+        }
+    }
+
+    /// Builds a capture session for the back camera and presents its preview full
+    /// screen over `parent`, with a cancel button. Must run on the main thread.
+    pub fn present(parent: &UIViewController, sender: Sender) {
+        let Some(mtm) = MainThreadMarker::new() else {
+            let _ = sender.send(Err("Scanning must start on the main thread".to_string()));
+            return;
+        };
+
+        let busy = CURRENT.with_borrow(|current| {
+            current
+                .as_ref()
+                .is_some_and(|delegate| delegate.ivars().sender.borrow().is_some())
+        });
+        if busy {
+            let _ = sender.send(Err("A scan is already in progress".to_string()));
+            return;
+        }
+
+        let Some(media_type) = (unsafe { AVMediaTypeVideo }) else {
+            let _ = sender.send(Err("Video capture is not available".to_string()));
+            return;
+        };
+        let status = unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) };
+        if status == AVAuthorizationStatus::Denied || status == AVAuthorizationStatus::Restricted {
+            let _ = sender.send(Err("Camera access is not allowed".to_string()));
+            return;
+        }
+        let Some(device) = (unsafe { AVCaptureDevice::defaultDeviceWithMediaType(media_type) })
+        else {
+            let _ = sender.send(Err("No camera is available".to_string()));
+            return;
+        };
+        let input = match unsafe { AVCaptureDeviceInput::deviceInputWithDevice_error(&device) } {
+            Ok(input) => input,
+            Err(e) => {
+                let _ = sender.send(Err(format!(
+                    "Failed to open the camera: {}",
+                    e.localizedDescription()
+                )));
+                return;
+            }
+        };
+
+        let session = unsafe { AVCaptureSession::new() };
+        let output = unsafe { AVCaptureMetadataOutput::new() };
+        unsafe {
+            if !session.canAddInput(&input) || !session.canAddOutput(&output) {
+                let _ = sender.send(Err("Failed to set up the camera".to_string()));
+                return;
+            }
+            session.addInput(&input);
+            session.addOutput(&output);
+        }
+
+        let controller = UIViewController::new(mtm);
+        controller.setModalPresentationStyle(UIModalPresentationStyle::FullScreen);
+
+        let delegate = ScanDelegate::alloc(mtm).set_ivars(ScanIvars {
+            session: session.clone(),
+            controller: RefCell::new(Some(controller.clone())),
+            sender: RefCell::new(Some(sender)),
+        });
+        let delegate: Retained<ScanDelegate> = unsafe { msg_send![super(delegate), init] };
+
+        unsafe {
+            output.setMetadataObjectsDelegate_queue(
+                Some(ProtocolObject::from_ref(&*delegate)),
+                Some(dispatch2::DispatchQueue::main()),
+            );
+            // Only types the output supports once it is attached to the session
+            output.setMetadataObjectTypes(&NSArray::from_slice(&[
+                AVMetadataObjectTypeQRCode,
+                AVMetadataObjectTypeCode128Code,
+                AVMetadataObjectTypeEAN13Code,
+            ]));
+        }
+
+        if let Some(view) = controller.view() {
+            let bounds = view.bounds();
+
+            let preview = unsafe { AVCaptureVideoPreviewLayer::layerWithSession(&session) };
+            if let Some(gravity) = unsafe { AVLayerVideoGravityResizeAspectFill } {
+                unsafe { preview.setVideoGravity(gravity) };
+            }
+            preview.setFrame(bounds);
+            view.layer().addSublayer(&preview);
+
+            let button = UIButton::buttonWithType(UIButtonType::System, mtm);
+            button.setTitle_forState(Some(ns_string!("Cancel")), UIControlState::Normal);
+            button.setFrame(CGRect::new(
+                CGPoint::new(0.0, bounds.size.height - 96.0),
+                CGSize::new(bounds.size.width, 48.0),
+            ));
+            let target: &AnyObject = &delegate;
+            unsafe {
+                button.addTarget_action_forControlEvents(
+                    Some(target),
+                    sel!(cancel),
+                    UIControlEvents::TouchUpInside,
+                );
+            }
+            view.addSubview(&button);
+        }
+
+        CURRENT.set(Some(delegate));
+        parent.presentViewController_animated_completion(&controller, true, None);
+        unsafe { session.startRunning() };
+    }
+}
+
+#[cfg(target_os = "android")]
+async fn scan(_app: &AppHandle) -> Result<Option<String>, AppError> {
+    tauri::async_runtime::spawn_blocking(scan_blocking)
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to scan barcode: {}", e)))?
+        .map_err(AppError::internal)
+}
+
+/// Shows the camera over the app until a QR, Code 128 or EAN-13 code comes into view.
+#[cfg(target_os = "ios")]
+async fn scan(app: &AppHandle) -> Result<Option<String>, AppError> {
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| AppError::invalid_state("Main window is not open"))?;
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    window
+        .with_webview(move |webview| {
+            let parent = webview
+                .view_controller()
+                .cast::<objc2_ui_kit::UIViewController>();
+            match unsafe { parent.as_ref() } {
+                Some(parent) => capture::present(parent, sender),
+                None => {
+                    let _ = sender.send(Err("The app has no view controller".to_string()));
+                }
+            }
+        })
+        .map_err(|e| AppError::internal(format!("Failed to open the camera: {}", e)))?;
+
+    receiver
+        .await
+        .map_err(|_| AppError::internal("Scanning was interrupted"))?
+        .map_err(AppError::internal)
+}
+
+/// Desktop webcams would need a barcode decoder bundled with the app, which it does not
+/// ship yet.
+#[cfg(desktop)]
+async fn scan(_app: &AppHandle) -> Result<Option<String>, AppError> {
+    Err(AppError::unsupported(
+        "Barcode scanning is not supported on this platform",
+    ))
+}
+
+/// Opens the camera and returns the text of the first code it reads, or `None` if the
+/// user closes the scanner.
+#[tauri::command]
+pub async fn scan_barcode(app: AppHandle) -> Result<Option<String>, AppError> {
+    scan(&app).await
+}
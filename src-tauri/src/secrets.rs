@@ -0,0 +1,139 @@
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::profiles::scoped_secret_key;
+
+/// Service name under which all keychain entries are stored
+const KEYCHAIN_SERVICE: &str = "com.th3void.lotusroutine";
+
+/// Keychain entry holding the current website session token
+pub const SESSION_TOKEN_KEY: &str = "session_token";
+
+/// Keychain entry holding the refresh token of a native sign-in
+pub const REFRESH_TOKEN_KEY: &str = "refresh_token";
+
+/// Prefix keeping frontend-managed entries apart from the ones the backend owns
+const WEB_SECRET_PREFIX: &str = "web/";
+
+/// Names of frontend-managed secrets, since keychains cannot be enumerated portably
+const WEB_SECRET_INDEX_FILE: &str = "secrets";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WebSecretIndex {
+    keys: Vec<String>,
+}
+
+fn keychain_entry(key: &str) -> Result<Entry, AppError> {
+    Entry::new(KEYCHAIN_SERVICE, key)
+        .map_err(|e| AppError::storage(format!("Failed to open keychain entry: {}", e)))
+}
+
+pub fn read_secret(key: &str) -> Result<Option<String>, AppError> {
+    match keychain_entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::storage(format!(
+            "Failed to read secret from keychain: {}",
+            e
+        ))),
+    }
+}
+
+pub fn write_secret(key: &str, value: &str) -> Result<(), AppError> {
+    keychain_entry(key)?
+        .set_password(value)
+        .map_err(|e| AppError::storage(format!("Failed to write secret to keychain: {}", e)))
+}
+
+pub fn remove_secret(key: &str) -> Result<(), AppError> {
+    match keychain_entry(key)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::storage(format!(
+            "Failed to delete secret from keychain: {}",
+            e
+        ))),
+    }
+}
+
+fn web_secret_key(key: &str) -> Result<String, AppError> {
+    let valid = !key.is_empty()
+        && key.len() <= 128
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if !valid {
+        return Err(AppError::invalid_input(format!(
+            "Invalid secret name: {}",
+            key
+        )));
+    }
+
+    Ok(format!("{}{}", WEB_SECRET_PREFIX, key))
+}
+
+async fn update_web_secret_index(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Vec<String>),
+) -> Result<(), AppError> {
+    let mut index: WebSecretIndex =
+        crate::read_state_file_blocking(app, WEB_SECRET_INDEX_FILE).await?;
+    change(&mut index.keys);
+    crate::write_state_file_blocking(app, WEB_SECRET_INDEX_FILE, &index).await
+}
+
+/// Unscoped names of the frontend secrets recorded in a profile's data directory.
+pub fn web_secret_keys_in(profile_dir: &Path) -> Vec<String> {
+    let index_path = profile_dir
+        .join("state")
+        .join(format!("{}.json", WEB_SECRET_INDEX_FILE));
+
+    std::fs::read_to_string(index_path)
+        .ok()
+        .and_then(|content| {
+            crate::storage::decode::<WebSecretIndex>(WEB_SECRET_INDEX_FILE, &content).ok()
+        })
+        .map(|decoded| {
+            decoded
+                .value
+                .keys
+                .iter()
+                .map(|key| format!("{}{}", WEB_SECRET_PREFIX, key))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Persists a frontend secret such as a refresh token in the OS keychain.
+#[tauri::command]
+pub async fn store_secret(app: AppHandle, key: String, value: String) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    write_secret(&scoped_secret_key(&app, &web_secret_key(&key)?), &value)?;
+    update_web_secret_index(&app, |keys| {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_secret(app: AppHandle, key: String) -> Result<Option<String>, AppError> {
+    if crate::demo::is_demo(&app) {
+        return Ok(None);
+    }
+
+    read_secret(&scoped_secret_key(&app, &web_secret_key(&key)?))
+}
+
+#[tauri::command]
+pub async fn delete_secret(app: AppHandle, key: String) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    remove_secret(&scoped_secret_key(&app, &web_secret_key(&key)?))?;
+    update_web_secret_index(&app, |keys| keys.retain(|k| k != &key)).await
+}
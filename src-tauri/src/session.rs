@@ -0,0 +1,519 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::webview::cookie::time::OffsetDateTime;
+use tauri::webview::cookie::{Expiration, SameSite};
+use tauri::webview::{Cookie, PageLoadEvent, PageLoadPayload};
+use tauri::{AppHandle, Manager, RunEvent, Url, Webview, WebviewWindow, WindowEvent};
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::profiles::scoped_secret_key;
+use crate::secrets;
+
+/// Keychain entry holding the key the session backup is encrypted with
+pub const SESSION_BACKUP_KEY: &str = "session_backup_key";
+
+/// Encrypted backup in the profile's data directory
+const SESSION_FILE: &str = "session.bin";
+
+/// Name the backup is versioned under, like the state files
+const SESSION_STATE_NAME: &str = "session";
+
+/// How long a script in the page may take to answer
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long quitting waits for the backup
+const EXIT_SAVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const READ_LOCAL_STORAGE_SCRIPT: &str = r#"
+    (() => {
+        const entries = {};
+        for (let i = 0; i < localStorage.length; i++) {
+            const key = localStorage.key(i);
+            entries[key] = localStorage.getItem(key);
+        }
+        return JSON.stringify(entries);
+    })()
+"#;
+
+#[derive(Default)]
+pub struct SessionState {
+    /// Whether the backup was restored (or tried) since launch
+    restore_attempted: AtomicBool,
+    /// Whether the backup for quitting was made, so the exit can go ahead
+    saved_for_exit: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    expires_at: i64,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SavedSession {
+    saved_at: i64,
+    /// Website origin the cookies and storage belong to
+    origin: String,
+    cookies: Vec<SavedCookie>,
+    local_storage: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub saved_at: i64,
+    pub cookies: usize,
+    pub local_storage_keys: usize,
+}
+
+/// What a restore put back; entries the webview still had are left alone
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredSession {
+    /// When the backup was made
+    pub saved_at: i64,
+    pub cookies: usize,
+    pub local_storage_keys: usize,
+}
+
+impl SavedCookie {
+    /// Only cookies meant to outlive the app are kept; session cookies would not survive a
+    /// restart anyway.
+    fn from_cookie(cookie: &Cookie<'_>) -> Option<Self> {
+        let expires_at = match cookie.expires()? {
+            Expiration::DateTime(expires) => expires.unix_timestamp(),
+            Expiration::Session => return None,
+        };
+
+        Some(Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: cookie.domain().map(str::to_string),
+            path: cookie.path().map(str::to_string),
+            expires_at,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site: cookie.same_site().map(|same_site| same_site.to_string()),
+        })
+    }
+
+    fn to_cookie(&self) -> Option<Cookie<'static>> {
+        let expires = OffsetDateTime::from_unix_timestamp(self.expires_at).ok()?;
+        let mut cookie = Cookie::build((self.name.clone(), self.value.clone()))
+            .expires(expires)
+            .secure(self.secure)
+            .http_only(self.http_only);
+        if let Some(domain) = &self.domain {
+            cookie = cookie.domain(domain.clone());
+        }
+        if let Some(path) = &self.path {
+            cookie = cookie.path(path.clone());
+        }
+        cookie = match self.same_site.as_deref() {
+            Some("Strict") => cookie.same_site(SameSite::Strict),
+            Some("Lax") => cookie.same_site(SameSite::Lax),
+            Some("None") => cookie.same_site(SameSite::None),
+            _ => cookie,
+        };
+        Some(cookie.build())
+    }
+
+    fn identity(&self) -> (String, Option<String>, Option<String>) {
+        (self.name.clone(), self.domain.clone(), self.path.clone())
+    }
+}
+
+fn main_window(app: &AppHandle) -> Result<WebviewWindow, AppError> {
+    app.get_webview_window("main")
+        .ok_or_else(|| AppError::invalid_state("Main window is not open"))
+}
+
+fn website(app: &AppHandle) -> Result<Url, AppError> {
+    Url::parse(&crate::website_url(app))
+        .map_err(|e| AppError::corrupt_data(format!("Invalid website URL: {}", e)))
+}
+
+/// Whether the main window shows the website, so its storage can be reached.
+fn shows_website(window: &WebviewWindow, website: &Url) -> bool {
+    window
+        .url()
+        .is_ok_and(|url| url.origin() == website.origin())
+}
+
+/// Runs `script` in the main window and returns its result as JSON.
+async fn run_script(window: &WebviewWindow, script: &str) -> Result<String, AppError> {
+    let (sender, receiver) = oneshot::channel();
+    let sender = Mutex::new(Some(sender));
+    window
+        .eval_with_callback(script, move |result| {
+            if let Some(sender) = sender.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                let _ = sender.send(result);
+            }
+        })
+        .map_err(|e| AppError::internal(format!("Failed to run script in the page: {}", e)))?;
+
+    tokio::time::timeout(SCRIPT_TIMEOUT, receiver)
+        .await
+        .map_err(|_| AppError::internal("The page did not answer in time"))?
+        .map_err(|_| AppError::internal("The page did not answer"))
+}
+
+/// Key the backup is encrypted with, created on first use when `create` is set.
+fn backup_key(app: &AppHandle, create: bool) -> Result<Option<LessSafeKey>, AppError> {
+    let name = scoped_secret_key(app, SESSION_BACKUP_KEY);
+    let bytes = match secrets::read_secret(&name)? {
+        Some(encoded) => STANDARD
+            .decode(encoded)
+            .map_err(|e| AppError::corrupt_data(format!("Invalid session backup key: {}", e)))?,
+        None if create => {
+            let mut bytes = vec![0u8; AES_256_GCM.key_len()];
+            SystemRandom::new()
+                .fill(&mut bytes)
+                .map_err(|_| AppError::internal("Failed to generate session backup key"))?;
+            secrets::write_secret(&name, &STANDARD.encode(&bytes))?;
+            bytes
+        }
+        None => return Ok(None),
+    };
+
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| AppError::corrupt_data("Invalid session backup key"))?;
+    Ok(Some(LessSafeKey::new(key)))
+}
+
+fn encrypt(key: &LessSafeKey, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| AppError::internal("Failed to generate nonce"))?;
+
+    let mut sealed = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut sealed,
+    )
+    .map_err(|_| AppError::internal("Failed to encrypt session backup"))?;
+
+    let mut contents = nonce.to_vec();
+    contents.extend_from_slice(&sealed);
+    Ok(contents)
+}
+
+fn decrypt(key: &LessSafeKey, contents: &[u8]) -> Result<Vec<u8>, AppError> {
+    if contents.len() < NONCE_LEN {
+        return Err(AppError::corrupt_data("Session backup is truncated"));
+    }
+
+    let (nonce, sealed) = contents.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| AppError::corrupt_data("Session backup is truncated"))?;
+    let mut sealed = sealed.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| AppError::corrupt_data("Session backup could not be decrypted"))?;
+    Ok(plaintext.to_vec())
+}
+
+async fn read_backup(app: &AppHandle) -> Result<Option<SavedSession>, AppError> {
+    let path = crate::get_data_dir(app)?.join(SESSION_FILE);
+    let Some(key) = backup_key(app, false)? else {
+        return Ok(None);
+    };
+
+    crate::storage::run_blocking(move || {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read(&path)
+            .map_err(|e| AppError::storage(format!("Failed to read session backup: {}", e)))?;
+        let plaintext = String::from_utf8(decrypt(&key, &contents)?)
+            .map_err(|e| AppError::corrupt_data(format!("Invalid session backup: {}", e)))?;
+        Ok(Some(
+            crate::storage::decode(SESSION_STATE_NAME, &plaintext)
+                .map_err(AppError::corrupt_data)?
+                .value,
+        ))
+    })
+    .await
+}
+
+async fn write_backup(app: &AppHandle, session: &SavedSession) -> Result<(), AppError> {
+    let path = crate::get_data_dir(app)?.join(SESSION_FILE);
+    let key =
+        backup_key(app, true)?.ok_or_else(|| AppError::storage("Session backup key is missing"))?;
+    let contents = encrypt(
+        &key,
+        crate::storage::encode(SESSION_STATE_NAME, session)
+            .map_err(AppError::internal)?
+            .as_bytes(),
+    )?;
+
+    crate::storage::run_blocking(move || {
+        crate::storage::write_atomic(&path, &contents)
+            .map_err(|e| AppError::storage(format!("Failed to write session backup: {}", e)))
+    })
+    .await
+}
+
+/// Backs up the website's persistent cookies and, when the website is open, its
+/// localStorage. If the page cannot be read, the localStorage from the last backup is kept.
+async fn save(app: &AppHandle) -> Result<SessionSummary, AppError> {
+    let window = main_window(app)?;
+    let website = website(app)?;
+    let origin = website.origin().ascii_serialization();
+
+    let cookies: Vec<SavedCookie> = window
+        .cookies_for_url(website.clone())
+        .map_err(|e| AppError::internal(format!("Failed to read cookies: {}", e)))?
+        .iter()
+        .filter_map(SavedCookie::from_cookie)
+        .collect();
+
+    let local_storage = if shows_website(&window, &website) {
+        let result = run_script(&window, READ_LOCAL_STORAGE_SCRIPT).await?;
+        let json: String = serde_json::from_str(&result)
+            .map_err(|e| AppError::corrupt_data(format!("Failed to read localStorage: {}", e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| AppError::corrupt_data(format!("Failed to read localStorage: {}", e)))?
+    } else {
+        match read_backup(app).await {
+            Ok(Some(previous)) if previous.origin == origin => previous.local_storage,
+            _ => BTreeMap::new(),
+        }
+    };
+
+    let session = SavedSession {
+        saved_at: chrono::Utc::now().timestamp(),
+        origin,
+        cookies,
+        local_storage,
+    };
+    write_backup(app, &session).await?;
+
+    Ok(SessionSummary {
+        saved_at: session.saved_at,
+        cookies: session.cookies.len(),
+        local_storage_keys: session.local_storage.len(),
+    })
+}
+
+/// Puts back cookies and localStorage entries from the backup that the webview no longer
+/// has, and reloads the page if anything was missing.
+async fn restore(app: &AppHandle) -> Result<Option<RestoredSession>, AppError> {
+    let Some(session) = read_backup(app).await? else {
+        return Ok(None);
+    };
+    let window = main_window(app)?;
+    let website = website(app)?;
+    // The backup belongs to another website if the base URL was changed since
+    if session.origin != website.origin().ascii_serialization() {
+        return Ok(None);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let present: HashSet<_> = window
+        .cookies_for_url(website.clone())
+        .map_err(|e| AppError::internal(format!("Failed to read cookies: {}", e)))?
+        .iter()
+        .filter_map(SavedCookie::from_cookie)
+        .map(|cookie| cookie.identity())
+        .collect();
+
+    let mut cookies = 0;
+    for saved in &session.cookies {
+        if saved.expires_at <= now || present.contains(&saved.identity()) {
+            continue;
+        }
+        let Some(cookie) = saved.to_cookie() else {
+            continue;
+        };
+        match window.set_cookie(cookie) {
+            Ok(()) => cookies += 1,
+            Err(e) => tracing::warn!("Failed to restore cookie {}: {}", saved.name, e),
+        }
+    }
+
+    let mut local_storage_keys = 0;
+    if !session.local_storage.is_empty() && shows_website(&window, &website) {
+        let origin = serde_json::to_string(&session.origin)
+            .map_err(|e| AppError::internal(format!("Failed to serialize origin: {}", e)))?;
+        let entries = serde_json::to_string(&session.local_storage)
+            .map_err(|e| AppError::internal(format!("Failed to serialize localStorage: {}", e)))?;
+        let script = format!(
+            r#"
+            (() => {{
+                if (location.origin !== {}) return 0;
+                let restored = 0;
+                for (const [key, value] of Object.entries({})) {{
+                    if (localStorage.getItem(key) === null) {{
+                        localStorage.setItem(key, value);
+                        restored += 1;
+                    }}
+                }}
+                return restored;
+            }})()
+            "#,
+            origin, entries
+        );
+        let result = run_script(&window, &script).await?;
+        local_storage_keys = serde_json::from_str(&result).map_err(|e| {
+            AppError::corrupt_data(format!("Failed to restore localStorage: {}", e))
+        })?;
+    }
+
+    if cookies > 0 || local_storage_keys > 0 {
+        tracing::info!(
+            "Restored {} cookies and {} localStorage entries from the session backup",
+            cookies,
+            local_storage_keys
+        );
+        if let Err(e) = window.eval("window.location.reload()") {
+            tracing::warn!("Failed to reload after restoring the session: {}", e);
+        }
+    }
+
+    Ok(Some(RestoredSession {
+        saved_at: session.saved_at,
+        cookies,
+        local_storage_keys,
+    }))
+}
+
+/// Restores the session once the website first loads after launch, in case the webview's
+/// storage was cleared since the app last quit.
+pub fn handle_page_load(webview: &Webview, payload: &PageLoadPayload<'_>) {
+    if webview.label() != "main" || payload.event() != PageLoadEvent::Finished {
+        return;
+    }
+
+    let app = webview.app_handle().clone();
+    let Ok(website) = website(&app) else {
+        return;
+    };
+    if payload.url().origin() != website.origin() || crate::demo::is_demo(&app) {
+        return;
+    }
+    if app
+        .state::<SessionState>()
+        .restore_attempted
+        .swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = restore(&app).await {
+            tracing::warn!("Failed to restore session: {}", e);
+        }
+    });
+}
+
+/// Backs up the session before the app quits. Returns false if it was already done.
+async fn save_before_exit(app: &AppHandle) -> bool {
+    if app
+        .state::<SessionState>()
+        .saved_for_exit
+        .swap(true, Ordering::SeqCst)
+    {
+        return false;
+    }
+
+    match tokio::time::timeout(EXIT_SAVE_TIMEOUT, save(app)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::warn!("Failed to save session: {}", e),
+        Err(_) => tracing::warn!("Saving the session took too long"),
+    }
+    true
+}
+
+/// Holds the exit back until the session is saved. Reading cookies must not block the
+/// main thread, so the backup runs on the async runtime and exits again when done.
+pub fn handle_run_event(app: &AppHandle, event: RunEvent) {
+    let RunEvent::ExitRequested { api, code, .. } = event else {
+        return;
+    };
+    let state = app.state::<SessionState>();
+    if crate::demo::is_demo(app)
+        || state.saved_for_exit.load(Ordering::SeqCst)
+        || app.get_webview_window("main").is_none()
+    {
+        return;
+    }
+
+    api.prevent_exit();
+    let app = app.clone();
+    let code = code.unwrap_or(0);
+    tauri::async_runtime::spawn(async move {
+        save_before_exit(&app).await;
+        app.exit(code);
+    });
+}
+
+/// Saves the session when closing the main window quits the app, while the webview can
+/// still be read; on mobile, when the app goes to the background, since it is usually
+/// killed there rather than quit.
+pub fn watch(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        #[cfg(desktop)]
+        WindowEvent::CloseRequested { api, .. }
+            if crate::settings::current(handle.app_handle()).close_behavior
+                == crate::settings::CloseBehavior::Quit
+                && !crate::demo::is_demo(handle.app_handle())
+                && !handle
+                    .state::<SessionState>()
+                    .saved_for_exit
+                    .load(Ordering::SeqCst) =>
+        {
+            api.prevent_close();
+            let window = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                save_before_exit(window.app_handle()).await;
+                if let Err(e) = window.destroy() {
+                    tracing::warn!("Failed to close main window: {}", e);
+                }
+            });
+        }
+        #[cfg(mobile)]
+        WindowEvent::Focused(false) if !crate::demo::is_demo(handle.app_handle()) => {
+            let app = handle.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = save(&app).await {
+                    tracing::warn!("Failed to save session: {}", e);
+                }
+            });
+        }
+        _ => {}
+    });
+}
+
+/// Backs up the website's cookies, including HTTP-only ones, and its localStorage to an
+/// encrypted file in app storage, whose key is kept in the keychain. The app does this by
+/// itself when it quits.
+#[tauri::command]
+pub async fn save_session(app: AppHandle) -> Result<SessionSummary, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    save(&app).await
+}
+
+/// Puts back whatever the webview lost since the last backup, e.g. after its storage was
+/// cleared, and reloads the page if anything was missing. `None` if there is no backup for
+/// the current website. The app does this by itself on launch.
+#[tauri::command]
+pub async fn restore_session(app: AppHandle) -> Result<Option<RestoredSession>, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+    restore(&app).await
+}
@@ -0,0 +1,457 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audio::SoundSettings;
+use crate::dnd::DndBehavior;
+use crate::error::AppError;
+use crate::focus_block::FocusBlockSettings;
+use crate::idle::IdleSettings;
+use crate::proxy::ProxyConfig;
+use crate::speech::SpeechSettings;
+use crate::usage::UsageSettings;
+
+const SETTINGS_FILE: &str = "settings";
+
+/// Sent with the new settings whenever they change
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// Release track the updater follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+/// How queued changes are reconciled when the server copy changed after they were made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictStrategy {
+    /// The queued change is sent anyway, overwriting the server copy
+    #[default]
+    LastWriteWins,
+    /// The queued change is discarded
+    ServerWins,
+    /// The queued change is set aside until the user resolves it
+    Ask,
+}
+
+/// What closing the main window does on desktop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CloseBehavior {
+    #[default]
+    Quit,
+    /// The window is hidden and the app keeps syncing and firing reminders from the tray
+    HideToTray,
+}
+
+/// Quick check-in hotkey used until the user picks another one
+const DEFAULT_QUICK_CHECKIN_SHORTCUT: &str = "CommandOrControl+Shift+L";
+
+/// Cache size limit used until the user picks one
+const DEFAULT_MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Per-profile application settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Suspends background network activity such as scheduled prefetching
+    pub data_saver: bool,
+    pub update_channel: UpdateChannel,
+    /// Cached payloads beyond this size are evicted, least recently used first
+    pub max_cache_bytes: u64,
+    /// Website deployment to use instead of the production site
+    pub base_url: Option<String>,
+    /// Minimum level written to the log, e.g. `info` or `debug`
+    pub log_level: String,
+    pub conflict_strategy: ConflictStrategy,
+    /// Global hotkey for the quick check-in, or `None` to disable it
+    pub quick_checkin_shortcut: Option<String>,
+    pub close_behavior: CloseBehavior,
+    /// Hides the main window to the tray instead of the taskbar when minimized
+    pub minimize_to_tray: bool,
+    /// Checks first-party certificates against the pinned public keys
+    pub certificate_pinning: bool,
+    pub proxy: ProxyConfig,
+    pub sound: SoundSettings,
+    pub speech: SpeechSettings,
+    pub idle: IdleSettings,
+    pub usage: UsageSettings,
+    /// What happens to reminders while the system's Do Not Disturb is on
+    pub dnd_behavior: DndBehavior,
+    pub focus_block: FocusBlockSettings,
+    /// Saves a report to disk when the app panics; off until the user opts in
+    pub crash_reporting: bool,
+    /// Language of menus, dialogs and notifications, e.g. `de`; follows the system when
+    /// unset
+    pub locale_override: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            data_saver: false,
+            update_channel: UpdateChannel::default(),
+            max_cache_bytes: DEFAULT_MAX_CACHE_BYTES,
+            base_url: None,
+            log_level: "info".to_string(),
+            conflict_strategy: ConflictStrategy::default(),
+            quick_checkin_shortcut: Some(DEFAULT_QUICK_CHECKIN_SHORTCUT.to_string()),
+            close_behavior: CloseBehavior::default(),
+            minimize_to_tray: false,
+            certificate_pinning: true,
+            proxy: ProxyConfig::default(),
+            sound: SoundSettings::default(),
+            speech: SpeechSettings::default(),
+            idle: IdleSettings::default(),
+            usage: UsageSettings::default(),
+            dnd_behavior: DndBehavior::default(),
+            focus_block: FocusBlockSettings::default(),
+            crash_reporting: false,
+            locale_override: None,
+        }
+    }
+}
+
+pub struct SettingsState(RwLock<Settings>);
+
+impl SettingsState {
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        Ok(Self(RwLock::new(crate::read_state_file(app, SETTINGS_FILE)?)))
+    }
+
+    pub fn get(&self) -> Settings {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Applies `change` and persists the result.
+    pub fn update(
+        &self,
+        app: &AppHandle,
+        change: impl FnOnce(&mut Settings),
+    ) -> Result<Settings, AppError> {
+        let mut settings = self.0.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = settings.clone();
+        change(&mut updated);
+
+        crate::write_state_file(app, SETTINGS_FILE, &updated)?;
+        *settings = updated.clone();
+        drop(settings);
+
+        notify_changed(app, &updated);
+        Ok(updated)
+    }
+}
+
+fn notify_changed(app: &AppHandle, settings: &Settings) {
+    if let Err(e) = app.emit(SETTINGS_CHANGED_EVENT, settings) {
+        tracing::warn!("Failed to emit settings change: {}", e);
+    }
+}
+
+/// Re-reads settings after the active profile changes.
+pub fn reload(app: &AppHandle) -> Result<(), AppError> {
+    let settings: Settings = crate::read_state_file(app, SETTINGS_FILE)?;
+    *app.state::<SettingsState>()
+        .0
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = settings.clone();
+    notify_changed(app, &settings);
+
+    app.state::<crate::http_client::HttpClient>()
+        .set_pinning(settings.certificate_pinning);
+    app.state::<crate::crash::CrashReporter>()
+        .set_enabled(settings.crash_reporting);
+    crate::proxy::apply(app)
+}
+
+/// Replaces the settings with those from a backup, checked and applied the way
+/// [`update_settings`] does. The website address, proxy and certificate pinning stay as
+/// they are, so a backup cannot send requests elsewhere or turn pinning off.
+pub async fn restore(app: &AppHandle, mut settings: Settings) -> Result<Settings, AppError> {
+    let current = current(app);
+    settings.base_url = current.base_url.clone();
+    settings.proxy = current.proxy.clone();
+    settings.certificate_pinning = current.certificate_pinning;
+
+    let Value::Object(current_fields) = to_value(&current)? else {
+        return Err(AppError::internal("Settings are not an object"));
+    };
+    let changed = changed_fields(&current_fields, &to_value(&settings)?);
+    save_changes(app, settings, &changed).await
+}
+
+pub fn current(app: &AppHandle) -> Settings {
+    app.state::<SettingsState>().get()
+}
+
+fn to_value(settings: &Settings) -> Result<Value, AppError> {
+    serde_json::to_value(settings)
+        .map_err(|e| AppError::internal(format!("Failed to serialize settings: {}", e)))
+}
+
+/// Names of the top-level settings whose value in `updated` differs from `current`.
+fn changed_fields(current: &Map<String, Value>, updated: &Value) -> Vec<String> {
+    current
+        .iter()
+        .filter(|(key, value)| updated.get(key.as_str()) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Merges `patch` into `target` like a JSON merge patch, except that `null` sets a value to
+/// `null` rather than removing it, so optional settings can be cleared.
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Checks the settings named in `changed`, normalizing them the way their own setters do.
+/// Settings that did not change are left alone, so one that became invalid on its own,
+/// e.g. a deleted sound, does not block unrelated updates.
+fn validate(app: &AppHandle, settings: &mut Settings, changed: &[String]) -> Result<(), AppError> {
+    for key in changed {
+        match key.as_str() {
+            "max_cache_bytes" if settings.max_cache_bytes < crate::MIN_CACHE_BYTES => {
+                return Err(AppError::invalid_input(format!(
+                    "Cache limit must be at least {} bytes",
+                    crate::MIN_CACHE_BYTES
+                )));
+            }
+            "base_url" => {
+                settings.base_url = settings
+                    .base_url
+                    .as_deref()
+                    .map(crate::normalize_base_url)
+                    .transpose()?;
+            }
+            "log_level" => {
+                settings.log_level = crate::logging::parse_level(&settings.log_level)
+                    .map_err(AppError::invalid_input)?
+                    .to_string()
+                    .to_lowercase();
+            }
+            "quick_checkin_shortcut" => {
+                if let Some(shortcut) = &mut settings.quick_checkin_shortcut {
+                    *shortcut = shortcut.trim().to_string();
+                    #[cfg(desktop)]
+                    crate::shortcuts::parse(shortcut).map_err(AppError::invalid_input)?;
+                }
+            }
+            "proxy" => {
+                app.state::<crate::http_client::HttpClient>()
+                    .with_proxy(&settings.proxy, None)
+                    .map_err(AppError::invalid_input)?;
+            }
+            "sound" => settings.sound.validate(app)?,
+            "speech" => settings.speech.validate()?,
+            "idle" => settings.idle.validate()?,
+            "usage" => settings.usage.validate()?,
+            "focus_block" => {
+                settings.focus_block.blocked_domains = crate::focus_block::normalize_domains(
+                    app,
+                    &settings.focus_block.blocked_domains,
+                )
+                .map_err(AppError::invalid_input)?;
+            }
+            "locale_override" => {
+                if let Some(locale) = &settings.locale_override {
+                    if crate::i18n::bundled_locale(locale).is_none() {
+                        return Err(AppError::invalid_input(format!(
+                            "No translation available for {}",
+                            locale
+                        )));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Puts saved changes into effect where the app does not read the setting each time it
+/// needs it. Failures are logged; the settings are saved either way.
+async fn apply_changes(app: &AppHandle, settings: &Settings, changed: &[String]) {
+    for key in changed {
+        let result: Result<(), AppError> = match key.as_str() {
+            "certificate_pinning" => {
+                app.state::<crate::http_client::HttpClient>()
+                    .set_pinning(settings.certificate_pinning);
+                Ok(())
+            }
+            "crash_reporting" => {
+                app.state::<crate::crash::CrashReporter>()
+                    .set_enabled(settings.crash_reporting);
+                Ok(())
+            }
+            "proxy" => crate::proxy::apply(app),
+            "log_level" => crate::logging::apply(app).map_err(AppError::invalid_input),
+            "max_cache_bytes" => {
+                let max_bytes = settings.max_cache_bytes;
+                crate::cache_store::with_store_mut_blocking(app, move |store| {
+                    store.set_max_bytes(max_bytes)
+                })
+                .await
+                .map_err(AppError::storage)
+            }
+            "base_url" => crate::load_website(app).map(drop),
+            #[cfg(desktop)]
+            "quick_checkin_shortcut" => {
+                crate::shortcuts::apply(app);
+                Ok(())
+            }
+            #[cfg(desktop)]
+            "locale_override" => {
+                crate::tray::refresh_menu(app);
+                crate::app_menu::refresh(app);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to apply {} setting: {}", key, e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, SettingsState>) -> Result<Settings, AppError> {
+    Ok(state.get())
+}
+
+/// Changes the settings in `patch`, an object with the same shape as the settings that
+/// holds only what should change, e.g. `{"speech": {"rate": 1.2}}`. `null` clears an
+/// optional setting. Nothing is saved unless every changed setting is valid. Returns the
+/// saved settings, which are also sent with the `settings-changed` event.
+#[tauri::command]
+pub async fn update_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    patch: Value,
+) -> Result<Settings, AppError> {
+    let Value::Object(fields) = &patch else {
+        return Err(AppError::invalid_input("Settings patch must be an object"));
+    };
+
+    let current = to_value(&state.get())?;
+    let Value::Object(current_fields) = &current else {
+        return Err(AppError::internal("Settings are not an object"));
+    };
+    if let Some(unknown) = fields.keys().find(|key| !current_fields.contains_key(*key)) {
+        return Err(AppError::invalid_input(format!(
+            "Unknown setting: {}",
+            unknown
+        )));
+    }
+
+    let mut merged = current.clone();
+    merge(&mut merged, patch);
+    let changed = changed_fields(current_fields, &merged);
+
+    let settings: Settings = serde_json::from_value(merged)
+        .map_err(|e| AppError::invalid_input(format!("Invalid settings: {}", e)))?;
+    save_changes(&app, settings, &changed).await
+}
+
+/// Checks the settings named in `changed`, saves `settings` if they are all valid and
+/// applies the changes.
+async fn save_changes(
+    app: &AppHandle,
+    mut settings: Settings,
+    changed: &[String],
+) -> Result<Settings, AppError> {
+    validate(app, &mut settings, changed)?;
+
+    let updated = app
+        .state::<SettingsState>()
+        .update(app, |current| *current = settings)?;
+    apply_changes(app, &updated, changed).await;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub async fn get_data_saver(state: State<'_, SettingsState>) -> Result<bool, AppError> {
+    Ok(state.get().data_saver)
+}
+
+#[tauri::command]
+pub async fn set_data_saver(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.data_saver = enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_update_channel(
+    state: State<'_, SettingsState>,
+) -> Result<UpdateChannel, AppError> {
+    Ok(state.get().update_channel)
+}
+
+#[tauri::command]
+pub async fn set_update_channel(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    channel: UpdateChannel,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.update_channel = channel)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_conflict_strategy(
+    state: State<'_, SettingsState>,
+) -> Result<ConflictStrategy, AppError> {
+    Ok(state.get().conflict_strategy)
+}
+
+#[tauri::command]
+pub async fn set_conflict_strategy(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    strategy: ConflictStrategy,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.conflict_strategy = strategy)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_certificate_pinning(state: State<'_, SettingsState>) -> Result<bool, AppError> {
+    Ok(state.get().certificate_pinning)
+}
+
+#[tauri::command]
+pub async fn set_certificate_pinning(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| settings.certificate_pinning = enabled)?;
+    app.state::<crate::http_client::HttpClient>().set_pinning(enabled);
+    Ok(())
+}
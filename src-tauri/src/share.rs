@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// What to hand to the share sheet; at least one field must be set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareContent {
+    /// Subject line for targets that have one, such as email
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub url: Option<String>,
+    /// PNG image, base64-encoded or as a `data:` URL, e.g. a streak card drawn on a canvas
+    pub image: Option<String>,
+}
+
+impl ShareContent {
+    fn is_empty(&self) -> bool {
+        self.text.is_none() && self.url.is_none() && self.image.is_none()
+    }
+
+    /// Text and link combined, for targets that take a single message.
+    #[cfg(target_os = "android")]
+    fn message(&self) -> Option<String> {
+        match (&self.text, &self.url) {
+            (Some(text), Some(url)) => Some(format!("{}\n{}", text, url)),
+            (Some(text), None) => Some(text.clone()),
+            (None, Some(url)) => Some(url.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Decodes an image passed from the frontend, either plain base64 or a `data:` URL.
+pub fn decode_image(image: &str) -> Result<Vec<u8>, AppError> {
+    use base64::Engine;
+
+    let data = image.split_once(";base64,").map_or(image, |(_, data)| data);
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| AppError::invalid_input(format!("Invalid image: {}", e)))
+}
+
+/// Sends an `ACTION_SEND` intent through the system chooser. Images are written to the
+/// cache directory and passed through the app's `FileProvider`.
+#[cfg(target_os = "android")]
+fn present(_app: &AppHandle, content: ShareContent) -> Result<(), AppError> {
+    use jni::objects::{JClass, JObject, JString, JValue};
+    use jni::JNIEnv;
+
+    fn cache_dir(env: &mut JNIEnv, activity: &JObject) -> jni::errors::Result<String> {
+        let dir = env
+            .call_method(activity, "getCacheDir", "()Ljava/io/File;", &[])?
+            .l()?;
+        let path: JString = env
+            .call_method(&dir, "getAbsolutePath", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let path: String = env.get_string(&path)?.into();
+        Ok(path)
+    }
+
+    fn attach_image(
+        env: &mut JNIEnv,
+        activity: &JObject,
+        intent: &JObject,
+        path: &str,
+    ) -> jni::errors::Result<()> {
+        let path = env.new_string(path)?;
+        let file = env.new_object(
+            "java/io/File",
+            "(Ljava/lang/String;)V",
+            &[JValue::from(&path)],
+        )?;
+
+        // AndroidX classes are only visible through the app's class loader
+        let loader = env
+            .call_method(activity, "getClassLoader", "()Ljava/lang/ClassLoader;", &[])?
+            .l()?;
+        let class_name = env.new_string("androidx.core.content.FileProvider")?;
+        let provider: JClass = env
+            .call_method(
+                &loader,
+                "loadClass",
+                "(Ljava/lang/String;)Ljava/lang/Class;",
+                &[JValue::from(&class_name)],
+            )?
+            .l()?
+            .into();
+
+        let package: JString = env
+            .call_method(activity, "getPackageName", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let package: String = env.get_string(&package)?.into();
+        let authority = env.new_string(format!("{}.fileprovider", package))?;
+
+        let uri = env
+            .call_static_method(
+                provider,
+                "getUriForFile",
+                "(Landroid/content/Context;Ljava/lang/String;Ljava/io/File;)Landroid/net/Uri;",
+                &[
+                    JValue::from(activity),
+                    JValue::from(&authority),
+                    JValue::from(&file),
+                ],
+            )?
+            .l()?;
+
+        let stream = env.new_string("android.intent.extra.STREAM")?;
+        env.call_method(
+            intent,
+            "putExtra",
+            "(Ljava/lang/String;Landroid/os/Parcelable;)Landroid/content/Intent;",
+            &[JValue::from(&stream), JValue::from(&uri)],
+        )?;
+
+        // The chooser only passes the read grant on for URIs in the clip data
+        let label = env.new_string("")?;
+        let clip = env
+            .call_static_method(
+                "android/content/ClipData",
+                "newRawUri",
+                "(Ljava/lang/CharSequence;Landroid/net/Uri;)Landroid/content/ClipData;",
+                &[JValue::from(&label), JValue::from(&uri)],
+            )?
+            .l()?;
+        env.call_method(
+            intent,
+            "setClipData",
+            "(Landroid/content/ClipData;)V",
+            &[JValue::from(&clip)],
+        )?;
+
+        // FLAG_GRANT_READ_URI_PERMISSION
+        env.call_method(
+            intent,
+            "addFlags",
+            "(I)Landroid/content/Intent;",
+            &[JValue::Int(1)],
+        )?;
+        Ok(())
+    }
+
+    fn send(
+        env: &mut JNIEnv,
+        activity: &JObject,
+        content: &ShareContent,
+        image_path: Option<&str>,
+    ) -> jni::errors::Result<()> {
+        let action = env.new_string("android.intent.action.SEND")?;
+        let intent = env.new_object(
+            "android/content/Intent",
+            "(Ljava/lang/String;)V",
+            &[JValue::from(&action)],
+        )?;
+
+        let extras = [
+            ("android.intent.extra.SUBJECT", content.title.clone()),
+            ("android.intent.extra.TEXT", content.message()),
+        ];
+        for (name, value) in extras {
+            let Some(value) = value else {
+                continue;
+            };
+            let name = env.new_string(name)?;
+            let value = env.new_string(value)?;
+            env.call_method(
+                &intent,
+                "putExtra",
+                "(Ljava/lang/String;Ljava/lang/String;)Landroid/content/Intent;",
+                &[JValue::from(&name), JValue::from(&value)],
+            )?;
+        }
+
+        let mime_type = match image_path {
+            Some(path) => {
+                attach_image(env, activity, &intent, path)?;
+                "image/png"
+            }
+            None => "text/plain",
+        };
+        let mime_type = env.new_string(mime_type)?;
+        env.call_method(
+            &intent,
+            "setType",
+            "(Ljava/lang/String;)Landroid/content/Intent;",
+            &[JValue::from(&mime_type)],
+        )?;
+
+        let chooser_title = env.new_string(content.title.as_deref().unwrap_or(""))?;
+        let chooser = env
+            .call_static_method(
+                "android/content/Intent",
+                "createChooser",
+                "(Landroid/content/Intent;Ljava/lang/CharSequence;)Landroid/content/Intent;",
+                &[JValue::from(&intent), JValue::from(&chooser_title)],
+            )?
+            .l()?;
+        env.call_method(
+            activity,
+            "startActivity",
+            "(Landroid/content/Intent;)V",
+            &[JValue::from(&chooser)],
+        )?;
+
+        Ok(())
+    }
+
+    let image = content.image.as_deref().map(decode_image).transpose()?;
+
+    let context = ndk_context::android_context();
+    let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+        .map_err(|e| AppError::internal(format!("Failed to reach the Java VM: {}", e)))?;
+    let activity = unsafe { JObject::from_raw(context.context().cast()) };
+    let mut env = vm
+        .attach_current_thread()
+        .map_err(|e| AppError::internal(format!("Failed to attach to the Java VM: {}", e)))?;
+
+    let image_path = match image {
+        Some(bytes) => {
+            let dir = cache_dir(&mut env, &activity).map_err(|e| {
+                AppError::storage(format!("Failed to find the cache directory: {}", e))
+            })?;
+            let path = std::path::Path::new(&dir).join("share.png");
+            std::fs::write(&path, bytes)
+                .map_err(|e| AppError::storage(format!("Failed to write share image: {}", e)))?;
+            Some(path.to_string_lossy().into_owned())
+        }
+        None => None,
+    };
+
+    send(&mut env, &activity, &content, image_path.as_deref()).map_err(|e| {
+        // A pending Java exception would abort the next JNI call
+        let _ = env.exception_clear();
+        AppError::internal(format!("Failed to open share sheet: {}", e))
+    })
+}
+
+/// WebKit implements the Web Share API on top of the native share sheet, so the page's
+/// `navigator.share` is used rather than driving UIKit or AppKit directly.
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn present(app: &AppHandle, content: ShareContent) -> Result<(), AppError> {
+    use tauri::Manager;
+
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| AppError::invalid_state("Main window is not open"))?;
+    let content = serde_json::to_string(&content)
+        .map_err(|e| AppError::internal(format!("Failed to encode share content: {}", e)))?;
+
+    let script = format!(
+        r#"
+        (async function() {{
+            const content = {content};
+            const data = {{}};
+            if (content.title) data.title = content.title;
+            if (content.text) data.text = content.text;
+            if (content.url) data.url = content.url;
+            if (content.image) {{
+                const url = content.image.startsWith('data:')
+                    ? content.image
+                    : 'data:image/png;base64,' + content.image;
+                const blob = await (await fetch(url)).blob();
+                data.files = [new File([blob], 'share.png', {{ type: 'image/png' }})];
+            }}
+            try {{
+                await navigator.share(data);
+            }} catch (error) {{
+                if (error.name !== 'AbortError') console.error('Share failed:', error);
+            }}
+        }})();
+        "#,
+        content = content,
+    );
+
+    window
+        .eval(script)
+        .map_err(|e| AppError::internal(format!("Failed to open share sheet: {}", e)))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios", target_os = "macos")))]
+fn present(_app: &AppHandle, _content: ShareContent) -> Result<(), AppError> {
+    Err(AppError::unsupported(
+        "Sharing is not supported on this platform",
+    ))
+}
+
+/// Opens the system share sheet with text, a link and/or an image.
+#[tauri::command]
+pub async fn share(app: AppHandle, content: ShareContent) -> Result<(), AppError> {
+    if content.is_empty() {
+        return Err(AppError::invalid_input("Nothing to share"));
+    }
+
+    present(&app, content)
+}
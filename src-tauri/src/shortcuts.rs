@@ -0,0 +1,91 @@
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, State, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+/// Global shortcut plugin whose only action is the quick check-in.
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                crate::tray::emit_to_webview(app, crate::tray::QUICK_CHECKIN_EVENT);
+            }
+        })
+        .build()
+}
+
+pub fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))
+}
+
+/// Registers the shortcut from the active profile's settings in place of any other.
+pub fn apply(app: &AppHandle) {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        tracing::warn!("Failed to unregister shortcuts: {}", e);
+    }
+
+    let Some(shortcut) = crate::settings::current(app).quick_checkin_shortcut else {
+        return;
+    };
+
+    if let Err(e) = parse(&shortcut).and_then(|parsed| {
+        global_shortcut
+            .register(parsed)
+            .map_err(|e| format!("Failed to register shortcut '{}': {}", shortcut, e))
+    }) {
+        tracing::warn!("{}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_shortcut(state: State<'_, SettingsState>) -> Result<Option<String>, AppError> {
+    Ok(state.get().quick_checkin_shortcut)
+}
+
+/// Sets the quick check-in hotkey, e.g. `CommandOrControl+Shift+L`.
+#[tauri::command]
+pub async fn register_shortcut(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    shortcut: String,
+) -> Result<(), AppError> {
+    let shortcut = shortcut.trim().to_string();
+    let parsed = parse(&shortcut).map_err(AppError::invalid_input)?;
+
+    let global_shortcut = app.global_shortcut();
+    global_shortcut
+        .unregister_all()
+        .map_err(|e| AppError::internal(format!("Failed to unregister shortcuts: {}", e)))?;
+
+    if let Err(e) = global_shortcut.register(parsed) {
+        // Another application may own the combination; keep the previous one working
+        apply(&app);
+        return Err(AppError::invalid_input(format!(
+            "Failed to register shortcut '{}': {}",
+            shortcut, e
+        )));
+    }
+
+    state.update(&app, |settings| {
+        settings.quick_checkin_shortcut = Some(shortcut)
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unregister_shortcut(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+) -> Result<(), AppError> {
+    app.global_shortcut()
+        .unregister_all()
+        .map_err(|e| AppError::internal(format!("Failed to unregister shortcuts: {}", e)))?;
+
+    state.update(&app, |settings| settings.quick_checkin_shortcut = None)?;
+    Ok(())
+}
@@ -0,0 +1,635 @@
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+const MIN_RATE: f32 = 0.5;
+const MAX_RATE: f32 = 2.0;
+
+/// Hours during which nothing is read aloud, in local `HH:MM` time. A range whose end is
+/// before its start runs past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    fn contains(&self, time: NaiveTime) -> Result<bool, String> {
+        let start = crate::reminders::parse_time(&self.start)?;
+        let end = crate::reminders::parse_time(&self.end)?;
+
+        Ok(if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        })
+    }
+}
+
+/// How text is read aloud, persisted with the other settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeechSettings {
+    /// Voice id from `list_voices`; the system default when `None`
+    pub voice: Option<String>,
+    /// Speaking rate relative to normal speed, from 0.5 to 2.0
+    pub rate: f32,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for SpeechSettings {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            rate: 1.0,
+            quiet_hours: None,
+        }
+    }
+}
+
+impl SpeechSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        check_rate(self.rate)?;
+        if let Some(quiet_hours) = &self.quiet_hours {
+            quiet_hours
+                .contains(NaiveTime::MIN)
+                .map_err(AppError::invalid_input)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Overrides for a single `speak` call
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SpeakOptions {
+    pub voice: Option<String>,
+    pub rate: Option<f32>,
+    /// Speaks even during quiet hours, e.g. to preview a voice in settings
+    pub ignore_quiet_hours: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    /// BCP 47 language tag such as `en-US`, when the engine reports one
+    pub language: Option<String>,
+}
+
+fn check_rate(rate: f32) -> Result<(), AppError> {
+    if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+        return Err(AppError::invalid_input(format!(
+            "Speaking rate must be between {} and {}",
+            MIN_RATE, MAX_RATE
+        )));
+    }
+
+    Ok(())
+}
+
+/// Whether the settings' quiet hours cover the current local time.
+fn is_quiet_now(settings: &SpeechSettings) -> bool {
+    let Some(quiet_hours) = &settings.quiet_hours else {
+        return false;
+    };
+
+    quiet_hours
+        .contains(Local::now().time())
+        .unwrap_or_else(|e| {
+            tracing::warn!("Ignoring quiet hours: {}", e);
+            false
+        })
+}
+
+/// Reads a reminder aloud in the background unless quiet hours are on.
+pub fn announce(app: &AppHandle, text: String) {
+    let settings = crate::settings::current(app).speech;
+    if is_quiet_now(&settings) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = engine::speak(&app, &text, settings.voice.as_deref(), settings.rate) {
+            tracing::warn!("Failed to announce reminder: {}", e);
+        }
+    });
+}
+
+/// speech-dispatcher, which desktop Linux distributions ship for their screen readers,
+/// with eSpeak NG as a fallback.
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod engine {
+    use std::io::ErrorKind;
+    use std::process::{Command, Stdio};
+    use tauri::AppHandle;
+
+    use super::Voice;
+
+    /// eSpeak NG's normal speed, in words per minute
+    const ESPEAK_WORDS_PER_MINUTE: f32 = 175.0;
+
+    pub fn speak(
+        _app: &AppHandle,
+        text: &str,
+        voice: Option<&str>,
+        rate: f32,
+    ) -> Result<(), String> {
+        // spd-say takes a rate from -100 to 100 and returns once the text is queued
+        let mut command = Command::new("spd-say");
+        command.arg("--rate").arg(
+            (((rate - 1.0) * 100.0).round() as i32)
+                .clamp(-100, 100)
+                .to_string(),
+        );
+        if let Some(voice) = voice {
+            command.arg("--synthesis-voice").arg(voice);
+        }
+
+        match command.arg("--").arg(text).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => return Err(format!("spd-say failed with {}", status)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to run spd-say: {}", e)),
+        }
+
+        let mut command = Command::new("espeak-ng");
+        command
+            .arg("-s")
+            .arg(((ESPEAK_WORDS_PER_MINUTE * rate).round() as u32).to_string());
+        if let Some(voice) = voice {
+            command.arg("-v").arg(voice);
+        }
+
+        // eSpeak NG speaks before exiting, so it is waited on off the caller's thread
+        let mut child = command
+            .arg("--")
+            .arg(text)
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => {
+                    "No speech engine found; install speech-dispatcher or espeak-ng".to_string()
+                }
+                _ => format!("Failed to run espeak-ng: {}", e),
+            })?;
+        std::thread::spawn(move || child.wait());
+
+        Ok(())
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let output = match Command::new("spd-say")
+            .arg("--list-synthesis-voices")
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to list voices: {}", e)),
+        };
+
+        // A header, then one voice per line as name, language and variant; names may
+        // contain spaces
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [name @ .., language, _variant] = fields.as_slice() else {
+                    return None;
+                };
+                let name = name.join(" ");
+                Some(Voice {
+                    id: name.clone(),
+                    name,
+                    language: Some(language.to_string()),
+                })
+            })
+            .filter(|voice| !voice.name.is_empty())
+            .collect())
+    }
+}
+
+#[cfg(windows)]
+mod engine {
+    use std::time::{Duration, Instant};
+    use tauri::AppHandle;
+    use windows::core::HSTRING;
+    use windows::Media::Core::MediaSource;
+    use windows::Media::Playback::{MediaPlaybackState, MediaPlayer};
+    use windows::Media::SpeechSynthesis::SpeechSynthesizer;
+
+    use super::Voice;
+
+    /// Longest announcement waited on before the player is released
+    const MAX_PLAYBACK: Duration = Duration::from_secs(120);
+    const PLAYBACK_POLL: Duration = Duration::from_millis(100);
+
+    /// Keeps the player alive until it stops, since releasing it ends playback.
+    fn play_until_done(player: MediaPlayer) {
+        let deadline = Instant::now() + MAX_PLAYBACK;
+        let mut started = false;
+
+        while Instant::now() < deadline {
+            std::thread::sleep(PLAYBACK_POLL);
+            let Ok(state) = player
+                .PlaybackSession()
+                .and_then(|session| session.PlaybackState())
+            else {
+                return;
+            };
+
+            match state {
+                MediaPlaybackState::Playing | MediaPlaybackState::Buffering => started = true,
+                MediaPlaybackState::Opening => {}
+                _ if started => return,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn speak(
+        _app: &AppHandle,
+        text: &str,
+        voice: Option<&str>,
+        rate: f32,
+    ) -> Result<(), String> {
+        let failed = |e: windows::core::Error| format!("Text-to-speech failed: {}", e);
+
+        let synthesizer = SpeechSynthesizer::new().map_err(failed)?;
+        if let Some(voice) = voice {
+            let voices = SpeechSynthesizer::AllVoices().map_err(failed)?;
+            let chosen = voices
+                .into_iter()
+                .find(|info| info.Id().is_ok_and(|id| id.to_string_lossy() == voice));
+            match chosen {
+                Some(info) => synthesizer.SetVoice(&info).map_err(failed)?,
+                None => tracing::warn!("Voice '{}' is not installed", voice),
+            }
+        }
+        synthesizer
+            .Options()
+            .and_then(|options| options.SetSpeakingRate(rate as f64))
+            .map_err(failed)?;
+
+        let stream = synthesizer
+            .SynthesizeTextToStreamAsync(&HSTRING::from(text))
+            .and_then(|operation| operation.get())
+            .map_err(failed)?;
+        let source = stream
+            .ContentType()
+            .and_then(|content_type| MediaSource::CreateFromStream(&stream, &content_type))
+            .map_err(failed)?;
+
+        let player = MediaPlayer::new().map_err(failed)?;
+        player.SetSource(&source).map_err(failed)?;
+        player.Play().map_err(failed)?;
+        std::thread::spawn(move || play_until_done(player));
+
+        Ok(())
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let voices =
+            SpeechSynthesizer::AllVoices().map_err(|e| format!("Failed to list voices: {}", e))?;
+
+        Ok(voices
+            .into_iter()
+            .filter_map(|info| {
+                Some(Voice {
+                    id: info.Id().ok()?.to_string_lossy(),
+                    name: info.DisplayName().ok()?.to_string_lossy(),
+                    language: info
+                        .Language()
+                        .ok()
+                        .map(|language| language.to_string_lossy()),
+                })
+            })
+            .collect())
+    }
+}
+
+/// AVSpeechSynthesizer, shared by macOS and iOS.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod engine {
+    use objc2::rc::Retained;
+    use objc2_avf_audio::{
+        AVSpeechSynthesisVoice, AVSpeechSynthesizer, AVSpeechUtterance,
+        AVSpeechUtteranceDefaultSpeechRate, AVSpeechUtteranceMaximumSpeechRate,
+        AVSpeechUtteranceMinimumSpeechRate,
+    };
+    use objc2_foundation::NSString;
+    use std::cell::OnceCell;
+    use tauri::AppHandle;
+
+    use super::Voice;
+
+    thread_local! {
+        /// Created on the main thread and kept there, so utterances queue behind each
+        /// other and are not cut off when the synthesizer would otherwise be released
+        static SYNTHESIZER: OnceCell<Retained<AVSpeechSynthesizer>> = const { OnceCell::new() };
+    }
+
+    pub fn speak(
+        app: &AppHandle,
+        text: &str,
+        voice: Option<&str>,
+        rate: f32,
+    ) -> Result<(), String> {
+        let text = text.to_string();
+        let voice = voice.map(str::to_string);
+
+        app.run_on_main_thread(move || {
+            SYNTHESIZER.with(|synthesizer| {
+                let synthesizer = synthesizer.get_or_init(|| unsafe { AVSpeechSynthesizer::new() });
+
+                unsafe {
+                    let utterance =
+                        AVSpeechUtterance::speechUtteranceWithString(&NSString::from_str(&text));
+                    if let Some(voice) = voice {
+                        match AVSpeechSynthesisVoice::voiceWithIdentifier(&NSString::from_str(
+                            &voice,
+                        )) {
+                            Some(voice) => utterance.setVoice(Some(&voice)),
+                            None => tracing::warn!("Voice '{}' is not installed", voice),
+                        }
+                    }
+                    utterance.setRate((AVSpeechUtteranceDefaultSpeechRate * rate).clamp(
+                        AVSpeechUtteranceMinimumSpeechRate,
+                        AVSpeechUtteranceMaximumSpeechRate,
+                    ));
+                    synthesizer.speakUtterance(&utterance);
+                }
+            });
+        })
+        .map_err(|e| format!("Text-to-speech failed: {}", e))
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        let voices = unsafe { AVSpeechSynthesisVoice::speechVoices() };
+
+        Ok(voices
+            .iter()
+            .map(|voice| unsafe {
+                Voice {
+                    id: voice.identifier().to_string(),
+                    name: voice.name().to_string(),
+                    language: Some(voice.language().to_string()),
+                }
+            })
+            .collect())
+    }
+}
+
+/// The platform's `TextToSpeech` engine, reached through JNI.
+#[cfg(target_os = "android")]
+mod engine {
+    use jni::objects::{GlobalRef, JObject, JObjectArray, JString, JValue};
+    use jni::JNIEnv;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use tauri::AppHandle;
+
+    use super::Voice;
+
+    const SUCCESS: i32 = 0;
+    const QUEUE_ADD: i32 = 1;
+
+    /// The engine connects to the system service after it is created, and cannot speak
+    /// or list voices until then
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+    const CONNECT_POLL: Duration = Duration::from_millis(200);
+
+    /// Created once and kept, since connecting to the service takes a moment
+    static ENGINE: Mutex<Option<GlobalRef>> = Mutex::new(None);
+
+    fn with_env<T>(
+        work: impl FnOnce(&mut JNIEnv, &JObject) -> jni::errors::Result<T>,
+    ) -> Result<T, String> {
+        let context = ndk_context::android_context();
+        let vm = unsafe { jni::JavaVM::from_raw(context.vm().cast()) }
+            .map_err(|e| format!("Failed to reach the Java VM: {}", e))?;
+        let activity = unsafe { JObject::from_raw(context.context().cast()) };
+        let mut env = vm
+            .attach_current_thread()
+            .map_err(|e| format!("Failed to attach to the Java VM: {}", e))?;
+
+        let result = work(&mut env, &activity);
+        if let Err(jni::errors::Error::JavaException) = result {
+            let _ = env.exception_clear();
+        }
+        result.map_err(|e| format!("Text-to-speech failed: {}", e))
+    }
+
+    fn engine(env: &mut JNIEnv, context: &JObject) -> jni::errors::Result<GlobalRef> {
+        let mut engine = ENGINE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(engine) = engine.as_ref() {
+            return Ok(engine.clone());
+        }
+
+        let created = env.new_object(
+            "android/speech/tts/TextToSpeech",
+            "(Landroid/content/Context;Landroid/speech/tts/TextToSpeech$OnInitListener;)V",
+            &[JValue::from(context), JValue::from(&JObject::null())],
+        )?;
+        let created = env.new_global_ref(created)?;
+        *engine = Some(created.clone());
+        Ok(created)
+    }
+
+    fn string_method(
+        env: &mut JNIEnv,
+        object: &JObject,
+        name: &str,
+    ) -> jni::errors::Result<String> {
+        let value: JString = env
+            .call_method(object, name, "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let value: String = env.get_string(&value)?.into();
+        Ok(value)
+    }
+
+    /// The engine's voices once it has connected, or `None` if it does not in time.
+    fn wait_for_voices<'local>(
+        env: &mut JNIEnv<'local>,
+        engine: &JObject,
+    ) -> jni::errors::Result<Option<Vec<JObject<'local>>>> {
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+
+        loop {
+            let set = env
+                .call_method(engine, "getVoices", "()Ljava/util/Set;", &[])?
+                .l()?;
+            if !set.is_null() {
+                let array: JObjectArray = env
+                    .call_method(&set, "toArray", "()[Ljava/lang/Object;", &[])?
+                    .l()?
+                    .into();
+                let length = env.get_array_length(&array)?;
+                return (0..length)
+                    .map(|index| env.get_object_array_element(&array, index))
+                    .collect::<Result<_, _>>()
+                    .map(Some);
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(CONNECT_POLL);
+        }
+    }
+
+    pub fn speak(
+        _app: &AppHandle,
+        text: &str,
+        voice: Option<&str>,
+        rate: f32,
+    ) -> Result<(), String> {
+        let spoken = with_env(|env, context| {
+            let engine = engine(env, context)?;
+            let Some(voices) = wait_for_voices(env, engine.as_obj())? else {
+                return Ok(false);
+            };
+
+            if let Some(voice) = voice {
+                let mut chosen = None;
+                for candidate in voices {
+                    if string_method(env, &candidate, "getName")? == voice {
+                        chosen = Some(candidate);
+                        break;
+                    }
+                }
+
+                match chosen {
+                    Some(chosen) => {
+                        env.call_method(
+                            &engine,
+                            "setVoice",
+                            "(Landroid/speech/tts/Voice;)I",
+                            &[JValue::from(&chosen)],
+                        )?;
+                    }
+                    None => tracing::warn!("Voice '{}' is not installed", voice),
+                }
+            }
+            env.call_method(&engine, "setSpeechRate", "(F)I", &[JValue::Float(rate)])?;
+
+            let text = env.new_string(text)?;
+            let utterance_id = env.new_string(format!("routine-{}", fastrand::u64(..)))?;
+            let result = env
+                .call_method(
+                    &engine,
+                    "speak",
+                    "(Ljava/lang/CharSequence;ILandroid/os/Bundle;Ljava/lang/String;)I",
+                    &[
+                        JValue::from(&text),
+                        JValue::Int(QUEUE_ADD),
+                        JValue::from(&JObject::null()),
+                        JValue::from(&utterance_id),
+                    ],
+                )?
+                .i()?;
+            Ok(result == SUCCESS)
+        })?;
+
+        if !spoken {
+            return Err("The text-to-speech engine is not available".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn voices() -> Result<Vec<Voice>, String> {
+        with_env(|env, context| {
+            let engine = engine(env, context)?;
+            let Some(voices) = wait_for_voices(env, engine.as_obj())? else {
+                return Ok(Vec::new());
+            };
+
+            voices
+                .iter()
+                .map(|voice| {
+                    let name = string_method(env, voice, "getName")?;
+                    let locale = env
+                        .call_method(voice, "getLocale", "()Ljava/util/Locale;", &[])?
+                        .l()?;
+                    let language = string_method(env, &locale, "toLanguageTag")?;
+                    Ok(Voice {
+                        id: name.clone(),
+                        name,
+                        language: Some(language),
+                    })
+                })
+                .collect()
+        })
+    }
+}
+
+/// Reads text aloud with the system speech engine, e.g. "Time for your evening review".
+/// Returns `false` without speaking during quiet hours unless `ignore_quiet_hours` is set.
+#[tauri::command]
+pub async fn speak(
+    app: AppHandle,
+    text: String,
+    options: Option<SpeakOptions>,
+) -> Result<bool, AppError> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(AppError::invalid_input("Nothing to speak"));
+    }
+
+    let options = options.unwrap_or_default();
+    let settings = crate::settings::current(&app).speech;
+    if !options.ignore_quiet_hours && is_quiet_now(&settings) {
+        return Ok(false);
+    }
+
+    let rate = options.rate.unwrap_or(settings.rate);
+    check_rate(rate)?;
+    let voice = options.voice.or(settings.voice);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        engine::speak(&app, &text, voice.as_deref(), rate)
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Text-to-speech failed: {}", e)))?
+    .map_err(AppError::internal)?;
+
+    Ok(true)
+}
+
+/// Lists the voices the system speech engine offers.
+#[tauri::command]
+pub async fn list_voices() -> Result<Vec<Voice>, AppError> {
+    tauri::async_runtime::spawn_blocking(engine::voices)
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to list voices: {}", e)))?
+        .map_err(AppError::internal)
+}
+
+#[tauri::command]
+pub async fn get_speech_settings(
+    state: State<'_, SettingsState>,
+) -> Result<SpeechSettings, AppError> {
+    Ok(state.get().speech)
+}
+
+#[tauri::command]
+pub async fn set_speech_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: SpeechSettings,
+) -> Result<(), AppError> {
+    settings.validate()?;
+
+    state.update(&app, |current| current.speech = settings)?;
+    Ok(())
+}
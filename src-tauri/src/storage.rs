@@ -0,0 +1,340 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Version of the shape of persisted state files. Bump it along with a migration in
+/// [`MIGRATIONS`] whenever a stored type changes in a way serde defaults cannot absorb.
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
+/// Files written before versioning hold the bare data, which is the version 1 shape.
+const UNVERSIONED: u32 = 0;
+
+/// Upgrades a file's data from one schema version to the next, in place
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Registered upgrades as `(file, from_version, migration)`, applied in version order
+/// to files older than [`STATE_SCHEMA_VERSION`]. Versions without an entry for a file
+/// left its shape unchanged.
+const MIGRATIONS: &[(&str, u32, Migration)] = &[];
+
+/// Envelope every state file is written in
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    schema_version: u32,
+    data: &'a T,
+}
+
+/// Contents of a state file, brought up to the current schema
+pub struct Decoded<T> {
+    pub value: T,
+    /// The file was older than the current schema and should be rewritten
+    pub upgraded: bool,
+}
+
+/// Splits stored JSON into its schema version and data. Legacy files are bare data.
+fn unwrap(stored: Value) -> (u32, Value) {
+    match stored {
+        Value::Object(mut map)
+            if map.len() == 2
+                && map.contains_key("data")
+                && map.get("schema_version").is_some_and(Value::is_u64) =>
+        {
+            let version = map["schema_version"].as_u64().unwrap_or_default();
+            let data = map.remove("data").unwrap_or_default();
+            (u32::try_from(version).unwrap_or(u32::MAX), data)
+        }
+        data => (UNVERSIONED, data),
+    }
+}
+
+/// Schema version of a state file's contents, or `None` if it is not valid JSON.
+pub fn version_of(content: &str) -> Option<u32> {
+    serde_json::from_str(content)
+        .ok()
+        .map(|stored| unwrap(stored).0)
+}
+
+fn migrate(name: &str, data: &mut Value, from: u32) -> Result<(), String> {
+    for version in from..STATE_SCHEMA_VERSION {
+        for (_, _, migration) in MIGRATIONS
+            .iter()
+            .filter(|(file, since, _)| *file == name && *since == version)
+        {
+            migration(data).map_err(|e| {
+                format!(
+                    "Failed to migrate {} file from schema {}: {}",
+                    name, version, e
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a state file named `name`, running the migrations it is missing. Fails for
+/// files written by a newer version of the app rather than dropping what it added.
+pub fn decode<T: DeserializeOwned>(name: &str, content: &str) -> Result<Decoded<T>, String> {
+    let stored: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse {} file: {}", name, e))?;
+    let (version, mut data) = unwrap(stored);
+
+    if version > STATE_SCHEMA_VERSION {
+        return Err(format!(
+            "The {} file was written by a newer version of the app (schema {})",
+            name, version
+        ));
+    }
+    migrate(name, &mut data, version)?;
+
+    let value = serde_json::from_value(data)
+        .map_err(|e| format!("Failed to parse {} file: {}", name, e))?;
+    Ok(Decoded {
+        value,
+        upgraded: version < STATE_SCHEMA_VERSION,
+    })
+}
+
+/// Serializes `value` in the envelope of the current schema version.
+pub fn encode<T: Serialize>(name: &str, value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(&Envelope {
+        schema_version: STATE_SCHEMA_VERSION,
+        data: value,
+    })
+    .map_err(|e| format!("Failed to serialize {}: {}", name, e))
+}
+
+/// Replaces the file at `path` so that a crash or power loss leaves either the old
+/// contents or the new ones, never a mix: the data goes to a uniquely named `.partial`
+/// file next to it, is flushed to disk, and is renamed over the original.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    replace_file(path, contents, |from, to| std::fs::rename(from, to))
+}
+
+/// Name of the temporary file `path` is written to. The process id and a random part
+/// keep concurrent writers, here or in another instance of the app, off each other's
+/// files, and keep a file left over by a crash from getting in the way.
+fn partial_path(path: &Path) -> std::io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "No file name"))?;
+
+    let mut random = [0u8; 8];
+    SystemRandom::new()
+        .fill(&mut random)
+        .map_err(|_| std::io::Error::other("Failed to generate a temporary file name"))?;
+
+    let mut partial_name = file_name.to_os_string();
+    partial_name.push(format!(
+        ".{}-{:016x}.partial",
+        std::process::id(),
+        u64::from_le_bytes(random)
+    ));
+    Ok(path.with_file_name(partial_name))
+}
+
+fn replace_file(
+    path: &Path,
+    contents: &[u8],
+    rename: impl FnOnce(&Path, &Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let partial = partial_path(path)?;
+
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&partial)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| rename(&partial, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result?;
+
+    // The rename itself is only durable once the directory entry is flushed. Windows
+    // cannot open directories as files, and NTFS journals the rename anyway.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Runs file or database work on the blocking thread pool, so commands that touch the
+/// disk never stall the async runtime the rest of the app shares.
+pub async fn run_blocking<T: Send + 'static, E: From<AppError> + Send + 'static>(
+    f: impl FnOnce() -> Result<T, E> + Send + 'static,
+) -> Result<T, E> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::internal(format!("Storage task failed: {}", e)))?
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StateFileVersion {
+    pub name: String,
+    /// 0 for files written before versioning, `None` if the file cannot be parsed
+    pub schema_version: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageVersion {
+    /// Schema version this build writes state files in
+    pub state_schema_version: u32,
+    /// State files of the active profile as found on disk
+    pub state_files: Vec<StateFileVersion>,
+    /// Migrations applied to the active profile's cache database
+    pub cache_schema_version: u32,
+    /// Migrations this build knows for the cache database
+    pub cache_latest_version: u32,
+}
+
+fn state_file_versions(app: &AppHandle) -> Result<Vec<StateFileVersion>, String> {
+    let state_dir = crate::profiles::active_data_dir(app)?.join("state");
+    let entries = match std::fs::read_dir(&state_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read state directory: {}", e)),
+    };
+
+    let mut files: Vec<StateFileVersion> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let schema_version = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| version_of(&content));
+            Some(StateFileVersion {
+                name,
+                schema_version,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(files)
+}
+
+/// Reports the schema versions of the persisted data, for diagnostics.
+#[tauri::command]
+pub async fn get_storage_version(app: AppHandle) -> Result<StorageVersion, AppError> {
+    run_blocking(move || {
+        let cache_schema_version =
+            crate::cache_store::with_store(&app, |store| store.schema_version())
+                .map_err(AppError::storage)?;
+
+        Ok(StorageVersion {
+            state_schema_version: STATE_SCHEMA_VERSION,
+            state_files: state_file_versions(&app).map_err(AppError::storage)?,
+            cache_schema_version,
+            cache_latest_version: crate::cache_store::LATEST_SCHEMA_VERSION,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".partial"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(partial_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn write_atomic_ignores_leftover_partial_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(dir.path().join("settings.json.partial"), b"stale").unwrap();
+        std::fs::write(
+            dir.path().join("settings.json.1-0000000000000000.partial"),
+            b"stale",
+        )
+        .unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert_eq!(
+            partial_files(dir.path()),
+            [
+                "settings.json.1-0000000000000000.partial",
+                "settings.json.partial"
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_rename_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        write_atomic(&path, b"old").unwrap();
+
+        let result = replace_file(&path, b"new", |_, _| {
+            Err(std::io::Error::other("injected failure"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "injected failure");
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert!(partial_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn write_atomic_over_directory_fails_cleanly() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("inside"), b"kept").unwrap();
+
+        assert!(write_atomic(&path, b"new").is_err());
+
+        assert_eq!(std::fs::read(path.join("inside")).unwrap(), b"kept");
+        assert!(partial_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn concurrent_writers_use_separate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let payloads: Vec<Vec<u8>> = (0..8).map(|i| vec![b'a' + i; 64 * 1024]).collect();
+
+        std::thread::scope(|scope| {
+            for payload in &payloads {
+                let path = &path;
+                scope.spawn(move || write_atomic(path, payload).unwrap());
+            }
+        });
+
+        let written = std::fs::read(&path).unwrap();
+        assert!(payloads.contains(&written));
+        assert!(partial_files(dir.path()).is_empty());
+    }
+}
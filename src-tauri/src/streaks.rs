@@ -0,0 +1,177 @@
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Field holding the day of a check-in in cached documents, unless the caller names another
+const DEFAULT_DATE_FIELD: &str = "date";
+
+/// One day's check-in; only completed ones count towards streaks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckInEntry {
+    /// Local calendar day in `YYYY-MM-DD` format
+    pub date: String,
+    #[serde(default = "completed_by_default")]
+    pub completed: bool,
+}
+
+fn completed_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreakSummary {
+    /// Completed days in the streak that is still alive today, or 0
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub total_completed: u32,
+    pub last_completed: Option<String>,
+    /// Missed days forgiven within the current streak
+    pub grace_days_used: u32,
+    /// Share of each weekday completed since the first check-in, Monday first
+    pub weekday_completion_rates: [f64; 7],
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|e| format!("Invalid check-in date '{}': {}", date, e))
+}
+
+/// Computes streaks over completed days. Up to `grace_days` missed days between two
+/// completions keep a streak going; a streak is current while today's check-in can
+/// still extend it.
+pub fn summarize(days: &BTreeSet<NaiveDate>, today: NaiveDate, grace_days: u32) -> StreakSummary {
+    let max_gap = i64::from(grace_days) + 1;
+    let mut summary = StreakSummary {
+        total_completed: days.len() as u32,
+        last_completed: days.last().map(|day| day.format(DATE_FORMAT).to_string()),
+        ..Default::default()
+    };
+
+    let mut run = 0u32;
+    let mut run_grace = 0u32;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in days {
+        match previous {
+            Some(prev) if (day - prev).num_days() <= max_gap => {
+                run += 1;
+                run_grace += ((day - prev).num_days() - 1) as u32;
+            }
+            _ => {
+                run = 1;
+                run_grace = 0;
+            }
+        }
+        summary.longest_streak = summary.longest_streak.max(run);
+        previous = Some(day);
+    }
+
+    if previous.is_some_and(|last| (today - last).num_days() <= max_gap) {
+        summary.current_streak = run;
+        summary.grace_days_used = run_grace;
+    }
+
+    if let Some(&first) = days.first() {
+        let mut scheduled = [0u32; 7];
+        let mut completed = [0u32; 7];
+
+        for day in first.iter_days().take_while(|day| *day <= today) {
+            let weekday = day.weekday().num_days_from_monday() as usize;
+            scheduled[weekday] += 1;
+            if days.contains(&day) {
+                completed[weekday] += 1;
+            }
+        }
+
+        for weekday in 0..7 {
+            if scheduled[weekday] > 0 {
+                summary.weekday_completion_rates[weekday] =
+                    f64::from(completed[weekday]) / f64::from(scheduled[weekday]);
+            }
+        }
+    }
+
+    summary
+}
+
+fn completed_days(
+    entries: &[CheckInEntry],
+    today: NaiveDate,
+) -> Result<BTreeSet<NaiveDate>, String> {
+    let mut days = BTreeSet::new();
+    for entry in entries.iter().filter(|entry| entry.completed) {
+        let day = parse_date(&entry.date)?;
+        // Future-dated entries cannot be part of a streak yet
+        if day <= today {
+            days.insert(day);
+        }
+    }
+    Ok(days)
+}
+
+/// Reads check-ins out of a cached document: either an array of dates, or an array of
+/// objects carrying the date in `date_field` and an optional `completed` flag.
+fn entries_from_document(data: &serde_json::Value, date_field: &str) -> Vec<CheckInEntry> {
+    let Some(items) = crate::document_records(data) else {
+        return Vec::new();
+    };
+
+    items
+        .iter()
+        .filter_map(|item| match item {
+            serde_json::Value::String(date) => Some(CheckInEntry {
+                date: date.clone(),
+                completed: true,
+            }),
+            serde_json::Value::Object(object) => {
+                let date = object.get(date_field)?.as_str()?;
+                Some(CheckInEntry {
+                    // Timestamps are cut down to their calendar day
+                    date: date.get(..10).unwrap_or(date).to_string(),
+                    completed: object
+                        .get("completed")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(true),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn compute_streaks(
+    entries: Vec<CheckInEntry>,
+    grace_days: Option<u32>,
+) -> Result<StreakSummary, AppError> {
+    let today = Local::now().date_naive();
+    let days = completed_days(&entries, today).map_err(AppError::invalid_input)?;
+    Ok(summarize(&days, today, grace_days.unwrap_or(0)))
+}
+
+/// Computes streaks over the check-ins cached under `key`, without touching the network.
+#[tauri::command]
+pub async fn get_streak_summary(
+    app: AppHandle,
+    key: String,
+    grace_days: Option<u32>,
+    date_field: Option<String>,
+) -> Result<StreakSummary, AppError> {
+    let cached = crate::load_local_data(app, key.clone())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No cached check-ins for '{}'", key)))?;
+
+    let entries = entries_from_document(
+        &cached.data,
+        date_field.as_deref().unwrap_or(DEFAULT_DATE_FIELD),
+    );
+
+    let today = Local::now().date_naive();
+    let days = completed_days(&entries, today).map_err(AppError::corrupt_data)?;
+    Ok(summarize(&days, today, grace_days.unwrap_or(0)))
+}
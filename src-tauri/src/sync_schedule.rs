@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, TimeZone};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::AppError;
+use crate::progress::ProgressReporter;
+use crate::retry::RetryOptions;
+
+const SYNC_SCHEDULE_FILE: &str = "sync_schedule";
+
+/// Emitted after every scheduled or [`sync_now`] sync with a [`SyncSummary`]
+pub const SYNC_COMPLETED_EVENT: &str = "sync-completed";
+
+/// How often the background runner looks for a due sync
+const SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A key refreshed by the scheduled sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncTarget {
+    pub key: String,
+    pub url: String,
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SyncScheduleFile {
+    /// Five-field cron expression in local time, or `None` when syncing is off
+    expression: Option<String>,
+    targets: Vec<SyncTarget>,
+    /// When the last slot was handled, whether it ran or was skipped while offline
+    last_run: Option<i64>,
+    /// When the schedule was set, so a new schedule waits for its first slot
+    updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncSchedule {
+    pub expression: Option<String>,
+    pub targets: Vec<SyncTarget>,
+    pub last_run: Option<i64>,
+    pub next_run: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncFailure {
+    pub key: String,
+    pub error: String,
+}
+
+/// Outcome of one scheduled sync
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncSummary {
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// Keys refreshed from the server
+    pub refreshed: Vec<String>,
+    /// Keys whose refresh failed and which still hold their cached copy
+    pub from_cache: Vec<String>,
+    pub failed: Vec<SyncFailure>,
+}
+
+fn parse_expression(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression)
+        .parse()
+        .map_err(|e| format!("Invalid sync schedule '{}': {}", expression, e))
+}
+
+/// First slot after the last handled one, or after the schedule was set.
+fn next_run(schedule: &SyncScheduleFile) -> Option<DateTime<Local>> {
+    let cron = parse_expression(schedule.expression.as_deref()?).ok()?;
+    let after = Local
+        .timestamp_opt(schedule.last_run.unwrap_or(schedule.updated_at), 0)
+        .single()?;
+
+    cron.find_next_occurrence(&after, false).ok()
+}
+
+async fn sync_targets(
+    app: &AppHandle,
+    targets: Vec<SyncTarget>,
+    progress: &ProgressReporter,
+) -> SyncSummary {
+    let started_at = chrono::Utc::now().timestamp();
+    let retry = RetryOptions::default();
+    let total = targets.len() as u64;
+
+    let mut refreshed = Vec::new();
+    let mut from_cache = Vec::new();
+    let mut failed = Vec::new();
+
+    for (index, target) in targets.into_iter().enumerate() {
+        progress.report("syncing", index as u64, Some(total));
+
+        let result = crate::fetch_with_fallback(
+            app.clone(),
+            target.key.clone(),
+            target.url,
+            target.headers,
+            true,
+            false,
+            &retry,
+            None,
+        )
+        .await;
+
+        match result {
+            Ok(result) if result.source.starts_with("online") => refreshed.push(target.key),
+            Ok(_) => from_cache.push(target.key),
+            Err(error) => {
+                tracing::warn!("Scheduled sync of '{}' failed: {}", target.key, error);
+                failed.push(SyncFailure {
+                    key: target.key,
+                    error: error.message,
+                });
+            }
+        }
+    }
+
+    progress.finish(
+        "syncing",
+        total,
+        Some(total),
+        Some(format!("Refreshed {} of {} keys", refreshed.len(), total)),
+    );
+
+    SyncSummary {
+        started_at,
+        finished_at: chrono::Utc::now().timestamp(),
+        refreshed,
+        from_cache,
+        failed,
+    }
+}
+
+async fn run_due_sync(app: &AppHandle) -> Result<(), String> {
+    if crate::demo::is_demo(app) || crate::settings::current(app).data_saver {
+        return Ok(());
+    }
+
+    let mut schedule: SyncScheduleFile =
+        crate::read_state_file_blocking(app, SYNC_SCHEDULE_FILE).await?;
+    let now = Local::now();
+
+    if next_run(&schedule).is_none_or(|next| next > now) {
+        return Ok(());
+    }
+
+    // A slot missed while offline is dropped rather than caught up on reconnect
+    schedule.last_run = Some(now.timestamp());
+    crate::write_state_file_blocking(app, SYNC_SCHEDULE_FILE, &schedule).await?;
+
+    if !crate::probe_network_status(app).await.can_reach_website {
+        tracing::info!("Skipping scheduled sync while offline");
+        return Ok(());
+    }
+
+    let progress = ProgressReporter::new(app, Some("scheduled-sync".to_string()));
+    let summary = sync_targets(app, schedule.targets, &progress).await;
+    crate::home_widget::refresh(app);
+
+    app.emit(SYNC_COMPLETED_EVENT, &summary)
+        .map_err(|e| format!("Failed to emit sync summary: {}", e))
+}
+
+/// Starts the background runner that executes the scheduled sync.
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let result = run_due_sync(&app).await;
+            if let Err(e) = &result {
+                tracing::warn!("Sync scheduler run failed: {}", e);
+            }
+            crate::health::record_run(&app, "sync", SYNC_CHECK_INTERVAL, &result);
+        }
+    });
+}
+
+fn to_schedule(schedule: &SyncScheduleFile) -> SyncSchedule {
+    SyncSchedule {
+        expression: schedule.expression.clone(),
+        targets: schedule.targets.clone(),
+        last_run: schedule.last_run,
+        next_run: next_run(schedule).map(|next| next.timestamp()),
+    }
+}
+
+/// Sets the cron expression, e.g. `*/30 * * * *`, and optionally replaces the synced keys.
+#[tauri::command]
+pub async fn set_sync_schedule(
+    app: AppHandle,
+    expression: String,
+    targets: Option<Vec<SyncTarget>>,
+) -> Result<SyncSchedule, AppError> {
+    parse_expression(&expression).map_err(AppError::invalid_input)?;
+
+    let mut schedule: SyncScheduleFile =
+        crate::read_state_file_blocking(&app, SYNC_SCHEDULE_FILE).await?;
+    schedule.expression = Some(expression);
+    if let Some(targets) = targets {
+        schedule.targets = targets;
+    }
+    schedule.last_run = None;
+    schedule.updated_at = chrono::Utc::now().timestamp();
+
+    crate::write_state_file_blocking(&app, SYNC_SCHEDULE_FILE, &schedule).await?;
+    Ok(to_schedule(&schedule))
+}
+
+#[tauri::command]
+pub async fn get_sync_schedule(app: AppHandle) -> Result<SyncSchedule, AppError> {
+    let schedule: SyncScheduleFile =
+        crate::read_state_file_blocking(&app, SYNC_SCHEDULE_FILE).await?;
+    Ok(to_schedule(&schedule))
+}
+
+/// Syncs the scheduled keys right away, reporting `operation-progress` events under
+/// `operation_id`. The schedule itself is left as is.
+#[tauri::command]
+pub async fn sync_now(
+    app: AppHandle,
+    operation_id: Option<String>,
+) -> Result<SyncSummary, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let schedule: SyncScheduleFile =
+        crate::read_state_file_blocking(&app, SYNC_SCHEDULE_FILE).await?;
+    let progress = ProgressReporter::new(&app, operation_id);
+    let summary = sync_targets(&app, schedule.targets, &progress).await;
+    crate::home_widget::refresh(&app);
+
+    if let Err(e) = app.emit(SYNC_COMPLETED_EVENT, &summary) {
+        tracing::warn!("Failed to emit sync summary: {}", e);
+    }
+    Ok(summary)
+}
+
+/// Turns the scheduled sync off, keeping the configured keys.
+#[tauri::command]
+pub async fn clear_sync_schedule(app: AppHandle) -> Result<(), AppError> {
+    let mut schedule: SyncScheduleFile =
+        crate::read_state_file_blocking(&app, SYNC_SCHEDULE_FILE).await?;
+    schedule.expression = None;
+    crate::write_state_file_blocking(&app, SYNC_SCHEDULE_FILE, &schedule).await
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Theme, Webview, WebviewWindow, WindowEvent};
+
+use crate::error::AppError;
+
+/// Emitted with the new [`SystemTheme`] when the OS appearance changes
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemTheme {
+    /// `dark` or `light`
+    pub theme: &'static str,
+    /// Accent color as `#rrggbb`; only Windows reports one
+    pub accent_color: Option<String>,
+}
+
+fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+#[cfg(windows)]
+fn accent_color() -> Option<String> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    // Stored as 0xAABBGGRR
+    let color: u32 = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey("Software\\Microsoft\\Windows\\DWM")
+        .and_then(|key| key.get_value("AccentColor"))
+        .ok()?;
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        color & 0xff,
+        (color >> 8) & 0xff,
+        (color >> 16) & 0xff
+    ))
+}
+
+#[cfg(not(windows))]
+fn accent_color() -> Option<String> {
+    None
+}
+
+fn system_theme(theme: Theme) -> SystemTheme {
+    SystemTheme {
+        theme: theme_name(theme),
+        accent_color: accent_color(),
+    }
+}
+
+/// Exposes the theme to the website as `data-system-theme` on the root element and the
+/// `--system-accent-color` variable, so its styles can follow the OS without a reload.
+fn apply(webview: &Webview, theme: &SystemTheme) {
+    let accent = serde_json::to_string(&theme.accent_color).unwrap_or_else(|_| "null".into());
+    let script = format!(
+        r#"
+        (function() {{
+            const root = document.documentElement;
+            root.dataset.systemTheme = '{theme}';
+            root.style.colorScheme = '{theme}';
+            const accent = {accent};
+            if (accent) {{
+                root.style.setProperty('--system-accent-color', accent);
+            }} else {{
+                root.style.removeProperty('--system-accent-color');
+            }}
+        }})();
+        "#,
+        theme = theme.theme,
+        accent = accent,
+    );
+
+    if let Err(e) = webview.eval(script) {
+        tracing::warn!("Failed to apply system theme: {}", e);
+    }
+}
+
+/// Marks a freshly loaded page with the current system theme.
+pub fn apply_to_page(webview: &Webview) {
+    match webview.window().theme() {
+        Ok(theme) => apply(webview, &system_theme(theme)),
+        Err(e) => tracing::warn!("Failed to read system theme: {}", e),
+    }
+}
+
+/// Forwards OS appearance changes of the main window to the page and the frontend.
+pub fn watch(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        let WindowEvent::ThemeChanged(theme) = event else {
+            return;
+        };
+
+        let theme = system_theme(*theme);
+        apply(handle.as_ref(), &theme);
+        if let Err(e) = handle.emit(THEME_CHANGED_EVENT, &theme) {
+            tracing::warn!("Failed to emit {}: {}", THEME_CHANGED_EVENT, e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_system_theme(app: AppHandle) -> Result<SystemTheme, AppError> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| AppError::invalid_state("Main window is not open"))?;
+    let theme = window
+        .theme()
+        .map_err(|e| AppError::internal(format!("Failed to read system theme: {}", e)))?;
+
+    Ok(system_theme(theme))
+}
@@ -0,0 +1,178 @@
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+/// Longest side used when the caller does not pick one, in pixels
+const DEFAULT_MAX_SIZE: u32 = 256;
+
+const MIN_MAX_SIZE: u32 = 16;
+const MAX_MAX_SIZE: u32 = 2048;
+
+const JPEG_QUALITY: u8 = 80;
+
+/// Cached file extensions and the types they hold
+const FORMATS: [(&str, &str); 2] = [("png", "image/png"), ("jpg", "image/jpeg")];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    pub hash: String,
+    pub mime_type: String,
+    /// Image bytes, base64-encoded so they can go straight into a `data:` URL
+    pub data: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn thumbnails_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::attachments::attachments_dir(app)?.join("thumbnails"))
+}
+
+fn cached(dir: &Path, hash: &str, max_size: u32) -> Option<(PathBuf, &'static str)> {
+    FORMATS
+        .iter()
+        .map(|(extension, mime_type)| {
+            let name = format!("{}-{}.{}", hash, max_size, extension);
+            (dir.join(name), *mime_type)
+        })
+        .find(|(path, _)| path.is_file())
+}
+
+/// Decodes an image, turns it upright according to its EXIF orientation, and shrinks it
+/// to fit in a `max_size` square. Images with transparency stay PNG; the rest, which are
+/// almost always photos, become JPEG.
+fn render(data: &[u8], max_size: u32) -> Result<(Vec<u8>, &'static str), AppError> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| AppError::corrupt_data(format!("Failed to read image: {}", e)))?
+        .into_decoder()
+        .map_err(|e| AppError::corrupt_data(format!("Failed to decode image: {}", e)))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| AppError::corrupt_data(format!("Failed to decode image: {}", e)))?;
+    image.apply_orientation(orientation);
+
+    let image = if image.width() > max_size || image.height() > max_size {
+        image.thumbnail(max_size, max_size)
+    } else {
+        image
+    };
+
+    let mut bytes = Vec::new();
+    let mime_type = if image.color().has_alpha() {
+        image
+            .to_rgba8()
+            .write_with_encoder(PngEncoder::new(&mut bytes))
+            .map_err(|e| AppError::internal(format!("Failed to encode thumbnail: {}", e)))?;
+        "image/png"
+    } else {
+        image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))
+            .map_err(|e| AppError::internal(format!("Failed to encode thumbnail: {}", e)))?;
+        "image/jpeg"
+    };
+
+    Ok((bytes, mime_type))
+}
+
+/// Keeps a rendered thumbnail for next time; failing to do so only costs a re-render.
+fn store(dir: &Path, hash: &str, max_size: u32, bytes: &[u8], mime_type: &str) {
+    let Some((extension, _)) = FORMATS.iter().find(|(_, format)| *format == mime_type) else {
+        return;
+    };
+    let path = dir.join(format!("{}-{}.{}", hash, max_size, extension));
+
+    let result =
+        std::fs::create_dir_all(dir).and_then(|_| crate::storage::write_atomic(&path, bytes));
+    if let Err(e) = result {
+        tracing::warn!("Failed to cache thumbnail of {}: {}", hash, e);
+    }
+}
+
+/// Deletes every cached thumbnail of an attachment.
+pub fn remove(app: &AppHandle, hash: &str) -> Result<(), AppError> {
+    let dir = thumbnails_dir(app)?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(AppError::storage(format!(
+                "Failed to read thumbnails: {}",
+                e
+            )))
+        }
+    };
+
+    let prefix = format!("{}-", hash.to_ascii_lowercase());
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            std::fs::remove_file(entry.path())
+                .map_err(|e| AppError::storage(format!("Failed to delete thumbnail: {}", e)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a downscaled copy of an image attachment whose longer side is at most
+/// `max_size` pixels (256 by default). Thumbnails are cached on disk per size.
+#[tauri::command]
+pub async fn generate_thumbnail(
+    app: AppHandle,
+    attachment_id: String,
+    max_size: Option<u32>,
+) -> Result<Thumbnail, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let max_size = max_size
+        .unwrap_or(DEFAULT_MAX_SIZE)
+        .clamp(MIN_MAX_SIZE, MAX_MAX_SIZE);
+    let attachment = crate::attachments::load(&app, &attachment_id)?
+        .ok_or_else(|| AppError::not_found(format!("Unknown attachment: {}", attachment_id)))?;
+    if !attachment.content_type.starts_with("image/") {
+        return Err(AppError::invalid_input("Attachment is not an image"));
+    }
+
+    let dir = thumbnails_dir(&app)?;
+    let (bytes, mime_type) = match cached(&dir, &attachment.hash, max_size) {
+        Some((path, mime_type)) => {
+            let bytes = std::fs::read(path)
+                .map_err(|e| AppError::storage(format!("Failed to read thumbnail: {}", e)))?;
+            (bytes, mime_type)
+        }
+        None => {
+            let (_, data) = crate::attachments::read(&app, &attachment.hash)?;
+            let (bytes, mime_type) =
+                tauri::async_runtime::spawn_blocking(move || render(&data, max_size))
+                    .await
+                    .map_err(|e| {
+                        AppError::internal(format!("Failed to render thumbnail: {}", e))
+                    })??;
+            store(&dir, &attachment.hash, max_size, &bytes, mime_type);
+            (bytes, mime_type)
+        }
+    };
+
+    let (width, height) = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|e| AppError::corrupt_data(format!("Failed to read thumbnail: {}", e)))?
+        .into_dimensions()
+        .map_err(|e| AppError::corrupt_data(format!("Failed to read thumbnail: {}", e)))?;
+
+    Ok(Thumbnail {
+        hash: attachment.hash,
+        mime_type: mime_type.to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        width,
+        height,
+    })
+}
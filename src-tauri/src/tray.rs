@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow, WindowEvent, Wry};
+
+use crate::error::AppError;
+use crate::i18n::{text, text_with};
+use crate::profiles::{self, ProfileState};
+use crate::settings::{CloseBehavior, SettingsState};
+
+const TRAY_ID: &str = "main";
+
+/// Asks the webview to refetch what it is showing
+pub const TRAY_FORCE_REFRESH_EVENT: &str = "tray-force-refresh";
+
+/// Asks the webview to open today's check-in
+pub const QUICK_CHECKIN_EVENT: &str = "quick-checkin";
+
+/// Emitted with a [`SecondInstance`] when the app is launched again while running
+pub const SECOND_INSTANCE_EVENT: &str = "second-instance";
+
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecondInstance {
+    /// Command-line arguments of the new launch, without the program path
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let app_name = &app.package_info().name;
+    let open = MenuItem::with_id(
+        app,
+        "open",
+        text_with(app, "tray.open", &[("app", app_name)]),
+        true,
+        None::<&str>,
+    )?;
+    let refresh = MenuItem::with_id(
+        app,
+        "force-refresh",
+        text(app, "tray.force_refresh"),
+        true,
+        None::<&str>,
+    )?;
+    let check_in = MenuItem::with_id(
+        app,
+        "check-in",
+        text(app, "tray.check_in"),
+        true,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, "quit", text(app, "tray.quit"), true, None::<&str>)?;
+
+    let profile_menu = Submenu::with_id(app, "profiles", text(app, "tray.switch_profile"), true)?;
+    let list = app.state::<ProfileState>().snapshot();
+    for profile in &list.profiles {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{}", PROFILE_ITEM_PREFIX, profile.id),
+            &profile.name,
+            true,
+            profile.id == list.active,
+            None::<&str>,
+        )?;
+        profile_menu.append(&item)?;
+    }
+
+    Menu::with_items(
+        app,
+        &[
+            &open,
+            &refresh,
+            &check_in,
+            &PredefinedMenuItem::separator(app)?,
+            &profile_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Focuses the running window in place of a second copy of the app and passes the new
+/// launch's arguments on. Deep links among them also arrive as `deep-link` events.
+pub fn handle_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    show_main_window(app);
+
+    let launch = SecondInstance {
+        args: args.into_iter().skip(1).collect(),
+        cwd,
+    };
+    if let Err(e) = app.emit(SECOND_INSTANCE_EVENT, &launch) {
+        tracing::warn!("Failed to emit {}: {}", SECOND_INSTANCE_EVENT, e);
+    }
+}
+
+pub fn emit_to_webview(app: &AppHandle, event: &str) {
+    show_main_window(app);
+    if let Err(e) = app.emit(event, ()) {
+        tracing::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "open" => show_main_window(app),
+        "force-refresh" => emit_to_webview(app, TRAY_FORCE_REFRESH_EVENT),
+        "check-in" => emit_to_webview(app, QUICK_CHECKIN_EVENT),
+        "quit" => {
+            // Exiting skips the window's close events, which normally save its geometry
+            crate::window_state::save(app);
+            app.exit(0);
+        }
+        id => {
+            if let Some(profile_id) = id.strip_prefix(PROFILE_ITEM_PREFIX) {
+                if let Err(e) = profiles::activate_profile(app, profile_id) {
+                    tracing::warn!("Failed to switch profile from tray: {}", e);
+                }
+                // Check marks must reflect the active profile even if the switch failed
+                refresh_menu(app);
+            }
+        }
+    }
+}
+
+/// Creates the tray icon and its quick-action menu.
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Lotus Routine")
+        .menu(&build_menu(app)?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Keeps the app running in the tray when the main window is closed or minimized,
+/// depending on the settings.
+pub fn handle_window_events(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        let settings = crate::settings::current(handle.app_handle());
+        match event {
+            WindowEvent::CloseRequested { api, .. }
+                if settings.close_behavior == CloseBehavior::HideToTray =>
+            {
+                api.prevent_close();
+                if let Err(e) = handle.hide() {
+                    tracing::warn!("Failed to hide main window: {}", e);
+                }
+            }
+            // Minimizing has no event of its own; it shows up as a resize
+            WindowEvent::Resized(_)
+                if settings.minimize_to_tray && handle.is_minimized().unwrap_or(false) =>
+            {
+                if let Err(e) = handle.hide() {
+                    tracing::warn!("Failed to hide main window: {}", e);
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowBehavior {
+    pub close: CloseBehavior,
+    pub minimize_to_tray: bool,
+}
+
+#[tauri::command]
+pub async fn get_close_behavior(
+    state: State<'_, SettingsState>,
+) -> Result<WindowBehavior, AppError> {
+    let settings = state.get();
+    Ok(WindowBehavior {
+        close: settings.close_behavior,
+        minimize_to_tray: settings.minimize_to_tray,
+    })
+}
+
+/// Sets what closing the main window does; `minimize_to_tray` is kept as is when not given.
+#[tauri::command]
+pub async fn set_close_behavior(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    behavior: CloseBehavior,
+    minimize_to_tray: Option<bool>,
+) -> Result<(), AppError> {
+    state.update(&app, |settings| {
+        settings.close_behavior = behavior;
+        if let Some(minimize_to_tray) = minimize_to_tray {
+            settings.minimize_to_tray = minimize_to_tray;
+        }
+    })?;
+    Ok(())
+}
+
+/// Rebuilds the tray menu after the profile list or active profile changes.
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    match build_menu(app) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                tracing::warn!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to build tray menu: {}", e),
+    }
+}
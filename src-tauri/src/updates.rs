@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::error::AppError;
+use crate::progress::ProgressReporter;
+use crate::settings::UpdateChannel;
+
+/// Release manifest location; `{channel}` is filled in here, the rest by the updater plugin
+const UPDATE_ENDPOINT_TEMPLATE: &str =
+    "https://accounted.th3void.com/updates/{channel}/{{target}}/{{arch}}/{{current_version}}";
+
+/// Operation id used for download progress when the frontend does not pass one
+const UPDATE_OPERATION_ID: &str = "app-update";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub date: Option<String>,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+}
+
+/// Update found by the last check and the verified package once downloaded
+#[derive(Default)]
+pub struct UpdateState {
+    update: Mutex<Option<Update>>,
+    package: Mutex<Option<Vec<u8>>>,
+}
+
+fn endpoint(channel: UpdateChannel) -> Result<reqwest::Url, AppError> {
+    UPDATE_ENDPOINT_TEMPLATE
+        .replace("{channel}", channel.as_str())
+        .parse()
+        .map_err(|e| AppError::internal(format!("Invalid update endpoint: {}", e)))
+}
+
+fn pending_update(app: &AppHandle) -> Result<Update, AppError> {
+    app.state::<UpdateState>()
+        .update
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or_else(|| AppError::invalid_state("No update available. Check for updates first."))
+}
+
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    let channel = crate::settings::current(&app).update_channel;
+
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint(channel)?])
+        .and_then(|builder| builder.build())
+        .map_err(|e| AppError::internal(format!("Failed to create updater: {}", e)))?;
+
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| AppError::offline(format!("Failed to check for updates: {}", e)))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        date: update.date.map(|date| date.to_string()),
+        notes: update.body.clone(),
+        channel,
+    });
+
+    let state = app.state::<UpdateState>();
+    *state.update.lock().unwrap_or_else(|e| e.into_inner()) = update;
+    *state.package.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+    Ok(info)
+}
+
+/// Downloads and verifies the update found by [`check_for_update`], reporting byte progress.
+#[tauri::command]
+pub async fn download_update(app: AppHandle, operation_id: Option<String>) -> Result<(), AppError> {
+    let update = pending_update(&app)?;
+    let progress = ProgressReporter::new(
+        &app,
+        Some(operation_id.unwrap_or_else(|| UPDATE_OPERATION_ID.to_string())),
+    );
+
+    let mut downloaded = 0u64;
+    let mut total = None;
+    let package = update
+        .download(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                total = content_length;
+                progress.report("downloading", downloaded, content_length);
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| AppError::offline(format!("Failed to download update: {}", e)))?;
+
+    progress.finish(
+        "downloading",
+        downloaded,
+        total,
+        Some(format!("Downloaded version {}", update.version)),
+    );
+
+    *app.state::<UpdateState>()
+        .package
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Some(package);
+    Ok(())
+}
+
+/// Installs the downloaded package and restarts into the new version.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), AppError> {
+    let update = pending_update(&app)?;
+    let package = app
+        .state::<UpdateState>()
+        .package
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| AppError::invalid_state("Update has not been downloaded yet"))?;
+
+    update
+        .install(package)
+        .map_err(|e| AppError::internal(format!("Failed to install update: {}", e)))?;
+
+    app.restart()
+}
@@ -0,0 +1,280 @@
+use futures_util::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::io::AsyncReadExt;
+
+use crate::error::AppError;
+use crate::progress::ProgressReporter;
+use crate::retry::RetryOptions;
+use crate::{OnlineResponse, RequestBody, SendOutcome};
+
+/// Timeout for a whole upload, in place of the API timeout, which large files would
+/// exceed on slow links
+pub const UPLOAD_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Minimum gap between progress events of one upload
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const DEFAULT_FIELD: &str = "file";
+
+/// A file on disk sent as a `multipart/form-data` request, along with extra form fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileUpload {
+    pub path: String,
+    /// Form field the file is sent in
+    pub field: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UploadOptions {
+    /// Defaults to `POST`
+    pub method: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+    /// Form field the file is sent in; defaults to `file`
+    pub field: Option<String>,
+    /// Defaults to the name of the file on disk
+    pub file_name: Option<String>,
+    /// Defaults to a type guessed from the file extension
+    pub content_type: Option<String>,
+    /// Queue the upload when the website cannot be reached; defaults to `true`
+    pub queue_on_failure: Option<bool>,
+    pub retry: Option<RetryOptions>,
+}
+
+/// Content type for common photo, audio, video, document and archive extensions.
+fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "m4a" => "audio/mp4",
+        "ogg" | "opus" => "audio/ogg",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "txt" => "text/plain",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Quotes a name for a `Content-Disposition` header the way browsers encode form data.
+fn quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// A request body that streams the file from disk between the form fields and the
+/// closing boundary, reporting progress as it goes.
+pub struct MultipartBody {
+    pub content_type: String,
+    /// Total size of the encoded body, sent as `Content-Length`
+    pub length: u64,
+    pub body: reqwest::Body,
+}
+
+impl MultipartBody {
+    pub async fn open(upload: &FileUpload, progress: &ProgressReporter) -> Result<Self, String> {
+        let file = tokio::fs::File::open(&upload.path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", upload.path, e))?;
+        let file_size = file
+            .metadata()
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", upload.path, e))?
+            .len();
+
+        let boundary = format!(
+            "----LotusRoutine{:016x}{:016x}",
+            fastrand::u64(..),
+            fastrand::u64(..)
+        );
+
+        let mut fields: Vec<_> = upload.fields.iter().collect();
+        fields.sort();
+        let mut head = String::new();
+        for (name, value) in fields {
+            head.push_str(&format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary,
+                quote(name),
+                value
+            ));
+        }
+        head.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            quote(&upload.field),
+            quote(&upload.file_name),
+            upload.content_type
+        ));
+        let tail = format!("\r\n--{}--\r\n", boundary);
+
+        let length = head.len() as u64 + file_size + tail.len() as u64;
+        let file = Self::stream_file(file, file_size, progress.clone());
+        let body = reqwest::Body::wrap_stream(
+            stream::iter([Ok(head.into_bytes())])
+                .chain(file)
+                .chain(stream::iter([Ok(tail.into_bytes())])),
+        );
+
+        Ok(Self {
+            content_type: format!("multipart/form-data; boundary={}", boundary),
+            length,
+            body,
+        })
+    }
+
+    /// Reads the file in chunks, reporting how much of it has been handed to the
+    /// connection.
+    fn stream_file(
+        file: tokio::fs::File,
+        file_size: u64,
+        progress: ProgressReporter,
+    ) -> impl futures_util::Stream<Item = Result<Vec<u8>, std::io::Error>> + Send + 'static {
+        let mut last_report: Option<Instant> = None;
+
+        stream::try_unfold((file, 0u64), move |(mut file, sent)| async move {
+            if sent == file_size {
+                return Ok(None);
+            }
+
+            let mut chunk = vec![0; CHUNK_SIZE.min((file_size - sent) as usize)];
+            let read = file.read(&mut chunk).await?;
+            if read == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "File was truncated during upload",
+                ));
+            }
+            chunk.truncate(read);
+            let sent = sent + read as u64;
+            Ok(Some(((chunk, sent), (file, sent))))
+        })
+        .map_ok(move |(chunk, sent)| {
+            if sent == file_size || last_report.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+                progress.report("uploading", sent, Some(file_size));
+                last_report = Some(Instant::now());
+            }
+            chunk
+        })
+    }
+}
+
+/// Streams a file to `url` as `multipart/form-data`, with `fields` as extra form fields,
+/// reporting upload progress under `operation_id`. Transient failures are retried; if
+/// the website still cannot be reached the upload is queued, like other mutations, and
+/// sent when the queue is flushed, as long as the file is still there by then.
+#[tauri::command]
+pub async fn upload_file(
+    app: AppHandle,
+    url: String,
+    path: String,
+    fields: Option<HashMap<String, String>>,
+    options: Option<UploadOptions>,
+    operation_id: Option<String>,
+) -> Result<SendOutcome, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let options = options.unwrap_or_default();
+    let method = crate::outbox::parse_method(options.method.as_deref().unwrap_or("POST"))?;
+    let retry = options.retry.unwrap_or_default();
+    retry.validate()?;
+
+    let file_path = Path::new(&path);
+    let size = match tokio::fs::metadata(file_path).await {
+        Ok(metadata) if metadata.is_file() && file_path.is_absolute() => metadata.len(),
+        _ => {
+            return Err(AppError::invalid_input(format!(
+                "Upload path must be an absolute path to a file: {}",
+                path
+            )))
+        }
+    };
+
+    let upload = FileUpload {
+        field: options.field.unwrap_or_else(|| DEFAULT_FIELD.to_string()),
+        file_name: options.file_name.unwrap_or_else(|| {
+            file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        }),
+        content_type: options
+            .content_type
+            .unwrap_or_else(|| guess_content_type(file_path).to_string()),
+        fields: fields.unwrap_or_default(),
+        path,
+    };
+
+    let progress = ProgressReporter::new(&app, operation_id);
+    let outcome = crate::perform_request(
+        &app,
+        method.clone(),
+        &url,
+        options.headers.as_ref(),
+        Some(RequestBody::Multipart {
+            upload: &upload,
+            progress: &progress,
+        }),
+        None,
+        Some(&retry),
+    )
+    .await;
+
+    match outcome {
+        Ok(response) => {
+            progress.finish("uploading", size, Some(size), None);
+            Ok(SendOutcome {
+                data: match response {
+                    OnlineResponse::Fresh { data, .. } => Some(data),
+                    OnlineResponse::NotModified => None,
+                },
+                queued_id: None,
+            })
+        }
+        Err(e) if e.is_transient() && options.queue_on_failure.unwrap_or(true) => {
+            tracing::info!("Queueing upload of {} after failure: {}", upload.path, e);
+            let id = crate::outbox::enqueue_file_upload(
+                &app,
+                method.as_str(),
+                &url,
+                options.headers.as_ref(),
+                &upload,
+            )
+            .await?;
+            progress.finish("queued", 0, Some(size), Some(e.to_string()));
+            Ok(SendOutcome {
+                data: None,
+                queued_id: Some(id),
+            })
+        }
+        Err(e) => {
+            progress.finish("uploading", 0, Some(size), Some(e.to_string()));
+            Err(e.into())
+        }
+    }
+}
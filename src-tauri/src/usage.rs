@@ -0,0 +1,409 @@
+use chrono::{Duration as DateDuration, Local, NaiveDate};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::time::MissedTickBehavior;
+
+use crate::cache_store::{with_store, with_store_blocking};
+use crate::error::AppError;
+use crate::settings::SettingsState;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// How often the foreground app is sampled; each sample counts for this long
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Longest range a summary covers, to keep the query and payload small
+const MAX_RANGE_DAYS: i64 = 366;
+
+const MAX_RETENTION_DAYS: u32 = 3 * 365;
+
+/// App names longer than this are cut before they are stored
+const MAX_APP_NAME_CHARS: usize = 100;
+
+/// Whether and for how long app usage is recorded, persisted with the other settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageSettings {
+    /// Off until the user opts in
+    pub enabled: bool,
+    /// Days of usage kept; older days are deleted
+    pub retention_days: u32,
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: 90,
+        }
+    }
+}
+
+impl UsageSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.retention_days == 0 || self.retention_days > MAX_RETENTION_DAYS {
+            return Err(AppError::invalid_input(format!(
+                "Usage can be kept for 1 to {} days",
+                MAX_RETENTION_DAYS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Inclusive range of days to summarize, in `YYYY-MM-DD` format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUsage {
+    pub app: String,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUsage {
+    pub date: String,
+    pub total_secs: u64,
+    /// Most used first
+    pub apps: Vec<AppUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageSummary {
+    pub start_date: String,
+    pub end_date: String,
+    pub total_secs: u64,
+    /// Totals over the whole range, most used first
+    pub apps: Vec<AppUsage>,
+    /// Only days with recorded usage, oldest first
+    pub days: Vec<DailyUsage>,
+}
+
+/// Names the foreground app after its executable.
+#[cfg(windows)]
+mod platform {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    pub fn foreground_app() -> Option<String> {
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.is_invalid() {
+                return None;
+            }
+
+            let mut process_id = 0;
+            GetWindowThreadProcessId(window, Some(&mut process_id));
+            if process_id == 0 {
+                return None;
+            }
+
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+            let mut buffer = [0u16; 1024];
+            let mut length = buffer.len() as u32;
+            let queried = QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut length,
+            );
+            let _ = CloseHandle(process);
+            queried.ok()?;
+
+            // The executable's name without its folder or extension, e.g. `chrome`
+            let path = String::from_utf16_lossy(&buffer[..length as usize]);
+            std::path::Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2_app_kit::NSWorkspace;
+
+    pub fn foreground_app() -> Option<String> {
+        let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+        app.localizedName()
+            .or_else(|| app.bundleIdentifier())
+            .map(|name| name.to_string())
+    }
+}
+
+/// Reads the active window from the X server. Under Wayland this only sees apps
+/// running through XWayland.
+#[cfg(target_os = "linux")]
+mod platform {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    fn window_property(
+        connection: &RustConnection,
+        window: Window,
+        name: &[u8],
+        kind: AtomEnum,
+    ) -> Option<Vec<u8>> {
+        let atom = connection.intern_atom(true, name).ok()?.reply().ok()?.atom;
+        let reply = connection
+            .get_property(false, window, atom, kind, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(reply.value).filter(|value| !value.is_empty())
+    }
+
+    pub fn foreground_app() -> Option<String> {
+        let (connection, screen) = x11rb::connect(None).ok()?;
+        let root = connection.setup().roots.get(screen)?.root;
+
+        let active = window_property(&connection, root, b"_NET_ACTIVE_WINDOW", AtomEnum::WINDOW)?;
+        let window = u32::from_ne_bytes(active.get(..4)?.try_into().ok()?);
+        if window == 0 {
+            return None;
+        }
+
+        // The process name, e.g. `firefox`, is steadier than the window class
+        let process_name = window_property(&connection, window, b"_NET_WM_PID", AtomEnum::CARDINAL)
+            .and_then(|pid| Some(u32::from_ne_bytes(pid.get(..4)?.try_into().ok()?)))
+            .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        if process_name.is_some() {
+            return process_name;
+        }
+
+        // WM_CLASS holds the instance and class names, each ending in a NUL
+        let class = window_property(&connection, window, b"WM_CLASS", AtomEnum::STRING)?;
+        class
+            .split(|&byte| byte == 0)
+            .rfind(|part| !part.is_empty())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    pub fn foreground_app() -> Option<String> {
+        None
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+fn record(app: &AppHandle, date: &str, name: &str, seconds: u64) -> Result<(), String> {
+    let name: String = name.chars().take(MAX_APP_NAME_CHARS).collect();
+    with_store(app, |store| {
+        store
+            .connection()
+            .execute(
+                "INSERT INTO app_usage (date, app, seconds) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (date, app) DO UPDATE SET seconds = seconds + excluded.seconds",
+                params![date, name, seconds],
+            )
+            .map_err(|e| format!("Failed to record app usage: {}", e))?;
+        Ok(())
+    })
+}
+
+/// Deletes usage from before the retention period.
+async fn prune(app: &AppHandle, retention_days: u32) -> Result<(), String> {
+    let cutoff = Local::now().date_naive() - DateDuration::days(i64::from(retention_days));
+    with_store_blocking(app, move |store| {
+        store
+            .connection()
+            .execute(
+                "DELETE FROM app_usage WHERE date < ?1",
+                params![cutoff.format(DATE_FORMAT).to_string()],
+            )
+            .map_err(|e| format!("Failed to prune app usage: {}", e))?;
+        Ok(())
+    })
+    .await
+}
+
+/// Records the app in the foreground, unless the user is away from the computer or the
+/// app cannot be determined, e.g. on mobile.
+fn sample(app: &AppHandle) -> Result<(), String> {
+    if crate::idle::is_away(app) {
+        return Ok(());
+    }
+    let Some(name) = platform::foreground_app() else {
+        return Ok(());
+    };
+
+    let today = Local::now().date_naive().format(DATE_FORMAT).to_string();
+    record(app, &today, &name, SAMPLE_INTERVAL.as_secs())
+}
+
+/// Starts the background task that records foreground app usage while tracking is on.
+/// Everything stays in the local store.
+pub fn spawn_tracker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        // Ticks missed while the computer slept are not usage
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut pruned_on: Option<NaiveDate> = None;
+
+        loop {
+            interval.tick().await;
+
+            let settings = crate::settings::current(&app).usage;
+            if !settings.enabled || crate::demo::is_demo(&app) {
+                continue;
+            }
+
+            let today = Local::now().date_naive();
+            if pruned_on != Some(today) {
+                if let Err(e) = prune(&app, settings.retention_days).await {
+                    tracing::warn!("{}", e);
+                }
+                pruned_on = Some(today);
+            }
+
+            let handle = app.clone();
+            match tauri::async_runtime::spawn_blocking(move || sample(&handle)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!("{}", e),
+                Err(e) => tracing::warn!("App usage sample failed: {}", e),
+            }
+        }
+    });
+}
+
+fn sorted(totals: HashMap<String, u64>) -> Vec<AppUsage> {
+    let mut apps: Vec<AppUsage> = totals
+        .into_iter()
+        .map(|(app, seconds)| AppUsage { app, seconds })
+        .collect();
+    apps.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.app.cmp(&b.app)));
+    apps
+}
+
+/// Returns time spent per app over a range of days, in total and per day, so it can be
+/// compared with habit completions.
+#[tauri::command]
+pub async fn get_usage_summary(
+    app: AppHandle,
+    range: UsageRange,
+) -> Result<UsageSummary, AppError> {
+    let start = parse_date(&range.start_date).map_err(AppError::invalid_input)?;
+    let end = parse_date(&range.end_date).map_err(AppError::invalid_input)?;
+    if end < start {
+        return Err(AppError::invalid_input(
+            "The end date is before the start date",
+        ));
+    }
+    if (end - start).num_days() >= MAX_RANGE_DAYS {
+        return Err(AppError::invalid_input(format!(
+            "Ranges are limited to {} days",
+            MAX_RANGE_DAYS
+        )));
+    }
+
+    let start_date = start.format(DATE_FORMAT).to_string();
+    let end_date = end.format(DATE_FORMAT).to_string();
+
+    // Guest mode never shows the real profile's usage
+    let rows: Vec<(String, String, u64)> = if crate::demo::is_demo(&app) {
+        Vec::new()
+    } else {
+        let (from, to) = (start_date.clone(), end_date.clone());
+        with_store_blocking(&app, move |store| {
+            let mut statement = store
+                .connection()
+                .prepare(
+                    "SELECT date, app, seconds FROM app_usage
+                     WHERE date BETWEEN ?1 AND ?2 ORDER BY date",
+                )
+                .map_err(|e| format!("Failed to read app usage: {}", e))?;
+            let rows = statement
+                .query_map(params![from, to], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to read app usage: {}", e))?;
+            Ok(rows)
+        })
+        .await
+        .map_err(AppError::storage)?
+    };
+
+    let mut totals: HashMap<String, u64> = HashMap::new();
+    let mut by_day: BTreeMap<String, HashMap<String, u64>> = BTreeMap::new();
+    for (date, name, seconds) in rows {
+        *totals.entry(name.clone()).or_default() += seconds;
+        *by_day.entry(date).or_default().entry(name).or_default() += seconds;
+    }
+
+    let days = by_day
+        .into_iter()
+        .map(|(date, apps)| DailyUsage {
+            date,
+            total_secs: apps.values().sum(),
+            apps: sorted(apps),
+        })
+        .collect();
+
+    Ok(UsageSummary {
+        start_date,
+        end_date,
+        total_secs: totals.values().sum(),
+        apps: sorted(totals),
+        days,
+    })
+}
+
+#[tauri::command]
+pub async fn get_usage_settings(
+    state: State<'_, SettingsState>,
+) -> Result<UsageSettings, AppError> {
+    Ok(state.get().usage)
+}
+
+/// Turns tracking on or off. Turning it off keeps what was recorded; see
+/// `clear_usage_data`.
+#[tauri::command]
+pub async fn set_usage_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: UsageSettings,
+) -> Result<(), AppError> {
+    settings.validate()?;
+
+    state.update(&app, |current| current.usage = settings)?;
+    Ok(())
+}
+
+/// Deletes all recorded app usage.
+#[tauri::command]
+pub async fn clear_usage_data(app: AppHandle) -> Result<(), AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    with_store_blocking(&app, |store| {
+        store
+            .connection()
+            .execute("DELETE FROM app_usage", [])
+            .map_err(|e| format!("Failed to clear app usage: {}", e))?;
+        Ok(())
+    })
+    .await
+    .map_err(AppError::storage)
+}
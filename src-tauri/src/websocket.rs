@@ -0,0 +1,340 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::error::AppError;
+use crate::retry::RetryOptions;
+
+/// Emitted with a [`WsMessage`] for every text frame received
+pub const WS_MESSAGE_EVENT: &str = "ws-message";
+
+/// Emitted with a [`WsStatus`] whenever a connection's state changes
+pub const WS_STATUS_EVENT: &str = "ws-status";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages kept for delivery while a connection is down; the oldest are dropped beyond this
+const MAX_PENDING_MESSAGES: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsMessage {
+    pub id: u64,
+    /// Parsed JSON, or the raw text when the frame is not JSON
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WsConnectionState {
+    Connected,
+    /// Waiting out the backoff after a failed or dropped connection
+    Reconnecting,
+    /// Waiting for the network monitor to report the website reachable again
+    Offline,
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsStatus {
+    pub id: u64,
+    pub state: WsConnectionState,
+    /// Consecutive failed connection attempts
+    pub failures: u32,
+    pub error: Option<String>,
+}
+
+/// Open connections by id, plus the connectivity flag fed by the network monitor
+pub struct WebSocketState {
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<u64, mpsc::UnboundedSender<Message>>>,
+    online: watch::Sender<bool>,
+}
+
+impl Default for WebSocketState {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            connections: Mutex::new(HashMap::new()),
+            online: watch::channel(true).0,
+        }
+    }
+}
+
+/// Pauses reconnection while the website is unreachable and resumes it immediately
+/// once it is back.
+pub fn set_online(app: &AppHandle, online: bool) {
+    app.state::<WebSocketState>()
+        .online
+        .send_if_modified(|current| std::mem::replace(current, online) != online);
+}
+
+fn emit_status(
+    app: &AppHandle,
+    id: u64,
+    state: WsConnectionState,
+    failures: u32,
+    error: Option<String>,
+) {
+    let status = WsStatus {
+        id,
+        state,
+        failures,
+        error,
+    };
+
+    if let Err(e) = app.emit(WS_STATUS_EVENT, &status) {
+        tracing::warn!("Failed to emit WebSocket status: {}", e);
+    }
+}
+
+async fn connect(
+    app: &AppHandle,
+    url: &reqwest::Url,
+    headers: &HashMap<String, String>,
+) -> Result<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    String,
+> {
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("Invalid WebSocket request: {}", e))?;
+
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name '{}': {}", name, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header '{}': {}", name, e))?;
+        request.headers_mut().insert(name, value);
+    }
+
+    // Same rule as HTTP requests: the session token goes to trusted hosts only
+    if !request.headers().contains_key(AUTHORIZATION) {
+        if let Some(value) = crate::auth::session_authorization(app, url)? {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+    }
+
+    let (stream, _) =
+        tokio::time::timeout(CONNECT_TIMEOUT, tokio_tungstenite::connect_async(request))
+            .await
+            .map_err(|_| "WebSocket connection timed out".to_string())?
+            .map_err(|e| format!("Failed to connect WebSocket: {}", e))?;
+
+    Ok(stream)
+}
+
+fn buffer(pending: &mut VecDeque<Message>, message: Message) {
+    if pending.len() == MAX_PENDING_MESSAGES {
+        tracing::warn!("Dropping oldest unsent WebSocket message");
+        pending.pop_front();
+    }
+    pending.push_back(message);
+}
+
+/// Waits until `delay` has passed (forever when `None`) or connectivity changes, keeping
+/// messages sent meanwhile. Returns `false` once the connection has been closed.
+async fn pause(
+    delay: Option<Duration>,
+    online: &mut watch::Receiver<bool>,
+    outgoing: &mut mpsc::UnboundedReceiver<Message>,
+    pending: &mut VecDeque<Message>,
+) -> bool {
+    let sleep = tokio::time::sleep(delay.unwrap_or(Duration::MAX));
+    tokio::pin!(sleep);
+
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return true,
+            changed = online.changed() => return changed.is_ok(),
+            message = outgoing.recv() => match message {
+                Some(message) => buffer(pending, message),
+                None => return false,
+            },
+        }
+    }
+}
+
+async fn went_offline(online: &mut watch::Receiver<bool>) {
+    let _ = online.wait_for(|online| !*online).await;
+}
+
+/// Drives one connection until `ws_disconnect`, reconnecting with backoff whenever it drops.
+async fn run(
+    app: AppHandle,
+    id: u64,
+    url: reqwest::Url,
+    headers: HashMap<String, String>,
+    mut outgoing: mpsc::UnboundedReceiver<Message>,
+) {
+    let backoff = RetryOptions {
+        base_delay_ms: 1_000,
+        ..Default::default()
+    };
+    let mut online = app.state::<WebSocketState>().online.subscribe();
+    let mut pending = VecDeque::new();
+    let mut failures = 0;
+
+    loop {
+        if !*online.borrow_and_update() {
+            emit_status(&app, id, WsConnectionState::Offline, failures, None);
+            if !pause(None, &mut online, &mut outgoing, &mut pending).await {
+                break;
+            }
+            continue;
+        }
+
+        let error = match connect(&app, &url, &headers).await {
+            Ok(stream) => {
+                failures = 0;
+                emit_status(&app, id, WsConnectionState::Connected, failures, None);
+
+                let (mut write, mut read) = stream.split();
+                let session: Result<(), String> = async {
+                    while let Some(message) = pending.pop_front() {
+                        write
+                            .send(message)
+                            .await
+                            .map_err(|e| format!("Failed to send WebSocket message: {}", e))?;
+                    }
+
+                    loop {
+                        tokio::select! {
+                            incoming = read.next() => match incoming {
+                                Some(Ok(Message::Text(text))) => {
+                                    let data = serde_json::from_str(text.as_str())
+                                        .unwrap_or_else(|_| serde_json::Value::String(text.to_string()));
+                                    if let Err(e) = app.emit(WS_MESSAGE_EVENT, WsMessage { id, data }) {
+                                        tracing::warn!("Failed to emit WebSocket message: {}", e);
+                                    }
+                                }
+                                Some(Ok(Message::Close(_))) | None => {
+                                    return Err("Connection closed by server".to_string());
+                                }
+                                // Pings are answered by the protocol layer
+                                Some(Ok(_)) => {}
+                                Some(Err(e)) => return Err(format!("WebSocket error: {}", e)),
+                            },
+                            message = outgoing.recv() => match message {
+                                Some(message) => write
+                                    .send(message)
+                                    .await
+                                    .map_err(|e| format!("Failed to send WebSocket message: {}", e))?,
+                                None => {
+                                    let _ = write.send(Message::Close(None)).await;
+                                    return Ok(());
+                                }
+                            },
+                            _ = went_offline(&mut online) => {
+                                return Err("Website is unreachable".to_string());
+                            }
+                        }
+                    }
+                }
+                .await;
+
+                match session {
+                    Ok(()) => break,
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+
+        failures += 1;
+        tracing::info!("WebSocket {} disconnected: {}", id, error);
+        emit_status(
+            &app,
+            id,
+            WsConnectionState::Reconnecting,
+            failures,
+            Some(error),
+        );
+
+        let delay = backoff.delay(failures);
+        if !pause(Some(delay), &mut online, &mut outgoing, &mut pending).await {
+            break;
+        }
+    }
+
+    emit_status(&app, id, WsConnectionState::Closed, failures, None);
+}
+
+/// Opens a WebSocket and returns its id. The connection stays up until `ws_disconnect`,
+/// reconnecting on its own after drops.
+#[tauri::command]
+pub async fn ws_connect(
+    app: AppHandle,
+    state: State<'_, WebSocketState>,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+) -> Result<u64, AppError> {
+    crate::demo::ensure_not_demo(&app)?;
+
+    let url = reqwest::Url::parse(&url)
+        .map_err(|e| AppError::invalid_input(format!("Invalid WebSocket URL: {}", e)))?;
+    if !matches!(url.scheme(), "ws" | "wss") {
+        return Err(AppError::invalid_input("WebSocket URL must use ws or wss"));
+    }
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let (sender, receiver) = mpsc::unbounded_channel();
+    state
+        .connections
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, sender);
+
+    tauri::async_runtime::spawn(run(
+        app.clone(),
+        id,
+        url,
+        headers.unwrap_or_default(),
+        receiver,
+    ));
+
+    Ok(id)
+}
+
+/// Sends a text frame; JSON values other than strings are serialized first. Messages
+/// sent while the connection is down are delivered once it is back.
+#[tauri::command]
+pub async fn ws_send(
+    state: State<'_, WebSocketState>,
+    id: u64,
+    message: serde_json::Value,
+) -> Result<(), AppError> {
+    let text = match message {
+        serde_json::Value::String(text) => text,
+        other => other.to_string(),
+    };
+
+    state
+        .connections
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&id)
+        .ok_or_else(|| AppError::not_found(format!("Unknown WebSocket connection: {}", id)))?
+        .send(Message::text(text))
+        .map_err(|_| AppError::invalid_state(format!("WebSocket connection {} is closed", id)))
+}
+
+#[tauri::command]
+pub async fn ws_disconnect(state: State<'_, WebSocketState>, id: u64) -> Result<(), AppError> {
+    // Dropping the sender ends the connection task
+    state
+        .connections
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id)
+        .map(drop)
+        .ok_or_else(|| AppError::not_found(format!("Unknown WebSocket connection: {}", id)))
+}
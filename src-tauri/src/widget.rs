@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, LogicalSize, Manager, PhysicalPosition, State, WebviewUrl, WebviewWindowBuilder,
+    WindowEvent,
+};
+
+use crate::error::AppError;
+
+const WIDGET_LABEL: &str = "widget";
+
+const WIDGET_STATE_FILE: &str = "widget";
+
+/// Website route rendering the compact list of today's habits
+const WIDGET_ROUTE: &str = "/widget";
+
+const WIDGET_SIZE: LogicalSize<f64> = LogicalSize::new(320.0, 420.0);
+
+/// Saved placement and behaviour of the today widget
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WidgetConfig {
+    /// Last outer position in physical pixels; centered when unset
+    pub position: Option<(i32, i32)>,
+    /// Lets clicks pass through to the windows underneath
+    pub click_through: bool,
+}
+
+/// Position updates arrive on every move, so they are kept here and written once the
+/// widget closes
+#[derive(Default)]
+pub struct WidgetState(Mutex<Option<(i32, i32)>>);
+
+fn save_position(app: &AppHandle) {
+    let Some(position) = *app
+        .state::<WidgetState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+    else {
+        return;
+    };
+
+    let result =
+        crate::read_state_file::<WidgetConfig>(app, WIDGET_STATE_FILE).and_then(|config| {
+            crate::write_state_file(
+                app,
+                WIDGET_STATE_FILE,
+                &WidgetConfig {
+                    position: Some(position),
+                    ..config
+                },
+            )
+        });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to save widget position: {}", e);
+    }
+}
+
+/// Closes the widget if it is open, e.g. when the app locks.
+pub fn close(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(WIDGET_LABEL) {
+        if let Err(e) = window.close() {
+            tracing::warn!("Failed to close widget window: {}", e);
+        }
+    }
+}
+
+fn open(app: &AppHandle) -> Result<(), AppError> {
+    let config: WidgetConfig = crate::read_state_file(app, WIDGET_STATE_FILE)?;
+    let url = reqwest::Url::parse(&crate::website_url(app))
+        .and_then(|base| base.join(WIDGET_ROUTE))
+        .map_err(|e| AppError::corrupt_data(format!("Invalid widget URL: {}", e)))?;
+
+    let mut builder = WebviewWindowBuilder::new(app, WIDGET_LABEL, WebviewUrl::External(url))
+        .title("Today")
+        .inner_size(WIDGET_SIZE.width, WIDGET_SIZE.height)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false);
+    if config.position.is_none() {
+        builder = builder.center();
+    }
+
+    let window = builder
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to open widget window: {}", e)))?;
+
+    if let Some((x, y)) = config.position {
+        window
+            .set_position(PhysicalPosition::new(x, y))
+            .map_err(|e| AppError::internal(format!("Failed to position widget window: {}", e)))?;
+    }
+
+    window
+        .set_ignore_cursor_events(config.click_through)
+        .map_err(|e| AppError::internal(format!("Failed to set widget click-through: {}", e)))?;
+
+    let handle = app.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(position) => {
+            *handle
+                .state::<WidgetState>()
+                .0
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some((position.x, position.y));
+        }
+        WindowEvent::Destroyed => save_position(&handle),
+        _ => {}
+    });
+
+    Ok(())
+}
+
+/// Opens the always-on-top today widget, or closes it if it is already open. Returns
+/// whether the widget is now visible.
+#[tauri::command]
+pub async fn toggle_widget_window(app: AppHandle) -> Result<bool, AppError> {
+    match app.get_webview_window(WIDGET_LABEL) {
+        Some(window) => {
+            window
+                .close()
+                .map_err(|e| AppError::internal(format!("Failed to close widget window: {}", e)))?;
+            Ok(false)
+        }
+        None => {
+            open(&app)?;
+            Ok(true)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_widget_config(app: AppHandle) -> Result<WidgetConfig, AppError> {
+    crate::read_state_file_blocking(&app, WIDGET_STATE_FILE).await
+}
+
+/// Makes the widget ignore the mouse so it can sit over other windows; it is turned off
+/// again from the main window.
+#[tauri::command]
+pub async fn set_widget_click_through(
+    app: AppHandle,
+    state: State<'_, WidgetState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(WIDGET_LABEL) {
+        window.set_ignore_cursor_events(enabled).map_err(|e| {
+            AppError::internal(format!("Failed to set widget click-through: {}", e))
+        })?;
+    }
+
+    let mut config: WidgetConfig = crate::read_state_file_blocking(&app, WIDGET_STATE_FILE).await?;
+    config.click_through = enabled;
+    if let Some(position) = *state.0.lock().unwrap_or_else(|e| e.into_inner()) {
+        config.position = Some(position);
+    }
+    crate::write_state_file_blocking(&app, WIDGET_STATE_FILE, &config).await
+}
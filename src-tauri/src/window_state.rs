@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, LogicalSize, Manager, PhysicalPosition, PhysicalSize, State, WebviewWindow,
+    WindowEvent,
+};
+
+use crate::error::AppError;
+
+/// Geometry from `tauri.conf.json`, used by `reset_window_state`
+const DEFAULT_SIZE: LogicalSize<f64> = LogicalSize::new(1200.0, 800.0);
+
+const WINDOW_STATE_FILE: &str = "window-state";
+
+/// Last geometry of the main window, in physical pixels
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    /// Size and position while not maximized, so un-maximizing after a restart still works
+    pub size: Option<(u32, u32)>,
+    pub position: Option<(i32, i32)>,
+    pub maximized: bool,
+    /// Name of the monitor the window was on
+    pub monitor: Option<String>,
+}
+
+/// Geometry updates arrive on every move and resize, so they are kept here and written
+/// when the window closes or the app quits
+#[derive(Default)]
+pub struct WindowGeometryState(Mutex<WindowGeometry>);
+
+/// Kept in the app data root rather than the profile directory: the window is shared by
+/// all profiles.
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    Ok(dir.join(format!("{}.json", WINDOW_STATE_FILE)))
+}
+
+fn load(app: &AppHandle) -> Result<WindowGeometry, String> {
+    let path = state_path(app)?;
+    if !path.exists() {
+        return Ok(WindowGeometry::default());
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read window state file: {}", e))?;
+    Ok(crate::storage::decode(WINDOW_STATE_FILE, &content)?.value)
+}
+
+/// Writes the last known geometry of the main window.
+pub fn save(app: &AppHandle) {
+    let geometry = app
+        .state::<WindowGeometryState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if geometry.size.is_none() && geometry.position.is_none() {
+        return;
+    }
+
+    let result = state_path(app).and_then(|path| {
+        let json = crate::storage::encode(WINDOW_STATE_FILE, &geometry)?;
+        crate::storage::write_atomic(&path, json.as_bytes())
+            .map_err(|e| format!("Failed to write window state file: {}", e))
+    });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to save window state: {}", e);
+    }
+}
+
+fn record(window: &WebviewWindow) {
+    // A minimized window reports a meaningless position and size
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+
+    let maximized = window.is_maximized().unwrap_or(false);
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+
+    let state = window.state::<WindowGeometryState>();
+    let mut geometry = state.0.lock().unwrap_or_else(|e| e.into_inner());
+    geometry.maximized = maximized;
+    if maximized {
+        return;
+    }
+
+    if let Ok(size) = window.inner_size() {
+        geometry.size = Some((size.width, size.height));
+    }
+    if let Ok(position) = window.outer_position() {
+        geometry.position = Some((position.x, position.y));
+    }
+    geometry.monitor = monitor;
+}
+
+/// Whether the saved position is still on a connected monitor, so a window last seen on
+/// an unplugged display does not open off screen.
+fn is_visible(window: &WebviewWindow, geometry: &WindowGeometry) -> bool {
+    let Some((x, y)) = geometry.position else {
+        return false;
+    };
+    let Ok(monitors) = window.available_monitors() else {
+        return false;
+    };
+
+    monitors.iter().any(|monitor| {
+        let position = monitor.position();
+        let size = monitor.size();
+        let on_monitor = x >= position.x
+            && y >= position.y
+            && x < position.x + size.width as i32
+            && y < position.y + size.height as i32;
+
+        on_monitor
+            && geometry
+                .monitor
+                .as_ref()
+                .is_none_or(|name| monitor.name() == Some(name))
+    })
+}
+
+/// Restores the saved geometry of the main window and starts tracking changes to it.
+pub fn restore(window: &WebviewWindow) {
+    let geometry = load(window.app_handle()).unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        WindowGeometry::default()
+    });
+
+    if let Some((width, height)) = geometry.size {
+        if let Err(e) = window.set_size(PhysicalSize::new(width, height)) {
+            tracing::warn!("Failed to restore window size: {}", e);
+        }
+    }
+    if is_visible(window, &geometry) {
+        if let Some((x, y)) = geometry.position {
+            if let Err(e) = window.set_position(PhysicalPosition::new(x, y)) {
+                tracing::warn!("Failed to restore window position: {}", e);
+            }
+        }
+    }
+    if geometry.maximized {
+        if let Err(e) = window.maximize() {
+            tracing::warn!("Failed to maximize window: {}", e);
+        }
+    }
+
+    *window
+        .state::<WindowGeometryState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = geometry;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => record(&handle),
+        WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed => save(handle.app_handle()),
+        _ => {}
+    });
+}
+
+/// Forgets the saved geometry and puts the main window back at its default size, centered.
+#[tauri::command]
+pub async fn reset_window_state(
+    app: AppHandle,
+    state: State<'_, WindowGeometryState>,
+) -> Result<(), AppError> {
+    *state.0.lock().unwrap_or_else(|e| e.into_inner()) = WindowGeometry::default();
+
+    let path = state_path(&app).map_err(AppError::storage)?;
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| AppError::storage(format!("Failed to remove window state file: {}", e)))?;
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        window
+            .unmaximize()
+            .and_then(|_| window.set_size(DEFAULT_SIZE))
+            .and_then(|_| window.center())
+            .map_err(|e| AppError::internal(format!("Failed to reset window: {}", e)))?;
+    }
+
+    Ok(())
+}